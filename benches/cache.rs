@@ -0,0 +1,126 @@
+use coding_compairson::{
+    cached_fn, cached_fn_by_ref,
+    generators::{self, Bounds},
+    traveling_salesman, Metric, Point,
+};
+use criterion::{
+    black_box, criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput,
+};
+
+// The number of calls made to the cached function in each iteration
+const CALLS: usize = 10_000;
+
+// The fractions of calls answered from the cache
+const HIT_RATIOS: [f64; 4] = [0.0, 0.5, 0.9, 0.99];
+
+// Stands in for a distance computation, cheap enough that the cache's own overhead shows
+fn work(key: u64) -> f64 {
+    (key as f64).sqrt().sin()
+}
+
+// Keys cycling through enough distinct values that `hit_ratio` of the calls repeat one
+fn keys(hit_ratio: f64) -> Vec<u64> {
+    let distinct = ((CALLS as f64 * (1.0 - hit_ratio)).round() as usize).max(1);
+    (0..CALLS).map(|i| (i % distinct) as u64).collect()
+}
+
+fn benchmark_hit_ratios(c: &mut Criterion) {
+    let mut group = c.benchmark_group("cached_fn");
+    group.throughput(Throughput::Elements(CALLS as u64));
+    for hit_ratio in HIT_RATIOS {
+        let keys = keys(hit_ratio);
+        let label = format!("{}%", hit_ratio * 100.0);
+
+        // A fresh cache each iteration, so every distinct key misses once
+        group.bench_with_input(BenchmarkId::new("u64", &label), &keys, |b, keys| {
+            b.iter_batched(
+                || cached_fn(work),
+                |cached| {
+                    for &key in keys {
+                        black_box(cached(black_box(key)));
+                    }
+                },
+                BatchSize::SmallInput,
+            )
+        });
+
+        // String keys pay for hashing, comparing and cloning the key on every call
+        let strings = keys
+            .iter()
+            .map(|key| format!("destination-{key}"))
+            .collect::<Vec<_>>();
+        group.bench_with_input(BenchmarkId::new("String", &label), &strings, |b, keys| {
+            b.iter_batched(
+                || cached_fn(|key: String| work(key.len() as u64)),
+                |cached| {
+                    for key in keys {
+                        black_box(cached(black_box(key.clone())));
+                    }
+                },
+                BatchSize::SmallInput,
+            )
+        });
+
+        // Looking up by &str makes an owned key only on a miss
+        group.bench_with_input(BenchmarkId::new("&str", &label), &strings, |b, keys| {
+            b.iter_batched(
+                || cached_fn_by_ref(|key: &str| work(key.len() as u64)),
+                |cached| {
+                    for key in keys {
+                        black_box(cached(black_box(key)));
+                    }
+                },
+                BatchSize::SmallInput,
+            )
+        });
+    }
+
+    // The same calls without a cache, the cost a cache has to beat
+    let keys = keys(0.0);
+    group.bench_function("uncached", |b| {
+        b.iter(|| {
+            for &key in &keys {
+                black_box(work(black_box(key)));
+            }
+        })
+    });
+    group.finish();
+}
+
+// Stands in for a road network lookup, by recomputing a great-circle distance
+fn expensive_distance(a: &Point, b: &Point) -> f64 {
+    let mut distance = 0.0;
+    for _ in 0..50 {
+        distance = Metric::Haversine.distance(black_box(a), black_box(b));
+    }
+    distance
+}
+
+// The same exhaustive solve with an expensive metric, raw and memoized; see also
+// `cargo run --release --example memoized_distance` for the hit rate
+fn benchmark_memoized_solve(c: &mut Criterion) {
+    let points = generators::uniform_points(
+        7,
+        Bounds::new(Point::new(45.0, 5.0), Point::new(55.0, 15.0)),
+        7,
+    );
+    let mut group = c.benchmark_group("memoized_solve");
+    group.bench_function("raw", |b| {
+        b.iter(|| {
+            traveling_salesman(1..points.len(), 0, 0, |(&a, &b)| {
+                expensive_distance(&points[a], &points[b])
+            })
+        })
+    });
+    group.bench_function("cached_fn", |b| {
+        b.iter(|| {
+            let distance =
+                cached_fn(|(a, b): (usize, usize)| expensive_distance(&points[a], &points[b]));
+            traveling_salesman(1..points.len(), 0, 0, |(&a, &b)| distance((a, b)))
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, benchmark_hit_ratios, benchmark_memoized_solve);
+criterion_main!(benches);
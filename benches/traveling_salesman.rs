@@ -1,4 +1,8 @@
-use coding_compairson::{hand_rolled_traveling_salesman, traveling_salesman};
+use coding_compairson::{
+    hand_rolled_traveling_salesman, hand_rolled_traveling_salesman_f64,
+    hand_rolled_traveling_salesman_u64, hand_rolled_traveling_salesman_unchecked, registry,
+    traveling_salesman, traveling_salesman_const, Metric, Point, Problem,
+};
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
 
 // Generate a list of destinations that will stress the algorithm
@@ -22,7 +26,7 @@ fn benchmark_traveling_salesman(c: &mut Criterion) {
                 black_box(destinations.iter()),
                 black_box(&start),
                 black_box(&end),
-                &compute_distance,
+                compute_distance,
             )
         });
     });
@@ -36,7 +40,7 @@ fn benchmark_traveling_salesman(c: &mut Criterion) {
                 black_box(destinations.clone().into_iter()),
                 black_box(start),
                 black_box(end),
-                &compute_distance,
+                compute_distance,
             )
         });
     });
@@ -51,7 +55,99 @@ fn benchmark_traveling_salesman(c: &mut Criterion) {
             )
         });
     });
+
+    // Benchmark the hand-rolled version without bounds checks
+    c.bench_function("hand_rolled_traveling_salesman_unchecked", |b| {
+        b.iter(|| {
+            hand_rolled_traveling_salesman_unchecked(
+                black_box(&destinations),
+                black_box(&start),
+                black_box(&end),
+            )
+        });
+    });
+
+    // Benchmark the hand-rolled versions for other coordinate types
+    c.bench_function("hand_rolled_traveling_salesman_u64", |b| {
+        let destinations = destinations.iter().map(|&x| x as u64).collect::<Vec<_>>();
+        let (start, end) = (start as u64, end as u64);
+        b.iter(|| {
+            hand_rolled_traveling_salesman_u64(
+                black_box(&destinations),
+                black_box(&start),
+                black_box(&end),
+            )
+        });
+    });
+
+    c.bench_function("hand_rolled_traveling_salesman_f64", |b| {
+        let destinations = destinations
+            .iter()
+            .map(|&x| f64::from(x))
+            .collect::<Vec<_>>();
+        let (start, end) = (f64::from(start), f64::from(end));
+        b.iter(|| {
+            hand_rolled_traveling_salesman_f64(
+                black_box(&destinations),
+                black_box(&start),
+                black_box(&end),
+            )
+        });
+    });
+
+    // Benchmark the stack-only version
+    c.bench_function("traveling_salesman_const", |b| {
+        let destinations: [i32; 8] = destinations.clone().try_into().unwrap();
+        let compute_distance = |pair: (&i32, &i32)| pair.0.abs_diff(*pair.1);
+
+        b.iter(|| {
+            traveling_salesman_const(
+                black_box(destinations),
+                black_box(&start),
+                black_box(&end),
+                compute_distance,
+            )
+        });
+    });
+}
+
+// Benchmark every registered solver on the same problem
+fn benchmark_registry(c: &mut Criterion) {
+    // The same destinations as above, on the x axis so every solver supports them
+    let points = std::iter::once(0)
+        .chain(generate_stress_test_destinations())
+        .map(|x| Point::new(f64::from(x), 0.0))
+        .collect();
+    let problem = Problem::new(points, Metric::Euclidean);
+
+    let mut group = c.benchmark_group("registry");
+    for registered in registry::IMPLEMENTATIONS {
+        if registered.supports(&problem) {
+            group.bench_function(registered.name, |b| {
+                b.iter(|| (registered.solve)(black_box(&problem)))
+            });
+        }
+    }
+    group.finish();
 }
 
-criterion_group!(benches, benchmark_traveling_salesman);
+// With the `profiling` feature, `cargo bench --features profiling -- --profile-time 10`
+// samples each benchmark instead of timing it, and writes a flamegraph of it to
+// target/criterion/<benchmark>/profile/flamegraph.svg
+#[cfg(feature = "profiling")]
+fn config() -> Criterion {
+    use pprof::criterion::{Output, PProfProfiler};
+    Criterion::default().with_profiler(PProfProfiler::new(1000, Output::Flamegraph(None)))
+}
+
+#[cfg(not(feature = "profiling"))]
+fn config() -> Criterion {
+    Criterion::default()
+}
+
+criterion_group! {
+    name = benches;
+    config = config();
+    targets = benchmark_traveling_salesman, benchmark_registry
+}
 criterion_main!(benches);
@@ -0,0 +1,63 @@
+//! Solves one instance exhaustively twice, once measuring every leg with an expensive
+//! metric and once through `CachedFn`, and reports how much memoizing the metric saves.
+//!
+//! ```text
+//! cargo run --release --example memoized_distance
+//! ```
+
+use std::{cell::Cell, hint::black_box, time::Instant};
+
+use coding_compairson::{
+    cache::CachedFn,
+    generators::{self, Bounds},
+    traveling_salesman, Metric, Point,
+};
+
+// How many times the expensive metric recomputes a distance, standing in for the cost
+// of a road network lookup
+const COST: usize = 200;
+
+fn expensive_distance(a: &Point, b: &Point) -> f64 {
+    let mut distance = 0.0;
+    for _ in 0..COST {
+        distance = Metric::Haversine.distance(black_box(a), black_box(b));
+    }
+    distance
+}
+
+fn main() {
+    // Coordinates around central Europe, as latitude and longitude
+    let points = generators::uniform_points(
+        9,
+        Bounds::new(Point::new(45.0, 5.0), Point::new(55.0, 15.0)),
+        7,
+    );
+    let inner = 1..points.len();
+
+    let calls = Cell::new(0u64);
+    let started = Instant::now();
+    let raw = traveling_salesman(inner.clone(), 0, 0, |(&a, &b)| {
+        calls.set(calls.get() + 1);
+        expensive_distance(&points[a], &points[b])
+    });
+    let raw_time = started.elapsed();
+
+    let distance =
+        CachedFn::new(|(a, b): (usize, usize)| expensive_distance(&points[a], &points[b]));
+    let started = Instant::now();
+    let cached = traveling_salesman(inner, 0, 0, |(&a, &b)| distance.call((a, b)));
+    let cached_time = started.elapsed();
+    assert_eq!(raw, cached, "memoizing changed the route");
+
+    let misses = distance.len() as u64;
+    let hit_rate = 1.0 - misses as f64 / calls.get() as f64;
+    println!("destinations: {}", points.len());
+    println!("distance calls: {}", calls.get());
+    println!("raw metric: {raw_time:?}");
+    println!("memoized metric: {cached_time:?} ({misses} distances computed)");
+    println!("hit rate: {:.4}%", hit_rate * 100.0);
+    println!(
+        "speedup: {:.1}x",
+        raw_time.as_secs_f64() / cached_time.as_secs_f64()
+    );
+}
@@ -0,0 +1,81 @@
+//! Decodes arbitrary bytes into small instances and checks the solvers' invariants:
+//! no panics, routes that visit every destination once, and agreement between the
+//! exact solvers.
+//!
+//! Input layout, with missing bytes read as zero:
+//!
+//! * byte 0 - the number of destinations (`% MAX_DESTINATIONS`) and the metric
+//! * bytes 1, 2 - the start and end destinations
+//! * then little-endian `i32` pairs for each destination's coordinates
+//!
+//! Coordinates cover the whole `i32` range, so the `i32` solvers are also run on the
+//! x coordinates with the first two as start and end.
+
+#![no_main]
+
+use coding_compairson::{
+    testing::{check_exact_solvers_agree, check_i32_solvers_agree, check_route_is_permutation},
+    Metric, Point, Problem,
+};
+use libfuzzer_sys::fuzz_target;
+
+/// Exhaustive search is factorial, so keep instances tiny.
+const MAX_DESTINATIONS: u8 = 7;
+
+struct Input<'a> {
+    bytes: std::slice::Iter<'a, u8>,
+}
+
+impl Input<'_> {
+    fn byte(&mut self) -> u8 {
+        self.bytes.next().copied().unwrap_or(0)
+    }
+
+    fn i32(&mut self) -> i32 {
+        i32::from_le_bytes([self.byte(), self.byte(), self.byte(), self.byte()])
+    }
+}
+
+fuzz_target!(|data: &[u8]| {
+    let mut input = Input { bytes: data.iter() };
+
+    let header = input.byte();
+    let len = usize::from(header % MAX_DESTINATIONS);
+    let metric = match (header / MAX_DESTINATIONS) % 3 {
+        0 => Metric::Euclidean,
+        1 => Metric::Manhattan,
+        _ => Metric::Haversine,
+    };
+    let start = input.byte();
+    let end = input.byte();
+
+    let coordinates = (0..len)
+        .map(|_| (input.i32(), input.i32()))
+        .collect::<Vec<_>>();
+
+    // The i32 solvers accept any destinations, including none at all
+    let xs = coordinates.iter().map(|&(x, _)| x).collect::<Vec<_>>();
+    let (i32_start, i32_end) = match xs[..] {
+        [start, end, ..] => (start, end),
+        [only] => (only, only),
+        [] => (i32::MIN, i32::MAX),
+    };
+    check_i32_solvers_agree(&xs, i32_start, i32_end).unwrap();
+
+    // A problem needs at least one destination for its start and end
+    if len == 0 {
+        return;
+    }
+    let points = coordinates
+        .iter()
+        .map(|&(x, y)| Point::new(f64::from(x), f64::from(y)))
+        .collect();
+    let mut problem = Problem::new(points, metric);
+    problem.start = usize::from(start) % len;
+    problem.end = usize::from(end) % len;
+
+    let route = problem.solve();
+    check_route_is_permutation(&route, &problem).unwrap();
+    assert!(problem.route_distance(&route).is_finite());
+    check_exact_solvers_agree(&problem).unwrap();
+});
@@ -0,0 +1,75 @@
+/* C interface to the coding_compairson traveling salesman solvers.
+ *
+ * Generated with cbindgen from src/ffi.rs; see cbindgen.toml. */
+
+#ifndef CODING_COMPAIRSON_H
+#define CODING_COMPAIRSON_H
+
+#include <stdint.h>
+#include <stddef.h>
+
+/* The call succeeded. */
+#define TSP_OK 0
+
+/* A required pointer argument was null. */
+#define TSP_ERROR_NULL_POINTER -1
+
+/* `start` or `end` was not a valid index. */
+#define TSP_ERROR_INVALID_INDEX -2
+
+/* The metric was not one of the `TSP_METRIC_*` constants. */
+#define TSP_ERROR_INVALID_METRIC -3
+
+/* The solver panicked; `out_route` may have been partly written. */
+#define TSP_ERROR_PANIC -4
+
+/* Straight-line distance in the plane. */
+#define TSP_METRIC_EUCLIDEAN 0
+
+/* Sum of the absolute coordinate differences. */
+#define TSP_METRIC_MANHATTAN 1
+
+/* Great-circle distance, with `x` as latitude and `y` as longitude in degrees. */
+#define TSP_METRIC_HAVERSINE 2
+
+/* Straight-line distance rounded to the nearest integer, as TSPLIB's `EUC_2D`. */
+#define TSP_METRIC_ROUNDED_EUCLIDEAN 3
+
+/* A destination passed to `tsp_solve_points`. */
+typedef struct TspPoint {
+  double x;
+  double y;
+} TspPoint;
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Finds the shortest route through `destinations` from `start` to `end`, where the
+ * distance between two values is their absolute difference.
+ *
+ * On success `out_route` holds `len + 2` values: `start`, the destinations in
+ * visiting order, then `end`. */
+int32_t tsp_solve_i32(const int32_t *destinations,
+                      size_t len,
+                      int32_t start,
+                      int32_t end,
+                      int32_t *out_route);
+
+/* Finds the shortest route through every point from `points[start]` to `points[end]`
+ * under `metric` (one of the `TSP_METRIC_*` constants).
+ *
+ * On success `out_route` holds the point indices in visiting order: `len` values,
+ * or `len + 1` when `start == end` since the round trip returns to its start. */
+int32_t tsp_solve_points(const TspPoint *points,
+                         size_t len,
+                         size_t start,
+                         size_t end,
+                         uint32_t metric,
+                         size_t *out_route);
+
+#ifdef __cplusplus
+}  /* extern "C" */
+#endif
+
+#endif  /* CODING_COMPAIRSON_H */
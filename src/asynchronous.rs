@@ -0,0 +1,99 @@
+//! Solving from async code without blocking the tokio runtime.
+//!
+//! The search is CPU-bound, so it runs on tokio's blocking thread pool through
+//! [`spawn_blocking`](tokio::task::spawn_blocking) and must be called from within a
+//! runtime.
+
+use std::{ops::ControlFlow, panic};
+
+use tokio::sync::mpsc;
+use tokio_stream::{wrappers::ReceiverStream, Stream};
+
+use crate::{
+    search::{Improvement, Observer},
+    Problem, Route,
+};
+
+/// How many improvements may be waiting for the stream's reader before the search
+/// pauses for it to catch up.
+const BUFFERED_IMPROVEMENTS: usize = 16;
+
+/// Finds the shortest route as [`Problem::solve`] does, on a blocking thread.
+///
+/// If the solver panics, the panic is resumed in the awaiting task.
+pub async fn solve_async(problem: Problem) -> Route {
+    match tokio::task::spawn_blocking(move || problem.solve()).await {
+        Ok(route) => route,
+        Err(error) => panic::resume_unwind(error.into_panic()),
+    }
+}
+
+/// Searches for the shortest route on a blocking thread, streaming each route
+/// shorter than all those before it. The last item is the optimum.
+///
+/// Dropping the stream stops the search.
+pub fn improvements(problem: Problem) -> impl Stream<Item = Improvement> {
+    let (sender, receiver) = mpsc::channel(BUFFERED_IMPROVEMENTS);
+    tokio::task::spawn_blocking(move || problem.search(&mut Forward(sender)));
+    ReceiverStream::new(receiver)
+}
+
+/// Sends improvements down a channel, stopping once nobody is listening.
+struct Forward(mpsc::Sender<Improvement>);
+
+impl Observer for Forward {
+    fn improved(&mut self, improvement: &Improvement) -> ControlFlow<()> {
+        match self.0.blocking_send(improvement.clone()) {
+            Ok(()) => ControlFlow::Continue(()),
+            Err(_) => ControlFlow::Break(()),
+        }
+    }
+
+    fn examined(&mut self, _examined: u64, _total: Option<u64>) -> ControlFlow<()> {
+        if self.0.is_closed() {
+            ControlFlow::Break(())
+        } else {
+            ControlFlow::Continue(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio_stream::StreamExt as _;
+
+    use crate::{Metric, Point};
+
+    use super::*;
+
+    fn problem() -> Problem {
+        let mut problem = Problem::new(
+            (0..7)
+                .map(|i| Point::new(f64::from(i * 5 % 7), f64::from(i * i % 4)))
+                .collect(),
+            Metric::Manhattan,
+        );
+        problem.end = 6;
+        problem
+    }
+
+    #[tokio::test]
+    async fn test_solve_async() {
+        let problem = problem();
+        assert_eq!(solve_async(problem.clone()).await, problem.solve());
+    }
+
+    #[tokio::test]
+    async fn test_improvements() {
+        let problem = problem();
+        let improvements = improvements(problem.clone()).collect::<Vec<_>>().await;
+
+        assert!(improvements
+            .windows(2)
+            .all(|pair| pair[1].distance < pair[0].distance));
+        assert_eq!(
+            improvements.last().map(|improvement| improvement.distance),
+            Some(problem.route_distance(&problem.solve()))
+        );
+    }
+}
@@ -0,0 +1,387 @@
+//! Running the search on a background thread that can be watched and cancelled,
+//! for interactive applications.
+
+use std::{
+    ops::ControlFlow,
+    panic,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Mutex, OnceLock,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    registry::{self, Registered},
+    search::{Improvement, Observer},
+    Problem, Route,
+};
+
+/// The clock is only read every this many routes, as reading it costs about as
+/// much as examining a route.
+const CLOCK_INTERVAL: u64 = 256;
+
+/// Options for solving a problem.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SolverConfig {
+    /// The name of the [registered](crate::registry) solver to use, for callers that
+    /// choose one by configuration, such as the command line.
+    pub algorithm: Option<String>,
+    /// Stop searching after this long and keep the best route found so far.
+    pub time_limit: Option<Duration>,
+    /// For iterative heuristics, stop after this many iterations.
+    pub iterations: Option<u64>,
+    /// Seeds the random choices of heuristics, so runs are reproducible.
+    pub seed: u64,
+    /// For improvement heuristics, the route to refine, such as yesterday's solution or
+    /// one edited by hand, rather than constructing one from scratch. The `ils`,
+    /// `tempering` and `planar` solvers take one; `cluster` does not, as it solves each
+    /// cluster separately from a route of its own.
+    pub initial_route: Option<Route>,
+    /// For parallel solvers, return the same route on every run and the same as the
+    /// serial solver, breaking ties between equally short routes in a canonical order
+    /// at some cost in speed.
+    pub deterministic: bool,
+}
+
+impl SolverConfig {
+    pub fn with_algorithm(mut self, name: impl Into<String>) -> Self {
+        self.algorithm = Some(name.into());
+        self
+    }
+
+    pub fn with_time_limit(mut self, time_limit: Duration) -> Self {
+        self.time_limit = Some(time_limit);
+        self
+    }
+
+    pub fn with_iterations(mut self, iterations: u64) -> Self {
+        self.iterations = Some(iterations);
+        self
+    }
+
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    pub fn with_initial_route(mut self, route: Route) -> Self {
+        self.initial_route = Some(route);
+        self
+    }
+
+    pub fn with_deterministic(mut self, deterministic: bool) -> Self {
+        self.deterministic = deterministic;
+        self
+    }
+
+    /// The registered solver [`algorithm`](SolverConfig::algorithm) names, if it names
+    /// one.
+    pub fn solver(&self) -> Option<&'static Registered> {
+        self.algorithm.as_deref().and_then(registry::find)
+    }
+
+    /// The route an improvement heuristic starts from: the initial route if there is
+    /// one, or else the one `construct` builds.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the initial route is not a route of `problem`.
+    pub(crate) fn starting_route(
+        &self,
+        problem: &Problem,
+        construct: impl FnOnce(&Problem) -> Route,
+    ) -> Route {
+        match &self.initial_route {
+            Some(route) => {
+                assert!(
+                    problem.is_route(route),
+                    "the initial route must visit every destination once from start to end"
+                );
+                route.clone()
+            }
+            None => construct(problem),
+        }
+    }
+}
+
+/// How far a search has got.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Progress {
+    pub examined: u64,
+    /// The number of routes the search examines if it runs to completion, if known.
+    pub total: Option<u64>,
+}
+
+impl Progress {
+    /// The fraction of the search done, from 0 to 1.
+    pub fn fraction(&self) -> Option<f64> {
+        self.total
+            .map(|total| self.examined as f64 / total.max(1) as f64)
+    }
+}
+
+/// State shared between a [`SolverHandle`] and its search thread.
+#[derive(Default)]
+struct Shared {
+    best: Mutex<Option<Improvement>>,
+    examined: AtomicU64,
+    total: OnceLock<Option<u64>>,
+    cancelled: AtomicBool,
+}
+
+/// A search running on a background thread, started by [`spawn_solve`].
+pub struct SolverHandle {
+    shared: Arc<Shared>,
+    thread: JoinHandle<Improvement>,
+}
+
+/// Starts solving on a new thread with the solver `config` names, or by exhaustive
+/// search if it names none, `exhaustive` or `search`.
+///
+/// The exhaustive search publishes each improvement and its progress as it goes,
+/// stops at `config`'s time limit and can be cancelled at any point. Any other solver
+/// is run with [`solve_with`](Registered::solve_with), so it takes whichever of
+/// `config`'s budget, seed and initial route it can use, but its route is published only
+/// when it returns, and
+/// cancelling it does not stop it sooner.
+///
+/// # Arguments
+///
+/// * `problem` - The problem to solve
+/// * `config` - The solver and its options, such as a time limit
+///
+/// # Returns
+///
+/// A handle to query or cancel the search and wait for its result
+///
+/// # Panics
+///
+/// Panics if `config` names an algorithm that is not [registered](crate::registry), or
+/// one that does not support `problem`. Rather than ignore settings a solver has no use
+/// for, also panics if `config` has an iteration limit or initial route for a solver
+/// that takes no [`SolverConfig`], or a time limit for one that is not exhaustive.
+pub fn spawn_solve(problem: Problem, config: SolverConfig) -> SolverHandle {
+    let solver = config.algorithm.as_deref().map(|name| {
+        let solver =
+            registry::find(name).unwrap_or_else(|| panic!("no algorithm is named `{name}`"));
+        assert!(
+            solver.supports(&problem),
+            "the {name} algorithm does not support this problem"
+        );
+        solver
+    });
+    let exhaustive = solver.is_none_or(|solver| matches!(solver.name, "exhaustive" | "search"));
+    if solver.is_none_or(|solver| solver.solve_configured.is_none()) {
+        let name = solver.map_or("exhaustive", |solver| solver.name);
+        assert!(
+            config.iterations.is_none() && config.initial_route.is_none(),
+            "the {name} algorithm takes no iteration limit or initial route"
+        );
+        assert!(
+            exhaustive || config.time_limit.is_none(),
+            "the {name} algorithm takes no time limit"
+        );
+    }
+
+    let shared = Arc::new(Shared::default());
+    let thread = {
+        let shared = Arc::clone(&shared);
+        thread::spawn(move || match solver {
+            Some(solver) if !exhaustive => {
+                let route = solver.solve_with(&problem, &config);
+                let best = Improvement {
+                    distance: problem.route_distance(&route),
+                    route,
+                    examined: 1,
+                };
+                shared.total.get_or_init(|| Some(1));
+                *lock(&shared.best) = Some(best.clone());
+                shared.examined.store(1, Ordering::Relaxed);
+                best
+            }
+            _ => problem.search(&mut Publish {
+                shared: &shared,
+                deadline: config.time_limit.map(|limit| Instant::now() + limit),
+            }),
+        })
+    };
+    SolverHandle { shared, thread }
+}
+
+impl SolverHandle {
+    /// The shortest route found so far, if the search has examined any yet.
+    pub fn best_so_far(&self) -> Option<Improvement> {
+        lock(&self.shared.best).clone()
+    }
+
+    pub fn progress(&self) -> Progress {
+        Progress {
+            examined: self.shared.examined.load(Ordering::Relaxed),
+            total: self.shared.total.get().copied().flatten(),
+        }
+    }
+
+    /// Asks the search to stop. It stops after the route it is examining, and
+    /// [`join`](Self::join) then returns the best route found.
+    pub fn cancel(&self) {
+        self.shared.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    /// Whether the search has finished, having completed, been cancelled or run out of time.
+    pub fn is_finished(&self) -> bool {
+        self.thread.is_finished()
+    }
+
+    /// Waits for the search to finish and returns the best route it found.
+    ///
+    /// If the search panicked, the panic is resumed on the calling thread.
+    pub fn join(self) -> Improvement {
+        match self.thread.join() {
+            Ok(best) => best,
+            Err(payload) => panic::resume_unwind(payload),
+        }
+    }
+}
+
+/// Publishes the state of the search to the handle and stops it when asked to.
+struct Publish<'a> {
+    shared: &'a Shared,
+    deadline: Option<Instant>,
+}
+
+impl Observer for Publish<'_> {
+    fn improved(&mut self, improvement: &Improvement) -> ControlFlow<()> {
+        *lock(&self.shared.best) = Some(improvement.clone());
+        ControlFlow::Continue(())
+    }
+
+    fn examined(&mut self, examined: u64, total: Option<u64>) -> ControlFlow<()> {
+        self.shared.total.get_or_init(|| total);
+        self.shared.examined.store(examined, Ordering::Relaxed);
+
+        let out_of_time = examined.is_multiple_of(CLOCK_INTERVAL)
+            && self
+                .deadline
+                .is_some_and(|deadline| Instant::now() >= deadline);
+        if out_of_time || self.shared.cancelled.load(Ordering::Relaxed) {
+            ControlFlow::Break(())
+        } else {
+            ControlFlow::Continue(())
+        }
+    }
+}
+
+/// Locks the best route, which is only ever replaced whole so a panic while
+/// holding the lock cannot leave it inconsistent.
+fn lock(best: &Mutex<Option<Improvement>>) -> std::sync::MutexGuard<'_, Option<Improvement>> {
+    best.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Metric, Point};
+
+    use super::*;
+
+    /// Too many destinations to search exhaustively within a test.
+    fn large_problem() -> Problem {
+        Problem::new(
+            (0..14)
+                .map(|i| Point::new(f64::from(i * 5 % 14), f64::from(i * i % 9)))
+                .collect(),
+            Metric::Euclidean,
+        )
+    }
+
+    /// Waits until the first route has been examined and published.
+    fn wait_for_start(handle: &SolverHandle) {
+        while handle.progress().examined == 0 {
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    #[test]
+    fn test_spawn_solve_completes() {
+        let problem = Problem::new(
+            vec![
+                Point::new(0.0, 0.0),
+                Point::new(2.0, 2.0),
+                Point::new(2.0, 0.0),
+                Point::new(0.0, 2.0),
+            ],
+            Metric::Euclidean,
+        );
+
+        let handle = spawn_solve(problem.clone(), SolverConfig::default());
+        let best = handle.join();
+        assert_eq!(best.distance, 8.0);
+        assert_eq!(best.route, problem.solve());
+    }
+
+    #[test]
+    fn test_cancel() {
+        let handle = spawn_solve(large_problem(), SolverConfig::default());
+        wait_for_start(&handle);
+        handle.cancel();
+
+        let progress = handle.progress();
+        let best = handle.join();
+        assert_eq!(progress.total, Some(6_227_020_800));
+        assert!(progress.fraction().unwrap() < 1.0);
+        assert_eq!(best.route.len(), 15);
+    }
+
+    #[test]
+    fn test_spawn_configured_solver() {
+        let problem = large_problem();
+        let config = SolverConfig::default()
+            .with_algorithm("ils")
+            .with_iterations(50)
+            .with_seed(4);
+        let expected = registry::find("ils").unwrap().solve_with(&problem, &config);
+
+        let handle = spawn_solve(problem.clone(), config);
+        let best = handle.join();
+        assert_eq!(best.route, expected);
+        assert_eq!(best.distance, problem.route_distance(&expected));
+    }
+
+    #[test]
+    #[should_panic(expected = "no iteration limit")]
+    fn test_exhaustive_rejects_iterations() {
+        spawn_solve(large_problem(), SolverConfig::default().with_iterations(5));
+    }
+
+    #[test]
+    fn test_spawn_unconfigurable_solver() {
+        let problem = large_problem();
+        let config = SolverConfig::default().with_algorithm("christofides");
+        let best = spawn_solve(problem.clone(), config.clone()).join();
+        assert_eq!(best.route, crate::christofides::christofides(&problem));
+
+        let rejects = |config: SolverConfig| {
+            let problem = problem.clone();
+            thread::spawn(move || spawn_solve(problem, config))
+                .join()
+                .is_err()
+        };
+        assert!(rejects(config.clone().with_iterations(5)));
+        assert!(rejects(
+            config.clone().with_initial_route(best.route.clone())
+        ));
+        assert!(rejects(config.with_time_limit(Duration::from_secs(1))));
+    }
+
+    #[test]
+    fn test_time_limit() {
+        let config = SolverConfig::default().with_time_limit(Duration::from_millis(20));
+        let handle = spawn_solve(large_problem(), config);
+        wait_for_start(&handle);
+
+        assert!(handle.best_so_far().is_some());
+        let best = handle.join();
+        assert!(best.distance.is_finite());
+    }
+}
@@ -0,0 +1,146 @@
+//! Solving many independent problems at once, spread across the machine's cores, for
+//! jobs such as nightly planning that have hundreds of small instances to get through.
+
+use std::{
+    num::NonZeroUsize,
+    panic,
+    sync::atomic::{AtomicUsize, Ordering},
+    thread,
+};
+
+use crate::{background::SolverConfig, difficulty, Problem, Route};
+
+/// Solves each of `problems` on as many threads as the machine runs in parallel.
+///
+/// Each problem is solved with the solver `config` names, or the one
+/// [`estimate_difficulty`](difficulty::estimate_difficulty) recommends for it if `config`
+/// names none, and within `config`'s budget and from its seed, as if alone. The threads
+/// take the next unsolved problem as they finish one, so a few slow instances do not
+/// hold up the rest.
+///
+/// # Arguments
+///
+/// * `problems` - The problems to solve, in any order
+/// * `config` - The solver, budget and seed for every problem, without an initial
+///   route, which could not suit them all
+///
+/// # Returns
+///
+/// The route found for each problem, in the order of `problems`
+///
+/// # Panics
+///
+/// Panics if `config` names no registered solver, or has an initial route that is not a
+/// route of every problem, or if solving any problem panics.
+pub fn solve_batch(problems: &[Problem], config: &SolverConfig) -> Vec<Route> {
+    let threads = thread::available_parallelism().map_or(1, NonZeroUsize::get);
+    solve_batch_on(problems, config, threads)
+}
+
+/// Solves each of `problems` on at most `threads` threads, as [`solve_batch`] does.
+///
+/// # Panics
+///
+/// Panics if `threads` is zero, or as [`solve_batch`] does.
+pub fn solve_batch_on(problems: &[Problem], config: &SolverConfig, threads: usize) -> Vec<Route> {
+    assert!(threads > 0, "there must be at least one thread");
+    let solver = config.algorithm.as_ref().map(|name| {
+        config
+            .solver()
+            .unwrap_or_else(|| panic!("no algorithm is named `{name}`"))
+    });
+    let solve = |problem: &Problem| {
+        let solver = solver.unwrap_or_else(|| difficulty::estimate_difficulty(problem).recommended);
+        solver.solve_with(problem, config)
+    };
+
+    let next = AtomicUsize::new(0);
+    let solved = thread::scope(|scope| {
+        let workers = (0..threads.min(problems.len()))
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut solved = Vec::new();
+                    loop {
+                        let index = next.fetch_add(1, Ordering::Relaxed);
+                        let Some(problem) = problems.get(index) else {
+                            break solved;
+                        };
+                        solved.push((index, solve(problem)));
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+        workers
+            .into_iter()
+            .flat_map(|worker| {
+                worker
+                    .join()
+                    .unwrap_or_else(|err| panic::resume_unwind(err))
+            })
+            .collect::<Vec<_>>()
+    });
+
+    let mut routes = vec![None; problems.len()];
+    for (index, route) in solved {
+        routes[index] = Some(route);
+    }
+    routes
+        .into_iter()
+        .map(|route| route.expect("every problem is solved"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        generators::{self, Bounds},
+        Metric,
+    };
+
+    use super::*;
+
+    fn problems() -> Vec<Problem> {
+        (0..24)
+            .map(|seed| {
+                Problem::new(
+                    generators::uniform_points(3 + seed as usize % 6, Bounds::square(50.0), seed),
+                    Metric::Euclidean,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_solve_batch() {
+        let problems = problems();
+        let routes = solve_batch(&problems, &SolverConfig::default());
+        assert_eq!(routes.len(), problems.len());
+        // In input order, and the same as solving one at a time
+        for (problem, route) in problems.iter().zip(&routes) {
+            assert!(problem.is_route(route));
+            assert_eq!(*route, difficulty::solve_auto(problem));
+        }
+        assert!(solve_batch(&[], &SolverConfig::default()).is_empty());
+    }
+
+    #[test]
+    fn test_solve_batch_on() {
+        let problems = problems();
+        let config = SolverConfig::default()
+            .with_algorithm("ils")
+            .with_iterations(20)
+            .with_seed(4);
+        let serial = solve_batch_on(&problems, &config, 1);
+        assert_eq!(solve_batch_on(&problems, &config, 5), serial);
+        assert_eq!(solve_batch_on(&problems, &config, 100), serial);
+    }
+
+    #[test]
+    #[should_panic(expected = "no algorithm is named `telepathy`")]
+    fn test_unknown_algorithm() {
+        solve_batch(
+            &problems(),
+            &SolverConfig::default().with_algorithm("telepathy"),
+        );
+    }
+}
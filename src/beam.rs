@@ -0,0 +1,166 @@
+//! Beam search, a middle ground between greedily taking the nearest destination and
+//! trying every route.
+
+use crate::{kdtree::KdTree, Problem, Route};
+
+/// The beam width used by the registered `beam` solver.
+pub const DEFAULT_WIDTH: usize = 16;
+
+/// A route from the start through some of the destinations.
+#[derive(Clone)]
+struct Partial {
+    stops: Vec<usize>,
+    visited: Vec<bool>,
+    distance: f64,
+}
+
+/// Builds a route by beam search: starting from the start, extend every partial route
+/// by every destination it has not visited, keep the `width` shortest, and repeat until
+/// they visit every destination.
+///
+/// A width of one is the nearest-neighbor heuristic. A width of at least (n - 2)! keeps
+/// every partial route, so the search is exhaustive.
+///
+/// # Arguments
+///
+/// * `problem` - The problem to solve
+/// * `width` - The number of partial routes kept at each depth, at least one
+///
+/// # Returns
+///
+/// The shortest complete route in the final beam
+pub fn beam_search(problem: &Problem, width: usize) -> Route {
+    let width = width.max(1);
+    let inner = problem.inner_destinations();
+    let mut visited = vec![false; problem.len()];
+    visited[problem.start] = true;
+    visited[problem.end] = true;
+    let mut beam = vec![Partial {
+        stops: vec![problem.start],
+        visited,
+        distance: 0.0,
+    }];
+
+    for _ in 0..inner.len() {
+        let mut extended = Vec::with_capacity(beam.len() * inner.len());
+        for partial in &beam {
+            let last = partial.stops[partial.stops.len() - 1];
+            for &next in inner.iter().filter(|&&next| !partial.visited[next]) {
+                let mut stops = partial.stops.clone();
+                stops.push(next);
+                let mut visited = partial.visited.clone();
+                visited[next] = true;
+                extended.push(Partial {
+                    stops,
+                    visited,
+                    distance: partial.distance + problem.distance(last, next),
+                });
+            }
+        }
+        // A stable sort keeps ties in the order they were extended, so results are
+        // deterministic
+        extended.sort_by(|a, b| a.distance.total_cmp(&b.distance));
+        extended.truncate(width);
+        beam = extended;
+    }
+
+    let (_, mut stops) = beam
+        .into_iter()
+        .map(|partial| {
+            let last = partial.stops[partial.stops.len() - 1];
+            (
+                partial.distance + problem.distance(last, problem.end),
+                partial.stops,
+            )
+        })
+        .min_by(|a, b| a.0.total_cmp(&b.0))
+        .expect("the beam is never empty");
+    stops.push(problem.end);
+    Route::new(stops)
+}
+
+/// Builds a route by the nearest-neighbor heuristic, always going next to the nearest
+/// destination not yet visited, as [`beam_search`] with a width of one does but in
+/// O(n log n) time on average rather than O(n²), by finding each with a [`KdTree`].
+///
+/// Distances are planar, so this is the same route as the beam for Euclidean
+/// problems, ties aside.
+pub fn nearest_neighbor(problem: &Problem) -> Route {
+    let mut tree = KdTree::new(problem.points.clone());
+    Route::new(nearest_neighbor_tour(&mut tree, problem.start, problem.end))
+}
+
+/// The stops of the nearest-neighbor route from `start` through every point of `tree`
+/// to `end`, removing them all from the tree.
+pub(crate) fn nearest_neighbor_tour(tree: &mut KdTree, start: usize, end: usize) -> Vec<usize> {
+    tree.remove(start);
+    tree.remove(end);
+    let mut stops = vec![start];
+    let mut last = start;
+    while let Some(&next) = tree.nearest(&tree.points()[last], 1).first() {
+        tree.remove(next);
+        stops.push(next);
+        last = next;
+    }
+    stops.push(end);
+    stops
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{rng::SplitMix64, Metric, Point};
+
+    use super::*;
+
+    #[test]
+    fn test_nearest_neighbor() {
+        // The nearest destination each time leads out along the line and back
+        let problem = Problem::new(
+            [0.0, 3.0, 1.0, -4.0, 2.0]
+                .map(|x| Point::new(x, 0.0))
+                .to_vec(),
+            Metric::Euclidean,
+        );
+        assert_eq!(beam_search(&problem, 1).stops(), &[0, 2, 4, 1, 3, 0]);
+        assert_eq!(beam_search(&problem, 0), beam_search(&problem, 1));
+
+        let single = Problem::new(vec![Point::new(1.0, 1.0)], Metric::Euclidean);
+        assert_eq!(beam_search(&single, 4).stops(), &[0, 0]);
+
+        assert_eq!(nearest_neighbor(&problem), beam_search(&problem, 1));
+        assert_eq!(nearest_neighbor(&single).stops(), &[0, 0]);
+        let mut rng = SplitMix64::new(13);
+        let mut problem = Problem::new(
+            (0..200)
+                .map(|_| Point::new(rng.unit() * 100.0, rng.unit() * 100.0))
+                .collect(),
+            Metric::Euclidean,
+        );
+        assert_eq!(nearest_neighbor(&problem), beam_search(&problem, 1));
+        problem.end = 7;
+        assert_eq!(nearest_neighbor(&problem), beam_search(&problem, 1));
+    }
+
+    #[test]
+    fn test_wide_beam_is_exhaustive() {
+        let mut rng = SplitMix64::new(5);
+        for trial in 0..10 {
+            let mut problem = Problem::new(
+                (0..8)
+                    .map(|_| Point::new(rng.below(100) as f64, rng.below(100) as f64))
+                    .collect(),
+                Metric::Euclidean,
+            );
+            if trial % 2 == 1 {
+                problem.end = 3;
+            }
+            let optimal = problem.route_distance(&problem.solve());
+
+            // 7! partial routes is enough for 7 destinations between start and end
+            let route = beam_search(&problem, 5_040);
+            assert!((problem.route_distance(&route) - optimal).abs() < 1e-9);
+            let narrow = beam_search(&problem, 4);
+            assert!(problem.route_distance(&narrow) >= optimal - 1e-9);
+        }
+    }
+}
@@ -0,0 +1,539 @@
+//! Command line interface for solving route files.
+//!
+//! ```text
+//! tsp solve stops.csv --metric haversine --start depot
+//! tsp solve problem.json --out solution.json
+//! tsp validate berlin52.tsp --tour berlin52.opt.tour
+//! tsp convert stops.csv stops.tsp
+//! tsp bench stops.csv --runs 20 --config solver.toml
+//! tsp bench --sizes 6,8,10 --solvers all --seed 42 --out results.csv
+//! tsp estimate stops.csv --algorithm held-karp
+//! ```
+
+use std::{
+    error::Error,
+    fs::{self, File},
+    io::{BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+    process::ExitCode,
+    time::{Duration, Instant},
+};
+
+use clap::{
+    builder::{PossibleValue, PossibleValuesParser, TypedValueParser},
+    Args, Parser, Subcommand, ValueEnum,
+};
+use coding_compairson::{
+    background::SolverConfig,
+    difficulty,
+    generators::{self, Bounds},
+    json::SolutionDocument,
+    registry::{self, Registered},
+    report::{self, SolverResult},
+    tsplib::{self, Tour},
+    Metric, Problem, Route,
+};
+use itertools::Itertools;
+
+#[derive(Parser)]
+#[command(
+    name = "tsp",
+    about = "Solve traveling salesman problems from route files"
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Find the shortest route and print it with its distance
+    Solve {
+        #[command(flatten)]
+        input: Input,
+        #[command(flatten)]
+        solver: Solver,
+        /// Also write the route, as a TSPLIB .tour file or a .json solution
+        #[arg(long)]
+        out: Option<PathBuf>,
+    },
+    /// Check that a problem file (and optionally a tour for it) is well formed
+    Validate {
+        #[command(flatten)]
+        input: Input,
+        /// A TSPLIB .tour file to check against the problem
+        #[arg(long)]
+        tour: Option<PathBuf>,
+    },
+    /// Convert a problem file to another format
+    Convert {
+        #[command(flatten)]
+        input: Input,
+        /// Where to write the converted problem
+        output: PathBuf,
+        /// Output format; guessed from the output extension when omitted
+        #[arg(long, value_enum)]
+        to: Option<Format>,
+    },
+    /// Time the solver on a problem file, or compare solvers on random instances
+    Bench {
+        #[command(flatten)]
+        input: Option<Input>,
+        #[command(flatten)]
+        solver: Solver,
+        /// How many times to solve the problem
+        #[arg(long, default_value_t = 10)]
+        runs: usize,
+        /// Benchmark random instances with these numbers of destinations, including the
+        /// start, instead of a problem file
+        #[arg(
+            long,
+            value_delimiter = ',',
+            conflicts_with = "path",
+            required_unless_present = "path"
+        )]
+        sizes: Vec<usize>,
+        /// The solvers compared on the random instances: `all`, or their names
+        #[arg(
+            long,
+            value_delimiter = ',',
+            default_value = "all",
+            conflicts_with = "algorithm"
+        )]
+        solvers: Vec<String>,
+        /// Seeds the random instances and the solvers run on them
+        #[arg(long, default_value_t = 0)]
+        seed: u64,
+        /// Also write every run on the random instances as CSV, with the columns
+        /// `solver,instance,distance,time_seconds,gap`
+        #[arg(long, requires = "sizes")]
+        out: Option<PathBuf>,
+    },
+    /// Estimate how hard the problem is and which algorithm suits it, without solving it
+    Estimate {
+        #[command(flatten)]
+        input: Input,
+        #[command(flatten)]
+        solver: Solver,
+    },
+}
+
+#[derive(Args)]
+struct Input {
+    /// The problem file (.csv, .tsp or .json)
+    path: PathBuf,
+    /// Input format; guessed from the file extension when omitted
+    #[arg(long, value_enum)]
+    format: Option<Format>,
+    /// How distances are measured for CSV input (TSPLIB files name their own)
+    #[arg(long, value_enum, default_value_t = MetricArg::Euclidean)]
+    metric: MetricArg,
+}
+
+#[derive(Args)]
+struct Solver {
+    /// The algorithm used to find the route [default: the one in the configuration,
+    /// or exhaustive]
+    #[arg(long, value_parser = algorithm_parser())]
+    algorithm: Option<&'static Registered>,
+    /// A .toml or .json file of solver settings, such as a time limit and seed
+    #[arg(long)]
+    config: Option<PathBuf>,
+    /// The settings read from `config`
+    #[arg(skip)]
+    settings: SolverConfig,
+    /// Id of the destination the route starts at (defaults to the first one)
+    #[arg(long)]
+    start: Option<String>,
+    /// Id of the destination the route ends at (defaults to the end a .json problem
+    /// names, or else the start)
+    #[arg(long)]
+    end: Option<String>,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Format {
+    /// `id,x,y` or `id,lat,lon` rows
+    Csv,
+    /// A TSPLIB .tsp file with node coordinates
+    Tsplib,
+    /// A JSON problem document
+    Json,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum MetricArg {
+    Euclidean,
+    Manhattan,
+    Haversine,
+    /// Euclidean rounded to the nearest integer, as TSPLIB's EUC_2D
+    RoundedEuclidean,
+}
+
+impl From<MetricArg> for Metric {
+    fn from(metric: MetricArg) -> Self {
+        match metric {
+            MetricArg::Euclidean => Metric::Euclidean,
+            MetricArg::Manhattan => Metric::Manhattan,
+            MetricArg::Haversine => Metric::Haversine,
+            MetricArg::RoundedEuclidean => Metric::RoundedEuclidean,
+        }
+    }
+}
+
+type Result<T> = std::result::Result<T, Box<dyn Error>>;
+
+fn main() -> ExitCode {
+    match run(Cli::parse().command) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("error: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(command: Command) -> Result<()> {
+    match command {
+        Command::Solve {
+            input,
+            mut solver,
+            out,
+        } => {
+            let problem = solver.configure(input.load()?)?;
+            solver.warn_if_impractical(&problem);
+            let route = solver.solve(&problem);
+
+            let ids = route.iter().map(|&i| problem.ids[i].as_str());
+            println!("route: {}", ids.collect::<Vec<_>>().join(" -> "));
+            println!("distance: {}", problem.route_distance(&route));
+
+            if let Some(out) = out {
+                let mut writer = BufWriter::new(File::create(&out)?);
+                if out.extension().is_some_and(|ext| ext == "json") {
+                    let solution = SolutionDocument::new(&problem, &route);
+                    serde_json::to_writer_pretty(&mut writer, &solution)?;
+                } else {
+                    let mut nodes = route.into_stops();
+                    // A tour is implicitly closed, so the return to the start is not listed
+                    if problem.start == problem.end {
+                        nodes.pop();
+                    }
+                    tsplib::write_tour(&mut writer, &Tour::new(file_stem(&out), nodes))?;
+                }
+                writer.flush()?;
+            }
+        }
+        Command::Validate { input, tour } => {
+            let problem = input.load()?;
+            println!("{}: {} destinations", input.path.display(), problem.len());
+
+            if let Some(path) = tour {
+                let tour = tsplib::read_tour(BufReader::new(File::open(&path)?))?;
+                let mut seen = vec![false; problem.len()];
+                for &node in &tour.nodes {
+                    match seen.get_mut(node) {
+                        Some(seen) if !*seen => *seen = true,
+                        Some(_) => return Err(format!("node {} is visited twice", node + 1).into()),
+                        None => {
+                            return Err(format!("node {} is not in the problem", node + 1).into())
+                        }
+                    }
+                }
+                if let Some(missing) = seen.iter().position(|seen| !seen) {
+                    return Err(format!("node {} is never visited", missing + 1).into());
+                }
+
+                let mut closed = tour.nodes.clone();
+                closed.extend(tour.nodes.first());
+                println!(
+                    "{}: valid tour of length {}",
+                    path.display(),
+                    problem.route_distance(&closed)
+                );
+            }
+        }
+        Command::Convert { input, output, to } => {
+            let problem = input.load()?;
+            let mut writer = BufWriter::new(File::create(&output)?);
+            match to.map_or_else(|| Format::guess(&output), Ok)? {
+                Format::Csv => problem.write_csv(&mut writer)?,
+                Format::Tsplib => {
+                    tsplib::write_problem(&mut writer, &file_stem(&output), &problem)?
+                }
+                Format::Json => writer.write_all(problem.to_json().as_bytes())?,
+            }
+            writer.flush()?;
+        }
+        Command::Bench {
+            input: None,
+            mut solver,
+            runs,
+            sizes,
+            solvers,
+            seed,
+            out,
+        } => {
+            solver.configure_settings()?;
+            let results = bench_sizes(&sizes, &solvers, seed, runs, &solver.settings)?;
+            for (instance, name) in results
+                .iter()
+                .map(|result| (&result.instance, &result.solver))
+                .dedup()
+            {
+                let runs = results
+                    .iter()
+                    .filter(|result| (&result.instance, &result.solver) == (instance, name))
+                    .collect::<Vec<_>>();
+                let total = runs.iter().map(|result| result.time).sum::<Duration>();
+                let gap = runs
+                    .iter()
+                    .filter_map(|result| result.gap)
+                    .fold(0.0, f64::max);
+                println!(
+                    "{instance} {name}: mean {:?}, distance {}, gap {:.2}%",
+                    total / runs.len() as u32,
+                    runs[0].distance,
+                    gap * 100.0
+                );
+            }
+            if let Some(out) = out {
+                let mut writer = BufWriter::new(File::create(&out)?);
+                report::write_csv(&mut writer, &results)?;
+                writer.flush()?;
+            }
+        }
+        Command::Bench {
+            input: Some(input),
+            mut solver,
+            runs,
+            ..
+        } => {
+            let problem = solver.configure(input.load()?)?;
+            solver.warn_if_impractical(&problem);
+
+            let mut times = Vec::with_capacity(runs);
+            let mut route = Route::default();
+            for _ in 0..runs.max(1) {
+                let started = Instant::now();
+                route = solver.solve(&problem);
+                times.push(started.elapsed());
+            }
+
+            let total = times.iter().sum::<Duration>();
+            let min = times.iter().min().copied().unwrap_or_default();
+            println!("destinations: {}", problem.len());
+            println!("runs: {}", times.len());
+            println!("mean: {:?}", total / times.len() as u32);
+            println!("min: {min:?}");
+            println!("distance: {}", problem.route_distance(&route));
+        }
+        Command::Estimate { input, mut solver } => {
+            let problem = solver.configure(input.load()?)?;
+            let difficulty = difficulty::estimate_difficulty(&problem);
+            println!("destinations: {}", difficulty.destinations);
+            println!(
+                "exhaustive search: {:.1e} partial routes",
+                difficulty.exhaustive_nodes
+            );
+            println!("held-karp: {:.1e} subproblems", difficulty.held_karp_states);
+            match difficulty.expected_work(solver.algorithm()) {
+                Some(work) => println!(
+                    "{}: about {work:.1e} distance computations",
+                    solver.algorithm().name
+                ),
+                None => println!(
+                    "{}: limited by its iteration budget",
+                    solver.algorithm().name
+                ),
+            }
+            println!("recommended: {}", difficulty.recommended.name);
+        }
+    }
+    Ok(())
+}
+
+impl Input {
+    fn load(&self) -> Result<Problem> {
+        let reader = || File::open(&self.path).map(BufReader::new);
+        let problem = match self.format.map_or_else(|| Format::guess(&self.path), Ok)? {
+            Format::Csv => Problem::from_csv(reader()?, self.metric.into())?,
+            Format::Tsplib => tsplib::read_problem(reader()?)?,
+            Format::Json => Problem::from_json(&fs::read_to_string(&self.path)?)?,
+        };
+        Ok(problem)
+    }
+}
+
+impl Solver {
+    /// Reads the settings file, if any.
+    fn configure_settings(&mut self) -> Result<()> {
+        if let Some(path) = &self.config {
+            let text = fs::read_to_string(path)?;
+            self.settings = if path.extension().is_some_and(|ext| ext == "json") {
+                SolverConfig::from_json(&text)?
+            } else {
+                SolverConfig::from_toml(&text)?
+            };
+        }
+        Ok(())
+    }
+
+    /// Reads the settings file, if any, and applies the requested start and end to
+    /// `problem`.
+    fn configure(&mut self, mut problem: Problem) -> Result<Problem> {
+        self.configure_settings()?;
+        let find = |id: &str| {
+            problem
+                .ids
+                .iter()
+                .position(|candidate| candidate == id)
+                .ok_or_else(|| format!("no destination has id `{id}`"))
+        };
+        if let Some(start) = &self.start {
+            let start = find(start)?;
+            // A round trip stays one, from the new start
+            if problem.end == problem.start {
+                problem.end = start;
+            }
+            problem.start = start;
+        }
+        if let Some(end) = &self.end {
+            problem.end = find(end)?;
+        }
+        if !self.algorithm().supports(&problem) {
+            return Err(format!(
+                "the {} algorithm does not support this problem",
+                self.algorithm().name
+            )
+            .into());
+        }
+        Ok(problem)
+    }
+
+    /// The algorithm chosen on the command line, or else in the settings.
+    fn algorithm(&self) -> &'static Registered {
+        self.algorithm
+            .or_else(|| self.settings.solver())
+            .unwrap_or_else(|| registry::find("exhaustive").expect("exhaustive is registered"))
+    }
+
+    /// Warns on stderr when the algorithm is not expected to finish in reasonable time,
+    /// suggesting one that is.
+    fn warn_if_impractical(&self, problem: &Problem) {
+        let difficulty = difficulty::estimate_difficulty(problem);
+        if let Some(work) = difficulty
+            .expected_work(self.algorithm())
+            .filter(|&work| work > difficulty::WORK_BUDGET)
+        {
+            eprintln!(
+                "warning: the {} algorithm is expected to take about {work:.1e} distance \
+                 computations on {} destinations; --algorithm {} would be faster",
+                self.algorithm().name,
+                difficulty.destinations,
+                difficulty.recommended.name
+            );
+        }
+    }
+
+    fn solve(&self, problem: &Problem) -> Route {
+        self.algorithm().solve_with(problem, &self.settings)
+    }
+}
+
+/// Times each of `solvers` (or every registered solver, for `all`) `runs` times on a
+/// random round trip of each of `sizes` destinations, skipping those that do not
+/// support an instance or are not expected to finish on it.
+///
+/// Every solver is seeded with `seed`. The gap of each run is relative to the shortest
+/// route any solver found for the instance.
+fn bench_sizes(
+    sizes: &[usize],
+    solvers: &[String],
+    seed: u64,
+    runs: usize,
+    settings: &SolverConfig,
+) -> Result<Vec<SolverResult>> {
+    let solvers = if solvers.iter().any(|name| name == "all") {
+        registry::IMPLEMENTATIONS.iter().collect()
+    } else {
+        solvers
+            .iter()
+            .map(|name| {
+                registry::find(name).ok_or_else(|| format!("no algorithm is named `{name}`"))
+            })
+            .collect::<std::result::Result<Vec<_>, _>>()?
+    };
+
+    let settings = settings.clone().with_seed(seed);
+    let mut results = Vec::new();
+    for (index, &size) in sizes.iter().enumerate() {
+        if size == 0 {
+            return Err("an instance must have at least one destination".into());
+        }
+        let points = generators::uniform_points(
+            size,
+            Bounds::square(1000.0),
+            seed.wrapping_add(index as u64),
+        );
+        let problem = Problem::new(points, Metric::Euclidean);
+        let instance = format!("uniform-{size}");
+        let difficulty = difficulty::estimate_difficulty(&problem);
+        for &registered in &solvers {
+            if !registered.supports(&problem) {
+                continue;
+            }
+            if !difficulty.is_practical(registered) {
+                eprintln!(
+                    "skipping {} on {instance}: it is not expected to finish",
+                    registered.name
+                );
+                continue;
+            }
+            for _ in 0..runs.max(1) {
+                let started = Instant::now();
+                let route = registered.solve_with(&problem, &settings);
+                let time = started.elapsed();
+                results.push(SolverResult::new(
+                    registered.name,
+                    &instance,
+                    problem.route_distance(&route),
+                    time,
+                ));
+            }
+        }
+    }
+    report::fill_gaps(&mut results);
+    Ok(results)
+}
+
+/// Accepts the name of any registered solver, listing them all in the help.
+fn algorithm_parser() -> impl TypedValueParser<Value = &'static Registered> {
+    PossibleValuesParser::new(
+        registry::IMPLEMENTATIONS
+            .iter()
+            .map(|registered| PossibleValue::new(registered.name).help(registered.description)),
+    )
+    .map(|name| registry::find(&name).expect("only registered names are possible"))
+}
+
+impl Format {
+    fn guess(path: &Path) -> Result<Format> {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("csv") => Ok(Format::Csv),
+            Some("tsp") => Ok(Format::Tsplib),
+            Some("json") => Ok(Format::Json),
+            _ => Err(format!(
+                "cannot tell the format of {} from its extension",
+                path.display()
+            )
+            .into()),
+        }
+    }
+}
+
+fn file_stem(path: &Path) -> String {
+    path.file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
@@ -0,0 +1,205 @@
+//! Lower bounds on the length of the shortest route, to tell how far a route could be
+//! from optimal without solving the problem exactly.
+
+use crate::{mst::prim_by, Problem};
+
+/// The most subgradient steps [`lower_bound_one_tree`] takes.
+const ITERATIONS: usize = 1_000;
+/// Steps without improving the bound before the step size is halved.
+const PATIENCE: usize = 20;
+
+/// The length of the shortest path from `from` through every one of `rest` to `to`,
+/// found exactly by Held–Karp, for checking that what a search takes for a lower bound
+/// on it is one.
+#[cfg(feature = "debug-invariants")]
+pub(crate) fn shortest_path_through(
+    from: usize,
+    rest: &[usize],
+    to: usize,
+    distance: impl Fn(usize, usize) -> f64,
+) -> f64 {
+    let path = crate::variants::traveling_salesman_held_karp(
+        rest.iter().copied(),
+        from,
+        to,
+        |(&a, &b)| distance(a, b),
+    );
+    path.windows(2).map(|leg| distance(leg[0], leg[1])).sum()
+}
+
+/// The Held–Karp lower bound: the best Lagrangian relaxation of the route to a
+/// minimum 1-tree, found by subgradient optimization.
+///
+/// A round trip is a spanning tree of every destination but the start, plus two edges
+/// from the start, so the cheapest such 1-tree is no longer than it. Penalizing each
+/// destination for having other than two edges, and adjusting the penalties until the
+/// cheapest 1-tree is nearly a route, makes the bound typically within a few percent of
+/// the optimum. An open route from start to end is already a spanning tree, where the
+/// start and end have one edge each.
+///
+/// # Arguments
+///
+/// * `problem` - The problem to bound, with a symmetric metric
+///
+/// # Returns
+///
+/// A distance no longer than the shortest route
+pub fn lower_bound_one_tree(problem: &Problem) -> f64 {
+    let n = problem.len();
+    let round_trip = problem.start == problem.end;
+    if n < 2 {
+        return 0.0;
+    }
+    if n == 2 && round_trip {
+        // The only route is there and back
+        return 2.0 * problem.distance(0, 1);
+    }
+
+    let matrix = problem.distance_matrix();
+    let targets = (0..n)
+        .map(|i| {
+            if !round_trip && (i == problem.start || i == problem.end) {
+                1.0
+            } else {
+                2.0
+            }
+        })
+        .collect::<Vec<_>>();
+    // Visiting in index order is a route, so the optimum is no longer
+    let upper = problem.route_distance(
+        &[
+            vec![problem.start],
+            problem.inner_destinations(),
+            vec![problem.end],
+        ]
+        .concat(),
+    );
+
+    let mut penalties = vec![0.0; n];
+    let mut best = f64::NEG_INFINITY;
+    let mut scale = 2.0;
+    let mut since_improved = 0;
+    for _ in 0..ITERATIONS {
+        let weight = |a: usize, b: usize| matrix.get(a, b) + penalties[a] + penalties[b];
+        let edges = if round_trip {
+            one_tree(n, problem.start, weight)
+        } else {
+            prim_by(n, weight)
+        };
+
+        let mut degrees = vec![0.0; n];
+        let mut bound = 0.0;
+        for &(a, b) in &edges {
+            degrees[a] += 1.0;
+            degrees[b] += 1.0;
+            bound += weight(a, b);
+        }
+        bound -= penalties
+            .iter()
+            .zip(&targets)
+            .map(|(penalty, target)| penalty * target)
+            .sum::<f64>();
+
+        if bound > best {
+            best = bound;
+            since_improved = 0;
+        } else {
+            since_improved += 1;
+            if since_improved == PATIENCE {
+                scale /= 2.0;
+                since_improved = 0;
+            }
+        }
+
+        let subgradient = degrees
+            .iter()
+            .zip(&targets)
+            .map(|(degree, target)| degree - target)
+            .collect::<Vec<_>>();
+        let norm = subgradient.iter().map(|g| g * g).sum::<f64>();
+        if norm == 0.0 || scale < 1e-6 || best >= upper {
+            // With every degree on target, the 1-tree is a route and so optimal
+            break;
+        }
+        let step = scale * (upper - bound).max(upper * 1e-3) / norm;
+        for (penalty, g) in penalties.iter_mut().zip(&subgradient) {
+            *penalty += step * g;
+        }
+    }
+    best.min(upper)
+}
+
+/// The edges of a minimum 1-tree: a minimum spanning tree of every destination but
+/// `root`, plus the two shortest edges from `root`.
+fn one_tree(n: usize, root: usize, weight: impl Fn(usize, usize) -> f64) -> Vec<(usize, usize)> {
+    let others = (0..n).filter(|&i| i != root).collect::<Vec<_>>();
+    let mut edges = prim_by(others.len(), |a, b| weight(others[a], others[b]))
+        .into_iter()
+        .map(|(a, b)| (others[a], others[b]))
+        .collect::<Vec<_>>();
+
+    let mut nearest = others.clone();
+    nearest.sort_by(|&a, &b| weight(root, a).total_cmp(&weight(root, b)));
+    edges.extend(nearest.into_iter().take(2).map(|other| (root, other)));
+    edges
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{rng::SplitMix64, Metric, Point};
+
+    use super::*;
+
+    #[test]
+    fn test_lower_bound_one_tree() {
+        let square = Problem::new(
+            vec![
+                Point::new(0.0, 0.0),
+                Point::new(2.0, 2.0),
+                Point::new(2.0, 0.0),
+                Point::new(0.0, 2.0),
+            ],
+            Metric::Euclidean,
+        );
+        assert!((lower_bound_one_tree(&square) - 8.0).abs() < 1e-9);
+
+        let mut line = Problem::new(
+            [3.0, 0.0, 4.0, 1.0, 2.0]
+                .map(|x| Point::new(x, 0.0))
+                .to_vec(),
+            Metric::Euclidean,
+        );
+        line.start = 1;
+        line.end = 2;
+        assert!((lower_bound_one_tree(&line) - 4.0).abs() < 1e-9);
+
+        assert_eq!(
+            lower_bound_one_tree(&Problem::new(vec![], Metric::Euclidean)),
+            0.0
+        );
+        let pair = Problem::new(
+            vec![Point::new(0.0, 0.0), Point::new(3.0, 4.0)],
+            Metric::Euclidean,
+        );
+        assert_eq!(lower_bound_one_tree(&pair), 10.0);
+    }
+
+    #[test]
+    fn test_lower_bound_is_below_optimal() {
+        let mut rng = SplitMix64::new(7);
+        for trial in 0..20 {
+            let points = (0..8)
+                .map(|_| Point::new(rng.below(100) as f64, rng.below(100) as f64))
+                .collect();
+            let mut problem = Problem::new(points, Metric::Euclidean);
+            if trial % 2 == 1 {
+                problem.end = 5;
+            }
+
+            let optimal = problem.route_distance(&problem.solve());
+            let bound = lower_bound_one_tree(&problem);
+            assert!(bound <= optimal + 1e-9, "{bound} > {optimal}");
+            assert!(bound > 0.8 * optimal, "{bound} is loose for {optimal}");
+        }
+    }
+}
@@ -0,0 +1,235 @@
+//! Branch and bound that may stop short of optimal by a chosen factor, trading a
+//! little quality for a search that finishes far sooner, while still proving how far
+//! from optimal its route can be.
+
+use crate::{
+    bound::lower_bound_one_tree,
+    christofides::christofides,
+    local_search::{Neighborhood, TwoOpt},
+    DistanceMatrix, Problem, Route,
+};
+
+/// A route from [`branch_and_bound`] with a proof of how close it is to optimal.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BoundedSolution {
+    pub route: Route,
+    pub distance: f64,
+    /// A distance no longer than the shortest route.
+    pub lower_bound: f64,
+    /// The partial routes the search extended.
+    pub nodes: u64,
+}
+
+impl BoundedSolution {
+    /// How much longer than optimal the route can be at most, relatively, e.g. `0.05`
+    /// for up to 5% longer.
+    pub fn gap(&self) -> f64 {
+        if self.distance <= self.lower_bound {
+            0.0
+        } else {
+            self.distance / self.lower_bound - 1.0
+        }
+    }
+}
+
+/// Finds a route proven no longer than `1 + epsilon` times the shortest, by depth-first
+/// branch and bound.
+///
+/// The search starts from [`christofides`] improved by 2-opt and stops as soon as the
+/// route is within the factor of [`lower_bound_one_tree`]. Otherwise it extends partial
+/// routes nearest destination first, pruning each whose distance plus the cheapest leg
+/// out of every destination left could not beat the route by the factor. With an
+/// `epsilon` of zero it is exact, and larger ones prune far more.
+///
+/// # Arguments
+///
+/// * `problem` - The problem to solve, with a symmetric metric
+/// * `epsilon` - How much longer than optimal, relatively, the route may be
+///
+/// # Returns
+///
+/// The route, its distance, and a lower bound on the optimum no more than a factor of
+/// `1 + epsilon` shorter
+///
+/// # Panics
+///
+/// Panics if `epsilon` is negative or not a number.
+pub fn branch_and_bound(problem: &Problem, epsilon: f64) -> BoundedSolution {
+    assert!(
+        epsilon >= 0.0,
+        "epsilon must not be negative, not {epsilon}"
+    );
+    let matrix = problem.distance_matrix();
+    let mut route = christofides(problem);
+    TwoOpt.improve(&mut route, &matrix);
+    let distance = matrix.route_distance(&route);
+    let root_bound = lower_bound_one_tree(problem).min(distance);
+
+    let mut search = Search {
+        matrix: &matrix,
+        end: problem.end,
+        factor: 1.0 + epsilon,
+        root_bound,
+        stops: vec![problem.start],
+        best: route.into_stops(),
+        best_distance: distance,
+        pruned_bound: f64::INFINITY,
+        nodes: 0,
+    };
+    let stopped = search.is_good_enough() || {
+        let mut rest = problem.inner_destinations();
+        search.extend(&mut rest, 0.0)
+    };
+
+    // A search that ran to the end examined every route or a partial route below it,
+    // while one that stopped early has only the root's bound
+    let lower_bound = if stopped {
+        root_bound
+    } else {
+        let searched_bound = search.best_distance.min(search.pruned_bound);
+        root_bound.max(searched_bound).min(search.best_distance)
+    };
+    BoundedSolution {
+        route: Route::new(search.best),
+        distance: search.best_distance,
+        lower_bound,
+        nodes: search.nodes,
+    }
+}
+
+/// The state of the depth-first search.
+struct Search<'a> {
+    matrix: &'a DistanceMatrix,
+    end: usize,
+    factor: f64,
+    root_bound: f64,
+    stops: Vec<usize>,
+    best: Vec<usize>,
+    best_distance: f64,
+    /// The least bound of any partial route pruned, below which none of their
+    /// completions are.
+    pruned_bound: f64,
+    nodes: u64,
+}
+
+impl Search<'_> {
+    /// Whether the best route is already proven within the factor of optimal.
+    fn is_good_enough(&self) -> bool {
+        self.best_distance <= self.factor * self.root_bound
+    }
+
+    /// Tries the orders of `rest` after the stops so far, `partial` long, returning
+    /// `true` once the best route is good enough to stop. `rest` is left as it was.
+    fn extend(&mut self, rest: &mut Vec<usize>, partial: f64) -> bool {
+        self.nodes += 1;
+        let last = self.stops[self.stops.len() - 1];
+        if rest.is_empty() {
+            let distance = partial + self.matrix.get(last, self.end);
+            if distance < self.best_distance {
+                self.best = [&self.stops[..], &[self.end]].concat();
+                self.best_distance = distance;
+                return self.is_good_enough();
+            }
+            return false;
+        }
+
+        let bound = partial + self.remaining_bound(last, rest);
+        if bound * self.factor >= self.best_distance {
+            invariant!(
+                bound
+                    <= partial
+                        + crate::bound::shortest_path_through(last, rest, self.end, |a, b| {
+                            self.matrix.get(a, b)
+                        })
+                        + 1e-9 * bound.abs().max(1.0),
+                "a pruned partial route has a completion shorter than its bound"
+            );
+            self.pruned_bound = self.pruned_bound.min(bound);
+            return false;
+        }
+
+        let mut order = rest.clone();
+        order.sort_by(|&a, &b| {
+            self.matrix
+                .get(last, a)
+                .total_cmp(&self.matrix.get(last, b))
+        });
+        for next in order {
+            let index = rest.iter().position(|&stop| stop == next).unwrap();
+            rest.swap_remove(index);
+            self.stops.push(next);
+            let done = self.extend(rest, partial + self.matrix.get(last, next));
+            self.stops.pop();
+            rest.push(next);
+            let moved = rest.len() - 1;
+            rest.swap(index, moved);
+            if done {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// A lower bound on the rest of a route from `last` through `rest` to the end: each
+    /// of them leaves by one leg, to another of `rest` or the end.
+    fn remaining_bound(&self, last: usize, rest: &[usize]) -> f64 {
+        std::iter::once(last)
+            .chain(rest.iter().copied())
+            .map(|from| {
+                rest.iter()
+                    .chain([&self.end])
+                    .filter(|&&to| to != from)
+                    .map(|&to| self.matrix.get(from, to))
+                    .fold(f64::INFINITY, f64::min)
+            })
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        generators::{self, Bounds},
+        Metric,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_branch_and_bound() {
+        for seed in 0..4 {
+            let mut problem = Problem::new(
+                generators::uniform_points(10, Bounds::square(100.0), seed),
+                Metric::Euclidean,
+            );
+            if seed % 2 == 1 {
+                problem.end = 3;
+            }
+            let optimal = problem.route_distance(&problem.solve());
+
+            let exact = branch_and_bound(&problem, 0.0);
+            assert!(problem.is_route(&exact.route));
+            assert!((exact.distance - optimal).abs() < 1e-9);
+            assert_eq!(exact.distance, problem.route_distance(&exact.route));
+            assert_eq!(exact.gap(), 0.0);
+
+            let bounded = branch_and_bound(&problem, 0.1);
+            assert!(problem.is_route(&bounded.route));
+            assert!(bounded.distance <= 1.1 * optimal + 1e-9);
+            assert!(bounded.lower_bound <= optimal + 1e-9);
+            assert!(bounded.gap() <= 0.1 + 1e-9);
+            assert!(bounded.nodes <= exact.nodes);
+        }
+    }
+
+    #[test]
+    fn test_branch_and_bound_tiny() {
+        let problem = Problem::new(
+            generators::uniform_points(2, Bounds::square(10.0), 1),
+            Metric::Euclidean,
+        );
+        let solution = branch_and_bound(&problem, 0.0);
+        assert_eq!(solution.route, Route::new(vec![0, 1, 0]));
+        assert_eq!(solution.lower_bound, solution.distance);
+    }
+}
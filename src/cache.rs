@@ -0,0 +1,830 @@
+//! Memoized functions beyond [`cached_fn`](crate::cached_fn): one whose cache can be
+//! inspected and pruned, for tests and debugging tools that need to see what was cached;
+//! one whose results expire and are refreshed ahead of time, for values such as live
+//! travel times that go stale; and one shared between threads, for `par_iter` pipelines.
+
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    hash::{BuildHasher, Hash, RandomState},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, MutexGuard, PoisonError, TryLockError,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+/// A function that remembers its results, as [`cached_fn`](crate::cached_fn) makes, but
+/// as a value rather than a closure, so its cache can be examined.
+pub struct CachedFn<Input, Output, F> {
+    f: F,
+    cache: RefCell<HashMap<Input, Output>>,
+}
+
+impl<Input, Output, F> CachedFn<Input, Output, F>
+where
+    Input: Hash + Eq + Clone,
+    Output: Clone,
+    F: Fn(Input) -> Output,
+{
+    pub fn new(f: F) -> Self {
+        CachedFn {
+            f,
+            cache: RefCell::new(HashMap::new()),
+        }
+    }
+
+    /// A cache with room for `capacity` results before it reallocates, so filling it
+    /// with a known number of results, such as every pair of distances, never rehashes.
+    pub fn with_capacity(f: F, capacity: usize) -> Self {
+        CachedFn {
+            f,
+            cache: RefCell::new(HashMap::with_capacity(capacity)),
+        }
+    }
+
+    /// The result of the function for `input`, computed now if it is not cached.
+    pub fn call(&self, input: Input) -> Output {
+        if let Some(result) = self.cache.borrow().get(&input) {
+            return result.clone();
+        }
+        // The cache is not borrowed while `f` runs, so `f` may inspect it
+        let result = (self.f)(input.clone());
+        self.cache.borrow_mut().insert(input, result.clone());
+        result
+    }
+
+    /// The result of the function for `input`, as [`call`](CachedFn::call) gives, but
+    /// recomputed on a cache hit to check that the cached result is still the
+    /// function's, such as after [`load`](CachedFn::load)ing a snapshot.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the cached result differs from the recomputed one, by `==`, so also if
+    /// either is NaN.
+    pub fn call_checked(&self, input: Input) -> Output
+    where
+        Output: PartialEq,
+    {
+        let cached = self.cache.borrow().get(&input).cloned();
+        match cached {
+            Some(result) => {
+                assert!(
+                    (self.f)(input) == result,
+                    "the cached result differs from the function's, was a stale one loaded?"
+                );
+                result
+            }
+            None => self.call(input),
+        }
+    }
+
+    /// The cached result for `input`, without computing it if there is none.
+    pub fn get(&self, input: &Input) -> Option<Output> {
+        self.cache.borrow().get(input).cloned()
+    }
+
+    /// The number of results cached.
+    pub fn len(&self) -> usize {
+        self.cache.borrow().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cache.borrow().is_empty()
+    }
+
+    /// A snapshot of every cached input and its result, in no particular order. Calls
+    /// made while iterating do not change it.
+    pub fn iter(&self) -> impl Iterator<Item = (Input, Output)> {
+        let entries = self
+            .cache
+            .borrow()
+            .iter()
+            .map(|(input, output)| (input.clone(), output.clone()))
+            .collect::<Vec<_>>();
+        entries.into_iter()
+    }
+
+    /// A snapshot of every cached input, in no particular order.
+    pub fn keys(&self) -> impl Iterator<Item = Input> {
+        let keys = self.cache.borrow().keys().cloned().collect::<Vec<_>>();
+        keys.into_iter()
+    }
+
+    /// Forgets every cached result for which `keep` returns false, so it is computed
+    /// again the next time it is needed.
+    pub fn retain(&self, mut keep: impl FnMut(&Input, &Output) -> bool) {
+        self.cache
+            .borrow_mut()
+            .retain(|input, output| keep(input, output));
+    }
+
+    /// Forgets every cached result.
+    pub fn clear(&self) {
+        self.cache.borrow_mut().clear();
+    }
+
+    /// The number of results the cache can hold before it reallocates.
+    pub fn capacity(&self) -> usize {
+        self.cache.borrow().capacity()
+    }
+
+    /// Makes room for at least `additional` more results before the cache reallocates.
+    pub fn reserve(&self, additional: usize) {
+        self.cache.borrow_mut().reserve(additional);
+    }
+
+    /// Frees the room the cache has beyond what its results need, such as after
+    /// [`retain`](CachedFn::retain) forgot most of them.
+    pub fn shrink_to_fit(&self) {
+        self.cache.borrow_mut().shrink_to_fit();
+    }
+}
+
+/// The version of the snapshot format [`CachedFn::save`] writes, raised whenever the
+/// layout changes so snapshots from other versions are refused rather than misread.
+#[cfg(feature = "serde")]
+pub const SNAPSHOT_VERSION: u64 = 1;
+
+/// Marks a JSON document as a cache snapshot.
+#[cfg(feature = "serde")]
+const SNAPSHOT_FORMAT: &str = "coding_compairson-cache";
+
+/// An error produced while saving or loading a cache snapshot.
+#[cfg(feature = "serde")]
+#[derive(Debug)]
+pub enum SnapshotError {
+    Io(std::io::Error),
+    /// The snapshot was not valid JSON, or its entries were not of the cache's types.
+    Json(serde_json::Error),
+    /// The document has no snapshot header, so is not a cache snapshot.
+    NotASnapshot,
+    /// The snapshot was written in another version of the format.
+    Version {
+        found: u64,
+        expected: u64,
+    },
+}
+
+#[cfg(feature = "serde")]
+impl std::fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SnapshotError::Io(err) => err.fmt(f),
+            SnapshotError::Json(err) => write!(f, "invalid snapshot: {err}"),
+            SnapshotError::NotASnapshot => write!(f, "not a cache snapshot"),
+            SnapshotError::Version { found, expected } => write!(
+                f,
+                "the snapshot is version {found} of the format, but only version {expected} \
+                 can be read"
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl std::error::Error for SnapshotError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SnapshotError::Io(err) => Some(err),
+            SnapshotError::Json(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<std::io::Error> for SnapshotError {
+    fn from(err: std::io::Error) -> Self {
+        SnapshotError::Io(err)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<serde_json::Error> for SnapshotError {
+    fn from(err: serde_json::Error) -> Self {
+        SnapshotError::Json(err)
+    }
+}
+
+/// Saving and loading the cache, so results computed in one run, such as distances
+/// from a slow routing service, can be reused by the next.
+///
+/// A snapshot is JSON of the form
+/// `{"format": "coding_compairson-cache", "version": 1, "entries": [[input, output], ...]}`.
+#[cfg(feature = "serde")]
+impl<Input, Output, F> CachedFn<Input, Output, F>
+where
+    Input: Hash + Eq + Clone + serde::Serialize + serde::de::DeserializeOwned,
+    Output: Clone + serde::Serialize + serde::de::DeserializeOwned,
+    F: Fn(Input) -> Output,
+{
+    /// Writes every cached result to `writer` as a snapshot.
+    pub fn save(&self, writer: impl std::io::Write) -> Result<(), SnapshotError> {
+        let cache = self.cache.borrow();
+        let snapshot = serde_json::json!({
+            "format": SNAPSHOT_FORMAT,
+            "version": SNAPSHOT_VERSION,
+            "entries": cache.iter().collect::<Vec<_>>(),
+        });
+        serde_json::to_writer(writer, &snapshot)?;
+        Ok(())
+    }
+
+    /// Adds the results in the snapshot `reader` holds to the cache, replacing any for
+    /// the same inputs.
+    ///
+    /// # Errors
+    ///
+    /// [`SnapshotError::NotASnapshot`] or [`SnapshotError::Version`] if the header does
+    /// not match, checked before any entry is read, and otherwise if the snapshot cannot
+    /// be read or its entries are not of the cache's types. Nothing is added on error.
+    ///
+    /// # Returns
+    ///
+    /// The number of results loaded
+    pub fn load(&self, reader: impl std::io::Read) -> Result<usize, SnapshotError> {
+        let mut snapshot: serde_json::Value = serde_json::from_reader(reader)?;
+        if snapshot.get("format").and_then(|format| format.as_str()) != Some(SNAPSHOT_FORMAT) {
+            return Err(SnapshotError::NotASnapshot);
+        }
+        match snapshot.get("version").and_then(|version| version.as_u64()) {
+            Some(version) if version == SNAPSHOT_VERSION => {}
+            Some(found) => {
+                return Err(SnapshotError::Version {
+                    found,
+                    expected: SNAPSHOT_VERSION,
+                })
+            }
+            None => return Err(SnapshotError::NotASnapshot),
+        }
+        let entries: Vec<(Input, Output)> = serde_json::from_value(snapshot["entries"].take())?;
+        let loaded = entries.len();
+        self.cache.borrow_mut().extend(entries);
+        Ok(loaded)
+    }
+}
+
+/// Runs a refresh somewhere other than the caller's thread.
+pub type Spawner = Box<dyn Fn(Box<dyn FnOnce() + Send>) + Send + Sync>;
+
+/// A memoized function whose results expire `ttl` after they are computed, and are
+/// refreshed ahead of expiry so callers rarely wait for one.
+///
+/// A result accessed within `refresh_within` of expiring is returned as it is, and a
+/// refresh is started in the background, through [`std::thread::spawn`] unless
+/// [`with_spawner`](RefreshAhead::with_spawner) names a thread pool or async runtime.
+/// Only a result that has already expired, or was never computed, is computed while
+/// the caller waits, unless
+/// [`with_stale_while_revalidate`](RefreshAhead::with_stale_while_revalidate) allows
+/// expired results to be served while they are refreshed.
+pub struct RefreshAhead<Input, Output> {
+    shared: Arc<Shared<Input, Output>>,
+    spawn: Spawner,
+    ttl: Duration,
+    refresh_within: Duration,
+    stale_for: Duration,
+}
+
+struct Shared<Input, Output> {
+    f: Box<dyn Fn(&Input) -> Output + Send + Sync>,
+    entries: Mutex<HashMap<Input, Entry<Output>>>,
+}
+
+struct Entry<Output> {
+    value: Output,
+    computed: Instant,
+    /// Whether a refresh has been started and not yet finished.
+    refreshing: bool,
+}
+
+impl<Input, Output> RefreshAhead<Input, Output>
+where
+    Input: Hash + Eq + Clone + Send + Sync + 'static,
+    Output: Clone + Send + 'static,
+{
+    /// Caches `f`, whose results expire after `ttl` and are refreshed when accessed
+    /// within `refresh_within` of expiring.
+    pub fn new(
+        f: impl Fn(&Input) -> Output + Send + Sync + 'static,
+        ttl: Duration,
+        refresh_within: Duration,
+    ) -> Self {
+        RefreshAhead {
+            shared: Arc::new(Shared {
+                f: Box::new(f),
+                entries: Mutex::new(HashMap::new()),
+            }),
+            spawn: Box::new(|refresh| {
+                thread::spawn(refresh);
+            }),
+            ttl,
+            refresh_within,
+            stale_for: Duration::ZERO,
+        }
+    }
+
+    /// Runs refreshes with `spawn`, such as onto a rayon or tokio pool.
+    pub fn with_spawner(
+        mut self,
+        spawn: impl Fn(Box<dyn FnOnce() + Send>) + Send + Sync + 'static,
+    ) -> Self {
+        self.spawn = Box::new(spawn);
+        self
+    }
+
+    /// Serves results for up to `stale_for` after they expire, starting one refresh
+    /// rather than having every caller recompute, so a burst of calls for an expired
+    /// result, such as to a slow upstream distance service, makes one request to it.
+    pub fn with_stale_while_revalidate(mut self, stale_for: Duration) -> Self {
+        self.stale_for = stale_for;
+        self
+    }
+
+    /// The result of the function for `input`: the cached one if it has not expired,
+    /// or is stale but may still be served, starting a refresh if it soon will expire
+    /// or has, and otherwise one computed now.
+    pub fn get(&self, input: Input) -> Output {
+        {
+            let mut entries = self.shared.entries();
+            if let Some(entry) = entries.get_mut(&input) {
+                let age = entry.computed.elapsed();
+                if age < self.ttl.saturating_add(self.stale_for) {
+                    if age.saturating_add(self.refresh_within) >= self.ttl && !entry.refreshing {
+                        entry.refreshing = true;
+                        let shared = Arc::clone(&self.shared);
+                        let input = input.clone();
+                        (self.spawn)(Box::new(move || shared.refresh(input)));
+                    }
+                    return entry.value.clone();
+                }
+            }
+        }
+        // Computed without the lock held, so other inputs are not held up
+        let value = (self.shared.f)(&input);
+        self.shared.store(input, value.clone());
+        value
+    }
+
+    /// The number of results cached, including expired ones not yet replaced.
+    pub fn len(&self) -> usize {
+        self.shared.entries().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<Input: Hash + Eq, Output> Shared<Input, Output> {
+    /// The entries, even if a thread panicked holding them, as every entry is whole.
+    fn entries(&self) -> MutexGuard<'_, HashMap<Input, Entry<Output>>> {
+        self.entries.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn refresh(&self, input: Input) {
+        let value = (self.f)(&input);
+        self.store(input, value);
+    }
+
+    fn store(&self, input: Input, value: Output) {
+        self.entries().insert(
+            input,
+            Entry {
+                value,
+                computed: Instant::now(),
+                refreshing: false,
+            },
+        );
+    }
+}
+
+/// The number of shards in a [`ParCachedFn`], enough that threads on a many-core
+/// machine rarely want the same one.
+const SHARDS: usize = 64;
+
+/// Caches the results of a function called from many threads at once, such as inside
+/// a rayon `par_iter` pipeline, as [`cached_fn`](crate::cached_fn) does for one thread.
+///
+/// The cache is split into shards, each behind its own lock, so threads working on
+/// different inputs seldom wait for each other, and no lock is held while the function
+/// runs.
+///
+/// # Duplicate computation
+///
+/// Threads that miss the same input at the same time each compute it, so an input is
+/// computed at most once per thread racing for it, and never again once cached. The
+/// first result stored is the one every call returns, so callers agree on the value
+/// even if the function does not always return the same one. The calls that found
+/// their result already stored are counted by [`CacheStats::duplicates`].
+pub fn par_cached_fn<Input, Output, F>(f: F) -> ParCachedFn<Input, Output, F>
+where
+    Input: Hash + Eq + Clone,
+    Output: Clone,
+    F: Fn(Input) -> Output + Sync,
+{
+    ParCachedFn {
+        f,
+        hasher: RandomState::new(),
+        shards: (0..SHARDS).map(|_| Mutex::new(HashMap::new())).collect(),
+        hits: AtomicU64::new(0),
+        misses: AtomicU64::new(0),
+        contended: AtomicU64::new(0),
+        duplicates: AtomicU64::new(0),
+    }
+}
+
+/// A function cached for calls from many threads, from [`par_cached_fn`].
+pub struct ParCachedFn<Input, Output, F> {
+    f: F,
+    hasher: RandomState,
+    shards: Box<[Mutex<HashMap<Input, Output>>]>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    contended: AtomicU64,
+    duplicates: AtomicU64,
+}
+
+/// How a [`ParCachedFn`] has been used, to judge whether caching or contention
+/// dominates.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    /// Calls answered from the cache.
+    pub hits: u64,
+    /// Calls that computed their result.
+    pub misses: u64,
+    /// Lock acquisitions that had to wait for another thread.
+    pub contended: u64,
+    /// Misses whose result another thread had stored first, so the computation was
+    /// wasted.
+    pub duplicates: u64,
+}
+
+impl CacheStats {
+    /// The fraction of calls answered from the cache, or zero before any call.
+    pub fn hit_ratio(&self) -> f64 {
+        let calls = self.hits + self.misses;
+        if calls == 0 {
+            0.0
+        } else {
+            self.hits as f64 / calls as f64
+        }
+    }
+}
+
+impl<Input, Output, F> ParCachedFn<Input, Output, F>
+where
+    Input: Hash + Eq + Clone,
+    Output: Clone,
+    F: Fn(Input) -> Output + Sync,
+{
+    /// The result of the function for `input`, from the cache if any thread has already
+    /// computed it.
+    pub fn call(&self, input: Input) -> Output {
+        let shard = &self.shards[self.hasher.hash_one(&input) as usize % self.shards.len()];
+        if let Some(result) = self.lock(shard).get(&input) {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            return result.clone();
+        }
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        let result = (self.f)(input.clone());
+        let mut entries = self.lock(shard);
+        match entries.get(&input) {
+            Some(first) => {
+                self.duplicates.fetch_add(1, Ordering::Relaxed);
+                first.clone()
+            }
+            None => {
+                entries.insert(input, result.clone());
+                result
+            }
+        }
+    }
+
+    /// The number of results cached.
+    pub fn len(&self) -> usize {
+        self.shards.iter().map(|shard| self.lock(shard).len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The calls and contention so far.
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            contended: self.contended.load(Ordering::Relaxed),
+            duplicates: self.duplicates.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Locks `shard`, counting the acquisition as contended if another thread holds it.
+    fn lock<'a>(
+        &self,
+        shard: &'a Mutex<HashMap<Input, Output>>,
+    ) -> MutexGuard<'a, HashMap<Input, Output>> {
+        match shard.try_lock() {
+            Ok(entries) => entries,
+            Err(TryLockError::Poisoned(poisoned)) => poisoned.into_inner(),
+            Err(TryLockError::WouldBlock) => {
+                self.contended.fetch_add(1, Ordering::Relaxed);
+                shard.lock().unwrap_or_else(PoisonError::into_inner)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use super::*;
+
+    #[test]
+    fn test_inspect() {
+        let calls = Cell::new(0);
+        let double = CachedFn::new(|x: i32| {
+            calls.set(calls.get() + 1);
+            x * 2
+        });
+        assert!(double.is_empty());
+        for x in [1, 2, 3, 2, 1] {
+            double.call(x);
+        }
+        assert_eq!(calls.get(), 3);
+        assert_eq!(double.len(), 3);
+        assert_eq!(double.get(&2), Some(4));
+        assert_eq!(double.get(&4), None);
+
+        let mut entries = double.iter().collect::<Vec<_>>();
+        entries.sort();
+        assert_eq!(entries, [(1, 2), (2, 4), (3, 6)]);
+        let mut keys = double.keys().collect::<Vec<_>>();
+        keys.sort();
+        assert_eq!(keys, [1, 2, 3]);
+    }
+
+    #[test]
+    fn test_capacity() {
+        let double = CachedFn::with_capacity(|x: u32| x * 2, 1000);
+        let capacity = double.capacity();
+        assert!(capacity >= 1000);
+        for x in 0..1000 {
+            double.call(x);
+        }
+        assert_eq!(double.capacity(), capacity);
+
+        double.reserve(capacity);
+        assert!(double.capacity() >= 1000 + capacity);
+        double.retain(|&x, _| x < 10);
+        double.shrink_to_fit();
+        assert!(double.capacity() < 1000);
+        assert_eq!(double.len(), 10);
+    }
+
+    #[test]
+    fn test_retain() {
+        let calls = Cell::new(0);
+        let double = CachedFn::new(|x: i32| {
+            calls.set(calls.get() + 1);
+            x * 2
+        });
+        for x in 0..10 {
+            double.call(x);
+        }
+        double.retain(|&x, _| x % 2 == 0);
+        assert_eq!(double.len(), 5);
+        double.call(4);
+        assert_eq!(calls.get(), 10);
+        double.call(5);
+        assert_eq!(calls.get(), 11);
+
+        double.clear();
+        assert!(double.is_empty());
+    }
+
+    #[test]
+    fn test_refresh_ahead() {
+        let calls = Arc::new(Mutex::new(0));
+        let counter = Arc::clone(&calls);
+        // Refreshes queue up here, to be run when the test chooses
+        let queued = Arc::new(Mutex::new(Vec::<Box<dyn FnOnce() + Send>>::new()));
+        let queue = Arc::clone(&queued);
+        let cached = RefreshAhead::new(
+            move |&x: &u32| {
+                let mut calls = counter.lock().unwrap();
+                *calls += 1;
+                x * 100 + *calls
+            },
+            Duration::from_secs(60),
+            Duration::from_secs(60),
+        )
+        .with_spawner(move |refresh| queue.lock().unwrap().push(refresh));
+
+        // The first access computes, and every access after starts at most one refresh
+        assert_eq!(cached.get(1), 101);
+        assert_eq!(cached.get(1), 101);
+        assert_eq!(cached.get(1), 101);
+        assert_eq!(queued.lock().unwrap().len(), 1);
+        assert_eq!(*calls.lock().unwrap(), 1);
+
+        let refresh = queued.lock().unwrap().pop().unwrap();
+        refresh();
+        assert_eq!(cached.get(1), 102);
+        assert_eq!(cached.len(), 1);
+    }
+
+    #[test]
+    fn test_refresh_ahead_expiry() {
+        let calls = Arc::new(Mutex::new(0));
+        let counter = Arc::clone(&calls);
+        let cached = RefreshAhead::new(
+            move |&x: &u32| {
+                *counter.lock().unwrap() += 1;
+                x
+            },
+            Duration::from_millis(20),
+            Duration::ZERO,
+        );
+        assert_eq!(cached.get(7), 7);
+        assert_eq!(cached.get(7), 7);
+        assert_eq!(*calls.lock().unwrap(), 1);
+        // Far from expiring, nothing is refreshed; once expired, it is computed again
+        thread::sleep(Duration::from_millis(40));
+        assert_eq!(cached.get(7), 7);
+        assert_eq!(*calls.lock().unwrap(), 2);
+
+        // Refreshed on a thread of its own by default
+        let cached = RefreshAhead::new(|&x: &u32| x + 1, Duration::from_secs(60), Duration::MAX);
+        assert_eq!(cached.get(1), 2);
+        assert_eq!(cached.get(1), 2);
+    }
+
+    #[test]
+    fn test_par_cached_fn() {
+        let calls = AtomicU64::new(0);
+        let square = par_cached_fn(|x: u64| {
+            calls.fetch_add(1, Ordering::Relaxed);
+            x * x
+        });
+        thread::scope(|scope| {
+            for _ in 0..8 {
+                scope.spawn(|| {
+                    for x in 0..1000 {
+                        assert_eq!(square.call(x % 100), (x % 100) * (x % 100));
+                    }
+                });
+            }
+        });
+        let stats = square.stats();
+        assert_eq!(square.len(), 100);
+        assert_eq!(stats.hits + stats.misses, 8000);
+        assert_eq!(stats.misses, calls.load(Ordering::Relaxed));
+        // Every input is computed once, and again only by threads racing for it
+        assert_eq!(stats.misses - stats.duplicates, 100);
+        assert!(stats.hit_ratio() > 0.9);
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn test_par_cached_fn_in_par_iter() {
+        use rayon::prelude::*;
+
+        let problem = crate::Problem::new(
+            (0..20)
+                .map(|i| crate::Point::new(f64::from(i % 5), f64::from(i / 5)))
+                .collect(),
+            crate::Metric::Euclidean,
+        );
+        let distance = par_cached_fn(|(a, b): (usize, usize)| problem.distance(a, b));
+        let total = (0..20 * 20)
+            .into_par_iter()
+            .map(|pair| {
+                distance.call((pair / 20, pair % 20)) + distance.call((pair / 20, pair % 20))
+            })
+            .sum::<f64>();
+        let expected = (0..20 * 20)
+            .map(|pair| 2.0 * problem.distance(pair / 20, pair % 20))
+            .sum::<f64>();
+        assert!((total - expected).abs() < 1e-9);
+        assert_eq!(distance.len(), 400);
+    }
+
+    #[test]
+    fn test_par_cached_fn_agrees_on_first_result() {
+        // A function that differs call to call, which the cache makes consistent
+        let calls = AtomicU64::new(0);
+        let ticket = par_cached_fn(|_: u8| calls.fetch_add(1, Ordering::Relaxed));
+        let first = ticket.call(0);
+        assert_eq!(ticket.call(0), first);
+        assert_eq!(
+            ticket.stats(),
+            CacheStats {
+                hits: 1,
+                misses: 1,
+                contended: 0,
+                duplicates: 0,
+            }
+        );
+        assert_eq!(CacheStats::default().hit_ratio(), 0.0);
+    }
+
+    #[test]
+    fn test_stale_while_revalidate() {
+        let calls = Arc::new(Mutex::new(0));
+        let counter = Arc::clone(&calls);
+        let queued = Arc::new(Mutex::new(Vec::<Box<dyn FnOnce() + Send>>::new()));
+        let queue = Arc::clone(&queued);
+        let cached = RefreshAhead::new(
+            move |_: &u32| {
+                let mut calls = counter.lock().unwrap();
+                *calls += 1;
+                *calls
+            },
+            Duration::from_millis(20),
+            Duration::ZERO,
+        )
+        .with_stale_while_revalidate(Duration::from_secs(60))
+        .with_spawner(move |refresh| queue.lock().unwrap().push(refresh));
+
+        assert_eq!(cached.get(0), 1);
+        thread::sleep(Duration::from_millis(40));
+        // Every caller gets the stale result at once, and only one refresh is started
+        for _ in 0..5 {
+            assert_eq!(cached.get(0), 1);
+        }
+        assert_eq!(queued.lock().unwrap().len(), 1);
+        assert_eq!(*calls.lock().unwrap(), 1);
+
+        let refresh = queued.lock().unwrap().pop().unwrap();
+        refresh();
+        assert_eq!(cached.get(0), 2);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_snapshot() {
+        let distance = CachedFn::new(|(a, b): (u32, u32)| f64::from(a.abs_diff(b)));
+        distance.call((1, 4));
+        distance.call((2, 9));
+        let mut snapshot = Vec::new();
+        distance.save(&mut snapshot).unwrap();
+
+        let calls = Cell::new(0);
+        let restored = CachedFn::new(|(a, b): (u32, u32)| {
+            calls.set(calls.get() + 1);
+            f64::from(a.abs_diff(b))
+        });
+        assert_eq!(restored.load(snapshot.as_slice()).unwrap(), 2);
+        assert_eq!(restored.call((2, 9)), 7.0);
+        assert_eq!(calls.get(), 0);
+        assert_eq!(restored.call_checked((2, 9)), 7.0);
+        assert_eq!(calls.get(), 1);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    #[should_panic(expected = "the cached result differs")]
+    fn test_stale_snapshot() {
+        let distance = CachedFn::new(|(a, b): (u32, u32)| f64::from(a.abs_diff(b)));
+        let snapshot =
+            r#"{"format": "coding_compairson-cache", "version": 1, "entries": [[[2, 9], 8.0]]}"#;
+        distance.load(snapshot.as_bytes()).unwrap();
+        assert_eq!(distance.call((2, 9)), 8.0);
+        distance.call_checked((2, 9));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_snapshot_errors() {
+        let cache = CachedFn::new(|x: u32| x);
+        let load = |json: &str| cache.load(json.as_bytes());
+        assert!(matches!(
+            load(r#"{"format": "coding_compairson-cache", "version": 0, "entries": 5}"#),
+            Err(SnapshotError::Version {
+                found: 0,
+                expected: SNAPSHOT_VERSION
+            })
+        ));
+        assert!(matches!(load("[[1, 1]]"), Err(SnapshotError::NotASnapshot)));
+        assert!(matches!(
+            load(r#"{"format": "coding_compairson-cache", "entries": []}"#),
+            Err(SnapshotError::NotASnapshot)
+        ));
+        assert!(matches!(
+            load(r#"{"format": "coding_compairson-cache", "version": 1, "entries": [["a", 1]]}"#),
+            Err(SnapshotError::Json(_))
+        ));
+        assert!(matches!(load("{"), Err(SnapshotError::Json(_))));
+        assert!(cache.is_empty());
+        assert_eq!(
+            load(r#"{"format": "coding_compairson-cache", "version": 2, "entries": []}"#)
+                .unwrap_err()
+                .to_string(),
+            "the snapshot is version 2 of the format, but only version 1 can be read"
+        );
+    }
+}
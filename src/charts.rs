@@ -0,0 +1,196 @@
+//! Charts of solver runs drawn with plotters, for experiment write-ups: convergence
+//! curves from [`Trajectory`]s and maps of routes with axes.
+//!
+//! Charts are rendered as SVG, which plotters draws without any system fonts or native
+//! libraries, and returned as a string to write wherever they are needed.
+
+use plotters::prelude::*;
+
+use crate::{events::Trajectory, Point};
+
+/// Width and height of a chart in pixels.
+const SIZE: (u32, u32) = (800, 600);
+/// The colors given to successive curves, repeating after the last.
+const COLORS: [RGBColor; 6] = [
+    RGBColor(0x1f, 0x77, 0xb4),
+    RGBColor(0xd6, 0x27, 0x28),
+    RGBColor(0x2c, 0xa0, 0x2c),
+    RGBColor(0xff, 0x7f, 0x0e),
+    RGBColor(0x94, 0x67, 0xbd),
+    RGBColor(0x8c, 0x56, 0x4b),
+];
+
+/// Renders the best distance against the iteration for each named trajectory, as steps
+/// that hold each distance until the next improvement, with a legend of the names.
+///
+/// # Arguments
+///
+/// * `curves` - Each solver's name and trajectory, such as from
+///   [`iterated_local_search_traced`](crate::ils::iterated_local_search_traced)
+///
+/// # Returns
+///
+/// The chart as an SVG document
+pub fn convergence_chart(curves: &[(&str, &Trajectory)]) -> String {
+    let iterations = curves
+        .iter()
+        .map(|(_, trajectory)| trajectory.iterations)
+        .max()
+        .unwrap_or(0)
+        .max(1);
+    let distances = curves
+        .iter()
+        .flat_map(|(_, trajectory)| trajectory.points.iter().map(|&(_, distance)| distance));
+    let (low, high) = range(distances);
+
+    let mut svg = String::new();
+    {
+        let root = SVGBackend::with_string(&mut svg, SIZE).into_drawing_area();
+        draw(|| {
+            root.fill(&WHITE)?;
+            let mut chart = ChartBuilder::on(&root)
+                .margin(20)
+                .x_label_area_size(40)
+                .y_label_area_size(60)
+                .build_cartesian_2d(0..iterations, low..high)?;
+            chart
+                .configure_mesh()
+                .x_desc("iteration")
+                .y_desc("best distance")
+                .draw()?;
+            for (index, (name, trajectory)) in curves.iter().enumerate() {
+                let color = COLORS[index % COLORS.len()];
+                chart
+                    .draw_series(LineSeries::new(steps(trajectory), color.stroke_width(2)))?
+                    .label(*name)
+                    .legend(move |(x, y)| PathElement::new([(x, y), (x + 20, y)], color));
+            }
+            chart
+                .configure_series_labels()
+                .background_style(WHITE.mix(0.8))
+                .border_style(BLACK)
+                .draw()?;
+            root.present()
+        });
+    }
+    svg
+}
+
+/// Renders `route` (indices into `points`) over the points, on axes in the points' own
+/// coordinates, with the first stop drawn larger so the direction of travel can be
+/// followed from it.
+///
+/// # Arguments
+///
+/// * `route` - The stops of the route in visiting order
+/// * `points` - The location of each destination
+///
+/// # Returns
+///
+/// The chart as an SVG document
+pub fn route_chart(route: &[usize], points: &[Point]) -> String {
+    let (left, right) = range(points.iter().map(|point| point.x));
+    let (bottom, top) = range(points.iter().map(|point| point.y));
+    let color = COLORS[0];
+
+    let mut svg = String::new();
+    {
+        let root = SVGBackend::with_string(&mut svg, SIZE).into_drawing_area();
+        draw(|| {
+            root.fill(&WHITE)?;
+            let mut chart = ChartBuilder::on(&root)
+                .margin(20)
+                .x_label_area_size(40)
+                .y_label_area_size(60)
+                .build_cartesian_2d(left..right, bottom..top)?;
+            chart.configure_mesh().draw()?;
+            chart.draw_series(LineSeries::new(
+                route.iter().map(|&stop| (points[stop].x, points[stop].y)),
+                color.stroke_width(2),
+            ))?;
+            chart.draw_series(
+                points
+                    .iter()
+                    .map(|point| Circle::new((point.x, point.y), 3, BLACK.filled())),
+            )?;
+            if let Some(&first) = route.first() {
+                let point = points[first];
+                chart.draw_series([Circle::new((point.x, point.y), 6, color.filled())])?;
+            }
+            root.present()
+        });
+    }
+    svg
+}
+
+/// The corners of a trajectory's step curve, running on to its last iteration.
+fn steps(trajectory: &Trajectory) -> Vec<(u64, f64)> {
+    let mut steps = Vec::new();
+    for &(iteration, distance) in &trajectory.points {
+        if let Some(&(_, previous)) = steps.last() {
+            steps.push((iteration, previous));
+        }
+        steps.push((iteration, distance));
+    }
+    if let Some(&(last, distance)) = steps.last() {
+        if last < trajectory.iterations {
+            steps.push((trajectory.iterations, distance));
+        }
+    }
+    steps
+}
+
+/// The smallest and largest of `values`, padded by a twentieth of their spread, or of
+/// one if they are all the same, so nothing is drawn on the border.
+fn range(values: impl Iterator<Item = f64>) -> (f64, f64) {
+    let (low, high) = values.fold((f64::INFINITY, f64::NEG_INFINITY), |(low, high), value| {
+        (low.min(value), high.max(value))
+    });
+    if low > high {
+        return (0.0, 1.0);
+    }
+    let padding = if high > low { (high - low) / 20.0 } else { 1.0 };
+    (low - padding, high + padding)
+}
+
+/// Runs the drawing commands of a chart. Drawing into a string fails only on plotters
+/// bugs, so an error panics rather than burdening every caller.
+fn draw<E: std::error::Error>(commands: impl FnOnce() -> Result<(), E>) {
+    if let Err(err) = commands() {
+        panic!("drawing a chart failed: {err}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_convergence_chart() {
+        let mut fast = Trajectory::new();
+        fast.record(0, 100.0);
+        fast.record(2, 60.0);
+        fast.record(10, 60.0);
+        let mut slow = Trajectory::new();
+        slow.record(0, 100.0);
+        slow.record(8, 80.0);
+        let svg = convergence_chart(&[("ils", &fast), ("tempering", &slow)]);
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.contains("tempering"));
+        assert!(svg.contains("best distance"));
+        assert_eq!(
+            steps(&fast),
+            [(0, 100.0), (2, 100.0), (2, 60.0), (10, 60.0)]
+        );
+        assert!(convergence_chart(&[]).starts_with("<svg"));
+    }
+
+    #[test]
+    fn test_route_chart() {
+        let points = [(0.0, 0.0), (3.0, 0.0), (3.0, 4.0)].map(|(x, y)| Point::new(x, y));
+        let svg = route_chart(&[0, 1, 2, 0], &points);
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.contains("<polyline"));
+        assert!(route_chart(&[0, 0], &points[..1]).starts_with("<svg"));
+    }
+}
@@ -0,0 +1,141 @@
+//! Christofides' heuristic, which finds a route at most 3/2 times the optimum for
+//! metric problems in polynomial time.
+
+use crate::{matching::min_weight_perfect_matching, mst, Problem, Route};
+
+/// Finds a route by Christofides' algorithm: a minimum spanning tree, plus a
+/// minimum-weight perfect matching of its odd-degree destinations, makes a graph with
+/// an Euler tour that is shortcut past destinations already visited.
+///
+/// Open routes use Hoogeveen's variant, which matches the destinations whose degree
+/// parity is wrong for an Euler path from start to end, and is within 5/3 of optimal.
+///
+/// # Arguments
+///
+/// * `problem` - The problem to solve, whose metric should obey the triangle inequality
+///
+/// # Returns
+///
+/// A route from `start` through every destination to `end`
+pub fn christofides(problem: &Problem) -> Route {
+    let n = problem.len();
+    if n <= 3 {
+        // Every route is as short as any other, or there is only one
+        return Route::new(
+            [
+                vec![problem.start],
+                problem.inner_destinations(),
+                vec![problem.end],
+            ]
+            .concat(),
+        );
+    }
+
+    let matrix = problem.distance_matrix();
+    let tree = mst::prim(&matrix);
+    let mut odd = tree
+        .degrees(n)
+        .into_iter()
+        .map(|degree| degree % 2 == 1)
+        .collect::<Vec<_>>();
+    if problem.start != problem.end {
+        // An Euler path needs the start and end to have odd degree instead
+        odd[problem.start] = !odd[problem.start];
+        odd[problem.end] = !odd[problem.end];
+    }
+    let odd = (0..n).filter(|&v| odd[v]).collect::<Vec<_>>();
+
+    let mut edges = tree.edges;
+    edges.extend(min_weight_perfect_matching(&matrix, &odd));
+
+    // Shortcut the Euler trail to the first visit of each destination
+    let mut visited = vec![false; n];
+    visited[problem.end] = true;
+    let mut stops = Vec::with_capacity(n + 1);
+    for v in euler_trail(n, &edges, problem.start) {
+        if !visited[v] || (v == problem.start && stops.is_empty()) {
+            visited[v] = true;
+            stops.push(v);
+        }
+    }
+    stops.push(problem.end);
+    Route::new(stops)
+}
+
+/// An Euler trail through every edge of a connected multigraph from `start`, by
+/// Hierholzer's algorithm.
+fn euler_trail(n: usize, edges: &[(usize, usize)], start: usize) -> Vec<usize> {
+    let mut adjacent = vec![Vec::new(); n];
+    for (id, &(a, b)) in edges.iter().enumerate() {
+        adjacent[a].push((b, id));
+        adjacent[b].push((a, id));
+    }
+    let mut used = vec![false; edges.len()];
+
+    let mut stack = vec![start];
+    let mut trail = Vec::with_capacity(edges.len() + 1);
+    while let Some(&v) = stack.last() {
+        match adjacent[v].pop() {
+            Some((_, id)) if used[id] => {}
+            Some((w, id)) => {
+                used[id] = true;
+                stack.push(w);
+            }
+            None => trail.push(stack.pop().expect("the stack is not empty")),
+        }
+    }
+    trail.reverse();
+    trail
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{rng::SplitMix64, Metric, Point};
+
+    use super::*;
+
+    #[test]
+    fn test_christofides() {
+        let square = Problem::new(
+            vec![
+                Point::new(0.0, 0.0),
+                Point::new(2.0, 2.0),
+                Point::new(2.0, 0.0),
+                Point::new(0.0, 2.0),
+            ],
+            Metric::Euclidean,
+        );
+        let route = christofides(&square);
+        assert!(square.is_route(&route));
+        assert_eq!(square.route_distance(&route), 8.0);
+
+        let pair = Problem::new(
+            vec![Point::new(0.0, 0.0), Point::new(1.0, 0.0)],
+            Metric::Euclidean,
+        );
+        assert_eq!(christofides(&pair).stops(), &[0, 1, 0]);
+    }
+
+    #[test]
+    fn test_approximation_ratio() {
+        let mut rng = SplitMix64::new(11);
+        for trial in 0..30 {
+            let points = (0..8)
+                .map(|_| Point::new(rng.below(100) as f64, rng.below(100) as f64))
+                .collect();
+            let mut problem = Problem::new(points, Metric::Euclidean);
+            let ratio = if trial % 2 == 0 {
+                1.5
+            } else {
+                problem.start = 2;
+                problem.end = 6;
+                5.0 / 3.0
+            };
+
+            let route = christofides(&problem);
+            assert!(problem.is_route(&route), "{route:?}");
+            let optimal = problem.route_distance(&problem.solve());
+            assert!(problem.route_distance(&route) <= ratio * optimal + 1e-9);
+        }
+    }
+}
@@ -0,0 +1,225 @@
+//! Cluster-first, route-second decomposition, which solves problems of thousands of
+//! destinations by solving many small ones.
+
+use crate::{kdtree::KdTree, rng::SplitMix64, Point, Problem, Route};
+
+/// The seed for choosing the initial cluster centers, fixed so results are reproducible.
+const SEED: u64 = 0xc1a5_7e25;
+/// The most rounds of reassigning destinations to their nearest center.
+const ROUNDS: usize = 100;
+
+/// Solves a problem by grouping the destinations between start and end into `clusters`
+/// clusters by k-means, choosing the order to visit the clusters by solving the problem
+/// of visiting their centers, then solving each cluster as a route from where the
+/// previous one left off toward the next.
+///
+/// Only one cluster's distances are needed at a time, so memory stays bounded by the
+/// largest cluster. Clustering treats coordinates as planar.
+///
+/// # Arguments
+///
+/// * `problem` - The problem to solve
+/// * `clusters` - The number of clusters, which sets how large each subproblem is
+/// * `solve` - Solves the order of the clusters, and then each cluster
+///
+/// # Returns
+///
+/// A route from `start` through every destination to `end`
+pub fn cluster_first(
+    problem: &Problem,
+    clusters: usize,
+    solve: impl Fn(&Problem) -> Route,
+) -> Route {
+    let inner = problem.inner_destinations();
+    if inner.is_empty() {
+        return Route::new(vec![problem.start, problem.end]);
+    }
+    let groups = k_means(problem, &inner, clusters.clamp(1, inner.len()));
+
+    // The order of the clusters, from solving a problem over the start, end and centroids
+    let centroids = groups
+        .iter()
+        .map(|group| {
+            centroid(group.iter().map(|&i| &problem.points[i])).expect("clusters are not empty")
+        })
+        .collect::<Vec<_>>();
+    let round_trip = problem.start == problem.end;
+    let anchors = if round_trip { 1 } else { 2 };
+    let mut points = vec![problem.points[problem.start]];
+    if !round_trip {
+        points.push(problem.points[problem.end]);
+    }
+    points.extend(&centroids);
+    let mut order_problem = Problem::new(points, problem.metric);
+    order_problem.end = anchors - 1;
+    let order = solve(&order_problem)
+        .stops()
+        .iter()
+        .filter(|&&i| i >= anchors)
+        .map(|&i| i - anchors)
+        .collect::<Vec<_>>();
+
+    let mut stops = vec![problem.start];
+    for (position, &cluster) in order.iter().enumerate() {
+        let group = &groups[cluster];
+        let previous = stops[stops.len() - 1];
+        let next = match order.get(position + 1) {
+            Some(&next) => centroids[next],
+            None => problem.points[problem.end],
+        };
+        let nearest = |to: &Point, except: Option<usize>| {
+            (0..group.len())
+                .filter(|&local| Some(local) != except)
+                .min_by(|&a, &b| {
+                    let distance =
+                        |local: usize| problem.metric.distance(&problem.points[group[local]], to);
+                    distance(a).total_cmp(&distance(b))
+                })
+        };
+        let entry = nearest(&problem.points[previous], None).expect("clusters are not empty");
+        let Some(exit) = nearest(&next, Some(entry)) else {
+            // A single destination
+            stops.push(group[entry]);
+            continue;
+        };
+
+        let mut subproblem = Problem::new(
+            group.iter().map(|&i| problem.points[i]).collect(),
+            problem.metric,
+        );
+        subproblem.start = entry;
+        subproblem.end = exit;
+        stops.extend(solve(&subproblem).stops().iter().map(|&local| group[local]));
+    }
+    stops.push(problem.end);
+    Route::new(stops)
+}
+
+/// Groups `indices` into `k` non-empty clusters by Lloyd's algorithm, starting from
+/// centers chosen by k-means++.
+fn k_means(problem: &Problem, indices: &[usize], k: usize) -> Vec<Vec<usize>> {
+    let point = |i: usize| &problem.points[indices[i]];
+    let squared = |a: &Point, b: &Point| (a.x - b.x).powi(2) + (a.y - b.y).powi(2);
+    let nearest = |centers: &[Point], p: &Point| {
+        (0..centers.len())
+            .min_by(|&a, &b| squared(&centers[a], p).total_cmp(&squared(&centers[b], p)))
+            .expect("there are centers")
+    };
+
+    // k-means++: each center is chosen with probability proportional to its squared
+    // distance from the nearest center so far, which spreads them out
+    let mut rng = SplitMix64::new(SEED);
+    let mut centers = vec![*point(rng.below(indices.len()))];
+    while centers.len() < k {
+        let weights = (0..indices.len())
+            .map(|i| squared(&centers[nearest(&centers, point(i))], point(i)))
+            .collect::<Vec<_>>();
+        let total = weights.iter().sum::<f64>();
+        if total == 0.0 {
+            // Every remaining point coincides with a center
+            break;
+        }
+        let mut target = rng.unit() * total;
+        let chosen = weights
+            .iter()
+            .position(|&weight| {
+                target -= weight;
+                target < 0.0
+            })
+            .unwrap_or(indices.len() - 1);
+        centers.push(*point(chosen));
+    }
+
+    let mut assignment = vec![usize::MAX; indices.len()];
+    for _ in 0..ROUNDS {
+        // Each round finds every point's nearest center in a tree of the centers
+        let tree = KdTree::new(centers.clone());
+        let reassigned = (0..indices.len())
+            .map(|i| tree.nearest(point(i), 1)[0])
+            .collect::<Vec<_>>();
+        if reassigned == assignment {
+            break;
+        }
+        assignment = reassigned;
+        for (cluster, center) in centers.iter_mut().enumerate() {
+            let members = (0..indices.len()).filter(|&i| assignment[i] == cluster);
+            // An empty cluster keeps its center
+            if let Some(mean) = centroid(members.map(point)) {
+                *center = mean;
+            }
+        }
+    }
+
+    let mut groups = vec![Vec::new(); centers.len()];
+    for (i, &cluster) in assignment.iter().enumerate() {
+        groups[cluster].push(indices[i]);
+    }
+    groups.retain(|group| !group.is_empty());
+    groups
+}
+
+/// The mean of `points`, if there are any.
+fn centroid<'a>(points: impl Iterator<Item = &'a Point>) -> Option<Point> {
+    let (count, x, y) = points.fold((0, 0.0, 0.0), |(count, x, y), point| {
+        (count + 1, x + point.x, y + point.y)
+    });
+    (count > 0).then(|| Point::new(x / f64::from(count), y / f64::from(count)))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{beam::beam_search, Metric};
+
+    use super::*;
+
+    #[test]
+    fn test_k_means() {
+        // Three tight groups far apart
+        let points = [(0.0, 0.0), (100.0, 0.0), (0.0, 100.0)]
+            .iter()
+            .flat_map(|&(x, y)| (0..5).map(move |i| Point::new(x + f64::from(i), y)))
+            .collect::<Vec<_>>();
+        let problem = Problem::new(points, Metric::Euclidean);
+
+        let mut groups = k_means(&problem, &(0..15).collect::<Vec<_>>(), 3);
+        groups.sort();
+        assert_eq!(
+            groups,
+            vec![
+                (0..5).collect::<Vec<_>>(),
+                (5..10).collect(),
+                (10..15).collect()
+            ]
+        );
+        // Asking for more clusters than distinct points leaves out empty ones
+        let same = Problem::new(vec![Point::new(1.0, 1.0); 4], Metric::Euclidean);
+        assert_eq!(k_means(&same, &[0, 1, 2, 3], 3), vec![vec![0, 1, 2, 3]]);
+    }
+
+    #[test]
+    fn test_cluster_first() {
+        let mut rng = SplitMix64::new(12);
+        let mut problem = Problem::new(
+            (0..300)
+                .map(|_| Point::new(rng.below(1000) as f64, rng.below(1000) as f64))
+                .collect(),
+            Metric::Euclidean,
+        );
+        let solve = |problem: &Problem| beam_search(problem, 4);
+
+        for clusters in [0, 1, 20, 1000] {
+            let route = cluster_first(&problem, clusters, solve);
+            assert!(problem.is_route(&route), "{clusters} clusters");
+        }
+
+        problem.end = 150;
+        let route = cluster_first(&problem, 20, solve);
+        assert!(problem.is_route(&route));
+        let index_order =
+            problem.route_distance(&[vec![0], problem.inner_destinations(), vec![150]].concat());
+        assert!(problem.route_distance(&route) < index_order / 5.0);
+
+        let pair = Problem::new(vec![Point::new(0.0, 0.0); 2], Metric::Euclidean);
+        assert_eq!(cluster_first(&pair, 3, solve).stops(), &[0, 1, 0]);
+    }
+}
@@ -0,0 +1,445 @@
+//! Differential testing: running several solvers on the same instances and reporting
+//! where they disagree.
+
+use std::{fmt, time::Instant};
+
+use crate::{
+    registry::{Category, Registered, IMPLEMENTATIONS},
+    report::{CountingAllocator, SolverResult},
+    Problem, Route,
+};
+
+/// Exact solvers are considered to agree when their distances are this close,
+/// relative to the distance itself.
+const RELATIVE_TOLERANCE: f64 = 1e-9;
+
+/// The most destinations between the start and end that [`verify_optimal`] checks, as
+/// it tries every order of them.
+pub const MAX_VERIFIED_DESTINATIONS: usize = 10;
+
+/// A named problem to run every solver on.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Instance {
+    pub name: String,
+    pub problem: Problem,
+}
+
+impl Instance {
+    pub fn new(name: impl Into<String>, problem: Problem) -> Self {
+        Instance {
+            name: name.into(),
+            problem,
+        }
+    }
+}
+
+/// A solver under comparison.
+pub struct Implementation<'a> {
+    pub name: String,
+    /// Whether the solver guarantees an optimal route. Exact solvers must agree with
+    /// each other; heuristics must merely never beat them.
+    pub exact: bool,
+    solve: Box<dyn Fn(&Problem) -> Route + 'a>,
+    supports: Box<dyn Fn(&Problem) -> bool + 'a>,
+}
+
+impl<'a> Implementation<'a> {
+    /// A solver that always returns an optimal route.
+    pub fn exact(name: impl Into<String>, solve: impl Fn(&Problem) -> Route + 'a) -> Self {
+        Implementation {
+            name: name.into(),
+            exact: true,
+            solve: Box::new(solve),
+            supports: Box::new(|_| true),
+        }
+    }
+
+    /// A solver that returns a valid but not necessarily optimal route.
+    pub fn heuristic(name: impl Into<String>, solve: impl Fn(&Problem) -> Route + 'a) -> Self {
+        Implementation {
+            exact: false,
+            ..Implementation::exact(name, solve)
+        }
+    }
+
+    /// Only runs the solver on problems for which `supports` returns true.
+    pub fn with_support(mut self, supports: impl Fn(&Problem) -> bool + 'a) -> Self {
+        self.supports = Box::new(supports);
+        self
+    }
+}
+
+impl From<&'static Registered> for Implementation<'static> {
+    fn from(registered: &'static Registered) -> Self {
+        Implementation {
+            name: registered.name.to_string(),
+            exact: registered.category == Category::Exact,
+            solve: Box::new(registered.solve),
+            supports: Box::new(|problem| registered.supports(problem)),
+        }
+    }
+}
+
+/// Every solver in the [registry](crate::registry).
+pub fn implementations() -> Vec<Implementation<'static>> {
+    IMPLEMENTATIONS.iter().map(Implementation::from).collect()
+}
+
+/// What was wrong with one solver's answer on one instance.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MismatchKind {
+    /// The route is not a tour from the start through every destination to the end.
+    InvalidRoute(String),
+    /// An exact solver found a different distance than the first exact solver.
+    Distance {
+        reference: String,
+        expected: f64,
+        found: f64,
+    },
+    /// A heuristic found a shorter route than the exact solvers claim is optimal.
+    BelowOptimal { optimal: f64, found: f64 },
+}
+
+/// A disagreement found by [`compare_implementations`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mismatch {
+    pub instance: String,
+    pub solver: String,
+    pub kind: MismatchKind,
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} on {}: ", self.solver, self.instance)?;
+        match &self.kind {
+            MismatchKind::InvalidRoute(reason) => write!(f, "invalid route, {reason}"),
+            MismatchKind::Distance {
+                reference,
+                expected,
+                found,
+            } => write!(f, "found {found} but {reference} found {expected}"),
+            MismatchKind::BelowOptimal { optimal, found } => {
+                write!(f, "found {found}, shorter than the optimal {optimal}")
+            }
+        }
+    }
+}
+
+/// The outcome of [`compare_implementations`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Comparison {
+    /// One result per solver per instance, with the gap filled in relative to the
+    /// exact optimum when there is one. Invalid routes have a distance of NaN.
+    pub results: Vec<SolverResult>,
+    pub mismatches: Vec<Mismatch>,
+}
+
+impl Comparison {
+    /// Whether every solver returned a valid route consistent with the others.
+    pub fn is_consistent(&self) -> bool {
+        self.mismatches.is_empty()
+    }
+}
+
+/// Runs every solver on every instance and reports where their answers disagree.
+///
+/// # Arguments
+///
+/// * `instances` - The problems to solve
+/// * `solvers` - The solvers to compare, such as those from [`implementations`]
+///
+/// # Returns
+///
+/// The distance, time and, with [`CountingAllocator`] installed, allocations of each
+/// run along with every mismatch found. The first
+/// exact solver to return a valid route is the reference for the others.
+pub fn compare_implementations(
+    instances: &[Instance],
+    solvers: &[Implementation<'_>],
+) -> Comparison {
+    let mut comparison = Comparison::default();
+
+    for instance in instances {
+        // Each supporting solver with its result and whether its route was valid
+        let mut runs = Vec::with_capacity(solvers.len());
+        for solver in solvers {
+            if !(solver.supports)(&instance.problem) {
+                continue;
+            }
+            let allocations = CountingAllocator::allocations();
+            let started = Instant::now();
+            let route = (solver.solve)(&instance.problem);
+            let time = started.elapsed();
+            let allocations = allocations
+                .zip(CountingAllocator::allocations())
+                .map(|(before, after)| after - before);
+
+            // An invalid route may name stops that are not destinations, so it has no distance
+            let distance = match check_route(&route, &instance.problem) {
+                Ok(()) => instance.problem.route_distance(&route),
+                Err(reason) => {
+                    comparison.mismatches.push(Mismatch {
+                        instance: instance.name.clone(),
+                        solver: solver.name.clone(),
+                        kind: MismatchKind::InvalidRoute(reason),
+                    });
+                    f64::NAN
+                }
+            };
+            let mut result = SolverResult::new(&solver.name, &instance.name, distance, time);
+            result.allocations = allocations;
+            runs.push((solver, result, !distance.is_nan()));
+        }
+
+        let reference = runs
+            .iter()
+            .find(|(solver, _, valid)| solver.exact && *valid)
+            .map(|(solver, result, _)| (*solver, result.distance));
+
+        if let Some((reference, optimal)) = reference {
+            let tolerance = RELATIVE_TOLERANCE * optimal.abs().max(1.0);
+            for (solver, result, valid) in &runs {
+                if std::ptr::eq(*solver, reference) || !valid {
+                    continue;
+                }
+                let found = result.distance;
+                let kind = if solver.exact && (found - optimal).abs() > tolerance {
+                    MismatchKind::Distance {
+                        reference: reference.name.clone(),
+                        expected: optimal,
+                        found,
+                    }
+                } else if !solver.exact && found < optimal - tolerance {
+                    MismatchKind::BelowOptimal { optimal, found }
+                } else {
+                    continue;
+                };
+                comparison.mismatches.push(Mismatch {
+                    instance: instance.name.clone(),
+                    solver: solver.name.clone(),
+                    kind,
+                });
+            }
+        }
+
+        let optimal = reference.map(|(_, optimal)| optimal);
+        comparison
+            .results
+            .extend(runs.into_iter().map(|(_, result, _)| match optimal {
+                Some(optimal) => result.with_optimal(optimal),
+                None => result,
+            }));
+    }
+
+    comparison
+}
+
+/// Why [`verify_optimal`] did not confirm a route.
+#[derive(Debug, Clone, PartialEq)]
+pub enum VerifyError {
+    /// The route is not a tour from the start through every destination to the end.
+    InvalidRoute(String),
+    /// There are more than [`MAX_VERIFIED_DESTINATIONS`] destinations between the start
+    /// and end, too many to try every order of.
+    TooLarge { destinations: usize },
+    /// A shorter route exists.
+    NotOptimal {
+        claimed: f64,
+        optimal: f64,
+        shortest: Route,
+    },
+}
+
+impl fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VerifyError::InvalidRoute(reason) => write!(f, "invalid route, {reason}"),
+            VerifyError::TooLarge { destinations } => write!(
+                f,
+                "{destinations} destinations are too many to verify, the most is {MAX_VERIFIED_DESTINATIONS}"
+            ),
+            VerifyError::NotOptimal {
+                claimed, optimal, ..
+            } => write!(f, "the route is {claimed} long but the shortest is {optimal}"),
+        }
+    }
+}
+
+impl std::error::Error for VerifyError {}
+
+/// Confirms that `claimed` is a shortest route of `problem` by trying every route, for
+/// checking a heuristic under development against small instances.
+///
+/// # Arguments
+///
+/// * `problem` - The problem, with at most [`MAX_VERIFIED_DESTINATIONS`] destinations
+///   between the start and end
+/// * `claimed` - The route claimed to be optimal, as destination indices
+///
+/// # Returns
+///
+/// The route's distance if no route is shorter, within a relative tolerance for
+/// rounding, and otherwise why not, with a shortest route if there is a shorter one
+pub fn verify_optimal(problem: &Problem, claimed: &[usize]) -> Result<f64, VerifyError> {
+    check_route(claimed, problem).map_err(VerifyError::InvalidRoute)?;
+    let destinations = problem.inner_destinations().len();
+    if destinations > MAX_VERIFIED_DESTINATIONS {
+        return Err(VerifyError::TooLarge { destinations });
+    }
+
+    let claimed = problem.route_distance(claimed);
+    let shortest = problem.solve();
+    let optimal = problem.route_distance(&shortest);
+    if claimed > optimal + RELATIVE_TOLERANCE * optimal.abs().max(1.0) {
+        return Err(VerifyError::NotOptimal {
+            claimed,
+            optimal,
+            shortest,
+        });
+    }
+    Ok(claimed)
+}
+
+/// Explains why `route` is rejected if it is not a [route](Problem::is_route) of
+/// `problem`.
+fn check_route(route: &[usize], problem: &Problem) -> Result<(), String> {
+    if problem.is_route(route) {
+        Ok(())
+    } else {
+        Err(format!(
+            "{route:?} does not go from the start through every destination once to the end"
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Metric, Point};
+
+    use super::*;
+
+    fn instances() -> Vec<Instance> {
+        let square = Problem::new(
+            vec![
+                Point::new(0.0, 0.0),
+                Point::new(1.0, 1.0),
+                Point::new(1.0, 0.0),
+                Point::new(0.0, 1.0),
+            ],
+            Metric::Euclidean,
+        );
+        let mut line = Problem::new(
+            vec![
+                Point::new(0.0, 0.0),
+                Point::new(3.0, 0.0),
+                Point::new(1.0, 0.0),
+            ],
+            Metric::Manhattan,
+        );
+        line.end = 1;
+        vec![Instance::new("square", square), Instance::new("line", line)]
+    }
+
+    #[test]
+    fn test_verify_optimal() {
+        let instances = instances();
+        let (square, line) = (&instances[0].problem, &instances[1].problem);
+        assert_eq!(verify_optimal(square, &[0, 2, 1, 3, 0]), Ok(4.0));
+        // Crossing the diagonals
+        let err = verify_optimal(square, &[0, 1, 2, 3, 0]).unwrap_err();
+        assert!(matches!(
+            &err,
+            VerifyError::NotOptimal { optimal, shortest, .. }
+                if *optimal == 4.0 && square.route_distance(shortest) == 4.0
+        ));
+        assert!(err.to_string().starts_with("the route is 4.8"));
+        assert!(matches!(
+            verify_optimal(line, &[0, 1]),
+            Err(VerifyError::InvalidRoute(_))
+        ));
+        assert_eq!(verify_optimal(line, &[0, 2, 1]), Ok(3.0));
+
+        let large = Problem::new(vec![Point::new(0.0, 0.0); 12], Metric::Euclidean);
+        let route = [(0..12).collect::<Vec<_>>(), vec![0]].concat();
+        assert_eq!(
+            verify_optimal(&large, &route),
+            Err(VerifyError::TooLarge { destinations: 11 })
+        );
+    }
+
+    #[test]
+    fn test_builtin_implementations_agree() {
+        let comparison = compare_implementations(&instances(), &implementations());
+
+        assert!(comparison.is_consistent(), "{:?}", comparison.mismatches);
+        // Only the line is on the integer line the hand-rolled solvers need
+        assert_eq!(
+            comparison.results.len(),
+            2 * IMPLEMENTATIONS.len() - 2,
+            "{:?}",
+            comparison.results
+        );
+        assert!(comparison
+            .results
+            .iter()
+            .all(|result| result.gap == Some(0.0)));
+    }
+
+    #[test]
+    fn test_reports_mismatches() {
+        let mut solvers = implementations();
+        // Visits the destinations in index order, which is not optimal for the square
+        solvers.push(Implementation::exact("in order", |problem: &Problem| {
+            let mut stops = (0..problem.len()).collect::<Vec<_>>();
+            if problem.start == problem.end {
+                stops.push(problem.start);
+            }
+            Route::new(stops)
+        }));
+        solvers.push(Implementation::heuristic("skips", |problem: &Problem| {
+            Route::new(vec![problem.start, problem.end])
+        }));
+        solvers.push(Implementation::heuristic("lost", |_: &Problem| {
+            Route::new(vec![0, 9, 1, 2, 0])
+        }));
+
+        let instances = &instances()[..1];
+        let in_order = instances[0].problem.route_distance(&[0, 1, 2, 3, 0]);
+
+        let comparison = compare_implementations(instances, &solvers);
+        assert_eq!(
+            comparison.mismatches,
+            vec![
+                Mismatch {
+                    instance: "square".to_string(),
+                    solver: "skips".to_string(),
+                    kind: MismatchKind::InvalidRoute(
+                        "[0, 0] does not go from the start through every destination once to the end"
+                            .to_string(),
+                    ),
+                },
+                Mismatch {
+                    instance: "square".to_string(),
+                    solver: "lost".to_string(),
+                    kind: MismatchKind::InvalidRoute(
+                        "[0, 9, 1, 2, 0] does not go from the start through every destination once to the end"
+                            .to_string(),
+                    ),
+                },
+                Mismatch {
+                    instance: "square".to_string(),
+                    solver: "in order".to_string(),
+                    kind: MismatchKind::Distance {
+                        reference: "exhaustive".to_string(),
+                        expected: 4.0,
+                        found: in_order,
+                    },
+                },
+            ]
+        );
+        assert_eq!(
+            comparison.mismatches[2].to_string(),
+            format!("in order on square: found {in_order} but exhaustive found 4")
+        );
+    }
+}
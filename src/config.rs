@@ -0,0 +1,223 @@
+//! Reading a [`SolverConfig`] from a TOML or JSON file, so the command line and
+//! services can be configured without recompiling.
+//!
+//! A configuration looks like
+//!
+//! ```toml
+//! algorithm = "ils"
+//! time_limit = 2.5
+//! iterations = 5000
+//! seed = 7
+//! initial_route = [0, 2, 1, 3, 0]
+//! deterministic = true
+//! ```
+//!
+//! or the same keys in a JSON object. Every key is optional: `algorithm` names a
+//! solver in the [`registry`], `time_limit` is in seconds, and `deterministic` breaks
+//! ties as the serial solvers do in parallel ones. Unknown keys are rejected rather
+//! than silently ignored, so a misspelt or unsupported setting, such as a
+//! `[constraints]` table, is never mistaken for one that was applied.
+
+use std::{fmt, time::Duration};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{background::SolverConfig, registry, Route};
+
+/// An error produced while reading a configuration.
+#[derive(Debug)]
+pub enum ConfigError {
+    /// The document was not valid JSON or did not match the schema.
+    Json(serde_json::Error),
+    /// The document was not valid TOML or did not match the schema.
+    #[cfg(feature = "toml")]
+    Toml(toml::de::Error),
+    /// `algorithm` is not the name of a registered solver.
+    UnknownAlgorithm(String),
+    /// `time_limit` is negative, not a number or too long to represent.
+    InvalidTimeLimit(f64),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Json(err) => write!(f, "invalid configuration: {err}"),
+            #[cfg(feature = "toml")]
+            ConfigError::Toml(err) => write!(f, "invalid configuration: {err}"),
+            ConfigError::UnknownAlgorithm(name) => write!(f, "no algorithm is named `{name}`"),
+            ConfigError::InvalidTimeLimit(seconds) => {
+                write!(f, "{seconds} is not a time limit in seconds")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ConfigError::Json(err) => Some(err),
+            #[cfg(feature = "toml")]
+            ConfigError::Toml(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<serde_json::Error> for ConfigError {
+    fn from(err: serde_json::Error) -> Self {
+        ConfigError::Json(err)
+    }
+}
+
+#[cfg(feature = "toml")]
+impl From<toml::de::Error> for ConfigError {
+    fn from(err: toml::de::Error) -> Self {
+        ConfigError::Toml(err)
+    }
+}
+
+/// The file form of a [`SolverConfig`], converted to one with `try_into`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ConfigDocument {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    algorithm: Option<String>,
+    /// In seconds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    time_limit: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    iterations: Option<u64>,
+    #[serde(default)]
+    seed: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    initial_route: Option<Vec<usize>>,
+    #[serde(default)]
+    deterministic: bool,
+}
+
+impl From<&SolverConfig> for ConfigDocument {
+    fn from(config: &SolverConfig) -> Self {
+        ConfigDocument {
+            algorithm: config.algorithm.clone(),
+            time_limit: config.time_limit.map(|limit| limit.as_secs_f64()),
+            iterations: config.iterations,
+            seed: config.seed,
+            initial_route: config
+                .initial_route
+                .as_ref()
+                .map(|route| route.stops().to_vec()),
+            deterministic: config.deterministic,
+        }
+    }
+}
+
+impl TryFrom<ConfigDocument> for SolverConfig {
+    type Error = ConfigError;
+
+    fn try_from(document: ConfigDocument) -> Result<Self, Self::Error> {
+        if let Some(name) = &document.algorithm {
+            if registry::find(name).is_none() {
+                return Err(ConfigError::UnknownAlgorithm(name.clone()));
+            }
+        }
+        let time_limit = document
+            .time_limit
+            .map(|seconds| {
+                Duration::try_from_secs_f64(seconds)
+                    .map_err(|_| ConfigError::InvalidTimeLimit(seconds))
+            })
+            .transpose()?;
+        Ok(SolverConfig {
+            algorithm: document.algorithm,
+            time_limit,
+            iterations: document.iterations,
+            seed: document.seed,
+            initial_route: document.initial_route.map(Route::new),
+            deterministic: document.deterministic,
+        })
+    }
+}
+
+impl SolverConfig {
+    /// Reads a configuration from JSON.
+    pub fn from_json(json: &str) -> Result<Self, ConfigError> {
+        serde_json::from_str::<ConfigDocument>(json)?.try_into()
+    }
+
+    /// Reads a configuration from TOML.
+    #[cfg(feature = "toml")]
+    pub fn from_toml(toml: &str) -> Result<Self, ConfigError> {
+        toml::from_str::<ConfigDocument>(toml)?.try_into()
+    }
+
+    /// Serializes the configuration as a [`ConfigDocument`] in JSON, which
+    /// [`from_json`](SolverConfig::from_json) reads back.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(&ConfigDocument::from(self))
+            .expect("configuration documents always serialize")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_json() {
+        let config = SolverConfig::from_json(
+            r#"{"algorithm": "ils", "time_limit": 0.25, "seed": 3, "initial_route": [0, 1, 0]}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            config,
+            SolverConfig::default()
+                .with_algorithm("ils")
+                .with_time_limit(Duration::from_millis(250))
+                .with_seed(3)
+                .with_initial_route(Route::new(vec![0, 1, 0]))
+        );
+        assert_eq!(config.solver().unwrap().name, "ils");
+        assert_eq!(SolverConfig::from_json(&config.to_json()).unwrap(), config);
+        assert_eq!(
+            SolverConfig::from_json("{}").unwrap(),
+            SolverConfig::default()
+        );
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn test_from_toml() {
+        let config = SolverConfig::from_toml(
+            "algorithm = \"tempering\"\niterations = 20\nseed = 9\ndeterministic = true\n",
+        )
+        .unwrap();
+        assert_eq!(
+            config,
+            SolverConfig::default()
+                .with_algorithm("tempering")
+                .with_iterations(20)
+                .with_seed(9)
+                .with_deterministic(true)
+        );
+        assert!(matches!(
+            SolverConfig::from_toml("seed = \"nine\""),
+            Err(ConfigError::Toml(_))
+        ));
+        assert!(SolverConfig::from_toml("[constraints]\n").is_err());
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!(matches!(
+            SolverConfig::from_json(r#"{"algorithm": "telepathy"}"#),
+            Err(ConfigError::UnknownAlgorithm(name)) if name == "telepathy"
+        ));
+        assert!(matches!(
+            SolverConfig::from_json(r#"{"time_limit": -1}"#),
+            Err(ConfigError::InvalidTimeLimit(_))
+        ));
+        // Misspelt and unsupported settings are not silently ignored
+        assert!(SolverConfig::from_json(r#"{"iteration": 5}"#).is_err());
+        assert!(SolverConfig::from_json(r#"{"constraints": {"capacity": 5}}"#).is_err());
+    }
+}
@@ -0,0 +1,191 @@
+//! The change in a route's distance from a move, computed in O(1) time from only the
+//! legs the move affects, without making it.
+//!
+//! Positions are indices into the route's stops, as for the moves on
+//! [`Route`](crate::Route) that apply them. Legs past either end of the route are
+//! skipped, so moves may include the first and last stops. Distances should be
+//! symmetric, as reversed segments are assumed to keep their length.
+
+use std::ops::{Add, Sub};
+
+use num_traits::Zero;
+
+/// The change from reversing the stops at positions `i..=j`, which replaces the legs
+/// into and out of the segment.
+///
+/// # Panics
+///
+/// Panics unless `i <= j` and both are positions on the route.
+pub fn two_opt<Distance>(
+    stops: &[usize],
+    i: usize,
+    j: usize,
+    distance: impl Fn(usize, usize) -> Distance,
+) -> Distance
+where
+    Distance: Zero + Sub<Output = Distance>,
+{
+    assert!(
+        i <= j && j < stops.len(),
+        "{i}..={j} is not a segment of the route"
+    );
+    let legs = [i.wrapping_sub(1), j];
+    let moved = |position: usize| {
+        if (i..=j).contains(&position) {
+            stops[i + j - position]
+        } else {
+            stops[position]
+        }
+    };
+    difference(stops, &legs, &legs, moved, distance)
+}
+
+/// The change from exchanging the stops at positions `i` and `j`.
+///
+/// # Panics
+///
+/// Panics unless both are positions on the route.
+pub fn swap<Distance>(
+    stops: &[usize],
+    i: usize,
+    j: usize,
+    distance: impl Fn(usize, usize) -> Distance,
+) -> Distance
+where
+    Distance: Zero + Sub<Output = Distance>,
+{
+    let (i, j) = (i.min(j), i.max(j));
+    assert!(j < stops.len(), "{j} is not a position on the route");
+    let moved = |position: usize| {
+        if position == i {
+            stops[j]
+        } else if position == j {
+            stops[i]
+        } else {
+            stops[position]
+        }
+    };
+    // The legs into and out of each position, once each when they are adjacent
+    let legs = [i.wrapping_sub(1), i, j, j.wrapping_sub(1)];
+    let legs = match j - i {
+        0 => &legs[..0],
+        1 => &legs[..3],
+        _ => &legs[..],
+    };
+    difference(stops, legs, legs, moved, distance)
+}
+
+/// The change from moving the stop at position `i` to position `j`, shifting the stops
+/// between, which replaces the legs either side of it where it was and where it goes,
+/// and the leg that joins its old neighbors.
+///
+/// # Panics
+///
+/// Panics unless both are positions on the route.
+pub fn relocate<Distance>(
+    stops: &[usize],
+    i: usize,
+    j: usize,
+    distance: impl Fn(usize, usize) -> Distance,
+) -> Distance
+where
+    Distance: Zero + Sub<Output = Distance>,
+{
+    assert!(
+        i < stops.len() && j < stops.len(),
+        "{i} and {j} are not positions on the route"
+    );
+    let moved = |position: usize| {
+        if position == j {
+            stops[i]
+        } else if (i..j).contains(&position) {
+            stops[position + 1]
+        } else if (j + 1..=i).contains(&position) {
+            stops[position - 1]
+        } else {
+            stops[position]
+        }
+    };
+    let (before, after) = if i < j {
+        ([i.wrapping_sub(1), i, j], [i.wrapping_sub(1), j - 1, j])
+    } else {
+        (
+            [j.wrapping_sub(1), i.wrapping_sub(1), i],
+            [j.wrapping_sub(1), j, i],
+        )
+    };
+    let count = if i == j { 0 } else { 3 };
+    difference(stops, &before[..count], &after[..count], moved, distance)
+}
+
+/// The distance of the legs at positions `after` once the move puts `moved(p)` at each
+/// position p, less that of the legs at positions `before` now.
+fn difference<Distance>(
+    stops: &[usize],
+    before: &[usize],
+    after: &[usize],
+    moved: impl Fn(usize) -> usize,
+    distance: impl Fn(usize, usize) -> Distance,
+) -> Distance
+where
+    Distance: Zero + Sub<Output = Distance>,
+{
+    let last = stops.len().saturating_sub(1);
+    let legs = |positions: &[usize], at: &dyn Fn(usize) -> usize| {
+        positions
+            .iter()
+            .filter(|&&leg| leg < last)
+            .map(|&leg| distance(at(leg), at(leg + 1)))
+            .fold(Distance::zero(), Add::add)
+    };
+    legs(after, &moved) - legs(before, &|position| stops[position])
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{rng::SplitMix64, Metric, Point, Problem};
+
+    use super::*;
+
+    #[test]
+    fn test_deltas_match_moves() {
+        let mut rng = SplitMix64::new(2);
+        let problem = Problem::new(
+            (0..8)
+                .map(|_| Point::new(rng.below(100) as f64, rng.below(100) as f64))
+                .collect(),
+            Metric::Euclidean,
+        );
+        let distance = |a, b| problem.distance(a, b);
+        let stops = [0, 3, 6, 1, 7, 2, 5, 4, 0];
+        let original = problem.route_distance(&stops);
+
+        for i in 0..stops.len() {
+            for j in 0..stops.len() {
+                let mut moved = stops.to_vec();
+                moved.swap(i, j);
+                let expected = problem.route_distance(&moved) - original;
+                assert!((swap(&stops, i, j, distance) - expected).abs() < 1e-9);
+
+                let mut moved = stops.to_vec();
+                let stop = moved.remove(i);
+                moved.insert(j, stop);
+                let expected = problem.route_distance(&moved) - original;
+                assert!((relocate(&stops, i, j, distance) - expected).abs() < 1e-9);
+
+                if i <= j {
+                    let mut moved = stops.to_vec();
+                    moved[i..=j].reverse();
+                    let expected = problem.route_distance(&moved) - original;
+                    assert!((two_opt(&stops, i, j, distance) - expected).abs() < 1e-9);
+                }
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "not a segment")]
+    fn test_two_opt_out_of_range() {
+        two_opt(&[0, 1, 2], 1, 3, |_, _| 0.0);
+    }
+}
@@ -0,0 +1,232 @@
+//! Comparing two routes over the same destinations, to see how much a re-solve changed
+//! a plan before handing it out.
+
+use std::{
+    collections::HashMap,
+    fmt,
+    ops::{Add, Sub},
+};
+
+use num_traits::Zero;
+
+use crate::Problem;
+
+/// How route `b` differs from route `a`, from [`route_diff`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct RouteDiff<Distance = f64> {
+    /// Legs of `b` that `a` also travels, in either direction, in `b`'s order.
+    pub common_edges: Vec<(usize, usize)>,
+    /// Legs of `a` that `b` does not travel, in `a`'s order.
+    pub removed_edges: Vec<(usize, usize)>,
+    /// Legs of `b` that `a` does not travel, in `b`'s order.
+    pub added_edges: Vec<(usize, usize)>,
+    /// The positions `i..=j` in `b` of each longest run of stops that `a` visits in
+    /// the opposite order.
+    pub reversed_segments: Vec<(usize, usize)>,
+    /// `b`'s distance less `a`'s, negative if `b` is shorter.
+    pub distance_delta: Distance,
+}
+
+impl<Distance> RouteDiff<Distance> {
+    /// The fraction of `b`'s legs that `a` also travels, 1 for the same route in either
+    /// direction and 0 for one sharing no legs.
+    pub fn similarity(&self) -> f64 {
+        let legs = self.common_edges.len() + self.added_edges.len();
+        if legs == 0 {
+            1.0
+        } else {
+            self.common_edges.len() as f64 / legs as f64
+        }
+    }
+}
+
+impl<Distance: fmt::Display> fmt::Display for RouteDiff<Distance> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} of {} legs kept, {} added, {} segments reversed, distance changed by {}",
+            self.common_edges.len(),
+            self.common_edges.len() + self.removed_edges.len(),
+            self.added_edges.len(),
+            self.reversed_segments.len(),
+            self.distance_delta
+        )
+    }
+}
+
+/// Compares route `b` with route `a`, such as today's solution with yesterday's.
+///
+/// Legs are matched regardless of direction, as a leg driven the other way is usually
+/// the same road; those driven the other way are reported as reversed segments. A leg
+/// repeated in one route matches as many times as the other repeats it.
+///
+/// # Arguments
+///
+/// * `a` - The original route, as destination indices
+/// * `b` - The new route
+/// * `distance` - Computes the distance between two destination indices
+///
+/// # Returns
+///
+/// The legs in common, removed and added, the reversed segments and the change in
+/// distance
+pub fn route_diff<Distance>(
+    a: &[usize],
+    b: &[usize],
+    distance: impl Fn(usize, usize) -> Distance,
+) -> RouteDiff<Distance>
+where
+    Distance: Zero + Sub<Output = Distance>,
+{
+    let legs = |route: &[usize]| {
+        route
+            .windows(2)
+            .map(|leg| (leg[0], leg[1]))
+            .collect::<Vec<_>>()
+    };
+    let (a_legs, b_legs) = (legs(a), legs(b));
+
+    // How many times `a` travels each leg in each direction that `b` has not matched
+    let mut unmatched = HashMap::<(usize, usize), usize>::new();
+    for &leg in &a_legs {
+        *unmatched.entry(leg).or_default() += 1;
+    }
+    let mut take = |leg| match unmatched.get_mut(&leg) {
+        Some(count) if *count > 0 => {
+            *count -= 1;
+            true
+        }
+        _ => false,
+    };
+    // Legs in the same direction first, so only those `a` travels solely the other way
+    // count as reversed
+    let forward = b_legs.iter().map(|&leg| take(leg)).collect::<Vec<_>>();
+    let reversed = b_legs
+        .iter()
+        .zip(&forward)
+        .map(|(&(from, to), &forward)| !forward && take((to, from)))
+        .collect::<Vec<_>>();
+
+    let (mut common_edges, mut added_edges) = (Vec::new(), Vec::new());
+    for (index, &leg) in b_legs.iter().enumerate() {
+        if forward[index] || reversed[index] {
+            common_edges.push(leg);
+        } else {
+            added_edges.push(leg);
+        }
+    }
+    let removed_edges = a_legs.iter().copied().filter(|&leg| take(leg)).collect();
+
+    // A run of reversed legs from leg `start` to leg `end - 1` covers stops `start..=end`
+    let mut reversed_segments = Vec::new();
+    let mut run_start = None;
+    for (index, &is_reversed) in reversed.iter().chain([&false]).enumerate() {
+        match (is_reversed, run_start) {
+            (true, None) => run_start = Some(index),
+            (false, Some(start)) => {
+                reversed_segments.push((start, index));
+                run_start = None;
+            }
+            _ => {}
+        }
+    }
+
+    let total = |route: &[usize]| {
+        legs(route)
+            .into_iter()
+            .map(|(from, to)| distance(from, to))
+            .fold(Distance::zero(), Add::add)
+    };
+    RouteDiff {
+        common_edges,
+        removed_edges,
+        added_edges,
+        reversed_segments,
+        distance_delta: total(b) - total(a),
+    }
+}
+
+impl Problem {
+    /// Compares route `b` with route `a` by [`route_diff`], with the problem's
+    /// distances.
+    pub fn route_diff(&self, a: &[usize], b: &[usize]) -> RouteDiff {
+        route_diff(a, b, |from, to| self.distance(from, to))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Metric, Point};
+
+    use super::*;
+
+    fn line(a: usize, b: usize) -> i64 {
+        (a as i64 - b as i64).abs()
+    }
+
+    #[test]
+    fn test_same_route() {
+        let route = [0, 1, 2, 3, 0];
+        let diff = route_diff(&route, &route, line);
+        assert_eq!(diff.common_edges.len(), 4);
+        assert!(diff.added_edges.is_empty() && diff.removed_edges.is_empty());
+        assert!(diff.reversed_segments.is_empty());
+        assert_eq!(diff.distance_delta, 0);
+        assert_eq!(diff.similarity(), 1.0);
+
+        // The whole route driven the other way shares every leg
+        let reversed = [0, 3, 2, 1, 0];
+        let diff = route_diff(&route, &reversed, line);
+        assert_eq!(diff.similarity(), 1.0);
+        assert_eq!(diff.reversed_segments, vec![(0, 4)]);
+    }
+
+    #[test]
+    fn test_two_opt_move() {
+        // Reversing 2..=4 replaces the legs 1-2 and 4-5 with 1-4 and 2-5
+        let a = [0, 1, 2, 3, 4, 5, 0];
+        let b = [0, 1, 4, 3, 2, 5, 0];
+        let diff = route_diff(&a, &b, line);
+        assert_eq!(diff.removed_edges, vec![(1, 2), (4, 5)]);
+        assert_eq!(diff.added_edges, vec![(1, 4), (2, 5)]);
+        assert_eq!(diff.common_edges, vec![(0, 1), (4, 3), (3, 2), (5, 0)]);
+        assert_eq!(diff.reversed_segments, vec![(2, 4)]);
+        assert_eq!(diff.distance_delta, 4);
+        assert!((diff.similarity() - 4.0 / 6.0).abs() < 1e-12);
+        assert_eq!(
+            diff.to_string(),
+            "4 of 6 legs kept, 2 added, 1 segments reversed, distance changed by 4"
+        );
+    }
+
+    #[test]
+    fn test_repeated_legs() {
+        // A leg travelled twice only matches as many times as the other route travels it
+        let a = [0, 1, 0, 1];
+        let b = [0, 1, 2, 1];
+        let diff = route_diff(&a, &b, |_, _| 1.0);
+        assert_eq!(diff.common_edges, vec![(0, 1)]);
+        assert_eq!(diff.added_edges, vec![(1, 2), (2, 1)]);
+        assert_eq!(diff.removed_edges, vec![(0, 1), (1, 0)]);
+        assert!(diff.reversed_segments.is_empty());
+        assert_eq!(diff.distance_delta, 0.0);
+
+        let diff = route_diff(&[0], &[0], line);
+        assert_eq!(diff.similarity(), 1.0);
+    }
+
+    #[test]
+    fn test_problem_route_diff() {
+        let problem = Problem::new(
+            [(0.0, 0.0), (0.0, 3.0), (4.0, 3.0), (4.0, 0.0)]
+                .map(|(x, y)| Point::new(x, y))
+                .to_vec(),
+            Metric::Euclidean,
+        );
+        // Crossing the rectangle's diagonals instead of going around it
+        let diff = problem.route_diff(&[0, 1, 2, 3, 0], &[0, 2, 1, 3, 0]);
+        assert_eq!(diff.common_edges, vec![(2, 1), (3, 0)]);
+        assert_eq!(diff.reversed_segments, vec![(1, 2)]);
+        assert!((diff.distance_delta - 4.0).abs() < 1e-12);
+    }
+}
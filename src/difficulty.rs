@@ -0,0 +1,197 @@
+//! Estimates of how hard a problem is for each solver, to pick one automatically or
+//! to warn before starting a search that would not finish in a lifetime.
+//!
+//! The estimates count work in units of about one distance computation with
+//! [`Metric::Manhattan`], so they rank solvers and flag hopeless ones but are only
+//! rough predictions of time.
+
+use crate::{
+    registry::{self, Category, Registered, IMPLEMENTATIONS},
+    Metric, Problem, Route,
+};
+
+/// The most work an exact solver is expected to do for it to be recommended, about a
+/// second on current hardware.
+pub const WORK_BUDGET: f64 = 1e8;
+
+/// The relative cost of looking up a subproblem in the memoized Held-Karp table,
+/// which hashes the subset on every lookup.
+const HELD_KARP_STATE_COST: f64 = 10.0;
+
+/// Up to this many destinations, iterated local search runs in well under a second;
+/// beyond it clustering keeps each local search small.
+const ILS_DESTINATIONS: usize = 200;
+
+/// Beyond this many destinations only a space-filling curve is fast enough.
+const CLUSTER_DESTINATIONS: usize = 50_000;
+
+/// How hard a problem is expected to be, from [`estimate_difficulty`].
+#[derive(Debug, Clone, Copy)]
+pub struct Difficulty {
+    /// The destinations to put in order, not counting the start and end.
+    pub destinations: usize,
+    /// The partial routes an exhaustive search extends, about e·n! for n destinations.
+    pub exhaustive_nodes: f64,
+    /// The subproblems Held-Karp solves, n²·2ⁿ.
+    pub held_karp_states: f64,
+    /// The cost of one distance with the problem's metric, relative to
+    /// [`Metric::Manhattan`].
+    pub distance_cost: f64,
+    /// The solver expected to give the best route in reasonable time: the exact solver
+    /// with the least work if any is within [`WORK_BUDGET`], a heuristic otherwise.
+    pub recommended: &'static Registered,
+}
+
+impl Difficulty {
+    /// The work `registered` is expected to do on the problem, or `None` for a
+    /// heuristic, whose work is set by its iteration budget rather than the problem.
+    pub fn expected_work(&self, registered: &Registered) -> Option<f64> {
+        let n = self.destinations as f64;
+        match (registered.category, registered.name) {
+            (Category::Heuristic, _) => None,
+            (_, "held-karp") => Some(self.held_karp_states * HELD_KARP_STATE_COST),
+            // Distances are computed once, then every route only looks them up
+            (_, "matrix") => Some((n + 2.0).powi(2) * self.distance_cost + self.exhaustive_nodes),
+            // Integer differences, whatever the metric
+            (_, "hand-rolled" | "hand-rolled-unchecked") => Some(self.exhaustive_nodes),
+            _ => Some(self.exhaustive_nodes * self.distance_cost),
+        }
+    }
+
+    /// Whether `registered` is expected to finish within [`WORK_BUDGET`], which every
+    /// heuristic is.
+    pub fn is_practical(&self, registered: &Registered) -> bool {
+        self.expected_work(registered)
+            .is_none_or(|work| work <= WORK_BUDGET)
+    }
+}
+
+/// Estimates how hard `problem` is from its number of destinations and its metric, and
+/// recommends a solver for it among those that [support](Registered::supports) it.
+pub fn estimate_difficulty(problem: &Problem) -> Difficulty {
+    let destinations = problem.inner_destinations().len();
+    // One node for the empty route, then n for the first stop, n(n-1) for the first two...
+    let (mut exhaustive_nodes, mut partial_routes) = (1.0, 1.0);
+    for remaining in (1..=destinations).rev() {
+        partial_routes *= remaining as f64;
+        exhaustive_nodes += partial_routes;
+    }
+    let n = destinations as f64;
+    let mut difficulty = Difficulty {
+        destinations,
+        exhaustive_nodes,
+        held_karp_states: n * n * n.exp2(),
+        distance_cost: distance_cost(problem.metric),
+        recommended: &IMPLEMENTATIONS[0],
+    };
+
+    let exact = IMPLEMENTATIONS
+        .iter()
+        .filter(|registered| registered.category == Category::Exact && registered.supports(problem))
+        .filter_map(|registered| Some((difficulty.expected_work(registered)?, registered)))
+        .filter(|&(work, _)| work <= WORK_BUDGET)
+        .min_by(|(a, _), (b, _)| a.total_cmp(b));
+    difficulty.recommended = match exact {
+        Some((_, registered)) => registered,
+        None => {
+            let name = if destinations <= ILS_DESTINATIONS {
+                "ils"
+            } else if destinations <= CLUSTER_DESTINATIONS {
+                "cluster"
+            } else {
+                "hilbert"
+            };
+            registry::find(name).expect("the heuristic is registered")
+        }
+    };
+    difficulty
+}
+
+/// Solves `problem` with the solver [`estimate_difficulty`] recommends: exactly when
+/// that is practical, with a heuristic suited to its size otherwise.
+pub fn solve_auto(problem: &Problem) -> Route {
+    (estimate_difficulty(problem).recommended.solve)(problem)
+}
+
+/// The cost of one distance, relative to the additions and absolute values of
+/// [`Metric::Manhattan`].
+fn distance_cost(metric: Metric) -> f64 {
+    match metric {
+        Metric::Manhattan => 1.0,
+        Metric::Euclidean | Metric::RoundedEuclidean => 2.0,
+        // Half a dozen trigonometric functions
+        Metric::Haversine => 20.0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{generators, Point};
+
+    use super::*;
+
+    fn instance(destinations: usize, metric: Metric) -> Problem {
+        let points = generators::uniform_points(
+            destinations + 1,
+            generators::Bounds::square(1.0),
+            destinations as u64,
+        );
+        Problem::new(points, metric)
+    }
+
+    #[test]
+    fn test_counts() {
+        let difficulty = estimate_difficulty(&instance(3, Metric::Euclidean));
+        assert_eq!(difficulty.destinations, 3);
+        // The empty route, 3 with one stop, 6 with two and 6 with all three
+        assert_eq!(difficulty.exhaustive_nodes, 16.0);
+        assert_eq!(difficulty.held_karp_states, 72.0);
+        assert_eq!(difficulty.distance_cost, 2.0);
+        assert!(difficulty
+            .expected_work(registry::find("ils").unwrap())
+            .is_none());
+    }
+
+    #[test]
+    fn test_recommendations() {
+        let recommended = |destinations, metric| {
+            estimate_difficulty(&instance(destinations, metric))
+                .recommended
+                .name
+        };
+        assert_eq!(recommended(3, Metric::Euclidean), "exhaustive");
+        assert_eq!(recommended(5, Metric::Haversine), "matrix");
+        assert_eq!(recommended(14, Metric::Euclidean), "held-karp");
+        assert_eq!(recommended(30, Metric::Euclidean), "ils");
+        assert_eq!(recommended(1000, Metric::Euclidean), "cluster");
+        assert_eq!(recommended(100_000, Metric::Manhattan), "hilbert");
+
+        // On the integer line, the hand-rolled search is the cheapest exhaustive one
+        let line = Problem::new(
+            (0..6).map(|x| Point::new(f64::from(x), 0.0)).collect(),
+            Metric::Euclidean,
+        );
+        assert_eq!(estimate_difficulty(&line).recommended.name, "hand-rolled");
+    }
+
+    #[test]
+    fn test_hopeless_search() {
+        let difficulty = estimate_difficulty(&instance(20, Metric::Euclidean));
+        assert!(!difficulty.is_practical(registry::find("exhaustive").unwrap()));
+        assert!(!difficulty.is_practical(registry::find("held-karp").unwrap()));
+        assert!(difficulty.is_practical(registry::find("christofides").unwrap()));
+        assert_eq!(difficulty.recommended.category, Category::Heuristic);
+    }
+
+    #[test]
+    fn test_solve_auto() {
+        let problem = instance(7, Metric::Euclidean);
+        let route = solve_auto(&problem);
+        assert!(
+            (problem.route_distance(&route) - problem.route_distance(&problem.solve())).abs()
+                < 1e-9
+        );
+        let problem = instance(60, Metric::Euclidean);
+        assert_eq!(solve_auto(&problem).len(), 62);
+    }
+}
@@ -0,0 +1,311 @@
+//! Dubins paths, the shortest paths for a vehicle that only drives forward and turns
+//! no tighter than a minimum radius, such as a fixed-wing aircraft or a car-like robot.
+//! Their lengths between poses are distances the solvers can use to find routes the
+//! vehicle can actually fly or drive.
+//!
+//! The lengths follow Shkel and Lumelsky's classification of the six candidate paths,
+//! each made of three arcs or straight segments.
+
+use std::f64::consts::TAU;
+
+use crate::DistanceMatrix;
+
+/// A position and the direction the vehicle faces there, in radians counterclockwise
+/// from the positive x axis.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Pose {
+    pub x: f64,
+    pub y: f64,
+    pub heading: f64,
+}
+
+impl Pose {
+    pub fn new(x: f64, y: f64, heading: f64) -> Self {
+        Pose { x, y, heading }
+    }
+}
+
+/// One part of a Dubins path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Segment {
+    /// A counterclockwise arc at the minimum radius
+    Left,
+    Straight,
+    /// A clockwise arc at the minimum radius
+    Right,
+}
+
+/// The six shapes a shortest Dubins path can take, named by their segments.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PathType {
+    Lsl,
+    Rsr,
+    Lsr,
+    Rsl,
+    Rlr,
+    Lrl,
+}
+
+impl PathType {
+    const ALL: [PathType; 6] = [
+        PathType::Lsl,
+        PathType::Rsr,
+        PathType::Lsr,
+        PathType::Rsl,
+        PathType::Rlr,
+        PathType::Lrl,
+    ];
+
+    /// The segments of the path in the order they are driven.
+    pub fn segments(self) -> [Segment; 3] {
+        use Segment::{Left, Right, Straight};
+        match self {
+            PathType::Lsl => [Left, Straight, Left],
+            PathType::Rsr => [Right, Straight, Right],
+            PathType::Lsr => [Left, Straight, Right],
+            PathType::Rsl => [Right, Straight, Left],
+            PathType::Rlr => [Right, Left, Right],
+            PathType::Lrl => [Left, Right, Left],
+        }
+    }
+}
+
+/// A shortest path between two poses.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DubinsPath {
+    pub path_type: PathType,
+    /// The length of each segment, in the same units as the poses.
+    pub lengths: [f64; 3],
+}
+
+impl DubinsPath {
+    /// The length of the whole path.
+    pub fn length(&self) -> f64 {
+        self.lengths.iter().sum()
+    }
+}
+
+/// Finds the shortest path from `from` to `to` for a vehicle that turns no tighter
+/// than `radius`.
+///
+/// # Panics
+///
+/// Panics if `radius` is not positive.
+pub fn shortest_path(from: Pose, to: Pose, radius: f64) -> DubinsPath {
+    assert!(radius > 0.0, "the turning radius must be positive");
+    let (dx, dy) = (to.x - from.x, to.y - from.y);
+    // In the frame where the line between the poses is the x axis, scaled to a unit
+    // turning radius
+    let d = dx.hypot(dy) / radius;
+    let theta = if d > 0.0 { dy.atan2(dx) } else { 0.0 };
+    let alpha = normalize(from.heading - theta);
+    let beta = normalize(to.heading - theta);
+
+    PathType::ALL
+        .into_iter()
+        .filter_map(|path_type| {
+            let [t, p, q] = unit_lengths(path_type, alpha, beta, d)?;
+            Some(DubinsPath {
+                path_type,
+                lengths: [t * radius, p * radius, q * radius],
+            })
+        })
+        .min_by(|a, b| a.length().total_cmp(&b.length()))
+        .expect("an LSL path joins any two poses")
+}
+
+/// The length of the shortest path from `from` to `to` for a vehicle that turns no
+/// tighter than `radius`.
+///
+/// # Panics
+///
+/// Panics if `radius` is not positive.
+pub fn dubins_distance(from: Pose, to: Pose, radius: f64) -> f64 {
+    shortest_path(from, to, radius).length()
+}
+
+/// Builds the matrix of [`dubins_distance`]s between every pair of `poses`, to solve
+/// with [`DistanceMatrix::solve`]. It is not symmetric, since driving the other way
+/// needs different turns.
+///
+/// # Panics
+///
+/// Panics if `radius` is not positive.
+pub fn distance_matrix(poses: &[Pose], radius: f64) -> DistanceMatrix {
+    DistanceMatrix::from_fn(poses.len(), |from, to| {
+        dubins_distance(poses[from], poses[to], radius)
+    })
+}
+
+/// Wraps an angle into [0, 2π).
+fn normalize(angle: f64) -> f64 {
+    angle.rem_euclid(TAU)
+}
+
+/// The lengths of the segments of `path_type` at a unit turning radius, where the
+/// poses are `d` apart along the x axis with headings `alpha` and `beta`, or `None`
+/// if no path of that type joins them.
+fn unit_lengths(path_type: PathType, alpha: f64, beta: f64, d: f64) -> Option<[f64; 3]> {
+    let (sa, ca) = alpha.sin_cos();
+    let (sb, cb) = beta.sin_cos();
+    let cos_ab = (alpha - beta).cos();
+    match path_type {
+        PathType::Lsl => {
+            let p_squared = 2.0 + d * d - 2.0 * cos_ab + 2.0 * d * (sa - sb);
+            let angle = (cb - ca).atan2(d + sa - sb);
+            (p_squared >= 0.0).then(|| {
+                [
+                    normalize(angle - alpha),
+                    p_squared.sqrt(),
+                    normalize(beta - angle),
+                ]
+            })
+        }
+        PathType::Rsr => {
+            let p_squared = 2.0 + d * d - 2.0 * cos_ab + 2.0 * d * (sb - sa);
+            let angle = (ca - cb).atan2(d - sa + sb);
+            (p_squared >= 0.0).then(|| {
+                [
+                    normalize(alpha - angle),
+                    p_squared.sqrt(),
+                    normalize(angle - beta),
+                ]
+            })
+        }
+        PathType::Lsr => {
+            let p_squared = -2.0 + d * d + 2.0 * cos_ab + 2.0 * d * (sa + sb);
+            (p_squared >= 0.0).then(|| {
+                let p = p_squared.sqrt();
+                let angle = (-ca - cb).atan2(d + sa + sb) - (-2.0f64).atan2(p);
+                [normalize(angle - alpha), p, normalize(angle - beta)]
+            })
+        }
+        PathType::Rsl => {
+            let p_squared = -2.0 + d * d + 2.0 * cos_ab - 2.0 * d * (sa + sb);
+            (p_squared >= 0.0).then(|| {
+                let p = p_squared.sqrt();
+                let angle = (ca + cb).atan2(d - sa - sb) - 2.0f64.atan2(p);
+                [normalize(alpha - angle), p, normalize(beta - angle)]
+            })
+        }
+        PathType::Rlr => {
+            let cos_p = (6.0 - d * d + 2.0 * cos_ab + 2.0 * d * (sa - sb)) / 8.0;
+            (cos_p.abs() <= 1.0).then(|| {
+                let p = normalize(TAU - cos_p.acos());
+                let t = normalize(alpha - (ca - cb).atan2(d - sa + sb) + p / 2.0);
+                [t, p, normalize(alpha - beta - t + p)]
+            })
+        }
+        PathType::Lrl => {
+            let cos_p = (6.0 - d * d + 2.0 * cos_ab + 2.0 * d * (sb - sa)) / 8.0;
+            (cos_p.abs() <= 1.0).then(|| {
+                let p = normalize(TAU - cos_p.acos());
+                let t = normalize(-alpha - (ca - cb).atan2(d + sa - sb) + p / 2.0);
+                [t, p, normalize(beta - alpha - t + p)]
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use crate::rng::SplitMix64;
+
+    use super::*;
+
+    /// Drives `path` from `from`, returning where the vehicle ends up.
+    fn follow(from: Pose, path: &DubinsPath, radius: f64) -> Pose {
+        let mut pose = from;
+        for (segment, length) in path.path_type.segments().into_iter().zip(path.lengths) {
+            let turn = match segment {
+                Segment::Left => length / radius,
+                Segment::Straight => 0.0,
+                Segment::Right => -length / radius,
+            };
+            if turn == 0.0 {
+                pose.x += length * pose.heading.cos();
+                pose.y += length * pose.heading.sin();
+            } else {
+                // Around the center of the turning circle, to the side being turned to
+                let side = radius * turn.signum();
+                let (cx, cy) = (
+                    pose.x - side * pose.heading.sin(),
+                    pose.y + side * pose.heading.cos(),
+                );
+                pose.heading += turn;
+                pose.x = cx + side * pose.heading.sin();
+                pose.y = cy - side * pose.heading.cos();
+            }
+        }
+        pose
+    }
+
+    fn assert_reaches(from: Pose, to: Pose, radius: f64) {
+        let path = shortest_path(from, to, radius);
+        let reached = follow(from, &path, radius);
+        let turned = normalize(reached.heading - to.heading);
+        assert!(
+            (reached.x - to.x).abs() < 1e-6
+                && (reached.y - to.y).abs() < 1e-6
+                && turned.min(TAU - turned) < 1e-6,
+            "{path:?} from {from:?} reaches {reached:?}, not {to:?}"
+        );
+    }
+
+    #[test]
+    fn test_dubins_distance() {
+        let origin = Pose::new(0.0, 0.0, 0.0);
+        assert_eq!(dubins_distance(origin, origin, 1.0), 0.0);
+        // Straight ahead
+        assert!((dubins_distance(origin, Pose::new(10.0, 0.0, 0.0), 1.0) - 10.0).abs() < 1e-9);
+        // A U-turn is half a turning circle
+        let path = shortest_path(origin, Pose::new(0.0, 4.0, PI), 2.0);
+        assert!((path.length() - 2.0 * PI).abs() < 1e-9);
+        assert_eq!(path.path_type.segments()[0], Segment::Left);
+        // Directly behind, facing the same way, means turning around and back again
+        let behind = dubins_distance(origin, Pose::new(-1.0, 0.0, 0.0), 1.0);
+        assert!(behind > 2.0 * PI);
+    }
+
+    #[test]
+    fn test_paths_reach_their_targets() {
+        let mut rng = SplitMix64::new(17);
+        for _ in 0..500 {
+            let mut pose = || {
+                Pose::new(
+                    rng.unit() * 20.0 - 10.0,
+                    rng.unit() * 20.0 - 10.0,
+                    rng.unit() * TAU,
+                )
+            };
+            let (from, to) = (pose(), pose());
+            let radius = 0.5 + rng.unit() * 3.0;
+            assert_reaches(from, to, radius);
+            // No shorter than a straight line
+            let straight = (to.x - from.x).hypot(to.y - from.y);
+            assert!(dubins_distance(from, to, radius) >= straight - 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_distance_matrix() {
+        let poses = [
+            Pose::new(0.0, 0.0, 0.0),
+            Pose::new(10.0, 0.0, 0.0),
+            Pose::new(10.0, 10.0, PI),
+        ];
+        let matrix = distance_matrix(&poses, 1.0);
+        assert!((matrix.get(0, 1) - 10.0).abs() < 1e-9);
+        assert!(matrix.get(1, 0) > matrix.get(0, 1));
+        let route = matrix.solve(0, 0);
+        assert_eq!(route.len(), 4);
+    }
+
+    #[test]
+    #[should_panic(expected = "radius must be positive")]
+    fn test_zero_radius() {
+        dubins_distance(Pose::new(0.0, 0.0, 0.0), Pose::new(1.0, 0.0, 0.0), 0.0);
+    }
+}
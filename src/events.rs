@@ -0,0 +1,331 @@
+//! A record of what a solver did, kept for analysis after the fact, such as why a
+//! heuristic stopped improving, and replayable into anything that watches a live
+//! search.
+//!
+//! With the `serde` feature a log serializes to JSON as a list of events tagged by
+//! kind:
+//!
+//! ```json
+//! [
+//!   {"event": "new_best", "at": 1, "distance": 42.0, "route": [0, 2, 1, 0]},
+//!   {"event": "pruned", "at": 2, "distance": 44.5, "bound": 42.0}
+//! ]
+//! ```
+
+use std::ops::ControlFlow;
+
+use crate::{
+    search::{Improvement, Observer},
+    Route,
+};
+
+/// Something that happened during a solve.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(tag = "event", rename_all = "snake_case")
+)]
+pub enum SolveEvent {
+    /// A route shorter than every one before it.
+    NewBest {
+        /// How many routes the solver had examined, including this one.
+        at: u64,
+        distance: f64,
+        route: Route,
+    },
+    /// A route or partial route given up on, because its distance was no shorter than
+    /// `bound`, the best known when it was examined.
+    Pruned { at: u64, distance: f64, bound: f64 },
+}
+
+impl SolveEvent {
+    /// How many routes the solver had examined when the event happened.
+    pub fn at(&self) -> u64 {
+        match self {
+            SolveEvent::NewBest { at, .. } | SolveEvent::Pruned { at, .. } => *at,
+        }
+    }
+}
+
+/// The events of a solve, in the order they happened.
+///
+/// A log observes [`Problem::search`](crate::Problem::search) directly, and
+/// [`iterated_local_search_logged`](crate::ils::iterated_local_search_logged) records
+/// into one.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent)
+)]
+pub struct SolveLog {
+    pub events: Vec<SolveEvent>,
+}
+
+impl SolveLog {
+    pub fn new() -> Self {
+        SolveLog::default()
+    }
+
+    pub fn record(&mut self, event: SolveEvent) {
+        self.events.push(event);
+    }
+
+    /// The last new best route and its distance, which is the solver's answer.
+    pub fn best(&self) -> Option<(&Route, f64)> {
+        self.events.iter().rev().find_map(|event| match event {
+            SolveEvent::NewBest {
+                route, distance, ..
+            } => Some((route, *distance)),
+            SolveEvent::Pruned { .. } => None,
+        })
+    }
+
+    /// How many routes were examined after the last new best, which for a heuristic is
+    /// how long it was stuck before it stopped.
+    pub fn stalled_for(&self) -> u64 {
+        let last = self.events.last().map_or(0, SolveEvent::at);
+        let best = self
+            .events
+            .iter()
+            .rev()
+            .find(|event| matches!(event, SolveEvent::NewBest { .. }))
+            .map_or(0, SolveEvent::at);
+        last - best
+    }
+
+    /// Feeds the log to `observer` as the solve unfolded: each new best as an
+    /// improvement, and each event as a route examined. Progress displays and other
+    /// watchers of a live search can so show a recorded one.
+    ///
+    /// # Returns
+    ///
+    /// [`ControlFlow::Break`] if the observer stopped the replay
+    pub fn replay(&self, observer: &mut impl Observer) -> ControlFlow<()> {
+        let total = self.events.last().map(SolveEvent::at);
+        for event in &self.events {
+            if let SolveEvent::NewBest {
+                at,
+                distance,
+                route,
+            } = event
+            {
+                observer.improved(&Improvement {
+                    route: route.clone(),
+                    distance: *distance,
+                    examined: *at,
+                })?;
+            }
+            observer.examined(event.at(), total)?;
+        }
+        ControlFlow::Continue(())
+    }
+
+    /// The best distance after each route examined, counting the first route as
+    /// iteration 0.
+    pub fn trajectory(&self) -> Trajectory {
+        let mut trajectory = Trajectory::new();
+        for event in &self.events {
+            let iteration = event.at().saturating_sub(1);
+            match event {
+                SolveEvent::NewBest { distance, .. } => trajectory.record(iteration, *distance),
+                SolveEvent::Pruned { bound, .. } => trajectory.record(iteration, *bound),
+            }
+        }
+        trajectory
+    }
+
+    /// Serializes the log as JSON.
+    #[cfg(feature = "serde")]
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("logs always serialize")
+    }
+
+    /// Reads a log written by [`to_json`](SolveLog::to_json).
+    #[cfg(feature = "serde")]
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+}
+
+/// How the best distance of an iterative solver fell over its iterations, for plotting
+/// convergence curves and comparing them between solvers.
+///
+/// Iteration 0 is the starting route. Only the iterations that found a new best are
+/// kept, so the curve is a step function holding each distance until the next point.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Trajectory {
+    /// Each new best distance and the iteration that found it, in order.
+    pub points: Vec<(u64, f64)>,
+    /// The last iteration the solver ran, where the curve ends.
+    pub iterations: u64,
+}
+
+impl Trajectory {
+    pub fn new() -> Self {
+        Trajectory::default()
+    }
+
+    /// Notes that after `iteration` the best distance was `best`, adding a point if it
+    /// is shorter than every one before.
+    pub fn record(&mut self, iteration: u64, best: f64) {
+        self.iterations = self.iterations.max(iteration);
+        if self.points.last().is_none_or(|&(_, last)| best < last) {
+            self.points.push((iteration, best));
+        }
+    }
+
+    /// The best distance after `iteration`, or `None` before the starting route.
+    pub fn best_at(&self, iteration: u64) -> Option<f64> {
+        let found = self.points.partition_point(|&(at, _)| at <= iteration);
+        found.checked_sub(1).map(|index| self.points[index].1)
+    }
+
+    /// The best distance the solver found.
+    pub fn final_distance(&self) -> Option<f64> {
+        self.points.last().map(|&(_, distance)| distance)
+    }
+
+    /// The curve as `iteration,best_distance` CSV with a header, one row per point and
+    /// one more at the last iteration so a plot runs to the end.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("iteration,best_distance\n");
+        for (iteration, distance) in &self.points {
+            csv.push_str(&format!("{iteration},{distance}\n"));
+        }
+        if let Some(&(last, distance)) = self.points.last() {
+            if last < self.iterations {
+                csv.push_str(&format!("{},{distance}\n", self.iterations));
+            }
+        }
+        csv
+    }
+}
+
+/// A solver's route together with how it converged on it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Traced {
+    pub route: Route,
+    pub trajectory: Trajectory,
+}
+
+/// A log records each improvement [`Problem::search`](crate::Problem::search) finds.
+impl Observer for SolveLog {
+    fn improved(&mut self, improvement: &Improvement) -> ControlFlow<()> {
+        self.record(SolveEvent::NewBest {
+            at: improvement.examined,
+            distance: improvement.distance,
+            route: improvement.route.clone(),
+        });
+        ControlFlow::Continue(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Metric, Point, Problem};
+
+    use super::*;
+
+    fn problem() -> Problem {
+        Problem::new(
+            (0..6)
+                .map(|i| Point::new(f64::from(i * 5 % 7), f64::from(i * i % 4)))
+                .collect(),
+            Metric::Euclidean,
+        )
+    }
+
+    #[test]
+    fn test_log_search() {
+        let problem = problem();
+        let mut log = SolveLog::new();
+        let best = problem.search(&mut log);
+        assert_eq!(log.best(), Some((&best.route, best.distance)));
+        assert!(log
+            .events
+            .windows(2)
+            .all(|pair| pair[0].at() < pair[1].at()));
+        assert_eq!(log.stalled_for(), 0);
+
+        // Replaying into a fresh log reproduces it
+        let mut replayed = SolveLog::new();
+        assert!(log.replay(&mut replayed).is_continue());
+        assert_eq!(replayed, log);
+    }
+
+    #[test]
+    fn test_stalled_for() {
+        let mut log = SolveLog::new();
+        assert_eq!(log.best(), None);
+        assert_eq!(log.stalled_for(), 0);
+        let route = Route::new(vec![0, 1, 0]);
+        log.record(SolveEvent::NewBest {
+            at: 3,
+            distance: 2.0,
+            route: route.clone(),
+        });
+        for at in 4..10 {
+            log.record(SolveEvent::Pruned {
+                at,
+                distance: 2.5,
+                bound: 2.0,
+            });
+        }
+        assert_eq!(log.best(), Some((&route, 2.0)));
+        assert_eq!(log.stalled_for(), 6);
+
+        // The replay stops when the observer does
+        let mut improvements = 0;
+        let flow = log.replay(&mut |_: &Improvement| {
+            improvements += 1;
+            ControlFlow::Break(())
+        });
+        assert!(flow.is_break());
+        assert_eq!(improvements, 1);
+    }
+
+    #[test]
+    fn test_trajectory() {
+        let mut trajectory = Trajectory::new();
+        assert_eq!(trajectory.best_at(5), None);
+        trajectory.record(0, 10.0);
+        trajectory.record(1, 10.0);
+        trajectory.record(3, 7.5);
+        trajectory.record(6, 7.5);
+        assert_eq!(trajectory.points, [(0, 10.0), (3, 7.5)]);
+        assert_eq!(trajectory.iterations, 6);
+        assert_eq!(trajectory.best_at(2), Some(10.0));
+        assert_eq!(trajectory.best_at(3), Some(7.5));
+        assert_eq!(trajectory.final_distance(), Some(7.5));
+        assert_eq!(
+            trajectory.to_csv(),
+            "iteration,best_distance\n0,10\n3,7.5\n6,7.5\n"
+        );
+
+        // A log's trajectory follows its new bests
+        let mut log = SolveLog::new();
+        let best = problem().search(&mut log);
+        let trajectory = log.trajectory();
+        assert_eq!(trajectory.points.len(), log.events.len());
+        assert_eq!(trajectory.final_distance(), Some(best.distance));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_json_round_trip() {
+        let mut log = SolveLog::new();
+        problem().search(&mut log);
+        log.record(SolveEvent::Pruned {
+            at: 1000,
+            distance: 30.0,
+            bound: 20.0,
+        });
+        let json = log.to_json();
+        assert!(json.contains(r#""event": "new_best""#));
+        assert!(json.contains(r#""event": "pruned""#));
+        assert_eq!(SolveLog::from_json(&json).unwrap(), log);
+    }
+}
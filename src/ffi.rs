@@ -0,0 +1,218 @@
+//! A C interface to the solvers.
+//!
+//! The matching header is `include/coding_compairson.h` (regenerate it with
+//! `cbindgen --config cbindgen.toml --output include/coding_compairson.h`). Build a
+//! library C can link against with
+//! `cargo rustc --release --features ffi --crate-type cdylib` (or `staticlib`).
+//!
+//! Every function returns one of the `TSP_*` status codes. A panic is caught and
+//! reported as [`TSP_ERROR_PANIC`] rather than unwinding into the caller.
+
+use std::{
+    panic::{self, AssertUnwindSafe},
+    slice,
+};
+
+use crate::{hand_rolled_traveling_salesman_owned, Metric, Point, Problem};
+
+/// The call succeeded.
+pub const TSP_OK: i32 = 0;
+/// A required pointer argument was null.
+pub const TSP_ERROR_NULL_POINTER: i32 = -1;
+/// `start` or `end` was not a valid index.
+pub const TSP_ERROR_INVALID_INDEX: i32 = -2;
+/// The metric was not one of the `TSP_METRIC_*` constants.
+pub const TSP_ERROR_INVALID_METRIC: i32 = -3;
+/// The solver panicked; `out_route` may have been partly written.
+pub const TSP_ERROR_PANIC: i32 = -4;
+
+/// Straight-line distance in the plane.
+pub const TSP_METRIC_EUCLIDEAN: u32 = 0;
+/// Sum of the absolute coordinate differences.
+pub const TSP_METRIC_MANHATTAN: u32 = 1;
+/// Great-circle distance, with `x` as latitude and `y` as longitude in degrees.
+pub const TSP_METRIC_HAVERSINE: u32 = 2;
+/// Straight-line distance rounded to the nearest integer, as TSPLIB's `EUC_2D`.
+pub const TSP_METRIC_ROUNDED_EUCLIDEAN: u32 = 3;
+
+/// A destination passed to [`tsp_solve_points`].
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct TspPoint {
+    pub x: f64,
+    pub y: f64,
+}
+
+/// Finds the shortest route through `destinations` from `start` to `end`, where the
+/// distance between two values is their absolute difference.
+///
+/// On success `out_route` holds `len + 2` values: `start`, the destinations in
+/// visiting order, then `end`.
+///
+/// # Safety
+///
+/// `destinations` must point to `len` readable values (it may be null when `len` is
+/// 0) and `out_route` must point to `len + 2` writable values.
+#[no_mangle]
+pub unsafe extern "C" fn tsp_solve_i32(
+    destinations: *const i32,
+    len: usize,
+    start: i32,
+    end: i32,
+    out_route: *mut i32,
+) -> i32 {
+    catch_panic(|| {
+        if out_route.is_null() || (destinations.is_null() && len > 0) {
+            return TSP_ERROR_NULL_POINTER;
+        }
+        let destinations = if len == 0 {
+            &[]
+        } else {
+            // SAFETY: non-null and `len` values long per the caller's contract
+            unsafe { slice::from_raw_parts(destinations, len) }
+        };
+        // SAFETY: non-null and `len + 2` values long per the caller's contract
+        let out_route = unsafe { slice::from_raw_parts_mut(out_route, len + 2) };
+
+        out_route.copy_from_slice(&hand_rolled_traveling_salesman_owned(
+            destinations,
+            start,
+            end,
+        ));
+        TSP_OK
+    })
+}
+
+/// Finds the shortest route through every point from `points[start]` to `points[end]`
+/// under `metric` (one of the `TSP_METRIC_*` constants).
+///
+/// On success `out_route` holds the point indices in visiting order: `len` values,
+/// or `len + 1` when `start == end` since the round trip returns to its start.
+///
+/// # Safety
+///
+/// `points` must point to `len` readable points and `out_route` must point to
+/// `len + 1` writable values.
+#[no_mangle]
+pub unsafe extern "C" fn tsp_solve_points(
+    points: *const TspPoint,
+    len: usize,
+    start: usize,
+    end: usize,
+    metric: u32,
+    out_route: *mut usize,
+) -> i32 {
+    catch_panic(|| {
+        if points.is_null() || out_route.is_null() {
+            return TSP_ERROR_NULL_POINTER;
+        }
+        if start >= len || end >= len {
+            return TSP_ERROR_INVALID_INDEX;
+        }
+        let metric = match metric {
+            TSP_METRIC_EUCLIDEAN => Metric::Euclidean,
+            TSP_METRIC_MANHATTAN => Metric::Manhattan,
+            TSP_METRIC_HAVERSINE => Metric::Haversine,
+            TSP_METRIC_ROUNDED_EUCLIDEAN => Metric::RoundedEuclidean,
+            _ => return TSP_ERROR_INVALID_METRIC,
+        };
+
+        // SAFETY: non-null and `len` points long per the caller's contract
+        let points = unsafe { slice::from_raw_parts(points, len) };
+        // SAFETY: non-null and `len + 1` values long per the caller's contract
+        let out_route = unsafe { slice::from_raw_parts_mut(out_route, len + 1) };
+
+        let mut problem = Problem::new(
+            points.iter().map(|p| Point::new(p.x, p.y)).collect(),
+            metric,
+        );
+        problem.start = start;
+        problem.end = end;
+
+        for (out, index) in out_route.iter_mut().zip(problem.solve()) {
+            *out = index;
+        }
+        TSP_OK
+    })
+}
+
+/// Runs the body of an exported function, returning [`TSP_ERROR_PANIC`] if it panics,
+/// as unwinding across the C boundary would abort the host.
+fn catch_panic(body: impl FnOnce() -> i32) -> i32 {
+    // Nothing observes the arguments after a panic but the caller, who is told of it
+    panic::catch_unwind(AssertUnwindSafe(body)).unwrap_or(TSP_ERROR_PANIC)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ptr;
+
+    use super::*;
+
+    #[test]
+    fn test_tsp_solve_i32() {
+        let destinations = [3, 1, 2];
+        let mut route = [0; 5];
+
+        let status = unsafe { tsp_solve_i32(destinations.as_ptr(), 3, 0, 4, route.as_mut_ptr()) };
+        assert_eq!(status, TSP_OK);
+        assert_eq!(route, [0, 1, 2, 3, 4]);
+
+        let mut route = [0; 2];
+        let status = unsafe { tsp_solve_i32(ptr::null(), 0, 5, 6, route.as_mut_ptr()) };
+        assert_eq!(status, TSP_OK);
+        assert_eq!(route, [5, 6]);
+    }
+
+    #[test]
+    fn test_tsp_solve_points() {
+        let points = [
+            TspPoint { x: 0.0, y: 0.0 },
+            TspPoint { x: 2.0, y: 0.0 },
+            TspPoint { x: 1.0, y: 0.0 },
+            TspPoint { x: 3.0, y: 0.0 },
+        ];
+        let mut route = [usize::MAX; 5];
+
+        let status = unsafe {
+            tsp_solve_points(
+                points.as_ptr(),
+                4,
+                0,
+                3,
+                TSP_METRIC_EUCLIDEAN,
+                route.as_mut_ptr(),
+            )
+        };
+        assert_eq!(status, TSP_OK);
+        // An open route fills one slot fewer than the buffer
+        assert_eq!(route, [0, 2, 1, 3, usize::MAX]);
+    }
+
+    #[test]
+    fn test_ffi_errors() {
+        let points = [TspPoint { x: 0.0, y: 0.0 }];
+        let mut route = [0; 2];
+
+        let solve = |start, metric, out: *mut usize| unsafe {
+            tsp_solve_points(points.as_ptr(), 1, start, 0, metric, out)
+        };
+        assert_eq!(
+            solve(1, TSP_METRIC_EUCLIDEAN, route.as_mut_ptr()),
+            TSP_ERROR_INVALID_INDEX
+        );
+        assert_eq!(solve(0, 9, route.as_mut_ptr()), TSP_ERROR_INVALID_METRIC);
+        assert_eq!(
+            solve(0, TSP_METRIC_EUCLIDEAN, ptr::null_mut()),
+            TSP_ERROR_NULL_POINTER
+        );
+        assert_eq!(solve(0, TSP_METRIC_EUCLIDEAN, route.as_mut_ptr()), TSP_OK);
+        assert_eq!(route, [0, 0]);
+    }
+
+    #[test]
+    fn test_catch_panic() {
+        assert_eq!(catch_panic(|| TSP_OK), TSP_OK);
+        assert_eq!(catch_panic(|| panic!("solver bug")), TSP_ERROR_PANIC);
+    }
+}
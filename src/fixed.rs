@@ -0,0 +1,204 @@
+//! A fixed-point distance, for results that are the same on every platform.
+//!
+//! Floating-point sums depend on the order legs are added in, so two solvers, or one
+//! solver compiled differently, can disagree in the last bit about which of two routes
+//! is shorter. Integer arithmetic is exact and associative, so once distances are
+//! rounded to [`FixedPoint`] every comparison, and so every route chosen, is
+//! reproducible.
+
+use std::{
+    fmt,
+    iter::Sum,
+    ops::{Add, Sub},
+};
+
+use num_traits::{CheckedAdd, Zero};
+
+use crate::{DistanceMatrix, Problem};
+
+/// A distance stored as a whole number of millionths, in an `i64`.
+///
+/// That is micrometers for distances in meters, up to about nine billion kilometers.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FixedPoint(i64);
+
+impl FixedPoint {
+    /// How many units of the underlying `i64` make one.
+    pub const SCALE: i64 = 1_000_000;
+
+    /// Rounds `value` to the nearest millionth.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `value` is not finite or too large to represent.
+    pub fn from_f64(value: f64) -> Self {
+        let scaled = (value * Self::SCALE as f64).round();
+        assert!(
+            scaled.is_finite() && scaled.abs() < i64::MAX as f64,
+            "{value} cannot be a fixed-point distance"
+        );
+        FixedPoint(scaled as i64)
+    }
+
+    /// The distance as a float, which may not be exact.
+    pub fn to_f64(self) -> f64 {
+        self.0 as f64 / Self::SCALE as f64
+    }
+
+    /// A distance of `raw` millionths.
+    pub fn from_raw(raw: i64) -> Self {
+        FixedPoint(raw)
+    }
+
+    /// The number of millionths.
+    pub fn raw(self) -> i64 {
+        self.0
+    }
+}
+
+impl Add for FixedPoint {
+    type Output = FixedPoint;
+
+    fn add(self, other: FixedPoint) -> FixedPoint {
+        FixedPoint(self.0 + other.0)
+    }
+}
+
+impl Sub for FixedPoint {
+    type Output = FixedPoint;
+
+    fn sub(self, other: FixedPoint) -> FixedPoint {
+        FixedPoint(self.0 - other.0)
+    }
+}
+
+impl Zero for FixedPoint {
+    fn zero() -> FixedPoint {
+        FixedPoint(0)
+    }
+
+    fn is_zero(&self) -> bool {
+        self.0 == 0
+    }
+}
+
+impl CheckedAdd for FixedPoint {
+    fn checked_add(&self, other: &FixedPoint) -> Option<FixedPoint> {
+        self.0.checked_add(other.0).map(FixedPoint)
+    }
+}
+
+impl Sum for FixedPoint {
+    fn sum<I: Iterator<Item = FixedPoint>>(iter: I) -> FixedPoint {
+        iter.fold(FixedPoint::zero(), Add::add)
+    }
+}
+
+impl From<FixedPoint> for f64 {
+    fn from(distance: FixedPoint) -> f64 {
+        distance.to_f64()
+    }
+}
+
+impl fmt::Display for FixedPoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Exactly, from the digits rather than through a float
+        let sign = if self.0 < 0 { "-" } else { "" };
+        let magnitude = self.0.unsigned_abs();
+        let scale = Self::SCALE as u64;
+        write!(f, "{sign}{}.{:06}", magnitude / scale, magnitude % scale)
+    }
+}
+
+impl DistanceMatrix {
+    /// Rounds every distance to a [`FixedPoint`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if a distance is not finite or too large to represent.
+    pub fn to_fixed_point(&self) -> DistanceMatrix<FixedPoint> {
+        self.map(|&distance| FixedPoint::from_f64(distance))
+    }
+}
+
+impl Problem {
+    /// The distances between every pair of destinations, rounded to [`FixedPoint`].
+    ///
+    /// The metric itself is still computed in floating point, and
+    /// [`Metric::Haversine`](crate::Metric::Haversine)'s trigonometry may differ in the
+    /// last bit between platforms; rounding to a millionth hides that unless a distance
+    /// falls right on the boundary between two.
+    pub fn fixed_point_matrix(&self) -> DistanceMatrix<FixedPoint> {
+        self.distance_matrix().to_fixed_point()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{rng::SplitMix64, Metric, Point};
+
+    use super::*;
+
+    #[test]
+    fn test_conversions() {
+        assert_eq!(FixedPoint::from_f64(1.5).raw(), 1_500_000);
+        assert_eq!(FixedPoint::from_f64(-0.000_000_4).raw(), 0);
+        assert_eq!(FixedPoint::from_f64(0.000_000_6).raw(), 1);
+        assert_eq!(FixedPoint::from_raw(2_250_000).to_f64(), 2.25);
+        assert_eq!(f64::from(FixedPoint::from_f64(3.0)), 3.0);
+        assert_eq!(FixedPoint::from_f64(12.5).to_string(), "12.500000");
+        assert_eq!(FixedPoint::from_raw(-1).to_string(), "-0.000001");
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot be a fixed-point distance")]
+    fn test_not_finite() {
+        FixedPoint::from_f64(f64::NAN);
+    }
+
+    #[test]
+    fn test_sums_are_exact() {
+        // Floats round differently depending on the order they are added in
+        let [a, b, c] = [0.1, 0.2, 0.3];
+        assert_ne!((a + b) + c, a + (b + c));
+        let [a, b, c] = [a, b, c].map(FixedPoint::from_f64);
+        assert_eq!((a + b) + c, a + (b + c));
+        assert_eq!(
+            [a, b, c].into_iter().sum::<FixedPoint>(),
+            FixedPoint::from_f64(0.6)
+        );
+        assert_eq!(
+            FixedPoint::from_raw(i64::MAX).checked_add(&FixedPoint::from_raw(1)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_fixed_point_matrix() {
+        let mut rng = SplitMix64::new(23);
+        let problem = Problem::new(
+            (0..8)
+                .map(|_| Point::new(rng.unit() * 100.0, rng.unit() * 100.0))
+                .collect(),
+            Metric::Euclidean,
+        );
+        let matrix = problem.fixed_point_matrix();
+        let route = matrix.solve(0, 0);
+        // Rounding each leg moves the total by at most half a millionth per leg
+        let error = (matrix.route_distance(&route).to_f64() - problem.route_distance(&route)).abs();
+        assert!(error <= 0.5e-6 * route.len() as f64);
+        assert!(
+            (problem.route_distance(&route) - problem.route_distance(&problem.solve())).abs()
+                < 1e-5
+        );
+        // The order of the legs does not change their sum
+        let reversed = route.iter().rev().copied().collect::<Vec<_>>();
+        assert_eq!(
+            matrix.route_distance(&route),
+            reversed
+                .windows(2)
+                .map(|leg| matrix.get(leg[1], leg[0]))
+                .sum::<FixedPoint>()
+        );
+    }
+}
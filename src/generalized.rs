@@ -0,0 +1,126 @@
+//! The generalized travelling salesman problem, where destinations come in groups and
+//! a route visits exactly one member of each, such as one branch of each bank.
+
+use itertools::Itertools;
+
+use crate::{Problem, Route};
+
+/// Finds the shortest route from `start` to `end` that visits exactly one member of
+/// each group, by exhaustive search over the orders of the groups, choosing the best
+/// member of each for every order by a shortest path through the groups in turn.
+/// Destinations in no group are not visited.
+///
+/// # Arguments
+///
+/// * `problem` - The destinations and where the route starts and ends
+/// * `groups` - The groups of destination indices, none of them empty and none
+///   containing the start or end
+///
+/// # Returns
+///
+/// The shortest route, visiting one member of each group
+///
+/// # Panics
+///
+/// Panics if a group is empty or contains the start or end.
+pub fn solve_generalized(problem: &Problem, groups: &[Vec<usize>]) -> Route {
+    assert!(
+        groups.iter().all(|group| !group.is_empty()),
+        "every group needs a member to visit"
+    );
+    assert!(
+        groups
+            .iter()
+            .flatten()
+            .all(|&member| member != problem.start && member != problem.end),
+        "the start and end cannot be in a group"
+    );
+    let matrix = problem.distance_matrix();
+
+    let (_, stops) = (0..groups.len())
+        .permutations(groups.len())
+        .map(|order| {
+            // distances[m] is the shortest distance from the start through the groups so
+            // far to member m of the latest, and choices[layer][m] the member before it
+            let mut layer = vec![problem.start];
+            let mut distances = vec![0.0];
+            let mut choices = Vec::with_capacity(order.len());
+            for members in order
+                .iter()
+                .map(|&g| &groups[g])
+                .chain([&vec![problem.end]])
+            {
+                let (next, choice): (Vec<_>, Vec<_>) = members
+                    .iter()
+                    .map(|&member| {
+                        (0..layer.len())
+                            .map(|m| (distances[m] + matrix.get(layer[m], member), m))
+                            .min_by(|a, b| a.0.total_cmp(&b.0))
+                            .expect("layers are not empty")
+                    })
+                    .unzip();
+                layer.clone_from(members);
+                distances = next;
+                choices.push(choice);
+            }
+
+            // Follow the choices back from the end, the only member of the last layer
+            let mut stops = vec![problem.end];
+            let mut m = 0;
+            for layer in (1..choices.len()).rev() {
+                m = choices[layer][m];
+                stops.push(groups[order[layer - 1]][m]);
+            }
+            stops.push(problem.start);
+            stops.reverse();
+            (distances[0], stops)
+        })
+        .min_by(|a, b| a.0.total_cmp(&b.0))
+        .expect("there is at least one order");
+    Route::new(stops)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Metric, Point};
+
+    use super::*;
+
+    #[test]
+    fn test_solve_generalized() {
+        // The depot at the origin, and pairs of alternatives
+        let problem = Problem::new(
+            [
+                (0.0, 0.0),
+                (10.0, 0.0),
+                (1.0, 1.0),
+                (0.0, 10.0),
+                (1.0, 2.0),
+                (5.0, 5.0),
+            ]
+            .map(|(x, y)| Point::new(x, y))
+            .to_vec(),
+            Metric::Euclidean,
+        );
+        let route = solve_generalized(&problem, &[vec![1, 2], vec![3, 4]]);
+        assert_eq!(route.stops(), &[0, 2, 4, 0]);
+
+        // Singleton groups are the travelling salesman problem over their members
+        let route = solve_generalized(&problem, &[vec![1], vec![3], vec![5]]);
+        let tsp = Problem::new(
+            [0, 1, 3, 5].map(|i| problem.points[i]).to_vec(),
+            Metric::Euclidean,
+        );
+        assert!((problem.route_distance(&route) - tsp.route_distance(&tsp.solve())).abs() < 1e-9);
+
+        // With no groups the route goes straight from start to end
+        assert_eq!(solve_generalized(&problem, &[]).stops(), &[0, 0]);
+    }
+
+    #[test]
+    #[should_panic(expected = "start and end")]
+    fn test_group_with_start() {
+        let problem = Problem::new(vec![Point::new(0.0, 0.0); 3], Metric::Euclidean);
+        solve_generalized(&problem, &[vec![0, 1]]);
+    }
+}
@@ -0,0 +1,203 @@
+//! Random problem instances, reproducible from a seed, for benchmarks, fuzzing and
+//! experiments.
+//!
+//! The same seed always gives the same instance on every platform, as the generator
+//! is the crate's own rather than `rand`'s.
+
+use std::{f64::consts::TAU, ops::Range};
+
+use crate::{rng::SplitMix64, DistanceMatrix, Point};
+
+/// The rectangle points are generated in.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bounds {
+    pub min: Point,
+    pub max: Point,
+}
+
+impl Bounds {
+    pub fn new(min: Point, max: Point) -> Self {
+        Bounds { min, max }
+    }
+
+    /// The square from the origin to `(size, size)`.
+    pub fn square(size: f64) -> Self {
+        Bounds::new(Point::new(0.0, 0.0), Point::new(size, size))
+    }
+
+    /// A uniformly random point inside the bounds.
+    fn sample(&self, rng: &mut SplitMix64) -> Point {
+        Point::new(
+            self.min.x + rng.unit() * (self.max.x - self.min.x),
+            self.min.y + rng.unit() * (self.max.y - self.min.y),
+        )
+    }
+
+    fn clamp(&self, point: Point) -> Point {
+        Point::new(
+            point.x.clamp(self.min.x, self.max.x),
+            point.y.clamp(self.min.y, self.max.y),
+        )
+    }
+}
+
+/// `n` points spread uniformly over `bounds`, the usual random Euclidean instance.
+pub fn uniform_points(n: usize, bounds: Bounds, seed: u64) -> Vec<Point> {
+    let mut rng = SplitMix64::new(seed);
+    (0..n).map(|_| bounds.sample(&mut rng)).collect()
+}
+
+/// A round trip over `n` points spread uniformly over a 1000 by 1000 square, the
+/// random Euclidean instance the solvers' tests share.
+#[cfg(test)]
+pub(crate) fn uniform_problem(n: usize, seed: u64) -> crate::Problem {
+    crate::Problem::new(
+        uniform_points(n, Bounds::square(1000.0), seed),
+        crate::Metric::Euclidean,
+    )
+}
+
+/// `n` points gathered around `clusters` centers placed uniformly over `bounds`, like
+/// towns on a map, which suits heuristics differently than uniform points do.
+///
+/// # Arguments
+///
+/// * `n` - The number of points
+/// * `clusters` - The number of centers, at least one
+/// * `spread` - The standard deviation of each point's distance from its center along
+///   each axis; points that would fall outside `bounds` are moved to its edge
+/// * `bounds` - Where centers and points lie
+/// * `seed` - Chooses the instance
+///
+/// # Panics
+///
+/// Panics if `clusters` is zero.
+pub fn clustered_points(
+    n: usize,
+    clusters: usize,
+    spread: f64,
+    bounds: Bounds,
+    seed: u64,
+) -> Vec<Point> {
+    assert!(clusters > 0, "there must be at least one cluster");
+    let mut rng = SplitMix64::new(seed);
+    let centers = (0..clusters)
+        .map(|_| bounds.sample(&mut rng))
+        .collect::<Vec<_>>();
+    (0..n)
+        .map(|_| {
+            let center = centers[rng.below(clusters)];
+            // Box-Muller turns two uniform samples into two normal ones; the first is
+            // never zero, so its logarithm is finite
+            let radius = spread * (-2.0 * (1.0 - rng.unit()).ln()).sqrt();
+            let angle = TAU * rng.unit();
+            bounds.clamp(Point::new(
+                center.x + radius * angle.cos(),
+                center.y + radius * angle.sin(),
+            ))
+        })
+        .collect()
+}
+
+/// An `n` × `n` matrix of distances drawn uniformly from `0.0..max`, with zeros on the
+/// diagonal. Random distances rarely obey the triangle inequality, which makes them a
+/// hard case for heuristics that rely on it.
+///
+/// If `symmetric`, the distance back is the same as the distance there.
+pub fn random_matrix(n: usize, max: f64, symmetric: bool, seed: u64) -> DistanceMatrix {
+    let mut rng = SplitMix64::new(seed);
+    let mut distance = |from: usize, to: usize| {
+        if from == to {
+            0.0
+        } else {
+            rng.unit() * max
+        }
+    };
+    if symmetric {
+        DistanceMatrix::from_symmetric_fn(n, &mut distance)
+    } else {
+        DistanceMatrix::from_fn(n, &mut distance)
+    }
+}
+
+/// `n` integers drawn uniformly from `range`, as destinations on a line for the
+/// generic solvers.
+///
+/// # Panics
+///
+/// Panics if `range` is empty.
+pub fn random_integers(n: usize, range: Range<i32>, seed: u64) -> Vec<i32> {
+    assert!(!range.is_empty(), "the range must not be empty");
+    let mut rng = SplitMix64::new(seed);
+    let width = range.start.abs_diff(range.end) as usize;
+    (0..n)
+        .map(|_| range.start.wrapping_add(rng.below(width) as i32))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_uniform_points() {
+        let bounds = Bounds::new(Point::new(-10.0, 5.0), Point::new(10.0, 6.0));
+        let points = uniform_points(500, bounds, 1);
+        assert_eq!(points.len(), 500);
+        assert!(points
+            .iter()
+            .all(|p| (-10.0..10.0).contains(&p.x) && (5.0..6.0).contains(&p.y)));
+        // Reproducible from the seed, and different for another
+        assert_eq!(points, uniform_points(500, bounds, 1));
+        assert_ne!(points, uniform_points(500, bounds, 2));
+        assert_eq!(
+            uniform_points(3, Bounds::square(1.0), 7)[..],
+            uniform_points(4, Bounds::square(1.0), 7)[..3]
+        );
+    }
+
+    #[test]
+    fn test_clustered_points() {
+        let bounds = Bounds::square(1000.0);
+        let points = clustered_points(400, 4, 10.0, bounds, 3);
+        assert_eq!(points, clustered_points(400, 4, 10.0, bounds, 3));
+        assert!(points
+            .iter()
+            .all(|p| (0.0..=1000.0).contains(&p.x) && (0.0..=1000.0).contains(&p.y)));
+        // Tightly clustered, each point is near many others
+        let near = |p: &Point| {
+            points
+                .iter()
+                .filter(|q| (p.x - q.x).hypot(p.y - q.y) < 50.0)
+                .count()
+        };
+        assert!(points.iter().all(|p| near(p) > 20));
+    }
+
+    #[test]
+    #[should_panic(expected = "at least one cluster")]
+    fn test_no_clusters() {
+        clustered_points(10, 0, 1.0, Bounds::square(1.0), 0);
+    }
+
+    #[test]
+    fn test_random_matrix() {
+        let matrix = random_matrix(20, 5.0, false, 4);
+        assert_eq!(matrix, random_matrix(20, 5.0, false, 4));
+        assert!((0..20).all(|i| matrix.get(i, i) == 0.0));
+        assert!((0..20).all(|i| matrix.row(i).all(|d| (0.0..5.0).contains(&d))));
+        assert!(matrix.to_symmetric().is_none());
+        let matrix = random_matrix(20, 5.0, true, 4);
+        assert!(matrix.is_symmetric());
+        assert_eq!(matrix.get(3, 17), matrix.get(17, 3));
+    }
+
+    #[test]
+    fn test_random_integers() {
+        let integers = random_integers(1000, -99..100, 5);
+        assert!(integers.iter().all(|i| (-99..100).contains(i)));
+        assert!(integers.contains(&-99) && integers.contains(&99));
+        assert_eq!(integers, random_integers(1000, -99..100, 5));
+        assert_eq!(random_integers(5, i32::MIN..i32::MAX, 6).len(), 5);
+    }
+}
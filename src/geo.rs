@@ -0,0 +1,177 @@
+//! Exporting routes over geographic destinations to formats that GPS devices and
+//! mapping tools load directly.
+//!
+//! These expect the problem's points to be latitude/longitude pairs, as used by
+//! [`Metric::Haversine`](crate::Metric::Haversine).
+
+use std::fmt::Write as _;
+
+use crate::{Problem, Route};
+
+impl Route {
+    /// Writes the route as a GPX 1.1 document containing a single `<rte>` whose
+    /// points are named after the destination ids.
+    pub fn to_gpx(&self, problem: &Problem) -> String {
+        let mut gpx = String::from(concat!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>"#,
+            "\n",
+            r#"<gpx version="1.1" creator="coding_compairson" xmlns="http://www.topografix.com/GPX/1/1">"#,
+            "\n  <rte>\n"
+        ));
+        // Writing to a String cannot fail
+        for &stop in self {
+            let point = problem.points[stop];
+            let _ = writeln!(
+                gpx,
+                r#"    <rtept lat="{}" lon="{}"><name>{}</name></rtept>"#,
+                point.x,
+                point.y,
+                escape_xml(&problem.ids[stop]),
+            );
+        }
+        gpx.push_str("  </rte>\n</gpx>\n");
+        gpx
+    }
+
+    /// Writes the route as a KML document with a placemark for each destination
+    /// and a line string following the route.
+    pub fn to_kml(&self, problem: &Problem) -> String {
+        let mut kml = String::from(concat!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>"#,
+            "\n",
+            r#"<kml xmlns="http://www.opengis.net/kml/2.2">"#,
+            "\n  <Document>\n"
+        ));
+
+        // A round trip lists its start twice, but it only needs one placemark
+        let mut placed = vec![false; problem.len()];
+        for &stop in self {
+            if std::mem::replace(&mut placed[stop], true) {
+                continue;
+            }
+            let point = problem.points[stop];
+            // KML coordinates are longitude first
+            let _ = writeln!(
+                kml,
+                "    <Placemark><name>{}</name><Point><coordinates>{},{}</coordinates></Point></Placemark>",
+                escape_xml(&problem.ids[stop]),
+                point.y,
+                point.x,
+            );
+        }
+
+        let coordinates = self
+            .iter()
+            .map(|&stop| {
+                let point = problem.points[stop];
+                format!("{},{}", point.y, point.x)
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+        let _ = writeln!(
+            kml,
+            "    <Placemark><name>Route</name><LineString><coordinates>{coordinates}</coordinates></LineString></Placemark>"
+        );
+        kml.push_str("  </Document>\n</kml>\n");
+        kml
+    }
+
+    /// Encodes the route with Google's encoded polyline algorithm at the standard
+    /// precision of five decimal places, as consumed by web mapping APIs.
+    pub fn to_polyline(&self, problem: &Problem) -> String {
+        let mut polyline = String::new();
+        let mut previous = (0, 0);
+        for &stop in self {
+            let point = problem.points[stop];
+            let current = (
+                (point.x * 1e5).round() as i64,
+                (point.y * 1e5).round() as i64,
+            );
+            encode_polyline_value(current.0 - previous.0, &mut polyline);
+            encode_polyline_value(current.1 - previous.1, &mut polyline);
+            previous = current;
+        }
+        polyline
+    }
+}
+
+/// Appends one signed delta in the polyline encoding: zig-zag the sign into the
+/// lowest bit, then emit five bits at a time, lowest first, offset into printable ASCII.
+fn encode_polyline_value(value: i64, out: &mut String) {
+    let mut value = if value < 0 { !(value << 1) } else { value << 1 } as u64;
+    while value >= 0x20 {
+        out.push(char::from((0x20 | (value & 0x1f)) as u8 + 63));
+        value >>= 5;
+    }
+    out.push(char::from(value as u8 + 63));
+}
+
+/// Escapes the characters that are not allowed verbatim in XML text and attributes.
+pub(crate) fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Metric, Point};
+
+    use super::*;
+
+    fn problem() -> Problem {
+        let mut problem = Problem::new(
+            vec![Point::new(51.5, -0.12), Point::new(48.85, 2.29)],
+            Metric::Haversine,
+        );
+        problem.ids = vec!["London".to_string(), "Paris & back".to_string()];
+        problem
+    }
+
+    #[test]
+    fn test_to_gpx() {
+        let gpx = Route::new(vec![0, 1, 0]).to_gpx(&problem());
+
+        assert!(gpx.starts_with("<?xml"));
+        assert!(gpx.contains(r#"<rtept lat="51.5" lon="-0.12"><name>London</name></rtept>"#));
+        assert!(gpx.contains("<name>Paris &amp; back</name>"));
+        assert_eq!(gpx.matches("<rtept").count(), 3);
+    }
+
+    #[test]
+    fn test_to_polyline() {
+        // The worked example from Google's polyline algorithm documentation
+        let problem = Problem::new(
+            vec![
+                Point::new(38.5, -120.2),
+                Point::new(40.7, -120.95),
+                Point::new(43.252, -126.453),
+            ],
+            Metric::Haversine,
+        );
+
+        let polyline = Route::new(vec![0, 1, 2]).to_polyline(&problem);
+        assert_eq!(polyline, "_p~iF~ps|U_ulLnnqC_mqNvxq`@");
+        assert_eq!(Route::new(vec![]).to_polyline(&problem), "");
+    }
+
+    #[test]
+    fn test_to_kml() {
+        let kml = Route::new(vec![0, 1, 0]).to_kml(&problem());
+
+        assert_eq!(kml.matches("<Point>").count(), 2);
+        assert!(kml.contains("<coordinates>-0.12,51.5</coordinates>"));
+        assert!(kml.contains(
+            "<LineString><coordinates>-0.12,51.5 2.29,48.85 -0.12,51.5</coordinates></LineString>"
+        ));
+    }
+}
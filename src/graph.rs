@@ -0,0 +1,188 @@
+//! Solving over [`petgraph`] graphs whose nodes are the destinations.
+//!
+//! Graphs rarely contain an edge between every pair of nodes, so the distance
+//! between two destinations is taken to be the length of the shortest path
+//! between them.
+
+use std::fmt;
+
+use num_traits::ToPrimitive;
+use petgraph::{
+    algo::{floyd_warshall, BoundedMeasure},
+    graph::{Graph, NodeIndex},
+    EdgeType,
+};
+
+use crate::{DistanceMatrix, Route};
+
+/// An error produced when a graph cannot be solved.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GraphError {
+    /// The graph contains a cycle of negative weight, so shortest paths are undefined.
+    NegativeCycle,
+    /// No route visits every node, as there is no path between two that one would
+    /// have to join.
+    Unreachable { from: NodeIndex, to: NodeIndex },
+    /// `start` or `end` is not a node of the graph.
+    UnknownNode(NodeIndex),
+}
+
+impl fmt::Display for GraphError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GraphError::NegativeCycle => write!(f, "the graph contains a negative cycle"),
+            GraphError::Unreachable { from, to } => write!(
+                f,
+                "node {} cannot be reached from node {}",
+                to.index(),
+                from.index()
+            ),
+            GraphError::UnknownNode(node) => write!(f, "node {} is not in the graph", node.index()),
+        }
+    }
+}
+
+impl std::error::Error for GraphError {}
+
+/// Finds a route that visits every node of `graph`, starting at `start` and ending at
+/// `end`, with `solve`.
+///
+/// Distances between nodes without a direct edge are derived from all-pairs shortest
+/// paths, so consecutive nodes of the returned route may be joined by a path through
+/// other nodes rather than a single edge. In a directed graph some nodes may be
+/// unreachable from others; they are infinitely far apart, which only rules out the
+/// routes that would join them.
+///
+/// # Arguments
+///
+/// * `graph` - A directed or undirected graph whose edge weights are distances
+/// * `start` - The node the route starts at
+/// * `end` - The node the route ends at (may equal `start` for a round trip)
+/// * `solve` - Finds a route through the distances between nodes by index, from the
+///   start's index to the end's, such as [`DistanceMatrix::solve`] for the shortest
+///   route or a heuristic for large graphs
+///
+/// # Returns
+///
+/// The nodes in visiting order, or an error if the route `solve` finds cannot visit
+/// them all.
+pub fn solve_on_graph<N, W, Ty>(
+    graph: &Graph<N, W, Ty>,
+    start: NodeIndex,
+    end: NodeIndex,
+    solve: impl FnOnce(&DistanceMatrix, usize, usize) -> Route,
+) -> Result<Vec<NodeIndex>, GraphError>
+where
+    W: BoundedMeasure + Copy + ToPrimitive,
+    Ty: EdgeType,
+{
+    for node in [start, end] {
+        if graph.node_weight(node).is_none() {
+            return Err(GraphError::UnknownNode(node));
+        }
+    }
+
+    let distances =
+        floyd_warshall(graph, |edge| *edge.weight()).map_err(|_| GraphError::NegativeCycle)?;
+    let matrix = DistanceMatrix::from_fn(graph.node_count(), |from, to| {
+        match distances[&(NodeIndex::new(from), NodeIndex::new(to))] {
+            distance if distance == W::max() => f64::INFINITY,
+            distance => distance.to_f64().expect("edge weights are numbers"),
+        }
+    });
+
+    let route = solve(&matrix, start.index(), end.index());
+    // The shortest route is unreachable only if every one is, a heuristic's may not be
+    match route
+        .windows(2)
+        .find(|leg| matrix.get(leg[0], leg[1]) == f64::INFINITY)
+    {
+        Some(leg) => Err(GraphError::Unreachable {
+            from: NodeIndex::new(leg[0]),
+            to: NodeIndex::new(leg[1]),
+        }),
+        None => Ok(route.iter().map(|&stop| NodeIndex::new(stop)).collect()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use petgraph::graph::UnGraph;
+
+    use crate::local_search::{Neighborhood, TwoOpt};
+
+    use super::*;
+
+    #[test]
+    fn test_solve_on_graph_with_missing_edges() {
+        // A ring a-b-c-d-a with one long chord; the chord is never worth taking
+        let mut graph = UnGraph::<&str, u32>::new_undirected();
+        let a = graph.add_node("a");
+        let b = graph.add_node("b");
+        let c = graph.add_node("c");
+        let d = graph.add_node("d");
+        graph.extend_with_edges([(a, b, 1), (b, c, 1), (c, d, 1), (d, a, 1), (a, c, 10)]);
+
+        let route = solve_on_graph(&graph, a, a, DistanceMatrix::solve).unwrap();
+        assert_eq!(route.len(), 5);
+        assert!(route == vec![a, b, c, d, a] || route == vec![a, d, c, b, a]);
+
+        // b and d are not adjacent, so this has to go around through a or c
+        let route = solve_on_graph(&graph, b, d, DistanceMatrix::solve).unwrap();
+        assert!(route == vec![b, a, c, d] || route == vec![b, c, a, d]);
+    }
+
+    #[test]
+    fn test_solve_on_graph_with_heuristic() {
+        // A 2 by 5 grid of unit edges, whose shortest round trip is its perimeter
+        let mut graph = UnGraph::<(), f64>::new_undirected();
+        let nodes = (0..10).map(|_| graph.add_node(())).collect::<Vec<_>>();
+        for column in 0..5 {
+            graph.add_edge(nodes[column], nodes[column + 5], 1.0);
+            if column < 4 {
+                graph.add_edge(nodes[column], nodes[column + 1], 1.0);
+                graph.add_edge(nodes[column + 5], nodes[column + 6], 1.0);
+            }
+        }
+
+        let two_opt = |matrix: &DistanceMatrix, start: usize, end: usize| {
+            let mut stops = (0..matrix.len())
+                .filter(|&i| i != start)
+                .collect::<Vec<_>>();
+            stops.insert(0, start);
+            stops.push(end);
+            let mut route = Route::new(stops);
+            TwoOpt.improve(&mut route, matrix);
+            route
+        };
+        let route = solve_on_graph(&graph, nodes[0], nodes[0], two_opt).unwrap();
+        assert_eq!(route.len(), 11);
+        let length = route
+            .windows(2)
+            .map(|leg| graph.find_edge(leg[0], leg[1]).map(|edge| graph[edge]))
+            .sum::<Option<f64>>();
+        assert_eq!(length, Some(10.0));
+    }
+
+    #[test]
+    fn test_solve_on_graph_errors() {
+        let mut graph = Graph::<(), f64>::new();
+        let a = graph.add_node(());
+        let b = graph.add_node(());
+        graph.add_edge(a, b, 1.0);
+
+        // b cannot reach a, but the route from a to b never needs it to
+        assert_eq!(
+            solve_on_graph(&graph, a, b, DistanceMatrix::solve),
+            Ok(vec![a, b])
+        );
+        assert_eq!(
+            solve_on_graph(&graph, b, a, DistanceMatrix::solve),
+            Err(GraphError::Unreachable { from: b, to: a })
+        );
+        assert_eq!(
+            solve_on_graph(&graph, a, NodeIndex::new(7), DistanceMatrix::solve),
+            Err(GraphError::UnknownNode(NodeIndex::new(7)))
+        );
+    }
+}
@@ -0,0 +1,122 @@
+//! How often each leg appears across a set of good routes, such as the k best from a
+//! [`SolutionPool`](crate::pool::SolutionPool), so planners can tell the connections
+//! every good route makes from those that are interchangeable.
+
+use std::collections::HashMap;
+
+use crate::Route;
+
+/// The fraction of routes that travel each leg, in either direction, from
+/// [`edge_heatmap`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EdgeHeatmap {
+    routes: usize,
+    /// The number of routes travelling each leg, keyed with the lower index first.
+    counts: HashMap<(usize, usize), usize>,
+}
+
+impl EdgeHeatmap {
+    /// The number of routes counted.
+    pub fn routes(&self) -> usize {
+        self.routes
+    }
+
+    /// The fraction of the routes that travel between `a` and `b`, in either direction.
+    pub fn frequency(&self, a: usize, b: usize) -> f64 {
+        match self.counts.get(&(a.min(b), a.max(b))) {
+            Some(&count) => count as f64 / self.routes as f64,
+            None => 0.0,
+        }
+    }
+
+    /// Every leg some route travels, with the lower index first, and its frequency,
+    /// most frequent first and then in index order.
+    pub fn edges(&self) -> Vec<((usize, usize), f64)> {
+        let mut edges = self
+            .counts
+            .iter()
+            .map(|(&edge, &count)| (edge, count))
+            .collect::<Vec<_>>();
+        edges.sort_by(|(a, a_count), (b, b_count)| b_count.cmp(a_count).then(a.cmp(b)));
+        edges
+            .into_iter()
+            .map(|(edge, count)| (edge, count as f64 / self.routes as f64))
+            .collect()
+    }
+
+    /// The legs every route travels, which are essentially forced, in index order.
+    pub fn forced(&self) -> Vec<(usize, usize)> {
+        let mut forced = self
+            .counts
+            .iter()
+            .filter(|&(_, &count)| count == self.routes)
+            .map(|(&edge, _)| edge)
+            .collect::<Vec<_>>();
+        forced.sort_unstable();
+        forced
+    }
+
+    /// The heatmap as `from,to,frequency` CSV with a header, one row per leg in the
+    /// order of [`edges`](EdgeHeatmap::edges).
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("from,to,frequency\n");
+        for ((from, to), frequency) in self.edges() {
+            csv.push_str(&format!("{from},{to},{frequency}\n"));
+        }
+        csv
+    }
+}
+
+/// Counts how often each leg appears across `routes`, in either direction. A leg a
+/// route travels twice is counted once for it.
+///
+/// # Arguments
+///
+/// * `routes` - Near-optimal routes of one problem, such as
+///   [`SolutionPool::routes`](crate::pool::SolutionPool::routes)
+pub fn edge_heatmap<'a>(routes: impl IntoIterator<Item = &'a Route>) -> EdgeHeatmap {
+    let mut heatmap = EdgeHeatmap::default();
+    let mut legs = Vec::new();
+    for route in routes {
+        heatmap.routes += 1;
+        legs.clear();
+        legs.extend(route.legs().map(|(a, b)| (a.min(b), a.max(b))));
+        legs.sort_unstable();
+        legs.dedup();
+        for &leg in &legs {
+            *heatmap.counts.entry(leg).or_insert(0) += 1;
+        }
+    }
+    heatmap
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_edge_heatmap() {
+        let routes = [
+            Route::new(vec![0, 1, 2, 3, 0]),
+            Route::new(vec![0, 3, 2, 1, 0]),
+            Route::new(vec![0, 2, 1, 3, 0]),
+            Route::new(vec![0, 1, 0]),
+        ];
+        let heatmap = edge_heatmap(&routes[..3]);
+        assert_eq!(heatmap.routes(), 3);
+        assert_eq!(heatmap.frequency(1, 2), 1.0);
+        assert_eq!(heatmap.frequency(1, 0), 2.0 / 3.0);
+        assert_eq!(heatmap.frequency(0, 2), 1.0 / 3.0);
+        assert_eq!(heatmap.frequency(1, 1), 0.0);
+        assert_eq!(heatmap.forced(), [(0, 3), (1, 2)]);
+        assert_eq!(heatmap.edges()[1], ((1, 2), 1.0));
+        assert_eq!(heatmap.edges().len(), 6);
+        assert!(heatmap
+            .to_csv()
+            .starts_with("from,to,frequency\n0,3,1\n1,2,1\n0,1,"));
+
+        // Out and back along the same leg counts once
+        assert_eq!(edge_heatmap(&routes[3..]).frequency(0, 1), 1.0);
+        assert_eq!(edge_heatmap(&[]).frequency(0, 1), 0.0);
+    }
+}
@@ -0,0 +1,155 @@
+//! Ordering destinations along a Hilbert curve, which finds a reasonable route in
+//! O(n log n) time for far more destinations than any other solver can handle.
+
+use crate::{Problem, Route};
+
+/// The curve fills a square grid with 2^ORDER cells on a side.
+const ORDER: u32 = 16;
+
+/// Finds a route by visiting the destinations in the order a Hilbert curve over their
+/// bounding box passes them. Points close together on the curve are close together in
+/// the plane, so routes are typically within about 25% of optimal for uniformly
+/// scattered points.
+///
+/// Coordinates are treated as planar, which for latitudes and longitudes is only
+/// reasonable away from the poles and the antimeridian.
+///
+/// # Arguments
+///
+/// * `problem` - The problem to solve
+///
+/// # Returns
+///
+/// A route from `start` through every destination to `end`
+pub fn hilbert_order(problem: &Problem) -> Route {
+    let keys = curve_keys(problem);
+    let by_key = |mut indices: Vec<usize>| {
+        indices.sort_by_key(|&i| keys[i]);
+        indices
+    };
+
+    let stops = if problem.start == problem.end {
+        // Follow the curve around from the start, and back to it
+        let mut order = by_key((0..problem.len()).collect());
+        let start = order.iter().position(|&i| i == problem.start).unwrap_or(0);
+        order.rotate_left(start);
+        order.push(problem.start);
+        order
+    } else {
+        // Follow the curve in whichever direction is shorter between the ends
+        let inner = by_key(problem.inner_destinations());
+        let forward = [vec![problem.start], inner.clone(), vec![problem.end]].concat();
+        let backward = [
+            vec![problem.start],
+            inner.into_iter().rev().collect(),
+            vec![problem.end],
+        ]
+        .concat();
+        if problem.route_distance(&backward) < problem.route_distance(&forward) {
+            backward
+        } else {
+            forward
+        }
+    };
+    Route::new(stops)
+}
+
+/// The distance of each destination along the curve, scaling the bounding box to the grid.
+fn curve_keys(problem: &Problem) -> Vec<u64> {
+    let (mut min_x, mut min_y) = (f64::INFINITY, f64::INFINITY);
+    let (mut max_x, mut max_y) = (f64::NEG_INFINITY, f64::NEG_INFINITY);
+    for point in &problem.points {
+        min_x = min_x.min(point.x);
+        min_y = min_y.min(point.y);
+        max_x = max_x.max(point.x);
+        max_y = max_y.max(point.y);
+    }
+    // The same scale on both axes keeps the curve's notion of closeness
+    let side = (max_x - min_x).max(max_y - min_y);
+    let cells = f64::from((1u32 << ORDER) - 1);
+    let scale = |value: f64, min: f64| {
+        if side > 0.0 {
+            ((value - min) / side * cells) as u32
+        } else {
+            0
+        }
+    };
+
+    problem
+        .points
+        .iter()
+        .map(|point| curve_index(scale(point.x, min_x), scale(point.y, min_y)))
+        .collect()
+}
+
+/// The index of cell (x, y) along the Hilbert curve of order [`ORDER`].
+fn curve_index(mut x: u32, mut y: u32) -> u64 {
+    let n = 1u32 << ORDER;
+    let mut index = 0;
+    let mut s = n / 2;
+    while s > 0 {
+        let rx = u32::from(x & s > 0);
+        let ry = u32::from(y & s > 0);
+        index += u64::from(s) * u64::from(s) * u64::from((3 * rx) ^ ry);
+        // Rotate the quadrant so the curve within it has the standard orientation
+        if ry == 0 {
+            if rx == 1 {
+                x = n - 1 - x;
+                y = n - 1 - y;
+            }
+            std::mem::swap(&mut x, &mut y);
+        }
+        s /= 2;
+    }
+    index
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use crate::{rng::SplitMix64, Metric, Point};
+
+    use super::*;
+
+    #[test]
+    fn test_curve_is_continuous() {
+        // On a 4 × 4 grid the curve steps between neighboring points
+        let points = (0..16)
+            .map(|i| Point::new(f64::from(i % 4), f64::from(i / 4)))
+            .collect();
+        let problem = Problem::new(points, Metric::Manhattan);
+        let route = hilbert_order(&problem);
+
+        assert_eq!(route.len(), 17);
+        let legs = route.legs().map(|(a, b)| problem.distance(a, b));
+        // Every leg but the one closing the round trip is between neighbors
+        assert_eq!(legs.filter(|&leg| leg != 1.0).count(), 1);
+    }
+
+    #[test]
+    fn test_large_instance() {
+        let mut rng = SplitMix64::new(8);
+        let len = 20_000;
+        let mut problem = Problem::new(
+            (0..len)
+                .map(|_| Point::new(rng.below(10_000) as f64, rng.below(10_000) as f64))
+                .collect(),
+            Metric::Euclidean,
+        );
+        problem.end = 1;
+
+        let started = Instant::now();
+        let route = hilbert_order(&problem);
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert_eq!((route[0], route[len - 1]), (0, 1));
+        let mut inner = route[1..len - 1].to_vec();
+        inner.sort();
+        assert_eq!(inner, problem.inner_destinations());
+
+        // Far shorter than visiting them in the arbitrary order they were generated
+        let index_order =
+            problem.route_distance(&[vec![0], problem.inner_destinations(), vec![1]].concat());
+        assert!(problem.route_distance(&route) < index_order / 20.0);
+    }
+}
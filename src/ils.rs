@@ -0,0 +1,449 @@
+//! Iterated local search, the workhorse heuristic for instances far too large to
+//! search exhaustively.
+
+use std::time::Instant;
+
+use crate::{
+    background::SolverConfig,
+    christofides::christofides,
+    events::{SolveEvent, SolveLog, Traced},
+    local_search::{Neighborhood, TwoOpt},
+    objective::Objective,
+    rng::SplitMix64,
+    DistanceMatrix, Problem, Route,
+};
+
+/// The iterations run when the config sets neither an iteration nor a time limit.
+pub const DEFAULT_ITERATIONS: u64 = 1_000;
+
+/// Changes must lower a route's cost by more than this, as in
+/// [`local_search`](crate::local_search).
+const EPSILON: f64 = 1e-10;
+
+/// Finds a short route by iterated local search: improve a route with 2-opt until no
+/// reversal shortens it, then repeatedly kick it out of that local optimum with a
+/// random double-bridge move and improve it again, keeping whichever route is shorter.
+///
+/// The search starts from `config`'s initial route, or [`christofides`] if it has none,
+/// and runs for its iterations or time limit, or [`DEFAULT_ITERATIONS`] if it has
+/// neither.
+///
+/// # Arguments
+///
+/// * `problem` - The problem to solve
+/// * `config` - The budget, random seed and initial route
+///
+/// # Returns
+///
+/// The shortest route found
+///
+/// # Panics
+///
+/// Panics if the initial route is not a route of `problem`.
+pub fn iterated_local_search(problem: &Problem, config: &SolverConfig) -> Route {
+    iterated_local_search_with(problem, config, &TwoOpt)
+}
+
+/// Iterated local search as [`iterated_local_search`], improving routes with
+/// `neighborhood`, such as a [`LocalSearch`](crate::local_search::LocalSearch) pipeline,
+/// rather than 2-opt alone.
+pub fn iterated_local_search_with(
+    problem: &Problem,
+    config: &SolverConfig,
+    neighborhood: &dyn Neighborhood,
+) -> Route {
+    search(
+        problem,
+        &problem.distance_matrix(),
+        config,
+        neighborhood,
+        None,
+    )
+}
+
+/// Iterated local search as [`iterated_local_search_with`], recording into `log` the
+/// starting route and each kicked route that became the best as
+/// [`NewBest`](SolveEvent::NewBest), and each that did not as
+/// [`Pruned`](SolveEvent::Pruned), so a search that stalls can be examined after.
+pub fn iterated_local_search_logged(
+    problem: &Problem,
+    config: &SolverConfig,
+    neighborhood: &dyn Neighborhood,
+    log: &mut SolveLog,
+) -> Route {
+    search(
+        problem,
+        &problem.distance_matrix(),
+        config,
+        neighborhood,
+        Some(log),
+    )
+}
+
+/// Iterated local search as [`iterated_local_search_with`], returning with the route the
+/// best distance after each kick, counting the starting route as iteration 0.
+pub fn iterated_local_search_traced(
+    problem: &Problem,
+    config: &SolverConfig,
+    neighborhood: &dyn Neighborhood,
+) -> Traced {
+    let mut log = SolveLog::new();
+    let route = iterated_local_search_logged(problem, config, neighborhood, &mut log);
+    Traced {
+        route,
+        trajectory: log.trajectory(),
+    }
+}
+
+/// Iterated local search over `matrix` in place of the problem's own distances, such
+/// as with penalties added to some legs.
+pub(crate) fn search(
+    problem: &Problem,
+    matrix: &DistanceMatrix,
+    config: &SolverConfig,
+    neighborhood: &dyn Neighborhood,
+    mut log: Option<&mut SolveLog>,
+) -> Route {
+    let mut best = config.starting_route(problem, christofides);
+    neighborhood.improve(&mut best, matrix);
+    let mut best_distance = matrix.route_distance(&best);
+    if let Some(log) = log.as_deref_mut() {
+        log.record(SolveEvent::NewBest {
+            at: 1,
+            distance: best_distance,
+            route: best.clone(),
+        });
+    }
+
+    let iterations = match (config.iterations, config.time_limit) {
+        (None, None) => DEFAULT_ITERATIONS,
+        (iterations, _) => iterations.unwrap_or(u64::MAX),
+    };
+    let deadline = config.time_limit.map(|limit| Instant::now() + limit);
+    let mut rng = SplitMix64::new(config.seed);
+    for iteration in 0..iterations {
+        if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            break;
+        }
+        let Some(mut candidate) = double_bridge(&best, &mut rng).map(Route::new) else {
+            // Too few destinations to kick, so local search alone is all there is
+            break;
+        };
+        neighborhood.improve(&mut candidate, matrix);
+        let distance = matrix.route_distance(&candidate);
+        // After the starting route and the candidates of earlier iterations
+        let examined = iteration + 2;
+        let improved = distance < best_distance;
+        if let Some(log) = log.as_deref_mut() {
+            log.record(if improved {
+                SolveEvent::NewBest {
+                    at: examined,
+                    distance,
+                    route: candidate.clone(),
+                }
+            } else {
+                SolveEvent::Pruned {
+                    at: examined,
+                    distance,
+                    bound: best_distance,
+                }
+            });
+        }
+        if improved {
+            best = candidate;
+            best_distance = distance;
+        }
+    }
+    best
+}
+
+/// Finds a route with a low cost under `objective` by iterated local search: move single
+/// stops and reverse segments while that lowers the cost, then kick the route with a
+/// double-bridge move and improve it again, keeping whichever route costs less.
+///
+/// Unlike the distance, an objective need not add up leg by leg, so each move is costed
+/// by evaluating the whole route. That takes O(n³) time per pass over a route of n
+/// stops, which suits problems of tens of destinations rather than thousands. The
+/// search starts and stops as [`iterated_local_search`] does.
+///
+/// # Arguments
+///
+/// * `problem` - The problem to solve
+/// * `config` - The budget, random seed and initial route
+/// * `objective` - The cost to minimize
+///
+/// # Returns
+///
+/// The cheapest route found
+///
+/// # Panics
+///
+/// Panics if the initial route is not a route of `problem`.
+pub fn iterated_local_search_objective(
+    problem: &Problem,
+    config: &SolverConfig,
+    objective: &dyn Objective,
+) -> Route {
+    let matrix = problem.distance_matrix();
+    let cost = |stops: &[usize]| objective.cost(&matrix, stops);
+    let mut best = config.starting_route(problem, christofides).into_stops();
+    improve(&mut best, cost);
+    let mut best_cost = cost(&best);
+
+    let iterations = match (config.iterations, config.time_limit) {
+        (None, None) => DEFAULT_ITERATIONS,
+        (iterations, _) => iterations.unwrap_or(u64::MAX),
+    };
+    let deadline = config.time_limit.map(|limit| Instant::now() + limit);
+    let mut rng = SplitMix64::new(config.seed);
+    for _ in 0..iterations {
+        if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            break;
+        }
+        let Some(mut candidate) = double_bridge(&best, &mut rng) else {
+            break;
+        };
+        improve(&mut candidate, cost);
+        let candidate_cost = cost(&candidate);
+        if candidate_cost < best_cost {
+            best = candidate;
+            best_cost = candidate_cost;
+        }
+    }
+    Route::new(best)
+}
+
+/// Relocates single stops and reverses segments between the first and last stops,
+/// taking each change that lowers `cost`, until none does.
+fn improve(stops: &mut Vec<usize>, cost: impl Fn(&[usize]) -> f64) {
+    let len = stops.len();
+    let mut current = cost(stops);
+    let mut improved = true;
+    while improved {
+        improved = false;
+        for i in 1..len.saturating_sub(1) {
+            for j in 1..len - 1 {
+                if i == j {
+                    continue;
+                }
+                let mut candidate = stops.clone();
+                let stop = candidate.remove(i);
+                candidate.insert(j, stop);
+                let candidate_cost = cost(&candidate);
+                if candidate_cost < current - EPSILON {
+                    *stops = candidate;
+                    current = candidate_cost;
+                    improved = true;
+                }
+                if i < j {
+                    let mut candidate = stops.clone();
+                    candidate[i..=j].reverse();
+                    let candidate_cost = cost(&candidate);
+                    if candidate_cost < current - EPSILON {
+                        *stops = candidate;
+                        current = candidate_cost;
+                        improved = true;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Cuts the destinations between the first and last stops into four segments A B C D
+/// at random, and reconnects them as A C B D, a change 2-opt cannot easily undo.
+/// Returns `None` if there are fewer than four destinations to cut between.
+pub(crate) fn double_bridge(stops: &[usize], rng: &mut SplitMix64) -> Option<Vec<usize>> {
+    let inner = &stops[1..stops.len() - 1];
+    if inner.len() < 4 {
+        return None;
+    }
+    // Three distinct cuts in 1..inner.len(), sorted
+    let mut cuts = [0; 3];
+    loop {
+        for cut in &mut cuts {
+            *cut = 1 + rng.below(inner.len() - 1);
+        }
+        cuts.sort();
+        if cuts[0] < cuts[1] && cuts[1] < cuts[2] {
+            break;
+        }
+    }
+    let [i, j, k] = cuts;
+
+    let mut kicked = Vec::with_capacity(stops.len());
+    kicked.push(stops[0]);
+    kicked.extend_from_slice(&inner[..i]);
+    kicked.extend_from_slice(&inner[j..k]);
+    kicked.extend_from_slice(&inner[i..j]);
+    kicked.extend_from_slice(&inner[k..]);
+    kicked.push(stops[stops.len() - 1]);
+    Some(kicked)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{ops::ControlFlow, time::Duration};
+
+    use crate::{
+        generators::uniform_problem,
+        local_search::{LocalSearch, OrOpt},
+        objective::{LongestLeg, TotalDistance},
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_double_bridge() {
+        let stops = (0..10).chain([0]).collect::<Vec<_>>();
+        let mut rng = SplitMix64::new(1);
+        for _ in 0..20 {
+            let kicked = double_bridge(&stops, &mut rng).unwrap();
+            assert_ne!(kicked, stops);
+            assert_eq!((kicked[0], kicked[10]), (0, 0));
+            let mut inner = kicked[1..10].to_vec();
+            inner.sort();
+            assert_eq!(inner, (1..10).collect::<Vec<_>>());
+        }
+        assert_eq!(double_bridge(&[0, 1, 2, 3, 0], &mut rng), None);
+    }
+
+    #[test]
+    fn test_finds_optimum_of_small_problems() {
+        for seed in 0..5 {
+            let mut problem = uniform_problem(9, seed);
+            if seed % 2 == 1 {
+                problem.end = 4;
+            }
+            let config = SolverConfig::default().with_seed(seed);
+            let route = iterated_local_search(&problem, &config);
+            let optimal = problem.route_distance(&problem.solve());
+            assert!((problem.route_distance(&route) - optimal).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_objective() {
+        let config = SolverConfig::default();
+        for seed in 0..4 {
+            let problem = uniform_problem(8, seed);
+            let matrix = problem.distance_matrix();
+            let exhaustive = |objective: &dyn Objective| {
+                let best =
+                    problem.search_objective(objective, &mut |_: &_| ControlFlow::Continue(()));
+                best.distance
+            };
+
+            let route = iterated_local_search_objective(&problem, &config, &TotalDistance);
+            assert!(problem.is_route(&route));
+            assert!((matrix.route_distance(&route) - exhaustive(&TotalDistance)).abs() < 1e-9);
+
+            // The longest leg does not add up leg by leg, but is costed all the same
+            let route = iterated_local_search_objective(&problem, &config, &LongestLeg);
+            assert!(problem.is_route(&route));
+            assert_eq!(LongestLeg.cost(&matrix, &route), exhaustive(&LongestLeg));
+        }
+    }
+
+    #[test]
+    fn test_warm_start() {
+        let problem = uniform_problem(100, 4);
+        let index_order = Route::new((0..100).chain([0]).collect());
+
+        // Without iterations the initial route is only improved by 2-opt
+        let config = SolverConfig::default()
+            .with_iterations(0)
+            .with_initial_route(index_order.clone());
+        let mut improved = index_order.clone();
+        TwoOpt.improve(&mut improved, &problem.distance_matrix());
+        assert_eq!(iterated_local_search(&problem, &config), improved);
+
+        let config = config.with_iterations(20);
+        let route = iterated_local_search(&problem, &config);
+        assert!(problem.is_route(&route));
+        assert!(problem.route_distance(&route) <= problem.route_distance(&improved));
+    }
+
+    #[test]
+    fn test_logged() {
+        let problem = uniform_problem(40, 6);
+        let config = SolverConfig::default().with_iterations(30).with_seed(2);
+        let mut log = SolveLog::new();
+        let route = iterated_local_search_logged(&problem, &config, &TwoOpt, &mut log);
+        // Logging does not change the search
+        assert_eq!(route, iterated_local_search(&problem, &config));
+        assert_eq!(log.events.len(), 31);
+        let (best, distance) = log.best().unwrap();
+        assert_eq!(best, &route);
+        assert!((distance - problem.route_distance(&route)).abs() < 1e-9);
+        assert!(log.events.iter().all(|event| match event {
+            SolveEvent::Pruned {
+                distance, bound, ..
+            } => distance >= bound,
+            SolveEvent::NewBest { .. } => true,
+        }));
+        assert_eq!(log.events.last().unwrap().at() - log.stalled_for(), {
+            log.events
+                .iter()
+                .rev()
+                .find(|event| matches!(event, SolveEvent::NewBest { .. }))
+                .unwrap()
+                .at()
+        });
+    }
+
+    #[test]
+    fn test_traced() {
+        let problem = uniform_problem(40, 6);
+        let config = SolverConfig::default().with_iterations(30).with_seed(2);
+        let traced = iterated_local_search_traced(&problem, &config, &TwoOpt);
+        assert_eq!(traced.route, iterated_local_search(&problem, &config));
+        let trajectory = traced.trajectory;
+        assert_eq!(trajectory.iterations, 30);
+        assert_eq!(trajectory.points[0].0, 0);
+        assert!(trajectory
+            .points
+            .windows(2)
+            .all(|pair| pair[0].1 > pair[1].1));
+        let distance = trajectory.final_distance().unwrap();
+        assert!((distance - problem.route_distance(&traced.route)).abs() < 1e-9);
+    }
+
+    #[test]
+    #[should_panic(expected = "initial route")]
+    fn test_warm_start_must_be_a_route() {
+        let problem = uniform_problem(10, 4);
+        let config = SolverConfig::default().with_initial_route(Route::new(vec![0, 1, 2, 0]));
+        iterated_local_search(&problem, &config);
+    }
+
+    #[test]
+    fn test_budget() {
+        let problem = uniform_problem(200, 9);
+        let index_order = problem.route_distance(&(0..200).chain([0]).collect::<Vec<_>>());
+
+        let config = SolverConfig::default().with_time_limit(Duration::from_millis(50));
+        let started = Instant::now();
+        let route = iterated_local_search(&problem, &config);
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert_eq!(route.len(), 201);
+        assert!(problem.route_distance(&route) < index_order);
+
+        // A richer pipeline does at least as well in fewer iterations
+        let config = SolverConfig::default().with_iterations(20);
+        let pipeline = LocalSearch::new()
+            .with_neighborhood(TwoOpt)
+            .with_neighborhood(OrOpt);
+        assert!(
+            problem.route_distance(&iterated_local_search_with(&problem, &config, &pipeline))
+                <= problem.route_distance(&iterated_local_search(&problem, &config)) * 1.05
+        );
+
+        // The same seed finds the same route
+        let config = SolverConfig::default().with_iterations(20).with_seed(3);
+        assert_eq!(
+            iterated_local_search(&problem, &config),
+            iterated_local_search(&problem, &config)
+        );
+    }
+}
@@ -0,0 +1,604 @@
+//! A JSON schema for problems and solutions, so services can drive the solvers
+//! without bespoke glue.
+//!
+//! A problem looks like
+//!
+//! ```json
+//! {
+//!   "destinations": [{"id": "depot", "x": 0, "y": 0}, {"id": "a", "x": 3, "y": 4}],
+//!   "start": "depot",
+//!   "end": "depot",
+//!   "metric": "euclidean",
+//!   "constraints": {}
+//! }
+//! ```
+//!
+//! Geographic destinations may use `lat`/`lon` in place of `x`/`y`, and any may have a
+//! `service_time`, which defaults to zero. `start` defaults to the first destination
+//! and `end` to `start`. A solution lists the destination ids in visiting order with
+//! the total distance, and a [`SolutionFile`] bundles the problem, the configuration
+//! that solved it and the solution, so an experiment can be rerun from one file.
+//!
+//! Distance matrices can also be imported from the responses of the OSRM `table`
+//! service and the Google Distance Matrix API, so road travel times can be solved
+//! over directly.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    background::SolverConfig,
+    config::{ConfigDocument, ConfigError},
+    events::Trajectory,
+    matrix::{DistanceMatrix, MatrixError},
+    Metric, Point, Problem, Route,
+};
+
+/// An error produced while reading a JSON document.
+#[derive(Debug)]
+pub enum JsonError {
+    /// The document was not valid JSON or did not match the schema.
+    Json(serde_json::Error),
+    /// `start` or `end` named an id that is not among the destinations.
+    UnknownDestination(String),
+    /// The document contained no destinations.
+    Empty,
+    /// A distance matrix service reported an error instead of a matrix.
+    Api(String),
+    /// A distance matrix response did not include the requested annotation.
+    MissingField(&'static str),
+    /// A distance matrix response had no value for a pair of destinations, usually
+    /// because no route between them was found.
+    MissingEntry { from: usize, to: usize },
+    /// A distance matrix response was not square.
+    Matrix(MatrixError),
+    /// A solution file's configuration could not be applied.
+    Config(ConfigError),
+}
+
+impl fmt::Display for JsonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JsonError::Json(err) => write!(f, "invalid document: {err}"),
+            JsonError::UnknownDestination(id) => write!(f, "no destination has id `{id}`"),
+            JsonError::Empty => write!(f, "no destinations found"),
+            JsonError::Api(message) => write!(f, "service error: {message}"),
+            JsonError::MissingField(field) => write!(f, "response has no `{field}`"),
+            JsonError::MissingEntry { from, to } => {
+                write!(f, "no value from destination {from} to destination {to}")
+            }
+            JsonError::Matrix(err) => err.fmt(f),
+            JsonError::Config(err) => err.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for JsonError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            JsonError::Json(err) => Some(err),
+            JsonError::Matrix(err) => Some(err),
+            JsonError::Config(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<serde_json::Error> for JsonError {
+    fn from(err: serde_json::Error) -> Self {
+        JsonError::Json(err)
+    }
+}
+
+impl From<MatrixError> for JsonError {
+    fn from(err: MatrixError) -> Self {
+        JsonError::Matrix(err)
+    }
+}
+
+impl From<ConfigError> for JsonError {
+    fn from(err: ConfigError) -> Self {
+        JsonError::Config(err)
+    }
+}
+
+/// A single destination of a [`ProblemDocument`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DestinationDocument {
+    pub id: String,
+    #[serde(alias = "lat")]
+    pub x: f64,
+    #[serde(alias = "lon")]
+    pub y: f64,
+    /// The time spent at the destination, left out when zero.
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub service_time: f64,
+}
+
+fn is_zero(value: &f64) -> bool {
+    *value == 0.0
+}
+
+/// Restrictions on which routes are acceptable.
+///
+/// No constraints are supported yet; unknown keys are rejected rather than
+/// silently ignored so a document never appears to be honored when it is not.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Constraints {}
+
+impl Constraints {
+    /// Whether no constraint is set, in which case it is left out when serializing.
+    pub fn is_empty(&self) -> bool {
+        *self == Constraints::default()
+    }
+}
+
+/// The JSON form of a [`Problem`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProblemDocument {
+    pub destinations: Vec<DestinationDocument>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end: Option<String>,
+    #[serde(default)]
+    pub metric: Metric,
+    #[serde(default, skip_serializing_if = "Constraints::is_empty")]
+    pub constraints: Constraints,
+}
+
+impl From<&Problem> for ProblemDocument {
+    fn from(problem: &Problem) -> Self {
+        ProblemDocument {
+            destinations: problem
+                .ids
+                .iter()
+                .zip(&problem.points)
+                .zip(&problem.service_times)
+                .map(|((id, point), &service_time)| DestinationDocument {
+                    id: id.clone(),
+                    x: point.x,
+                    y: point.y,
+                    service_time,
+                })
+                .collect(),
+            start: Some(problem.ids[problem.start].clone()),
+            end: Some(problem.ids[problem.end].clone()),
+            metric: problem.metric,
+            constraints: Constraints::default(),
+        }
+    }
+}
+
+impl TryFrom<ProblemDocument> for Problem {
+    type Error = JsonError;
+
+    fn try_from(document: ProblemDocument) -> Result<Self, Self::Error> {
+        if document.destinations.is_empty() {
+            return Err(JsonError::Empty);
+        }
+
+        let service_times = document
+            .destinations
+            .iter()
+            .map(|destination| destination.service_time)
+            .collect();
+        let (ids, points): (Vec<_>, Vec<_>) = document
+            .destinations
+            .into_iter()
+            .map(|destination| (destination.id, Point::new(destination.x, destination.y)))
+            .unzip();
+        let find = |id: Option<String>, default| match id {
+            Some(id) => ids
+                .iter()
+                .position(|candidate| *candidate == id)
+                .ok_or(JsonError::UnknownDestination(id)),
+            None => Ok(default),
+        };
+        let start = find(document.start, 0)?;
+        let end = find(document.end, start)?;
+
+        Ok(Problem {
+            ids,
+            points,
+            service_times,
+            metric: document.metric,
+            start,
+            end,
+        })
+    }
+}
+
+/// The JSON form of a solved route.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SolutionDocument {
+    /// Destination ids in visiting order.
+    pub route: Vec<String>,
+    pub distance: f64,
+}
+
+impl SolutionDocument {
+    /// Describes `route`, given as destination indices into `problem`.
+    pub fn new(problem: &Problem, route: &[usize]) -> Self {
+        SolutionDocument {
+            route: route.iter().map(|&i| problem.ids[i].clone()).collect(),
+            distance: problem.route_distance(route),
+        }
+    }
+}
+
+/// How the run recorded in a [`SolutionFile`] went.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RunStats {
+    /// Wall-clock time the solver took, in seconds.
+    pub time: f64,
+    /// How the best distance fell, for solvers that trace it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trajectory: Option<Trajectory>,
+}
+
+/// A problem, the configuration it was solved with, including the seed, and the
+/// solution found, in one JSON document from which the run can be repeated.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SolutionFile {
+    pub problem: ProblemDocument,
+    pub config: ConfigDocument,
+    pub solution: SolutionDocument,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stats: Option<RunStats>,
+}
+
+impl SolutionFile {
+    /// Records that `config` solved `problem` with `route`, given as destination
+    /// indices.
+    pub fn new(problem: &Problem, config: &SolverConfig, route: &[usize]) -> Self {
+        SolutionFile {
+            problem: ProblemDocument::from(problem),
+            config: ConfigDocument::from(config),
+            solution: SolutionDocument::new(problem, route),
+            stats: None,
+        }
+    }
+
+    pub fn with_stats(mut self, stats: RunStats) -> Self {
+        self.stats = Some(stats);
+        self
+    }
+
+    /// Reads a solution file from JSON.
+    pub fn from_json(json: &str) -> Result<Self, JsonError> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("solution files always serialize")
+    }
+
+    /// The problem, configuration and route the file records, the route as destination
+    /// indices into the problem.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the problem or configuration is invalid, or the route names
+    /// a destination the problem does not have.
+    pub fn to_parts(&self) -> Result<(Problem, SolverConfig, Route), JsonError> {
+        let problem = Problem::try_from(self.problem.clone())?;
+        let config = SolverConfig::try_from(self.config.clone())?;
+        let stops = self
+            .solution
+            .route
+            .iter()
+            .map(|id| {
+                problem
+                    .ids
+                    .iter()
+                    .position(|other| other == id)
+                    .ok_or_else(|| JsonError::UnknownDestination(id.clone()))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok((problem, config, Route::new(stops)))
+    }
+
+    /// Solves the recorded problem again with the recorded configuration, using the
+    /// [default solver](Problem::solve) if it names none.
+    ///
+    /// # Returns
+    ///
+    /// The solution of the new run, which for a deterministic solver is the recorded one
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the problem or configuration is invalid.
+    pub fn reproduce(&self) -> Result<SolutionDocument, JsonError> {
+        let (problem, config, _) = self.to_parts()?;
+        let route = match config.solver() {
+            Some(solver) => solver.solve_with(&problem, &config),
+            None => problem.solve(),
+        };
+        Ok(SolutionDocument::new(&problem, &route))
+    }
+}
+
+impl Problem {
+    /// Reads a problem from a [`ProblemDocument`] in JSON.
+    pub fn from_json(json: &str) -> Result<Self, JsonError> {
+        serde_json::from_str::<ProblemDocument>(json)?.try_into()
+    }
+
+    /// Serializes the problem as a [`ProblemDocument`].
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(&ProblemDocument::from(self))
+            .expect("problem documents always serialize")
+    }
+}
+
+/// Which quantity of a distance matrix response to import.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TravelCost {
+    /// Travel time in seconds.
+    Duration,
+    /// Travel distance in meters.
+    Distance,
+}
+
+#[derive(Deserialize)]
+struct OsrmTable {
+    code: String,
+    message: Option<String>,
+    durations: Option<Vec<Vec<Option<f64>>>>,
+    distances: Option<Vec<Vec<Option<f64>>>>,
+}
+
+#[derive(Deserialize)]
+struct GoogleMatrix {
+    status: String,
+    error_message: Option<String>,
+    #[serde(default)]
+    rows: Vec<GoogleRow>,
+}
+
+#[derive(Deserialize)]
+struct GoogleRow {
+    elements: Vec<GoogleElement>,
+}
+
+#[derive(Deserialize)]
+struct GoogleElement {
+    status: String,
+    duration: Option<GoogleValue>,
+    distance: Option<GoogleValue>,
+}
+
+#[derive(Deserialize)]
+struct GoogleValue {
+    value: f64,
+}
+
+impl DistanceMatrix {
+    /// Imports the response of an OSRM `table` request whose sources and
+    /// destinations are the same locations.
+    pub fn from_osrm_table(json: &str, cost: TravelCost) -> Result<Self, JsonError> {
+        let table = serde_json::from_str::<OsrmTable>(json)?;
+        if table.code != "Ok" {
+            return Err(JsonError::Api(table.message.unwrap_or(table.code)));
+        }
+
+        let (rows, field) = match cost {
+            TravelCost::Duration => (table.durations, "durations"),
+            TravelCost::Distance => (table.distances, "distances"),
+        };
+        let rows = rows.ok_or(JsonError::MissingField(field))?;
+        complete_rows(rows.into_iter().map(|row| row.into_iter()))
+    }
+
+    /// Imports the response of a Google Distance Matrix API request whose origins
+    /// and destinations are the same locations.
+    pub fn from_google_distance_matrix(json: &str, cost: TravelCost) -> Result<Self, JsonError> {
+        let matrix = serde_json::from_str::<GoogleMatrix>(json)?;
+        if matrix.status != "OK" {
+            return Err(JsonError::Api(
+                matrix.error_message.unwrap_or(matrix.status),
+            ));
+        }
+
+        complete_rows(matrix.rows.into_iter().map(|row| {
+            row.elements.into_iter().map(move |element| {
+                if element.status != "OK" {
+                    return None;
+                }
+                match cost {
+                    TravelCost::Duration => element.duration,
+                    TravelCost::Distance => element.distance,
+                }
+                .map(|value| value.value)
+            })
+        }))
+    }
+}
+
+/// Collects rows of optional entries into a matrix, failing on the first missing one.
+fn complete_rows(
+    rows: impl Iterator<Item = impl Iterator<Item = Option<f64>>>,
+) -> Result<DistanceMatrix, JsonError> {
+    let rows = rows
+        .enumerate()
+        .map(|(from, row)| {
+            row.enumerate()
+                .map(|(to, entry)| entry.ok_or(JsonError::MissingEntry { from, to }))
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(DistanceMatrix::from_rows(rows)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_problem_round_trip() {
+        let json = r#"{
+            "destinations": [
+                {"id": "depot", "x": 0, "y": 0},
+                {"id": "a", "x": 2, "y": 0},
+                {"id": "b", "x": 1, "y": 0, "service_time": 5}
+            ],
+            "end": "a",
+            "metric": "manhattan"
+        }"#;
+
+        let problem = Problem::from_json(json).unwrap();
+        assert_eq!(problem.start, 0);
+        assert_eq!(problem.end, 1);
+        assert_eq!(problem.metric, Metric::Manhattan);
+        assert_eq!(problem.service_times, vec![0.0, 0.0, 5.0]);
+
+        assert_eq!(Problem::from_json(&problem.to_json()).unwrap(), problem);
+
+        let route = problem.solve();
+        let solution = SolutionDocument::new(&problem, &route);
+        assert_eq!(solution.route, vec!["depot", "b", "a"]);
+        assert_eq!(solution.distance, 2.0);
+
+        let text = serde_json::to_string(&solution).unwrap();
+        assert_eq!(text, r#"{"route":["depot","b","a"],"distance":2.0}"#);
+    }
+
+    #[test]
+    fn test_solution_file() {
+        let problem = Problem::new(
+            [(0.0, 0.0), (4.0, 0.0), (4.0, 3.0), (0.0, 3.0)]
+                .map(|(x, y)| Point::new(x, y))
+                .to_vec(),
+            Metric::Euclidean,
+        );
+        let config = SolverConfig::default().with_algorithm("ils").with_seed(11);
+        let route = config.solver().unwrap().solve_with(&problem, &config);
+        let mut trajectory = Trajectory::new();
+        trajectory.record(0, 14.0);
+        let file = SolutionFile::new(&problem, &config, &route).with_stats(RunStats {
+            time: 0.5,
+            trajectory: Some(trajectory),
+        });
+
+        let read = SolutionFile::from_json(&file.to_json()).unwrap();
+        assert_eq!(read, file);
+        assert_eq!(read.solution.distance, 14.0);
+        let (read_problem, read_config, read_route) = read.to_parts().unwrap();
+        assert_eq!(read_problem, problem);
+        assert_eq!(read_config, config);
+        assert_eq!(read_route, route);
+        assert_eq!(read.reproduce().unwrap(), file.solution);
+
+        let mut unknown = file;
+        unknown.solution.route[1] = "z".into();
+        assert!(matches!(
+            unknown.to_parts(),
+            Err(JsonError::UnknownDestination(id)) if id == "z"
+        ));
+    }
+
+    #[test]
+    fn test_lat_lon_aliases() {
+        let json =
+            r#"{"destinations": [{"id": "x", "lat": 51.5, "lon": -0.12}], "metric": "haversine"}"#;
+
+        let problem = Problem::from_json(json).unwrap();
+        assert_eq!(problem.points[0], Point::new(51.5, -0.12));
+        assert_eq!(problem.end, 0);
+    }
+
+    #[test]
+    fn test_from_json_errors() {
+        assert!(matches!(
+            Problem::from_json(r#"{"destinations": []}"#),
+            Err(JsonError::Empty)
+        ));
+        assert!(matches!(
+            Problem::from_json(r#"{"destinations": [{"id": "a", "x": 0, "y": 0}], "start": "b"}"#),
+            Err(JsonError::UnknownDestination(id)) if id == "b"
+        ));
+        assert!(matches!(
+            Problem::from_json(
+                r#"{"destinations": [{"id": "a", "x": 0, "y": 0}], "constraints": {"capacity": 3}}"#
+            ),
+            Err(JsonError::Json(_))
+        ));
+    }
+
+    #[test]
+    fn test_from_osrm_table() {
+        let json = r#"{
+            "code": "Ok",
+            "durations": [[0, 12.5, 30], [11, 0, 20.5], [29, 21, 0]],
+            "sources": [], "destinations": []
+        }"#;
+
+        let matrix = DistanceMatrix::from_osrm_table(json, TravelCost::Duration).unwrap();
+        assert_eq!(matrix.len(), 3);
+        assert_eq!(matrix.get(0, 1), 12.5);
+        assert_eq!(matrix.get(2, 0), 29.0);
+
+        assert!(matches!(
+            DistanceMatrix::from_osrm_table(json, TravelCost::Distance),
+            Err(JsonError::MissingField("distances"))
+        ));
+
+        let unreachable = r#"{"code": "Ok", "durations": [[0, null], [1, 0]]}"#;
+        assert!(matches!(
+            DistanceMatrix::from_osrm_table(unreachable, TravelCost::Duration),
+            Err(JsonError::MissingEntry { from: 0, to: 1 })
+        ));
+
+        let failed = r#"{"code": "InvalidQuery", "message": "Query string malformed"}"#;
+        assert!(matches!(
+            DistanceMatrix::from_osrm_table(failed, TravelCost::Duration),
+            Err(JsonError::Api(message)) if message == "Query string malformed"
+        ));
+    }
+
+    #[test]
+    fn test_from_google_distance_matrix() {
+        let element = |meters, seconds| {
+            format!(
+                r#"{{"status": "OK", "distance": {{"text": "", "value": {meters}}}, "duration": {{"text": "", "value": {seconds}}}}}"#
+            )
+        };
+        let json = format!(
+            r#"{{
+                "status": "OK",
+                "origin_addresses": ["a", "b"],
+                "destination_addresses": ["a", "b"],
+                "rows": [
+                    {{"elements": [{}, {}]}},
+                    {{"elements": [{}, {}]}}
+                ]
+            }}"#,
+            element(0, 0),
+            element(1500, 120),
+            element(1400, 110),
+            element(0, 0)
+        );
+
+        let matrix =
+            DistanceMatrix::from_google_distance_matrix(&json, TravelCost::Distance).unwrap();
+        assert!(matrix.row(0).eq([0.0, 1500.0]));
+        let matrix =
+            DistanceMatrix::from_google_distance_matrix(&json, TravelCost::Duration).unwrap();
+        assert!(matrix.row(1).eq([110.0, 0.0]));
+
+        let not_found = r#"{"status": "OK", "rows": [{"elements": [{"status": "ZERO_RESULTS"}]}]}"#;
+        assert!(matches!(
+            DistanceMatrix::from_google_distance_matrix(not_found, TravelCost::Distance),
+            Err(JsonError::MissingEntry { from: 0, to: 0 })
+        ));
+
+        let ragged = r#"{"status": "OK", "rows": [{"elements": []}]}"#;
+        assert!(matches!(
+            DistanceMatrix::from_google_distance_matrix(ragged, TravelCost::Distance),
+            Err(JsonError::Matrix(MatrixError::NotSquare { .. }))
+        ));
+    }
+}
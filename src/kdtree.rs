@@ -0,0 +1,233 @@
+//! A k-d tree over points in the plane, for finding the nearest few destinations to a
+//! point without measuring the distance to all of them.
+//!
+//! Distances are planar, between the coordinates as given, which is the metric itself
+//! for [`Metric::Euclidean`](crate::Metric::Euclidean) and an approximation for the
+//! others over small areas.
+
+use std::cmp::Ordering;
+
+use crate::Point;
+
+/// A balanced k-d tree over a fixed set of points, from which points can be removed,
+/// such as once they have been visited.
+///
+/// The tree is stored implicitly: the points of each subtree occupy a range of `order`
+/// with its root at the middle, split by x at even depths and by y at odd ones.
+#[derive(Debug, Clone)]
+pub struct KdTree {
+    points: Vec<Point>,
+    /// The indices of the points, arranged as the tree.
+    order: Vec<usize>,
+    /// Where each point is in `order`.
+    positions: Vec<usize>,
+    /// For the root of each subtree, how many of its points have not been removed.
+    remaining: Vec<usize>,
+    removed: Vec<bool>,
+}
+
+impl KdTree {
+    /// Builds a tree over `points`, in O(n log n) time on average.
+    pub fn new(points: Vec<Point>) -> Self {
+        let n = points.len();
+        let mut order = (0..n).collect::<Vec<_>>();
+        let mut remaining = vec![0; n];
+        build(&points, &mut order, &mut remaining, 0, n, 0);
+        let mut positions = vec![0; n];
+        for (position, &index) in order.iter().enumerate() {
+            positions[index] = position;
+        }
+        KdTree {
+            points,
+            order,
+            positions,
+            remaining,
+            removed: vec![false; n],
+        }
+    }
+
+    /// Every point the tree was built over, removed or not, by index.
+    pub fn points(&self) -> &[Point] {
+        &self.points
+    }
+
+    /// The number of points not removed.
+    pub fn len(&self) -> usize {
+        self.remaining
+            .get(self.order.len() / 2)
+            .copied()
+            .unwrap_or(0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The indices of the `k` points nearest `target` that have not been removed,
+    /// nearest first, or all of them if there are fewer. Ties go to the lower index.
+    pub fn nearest(&self, target: &Point, k: usize) -> Vec<usize> {
+        let mut best = Vec::with_capacity(k + 1);
+        if k > 0 {
+            self.search(target, k, 0, self.order.len(), 0, &mut best);
+        }
+        best.into_iter().map(|(_, index)| index).collect()
+    }
+
+    /// Removes the point at `index` from later searches, returning whether it was
+    /// still in the tree.
+    pub fn remove(&mut self, index: usize) -> bool {
+        if self.removed[index] {
+            return false;
+        }
+        self.removed[index] = true;
+        // Walk down from the root to the point, counting it out of every subtree on the way
+        let position = self.positions[index];
+        let (mut lo, mut hi) = (0, self.order.len());
+        loop {
+            let mid = (lo + hi) / 2;
+            self.remaining[mid] -= 1;
+            match position.cmp(&mid) {
+                Ordering::Less => hi = mid,
+                Ordering::Equal => return true,
+                Ordering::Greater => lo = mid + 1,
+            }
+        }
+    }
+
+    /// Adds the nearest points of the subtree over `order[lo..hi]` to `best`, which is
+    /// kept sorted by squared distance and no longer than `k`.
+    fn search(
+        &self,
+        target: &Point,
+        k: usize,
+        lo: usize,
+        hi: usize,
+        depth: usize,
+        best: &mut Vec<(f64, usize)>,
+    ) {
+        if lo >= hi {
+            return;
+        }
+        let mid = (lo + hi) / 2;
+        if self.remaining[mid] == 0 {
+            return;
+        }
+        let index = self.order[mid];
+        let point = &self.points[index];
+        if !self.removed[index] {
+            let candidate = (squared(point, target), index);
+            let at = best.partition_point(|&(distance, other)| (distance, other) < candidate);
+            if at < k {
+                best.insert(at, candidate);
+                best.truncate(k);
+            }
+        }
+
+        let gap = axis(target, depth) - axis(point, depth);
+        let (near, far) = if gap < 0.0 {
+            ((lo, mid), (mid + 1, hi))
+        } else {
+            ((mid + 1, hi), (lo, mid))
+        };
+        self.search(target, k, near.0, near.1, depth + 1, best);
+        // Points across the split are at least the gap away
+        if best.len() < k || gap * gap <= best[best.len() - 1].0 {
+            self.search(target, k, far.0, far.1, depth + 1, best);
+        }
+    }
+}
+
+/// Arranges `order[lo..hi]` as a subtree split on the axis for `depth`, recording the
+/// size of each subtree at its root.
+fn build(
+    points: &[Point],
+    order: &mut [usize],
+    remaining: &mut [usize],
+    lo: usize,
+    hi: usize,
+    depth: usize,
+) {
+    if lo >= hi {
+        return;
+    }
+    let mid = (lo + hi) / 2;
+    order[lo..hi].select_nth_unstable_by(mid - lo, |&a, &b| {
+        axis(&points[a], depth).total_cmp(&axis(&points[b], depth))
+    });
+    remaining[mid] = hi - lo;
+    build(points, order, remaining, lo, mid, depth + 1);
+    build(points, order, remaining, mid + 1, hi, depth + 1);
+}
+
+fn axis(point: &Point, depth: usize) -> f64 {
+    if depth.is_multiple_of(2) {
+        point.x
+    } else {
+        point.y
+    }
+}
+
+fn squared(a: &Point, b: &Point) -> f64 {
+    (a.x - b.x).powi(2) + (a.y - b.y).powi(2)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rng::SplitMix64;
+
+    use super::*;
+
+    /// The `k` nearest by measuring every point.
+    fn brute_force(points: &[Point], removed: &[bool], target: &Point, k: usize) -> Vec<usize> {
+        let mut indices = (0..points.len())
+            .filter(|&i| !removed[i])
+            .collect::<Vec<_>>();
+        indices.sort_by(|&a, &b| {
+            squared(&points[a], target)
+                .total_cmp(&squared(&points[b], target))
+                .then(a.cmp(&b))
+        });
+        indices.truncate(k);
+        indices
+    }
+
+    #[test]
+    fn test_nearest() {
+        let mut rng = SplitMix64::new(31);
+        // Integer coordinates make ties, which must break the same way
+        let points = (0..300)
+            .map(|_| Point::new(rng.below(50) as f64, rng.below(50) as f64))
+            .collect::<Vec<_>>();
+        let mut tree = KdTree::new(points.clone());
+        let mut removed = vec![false; points.len()];
+        assert_eq!(tree.len(), 300);
+
+        for round in 0..200 {
+            let target = Point::new(rng.unit() * 60.0 - 5.0, rng.unit() * 60.0 - 5.0);
+            let k = round % 12;
+            assert_eq!(
+                tree.nearest(&target, k),
+                brute_force(&points, &removed, &target, k)
+            );
+            let index = rng.below(points.len());
+            assert_eq!(tree.remove(index), !removed[index]);
+            removed[index] = true;
+        }
+        assert_eq!(tree.len(), removed.iter().filter(|&&r| !r).count());
+    }
+
+    #[test]
+    fn test_empty_and_exhausted() {
+        let tree = KdTree::new(Vec::new());
+        assert!(tree.is_empty());
+        assert_eq!(tree.nearest(&Point::new(0.0, 0.0), 3), Vec::<usize>::new());
+
+        let mut tree = KdTree::new(vec![Point::new(1.0, 1.0), Point::new(2.0, 2.0)]);
+        assert_eq!(tree.nearest(&Point::new(0.0, 0.0), 5), vec![0, 1]);
+        assert!(tree.remove(0));
+        assert!(!tree.remove(0));
+        assert!(tree.remove(1));
+        assert!(tree.is_empty());
+        assert!(tree.nearest(&Point::new(0.0, 0.0), 1).is_empty());
+    }
+}
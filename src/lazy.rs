@@ -0,0 +1,173 @@
+//! Distances computed the first time they are needed and remembered after, for metrics
+//! too expensive to compute for every pair up front, such as road routing, when a
+//! search only ever looks at a fraction of the pairs but looks at those repeatedly.
+
+use std::{cell::Cell, ops::Add, rc::Rc};
+
+use num_traits::Zero;
+
+use crate::{cached_fn, Problem};
+
+/// A matrix whose entries are computed on first access and cached, through
+/// [`cached_fn`].
+///
+/// The moves on [`Route`](crate::Route) and in [`delta`](crate::delta) take the
+/// distance as a function, so pass them `|a, b| matrix.get(a, b)`.
+pub struct LazyDistanceMatrix<'a, Distance = f64> {
+    size: usize,
+    symmetric: bool,
+    distance: Box<dyn Fn((usize, usize)) -> Distance + 'a>,
+    /// The number of entries computed, shared with the cached function
+    computed: Rc<Cell<usize>>,
+}
+
+impl<'a, Distance> LazyDistanceMatrix<'a, Distance>
+where
+    Distance: Clone + 'a,
+{
+    /// A `size` × `size` matrix whose entry `(from, to)` is `distance(from, to)`.
+    pub fn new(size: usize, distance: impl Fn(usize, usize) -> Distance + 'a) -> Self {
+        Self::build(size, false, distance)
+    }
+
+    /// A symmetric `size` × `size` matrix, which computes `distance(from, to)` only
+    /// with `from <= to` and uses it for both directions.
+    pub fn symmetric(size: usize, distance: impl Fn(usize, usize) -> Distance + 'a) -> Self {
+        Self::build(size, true, distance)
+    }
+
+    fn build(
+        size: usize,
+        symmetric: bool,
+        distance: impl Fn(usize, usize) -> Distance + 'a,
+    ) -> Self {
+        let computed = Rc::new(Cell::new(0));
+        let counter = Rc::clone(&computed);
+        let distance = cached_fn(move |(from, to): (usize, usize)| {
+            counter.set(counter.get() + 1);
+            distance(from, to)
+        });
+        LazyDistanceMatrix {
+            size,
+            symmetric,
+            distance: Box::new(distance),
+            computed,
+        }
+    }
+
+    /// The number of destinations.
+    pub fn len(&self) -> usize {
+        self.size
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+
+    /// The distance from `from` to `to`, computed now if it has not been already.
+    ///
+    /// # Panics
+    ///
+    /// Panics if either destination is out of range.
+    pub fn get(&self, from: usize, to: usize) -> Distance {
+        assert!(
+            from < self.size && to < self.size,
+            "({from}, {to}) is outside a matrix of {} destinations",
+            self.size
+        );
+        if self.symmetric && from > to {
+            (self.distance)((to, from))
+        } else {
+            (self.distance)((from, to))
+        }
+    }
+
+    /// How many entries have been computed so far.
+    pub fn computed(&self) -> usize {
+        self.computed.get()
+    }
+
+    /// The total distance of a route given as destination indices.
+    pub fn route_distance(&self, route: &[usize]) -> Distance
+    where
+        Distance: Zero,
+    {
+        route
+            .windows(2)
+            .map(|leg| self.get(leg[0], leg[1]))
+            .fold(Distance::zero(), Add::add)
+    }
+}
+
+impl Problem {
+    /// Computes the distances between destinations as they are needed, rather than
+    /// every pair up front as [`distance_matrix`](Problem::distance_matrix) does.
+    pub fn lazy_distance_matrix(&self) -> LazyDistanceMatrix<'_> {
+        LazyDistanceMatrix::symmetric(self.len(), |from, to| self.distance(from, to))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{delta, rng::SplitMix64, Metric, Point, Route};
+
+    use super::*;
+
+    #[test]
+    fn test_lazy_distance_matrix() {
+        let calls = Cell::new(0);
+        let matrix = LazyDistanceMatrix::new(4, |from, to| {
+            calls.set(calls.get() + 1);
+            from * 10 + to
+        });
+        assert_eq!(matrix.len(), 4);
+        assert_eq!(matrix.computed(), 0);
+        assert_eq!(matrix.get(1, 2), 12);
+        assert_eq!(matrix.get(1, 2), 12);
+        assert_eq!(matrix.get(2, 1), 21);
+        assert_eq!(calls.get(), 2);
+        assert_eq!(matrix.computed(), 2);
+        assert_eq!(matrix.route_distance(&[1, 2, 1]), 33);
+        assert_eq!(calls.get(), 2);
+
+        let matrix = LazyDistanceMatrix::symmetric(4, |from, to| {
+            assert!(from <= to);
+            from * 10 + to
+        });
+        assert_eq!(matrix.get(3, 1), 13);
+        assert_eq!(matrix.get(1, 3), 13);
+        assert_eq!(matrix.computed(), 1);
+    }
+
+    #[test]
+    fn test_local_search() {
+        // 2-opt through the lazy matrix looks at only the pairs its moves touch
+        let mut rng = SplitMix64::new(29);
+        let problem = Problem::new(
+            (0..100)
+                .map(|_| Point::new(rng.unit() * 100.0, rng.unit() * 100.0))
+                .collect(),
+            Metric::Euclidean,
+        );
+        let matrix = problem.lazy_distance_matrix();
+        let distance = |a, b| matrix.get(a, b);
+        let mut route = Route::new((0..100).chain([0]).collect());
+        let before = matrix.route_distance(&route);
+        for i in 1..50 {
+            for j in i + 1..100 {
+                if delta::two_opt(&route, i, j, distance) < -1e-9 {
+                    route.reverse_segment(i, j, distance);
+                }
+            }
+        }
+        assert!(matrix.route_distance(&route) < before);
+        assert!((matrix.route_distance(&route) - problem.route_distance(&route)).abs() < 1e-9);
+        assert!(matrix.computed() < 100 * 101 / 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "outside a matrix")]
+    fn test_get_outside() {
+        LazyDistanceMatrix::new(2, |_, _| 0.0).get(2, 0);
+    }
+}
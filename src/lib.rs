@@ -1,6 +1,97 @@
-use std::{cell::RefCell, collections::HashMap, iter::Sum, ops::Add};
+use std::{cell::RefCell, cmp::Ordering, collections::HashMap, ops::Add};
 
 use itertools::Itertools as _;
+use num_traits::Zero;
+
+/// Asserts an invariant of a search in progress when the `debug-invariants` feature is
+/// enabled, and compiles to nothing otherwise. These catch algorithm mistakes during
+/// development but can be too expensive even for debug builds.
+macro_rules! invariant {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "debug-invariants")]
+        assert!($($arg)*);
+    };
+}
+
+#[cfg(feature = "tokio")]
+pub mod asynchronous;
+pub mod background;
+pub mod batch;
+pub mod beam;
+pub mod bound;
+pub mod branch_bound;
+pub mod cache;
+#[cfg(feature = "plotters")]
+pub mod charts;
+pub mod christofides;
+pub mod cluster;
+pub mod compare;
+#[cfg(feature = "serde")]
+pub mod config;
+pub mod delta;
+pub mod diff;
+pub mod difficulty;
+#[cfg(feature = "dubins")]
+pub mod dubins;
+pub mod events;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod fixed;
+pub mod generalized;
+pub mod generators;
+pub mod geo;
+#[cfg(feature = "graph")]
+pub mod graph;
+pub mod heatmap;
+pub mod hilbert;
+pub mod ils;
+#[cfg(feature = "serde")]
+pub mod json;
+pub mod kdtree;
+pub mod lazy;
+pub mod local_search;
+pub mod matching;
+pub mod matrix;
+#[cfg(feature = "memoize")]
+pub mod memoize;
+pub mod metric;
+pub mod mst;
+pub mod multi_depot;
+pub mod objective;
+pub mod optional;
+#[cfg(feature = "rayon")]
+pub mod parallel;
+pub mod pickup_delivery;
+pub mod pinned;
+pub mod planar;
+pub mod pool;
+pub mod problem;
+pub mod registry;
+pub mod relinking;
+pub mod report;
+mod rng;
+pub mod route;
+pub mod sampling;
+pub mod search;
+pub mod sensitivity;
+pub mod soft_constraints;
+pub mod stability;
+pub mod stats;
+pub mod streaming;
+pub mod svg;
+pub mod tempering;
+#[cfg(feature = "testing")]
+pub mod testing;
+pub mod trips;
+pub mod tsplib;
+pub mod units;
+pub mod variants;
+pub mod violations;
+
+pub use matrix::DistanceMatrix;
+pub use metric::{Metric, Point};
+pub use problem::Problem;
+pub use route::Route;
 
 /// Calculates the total distance of a route by summing the distances between consecutive destinations.
 ///
@@ -18,9 +109,62 @@ fn total_distance_of_route<'a, Destination, Distance>(
 ) -> Distance
 where
     Destination: 'a,
-    Distance: Sum,
+    Distance: Zero,
 {
-    route.tuples().map(compute_distance).sum()
+    route
+        .tuple_windows()
+        .map(compute_distance)
+        .fold(Distance::zero(), Add::add)
+}
+
+/// Scores every order of the inner destinations as a route from `start` to `end`, the
+/// pipeline [`traveling_salesman`] takes the shortest of, for callers that filter the
+/// routes, stop early or sample them themselves.
+///
+/// The orders come lazily, in the lexicographic order of the destinations' positions,
+/// so taking only some of them costs only their evaluation. With no inner destinations
+/// there is nothing to order, and so no routes.
+///
+/// # Arguments
+///
+/// * `inner_destinations` - The destinations to visit between the ends
+/// * `start` - The destination the routes start at
+/// * `end` - The destination the routes end at
+/// * `compute_distance` - A function that computes the distance between two destinations
+///
+/// # Returns
+///
+/// An iterator over each route's total distance and its inner destinations in order
+pub fn score_permutations<Destinations, Destination, Distance>(
+    inner_destinations: Destinations,
+    start: Destination,
+    end: Destination,
+    compute_distance: impl Fn((&Destination, &Destination)) -> Distance,
+) -> impl Iterator<Item = (Distance, Vec<Destination>)>
+where
+    Destinations: Iterator<Item = Destination> + ExactSizeIterator,
+    Destination: Clone,
+    Distance: PartialOrd + Zero,
+{
+    let count = inner_destinations.len();
+    inner_destinations
+        .permutations(count)
+        .filter(|r| !r.is_empty())
+        .map(move |route| {
+            let inner_distance = total_distance_of_route(route.iter(), &compute_distance);
+
+            // Add the distance from the start to the first destination and from the last destination to the end
+            // Safety: route.len() >= 1
+            let total_distance = inner_distance
+                + compute_distance((&start, &route[0]))
+                + compute_distance((&route[route.len() - 1], &end));
+            invariant!(
+                total_distance >= total_distance_of_route(route.iter(), &compute_distance),
+                "adding the legs to and from the ends made a route shorter, is a distance negative or NaN?"
+            );
+
+            (total_distance, route)
+        })
 }
 
 /// For all of the inner destinations, find the shortest path that visits all of them starting
@@ -41,63 +185,125 @@ pub fn traveling_salesman<Destinations, Destination, Distance>(
 where
     Destinations: Iterator<Item = Destination> + ExactSizeIterator,
     Destination: Clone,
-    Distance: Ord + Sum<Distance> + Add<Distance, Output = Distance>,
+    Distance: PartialOrd + Zero,
 {
-    // Get all permutations of the inner destinations
-    let permutations = {
-        let count = inner_destinations.len();
-        inner_destinations
-            .permutations(count)
-            .filter(|r| !r.is_empty())
-    };
-
-    // Calculate the distance for each route
-    // distance is a Iterator of tuples of (distance, route)
-    let distances = permutations.map(|route| {
-        let inner_distance = total_distance_of_route(route.iter(), &compute_distance);
-
-        // Add the distance from the start to the first destination and from the last destination to the end
-        // Safety: route.len() >= 1
-        let total_distance = inner_distance
-            + compute_distance((&start, &route[0]))
-            + compute_distance((&route[route.len() - 1], &end));
-
-        (total_distance, route)
-    });
-
+    #[cfg(feature = "debug-invariants")]
+    let count = inner_destinations.len();
     // Find the route with the shortest distance
-    let min_route = distances
-        .min_by(|a, b| a.0.cmp(&b.0))
-        .map(|(_, route)| route);
+    // Incomparable distances (e.g. NaN) are treated as equal
+    let min_route = score_permutations(
+        inner_destinations,
+        start.clone(),
+        end.clone(),
+        compute_distance,
+    )
+    .min_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(Ordering::Equal))
+    .map(|(_, route)| route);
 
     let mut route = vec![start];
     if let Some(min_route) = min_route {
         route.extend(min_route);
     }
     route.push(end);
+    invariant!(route.len() == count + 2, "the route skipped a destination");
     route
 }
 
 /// Caches the results of any function call.
+///
+/// Use [`CachedFn`](cache::CachedFn) to inspect or prune what has been cached.
 pub fn cached_fn<Input, Output>(f: impl Fn(Input) -> Output) -> impl Fn(Input) -> Output
 where
     Input: std::hash::Hash + std::cmp::Eq + Clone,
     Output: Clone,
 {
-    let cache = HashMap::<Input, Output>::new();
-    let cache = RefCell::new(cache);
+    let cached = cache::CachedFn::new(f);
+    move |input| cached.call(input)
+}
+
+/// Caches the results of a function of borrowed input, such as `&str`, storing each key
+/// as its owned form, such as `String`.
+///
+/// Unlike [`cached_fn`], which takes its input by value and so clones it on every call,
+/// an owned key is made only on a miss, which saves an allocation per call for
+/// string-keyed memoization.
+pub fn cached_fn_by_ref<Query, Output>(f: impl Fn(&Query) -> Output) -> impl Fn(&Query) -> Output
+where
+    Query: std::hash::Hash + std::cmp::Eq + ToOwned + ?Sized,
+    Query::Owned: std::hash::Hash + std::cmp::Eq,
+    Output: Clone,
+{
+    let cache = RefCell::new(HashMap::<Query::Owned, Output>::new());
 
     move |input| {
-        let mut cache = cache.borrow_mut();
+        if let Some(result) = cache.borrow().get(input) {
+            return result.clone();
+        }
+        let result = f(input);
+        cache.borrow_mut().insert(input.to_owned(), result.clone());
+        result
+    }
+}
 
-        if let Some(result) = cache.get(&input) {
-            result.clone()
-        } else {
-            let result = f(input.clone());
-            cache.insert(input, result.clone());
-            result
+/// Caches the results of a recursive function, so its recursive calls are cached too.
+///
+/// `f` is given the cached function to make its recursive calls through, along with
+/// the input, as in `cached_fix(|fib, n: u64| if n < 2 { n } else { fib(n - 1) + fib(n - 2) })`.
+pub fn cached_fix<Input, Output>(
+    f: impl Fn(&dyn Fn(Input) -> Output, Input) -> Output,
+) -> impl Fn(Input) -> Output
+where
+    Input: std::hash::Hash + std::cmp::Eq + Clone,
+    Output: Clone,
+{
+    fn call<Input, Output, F>(
+        f: &F,
+        cache: &RefCell<HashMap<Input, Output>>,
+        input: Input,
+    ) -> Output
+    where
+        Input: std::hash::Hash + std::cmp::Eq + Clone,
+        Output: Clone,
+        F: Fn(&dyn Fn(Input) -> Output, Input) -> Output,
+    {
+        if let Some(result) = cache.borrow().get(&input) {
+            return result.clone();
         }
+        // The cache is not borrowed while `f` runs, as its recursive calls use it
+        let result = f(&|input| call(f, cache, input), input.clone());
+        cache.borrow_mut().insert(input, result.clone());
+        result
     }
+
+    let cache = RefCell::new(HashMap::<Input, Output>::new());
+    move |input| call(&f, &cache, input)
+}
+
+/// Runs `body` with a cached version of `f`, as [`cached_fn`] makes, and drops the
+/// cache when `body` returns.
+///
+/// The cached function is only borrowed by `body`, so it cannot outlive the scope, and
+/// memoization meant for one solve, such as of distances, does not linger in a
+/// long-lived map after it.
+///
+/// # Arguments
+///
+/// * `f` - The function to cache
+/// * `body` - The work to do with the cached function, such as a solve
+///
+/// # Returns
+///
+/// What `body` returns
+pub fn with_cache<Input, Output, Result>(
+    f: impl Fn(Input) -> Output,
+    body: impl FnOnce(&dyn Fn(Input) -> Output) -> Result,
+) -> Result
+where
+    Input: std::hash::Hash + std::cmp::Eq + Clone,
+    Output: Clone,
+{
+    let cached = cached_fn(f);
+    body(&cached)
 }
 
 /// A hand-optimized version of the traveling salesman algorithm for i32 destinations.
@@ -128,17 +334,18 @@ pub fn hand_rolled_traveling_salesman<'a>(
         .filter(|p| !p.is_empty())
     {
         // Hand-calculate the distance of the pairs
-        let mut distance = 0;
+        // Accumulate in u64 since a handful of legs across the whole i32 range overflow u32
+        let mut distance = 0u64;
         for i in 1..perm.len() {
             // Safety: i >= 1 and i < perm.len()
-            distance += perm[i - 1].abs_diff(*perm[i]);
+            distance += u64::from(perm[i - 1].abs_diff(*perm[i]));
         }
 
         // Safety: Safe because perm.len() >= 1
         // add from start to the first
-        distance += start.abs_diff(*perm[0]);
+        distance += u64::from(start.abs_diff(*perm[0]));
         // add from end to the last
-        distance += end.abs_diff(*perm[perm.len() - 1]);
+        distance += u64::from(end.abs_diff(*perm[perm.len() - 1]));
 
         if let Some(min_distance) = min_distance {
             if distance >= min_distance {
@@ -155,10 +362,314 @@ pub fn hand_rolled_traveling_salesman<'a>(
         route.extend(min_route);
     }
     route.push(end);
+    invariant!(
+        route.len() == destination.len() + 2,
+        "the route skipped a destination"
+    );
+
+    route
+}
+
+/// [`hand_rolled_traveling_salesman`] returning the route's values rather than
+/// references into `destination`, as [`traveling_salesman`] does when given owned
+/// destinations, so the two can be compared directly.
+///
+/// # Arguments
+///
+/// * `destination` - Slice of destinations to visit
+/// * `start` - The starting point
+/// * `end` - The ending point
+///
+/// # Returns
+///
+/// The shortest path that visits all destinations
+pub fn hand_rolled_traveling_salesman_owned(destination: &[i32], start: i32, end: i32) -> Vec<i32> {
+    hand_rolled_traveling_salesman(destination, &start, &end)
+        .into_iter()
+        .copied()
+        .collect()
+}
+
+/// The hand-rolled solver with bounds checks removed, as the "how fast can we go"
+/// comparison point.
+///
+/// Permutations of indices are generated in place with Heap's algorithm, distances are
+/// summed with unchecked indexing, and nothing is allocated inside the loop.
+///
+/// # Arguments
+///
+/// * `destination` - Slice of destinations to visit
+/// * `start` - The starting point
+/// * `end` - The ending point
+///
+/// # Returns
+///
+/// The shortest path that visits all destinations
+pub fn hand_rolled_traveling_salesman_unchecked<'a>(
+    destination: &'a [i32],
+    start: &'a i32,
+    end: &'a i32,
+) -> Vec<&'a i32> {
+    let n = destination.len();
+    if n == 0 {
+        return vec![start, end];
+    }
+
+    /// Sums the legs of the route from `start` through `order` to `end`.
+    ///
+    /// # Safety
+    ///
+    /// `order` must be non-empty and every index in it less than `destination.len()`.
+    unsafe fn distance_unchecked(
+        destination: &[i32],
+        order: &[usize],
+        start: i32,
+        end: i32,
+    ) -> u64 {
+        // SAFETY: The caller guarantees every index in `order` is in bounds of
+        // `destination`, and that `order` is non-empty so 0 and len - 1 are in bounds
+        unsafe {
+            let at = |i: usize| *destination.get_unchecked(*order.get_unchecked(i));
+            let mut distance = u64::from(start.abs_diff(at(0)));
+            let mut i = 1;
+            while i < order.len() {
+                distance += u64::from(at(i - 1).abs_diff(at(i)));
+                i += 1;
+            }
+            distance + u64::from(at(order.len() - 1).abs_diff(end))
+        }
+    }
+
+    // `order` is always a permutation of 0..n, which every unchecked access relies on
+    let mut order = (0..n).collect::<Vec<_>>();
+    let mut min_order = order.clone();
+    // SAFETY: `order` is a permutation of 0..n with n > 0
+    let mut min_distance = unsafe { distance_unchecked(destination, &order, *start, *end) };
+
+    // Heap's algorithm: counters[i] is how many times position i has been swapped
+    // while the positions before it are permuted
+    let mut counters = vec![0usize; n];
+    let mut i = 1;
+    while i < n {
+        // SAFETY: i < n, and counters[i] < i whenever it is used as an index, so every
+        // index is in bounds of `counters` and `order`, which both have length n
+        unsafe {
+            let counter = counters.get_unchecked_mut(i);
+            if *counter < i {
+                let other = if i % 2 == 0 { 0 } else { *counter };
+                *counter += 1;
+                // Swapping two positions keeps `order` a permutation of 0..n
+                let pointer = order.as_mut_ptr();
+                std::ptr::swap(pointer.add(other), pointer.add(i));
+
+                let distance = distance_unchecked(destination, &order, *start, *end);
+                if distance < min_distance {
+                    min_distance = distance;
+                    min_order.copy_from_slice(&order);
+                }
+                i = 1;
+            } else {
+                *counter = 0;
+                i += 1;
+            }
+        }
+    }
 
+    let mut route = Vec::with_capacity(n + 2);
+    route.push(start);
+    // SAFETY: `min_order` is a copy of `order`, so a permutation of 0..n
+    route.extend(
+        min_order
+            .iter()
+            .map(|&index| unsafe { destination.get_unchecked(index) }),
+    );
+    route.push(end);
     route
 }
 
+/// A hand-optimized version of the traveling salesman algorithm for u64 destinations,
+/// the sibling of [`hand_rolled_traveling_salesman`] for unsigned coordinates such as
+/// mile markers or timestamps.
+///
+/// # Arguments
+///
+/// * `destination` - Slice of destinations to visit
+/// * `start` - The starting point
+/// * `end` - The ending point
+///
+/// # Returns
+///
+/// The shortest path that visits all destinations
+pub fn hand_rolled_traveling_salesman_u64<'a>(
+    destination: &'a [u64],
+    start: &'a u64,
+    end: &'a u64,
+) -> Vec<&'a u64> {
+    let mut min_distance = None;
+    let mut min_route = None;
+
+    for perm in destination
+        .iter()
+        .permutations(destination.len())
+        .filter(|p| !p.is_empty())
+    {
+        // Accumulate in u128 since two legs across the whole u64 range overflow u64
+        let mut distance = u128::from(start.abs_diff(*perm[0]));
+        for i in 1..perm.len() {
+            distance += u128::from(perm[i - 1].abs_diff(*perm[i]));
+        }
+        distance += u128::from(end.abs_diff(*perm[perm.len() - 1]));
+
+        if let Some(min_distance) = min_distance {
+            if distance >= min_distance {
+                continue; // skip if this route is longer than the current minimum
+            }
+        }
+        min_distance = Some(distance);
+        min_route = Some(perm);
+    }
+
+    let mut route = vec![start];
+    if let Some(min_route) = min_route {
+        route.extend(min_route);
+    }
+    route.push(end);
+    invariant!(
+        route.len() == destination.len() + 2,
+        "the route skipped a destination"
+    );
+
+    route
+}
+
+/// A hand-optimized version of the traveling salesman algorithm for f64 destinations,
+/// the sibling of [`hand_rolled_traveling_salesman`] for real coordinates.
+///
+/// Distances are compared with [`f64::total_cmp`], so the result is well defined even
+/// when a destination is NaN: a NaN distance is longer than any other, and a route
+/// through a NaN destination wins only if every route goes through one.
+///
+/// # Arguments
+///
+/// * `destination` - Slice of destinations to visit
+/// * `start` - The starting point
+/// * `end` - The ending point
+///
+/// # Returns
+///
+/// The shortest path that visits all destinations
+pub fn hand_rolled_traveling_salesman_f64<'a>(
+    destination: &'a [f64],
+    start: &'a f64,
+    end: &'a f64,
+) -> Vec<&'a f64> {
+    let mut min_distance: Option<f64> = None;
+    let mut min_route = None;
+
+    for perm in destination
+        .iter()
+        .permutations(destination.len())
+        .filter(|p| !p.is_empty())
+    {
+        let mut distance = (start - perm[0]).abs();
+        for i in 1..perm.len() {
+            distance += (perm[i - 1] - perm[i]).abs();
+        }
+        distance += (end - perm[perm.len() - 1]).abs();
+
+        if let Some(min_distance) = min_distance {
+            if distance.total_cmp(&min_distance).is_ge() {
+                continue; // skip if this route is no shorter than the current minimum
+            }
+        }
+        min_distance = Some(distance);
+        min_route = Some(perm);
+    }
+
+    let mut route = vec![start];
+    if let Some(min_route) = min_route {
+        route.extend(min_route);
+    }
+    route.push(end);
+    invariant!(
+        route.len() == destination.len() + 2,
+        "the route skipped a destination"
+    );
+
+    route
+}
+
+/// A version of the traveling salesman algorithm for a number of destinations fixed at
+/// compile time, which never allocates and so suits embedded and real-time callers.
+///
+/// Permutations are generated in place with Heap's algorithm, so the stack holds
+/// only the destinations, the best order so far and one counter per destination.
+///
+/// # Arguments
+///
+/// * `inner_destinations` - The destinations to visit
+/// * `start` - The starting destination
+/// * `end` - The ending destination
+/// * `compute_distance` - A function that computes the distance between two destinations
+///
+/// # Returns
+///
+/// The inner destinations in the order of the shortest route from `start` to `end`.
+/// The route itself is `start`, these, then `end`; returning `[Destination; N + 2]`
+/// would need const generic expressions, which are not yet stable.
+pub fn traveling_salesman_const<Destination, Distance, const N: usize>(
+    inner_destinations: [Destination; N],
+    start: &Destination,
+    end: &Destination,
+    compute_distance: impl Fn((&Destination, &Destination)) -> Distance,
+) -> [Destination; N]
+where
+    Destination: Copy,
+    Distance: PartialOrd + Add<Distance, Output = Distance>,
+{
+    let distance = |route: &[Destination; N]| match (route.first(), route.last()) {
+        (Some(first), Some(last)) => {
+            let mut distance = compute_distance((start, first));
+            for leg in route.windows(2) {
+                distance = distance + compute_distance((&leg[0], &leg[1]));
+            }
+            distance + compute_distance((last, end))
+        }
+        _ => compute_distance((start, end)),
+    };
+
+    let mut route = inner_destinations;
+    let mut min_route = route;
+    let mut min_distance = distance(&route);
+
+    // Heap's algorithm: counters[i] is how many times destination i has been swapped
+    // into place while the destinations before it are permuted
+    let mut counters = [0usize; N];
+    let mut i = 1;
+    while i < N {
+        if counters[i] < i {
+            if i % 2 == 0 {
+                route.swap(0, i);
+            } else {
+                route.swap(counters[i], i);
+            }
+            let route_distance = distance(&route);
+            // Incomparable distances (e.g. NaN) never replace the minimum
+            if route_distance < min_distance {
+                min_distance = route_distance;
+                min_route = route;
+            }
+            counters[i] += 1;
+            i = 1;
+        } else {
+            counters[i] = 0;
+            i += 1;
+        }
+    }
+
+    min_route
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -188,9 +699,9 @@ mod tests {
     /// algorithm matches the generic version for a set of random destinations.
     #[test]
     fn test_random_destinations_match_all() {
-        for _ in 0..10 {
+        for seed in 0..10 {
             let destinations =
-                HashSet::<i32>::from_iter((1..=5).map(|_| rand::random::<i32>() % 100));
+                HashSet::<i32>::from_iter(generators::random_integers(5, -99..100, seed));
 
             let start = destinations.clone().into_iter().min().unwrap_or(0);
             let end = destinations.clone().into_iter().max().unwrap_or(1);
@@ -205,6 +716,34 @@ mod tests {
         }
     }
 
+    #[cfg(feature = "debug-invariants")]
+    #[test]
+    #[should_panic(expected = "is a distance negative")]
+    fn test_negative_distance_breaks_invariant() {
+        traveling_salesman([1, 2].into_iter(), 0, 3, |(a, b)| a - b);
+    }
+
+    #[test]
+    fn test_score_permutations() {
+        let distance = |pair: (&i32, &i32)| pair.0.abs_diff(*pair.1);
+        let scored = score_permutations([1, 3, 2].into_iter(), 0, 4, distance).collect::<Vec<_>>();
+        assert_eq!(scored.len(), 6);
+        assert_eq!(scored[0], (6, vec![1, 3, 2]));
+        assert_eq!(scored[1], (4, vec![1, 2, 3]));
+        assert_eq!(scored.iter().map(|(distance, _)| *distance).min(), Some(4));
+
+        // Stopping at the first route short enough
+        let first = score_permutations(1..6, 0, 6, distance)
+            .find(|(distance, _)| *distance <= 6)
+            .unwrap();
+        assert_eq!(first, (6, vec![1, 2, 3, 4, 5]));
+
+        assert_eq!(
+            score_permutations(std::iter::empty(), 0, 4, distance).count(),
+            0
+        );
+    }
+
     #[test]
     fn test_traveling_salesman_empty_destinations() {
         let destinations: Vec<i32> = vec![];
@@ -217,6 +756,81 @@ mod tests {
         assert_eq!(result, vec![0, 1]);
     }
 
+    #[test]
+    fn test_hand_rolled_unchecked_matches_safe() {
+        let total = |route: &[&i32]| -> u64 {
+            route
+                .windows(2)
+                .map(|leg| u64::from(leg[0].abs_diff(*leg[1])))
+                .sum()
+        };
+
+        assert_eq!(
+            hand_rolled_traveling_salesman_unchecked(&[], &0, &1),
+            vec![&0, &1]
+        );
+        let extremes = [i32::MAX, i32::MIN, 0, i32::MAX, -1];
+        for len in 0..=extremes.len() {
+            let destinations = &extremes[..len];
+            assert_eq!(
+                total(&hand_rolled_traveling_salesman_unchecked(
+                    destinations,
+                    &i32::MIN,
+                    &7
+                )),
+                total(&hand_rolled_traveling_salesman(destinations, &i32::MIN, &7))
+            );
+        }
+
+        for seed in 0..10 {
+            let destinations = generators::random_integers(6, -99..100, seed);
+            let safe = hand_rolled_traveling_salesman(&destinations, &0, &0);
+            let unchecked = hand_rolled_traveling_salesman_unchecked(&destinations, &0, &0);
+            assert_eq!(total(&unchecked), total(&safe));
+
+            let mut visited = unchecked[1..unchecked.len() - 1]
+                .iter()
+                .map(|&&d| d)
+                .collect::<Vec<_>>();
+            let mut expected = destinations.clone();
+            visited.sort();
+            expected.sort();
+            assert_eq!(visited, expected);
+        }
+    }
+
+    #[test]
+    fn test_traveling_salesman_const() {
+        let compute_distance = |pair: (&i32, &i32)| pair.0.abs_diff(*pair.1);
+
+        let result = traveling_salesman_const([4, 1, 5, 3, 2], &0, &6, compute_distance);
+        assert_eq!(result, [1, 2, 3, 4, 5]);
+
+        assert_eq!(traveling_salesman_const([7], &0, &6, compute_distance), [7]);
+        assert_eq!(
+            traveling_salesman_const([], &0, &6, compute_distance),
+            [0i32; 0]
+        );
+    }
+
+    #[test]
+    fn test_traveling_salesman_const_matches_generic() {
+        for seed in 0..10 {
+            let destinations: [i32; 6] = generators::random_integers(6, -99..100, seed)
+                .try_into()
+                .unwrap();
+            let compute_distance = |pair: (&i32, &i32)| pair.0.abs_diff(*pair.1);
+            let total = |route: &[i32]| -> u32 {
+                route.windows(2).map(|leg| leg[0].abs_diff(leg[1])).sum()
+            };
+
+            let generic = traveling_salesman(destinations.into_iter(), 0, 50, compute_distance);
+            let constant = traveling_salesman_const(destinations, &0, &50, compute_distance);
+            let constant = [&[0][..], &constant, &[50]].concat();
+            assert_eq!(total(&constant), total(&generic));
+        }
+    }
+
     #[test]
     fn test_cached_fn() {
         let call_count = std::cell::Cell::new(0);
@@ -231,6 +845,140 @@ mod tests {
         assert_eq!(call_count.get(), 1); // Verify f was only called once
     }
 
+    #[test]
+    fn test_cached_fn_by_ref() {
+        let call_count = std::cell::Cell::new(0);
+        let cached_len = cached_fn_by_ref(|name: &str| {
+            call_count.set(call_count.get() + 1);
+            name.len()
+        });
+
+        let owned = String::from("depot");
+        assert_eq!(cached_len("depot"), 5);
+        assert_eq!(cached_len(&owned), 5); // A String key is found by its &str
+        assert_eq!(cached_len("stop"), 4);
+        assert_eq!(call_count.get(), 2);
+
+        let cached_sum = cached_fn_by_ref(|legs: &[u32]| legs.iter().sum::<u32>());
+        assert_eq!(cached_sum(&[1, 2, 3]), 6);
+        assert_eq!(cached_sum(&[3, 2, 1]), 6);
+        assert_eq!(cached_sum(&Vec::from([1, 2, 3])), 6);
+    }
+
+    #[test]
+    fn test_cached_fix() {
+        let call_count = std::cell::Cell::new(0);
+        let fib = cached_fix(|fib, n: u64| {
+            call_count.set(call_count.get() + 1);
+            if n < 2 {
+                n
+            } else {
+                fib(n - 1) + fib(n - 2)
+            }
+        });
+
+        assert_eq!(fib(50), 12_586_269_025);
+        assert_eq!(call_count.get(), 51); // Once for each of 0..=50
+        assert_eq!(fib(30), 832_040);
+        assert_eq!(call_count.get(), 51);
+    }
+
+    #[test]
+    fn test_with_cache() {
+        let call_count = std::cell::Cell::new(0);
+        let distance = |(a, b): (i32, i32)| {
+            call_count.set(call_count.get() + 1);
+            a.abs_diff(b)
+        };
+        let route = with_cache(distance, |distance| {
+            traveling_salesman([3, 1, 2].into_iter(), 0, 4, |(a, b)| distance((*a, *b)))
+        });
+        assert_eq!(route, [0, 1, 2, 3, 4]);
+        // Each distinct leg once, rather than four legs for each of six routes
+        assert_eq!(call_count.get(), 12);
+
+        // The cache is dropped with the scope, and its entries with it
+        let shared = std::rc::Rc::new(());
+        with_cache(|_: u8| std::rc::Rc::clone(&shared), |cached| cached(1));
+        assert_eq!(std::rc::Rc::strong_count(&shared), 1);
+    }
+
+    #[test]
+    fn test_hand_rolled_extreme_values() {
+        let destinations = vec![i32::MAX, i32::MIN, i32::MAX, i32::MIN];
+
+        let result = hand_rolled_traveling_salesman(&destinations, &i32::MIN, &i32::MAX);
+        assert_eq!(
+            result,
+            vec![
+                &i32::MIN,
+                &i32::MIN,
+                &i32::MIN,
+                &i32::MAX,
+                &i32::MAX,
+                &i32::MAX
+            ]
+        );
+    }
+
+    #[test]
+    fn test_hand_rolled_owned() {
+        let destinations = generators::random_integers(6, -99..100, 3);
+        let generic =
+            traveling_salesman(destinations.iter().copied(), 0, 50, |(a, b)| a.abs_diff(*b));
+        let owned = hand_rolled_traveling_salesman_owned(&destinations, 0, 50);
+        assert_eq!(owned, generic);
+        assert_eq!(hand_rolled_traveling_salesman_owned(&[], 1, 2), [1, 2]);
+    }
+
+    #[test]
+    fn test_hand_rolled_u64_matches_generic() {
+        for seed in 0..10 {
+            let destinations = generators::random_integers(6, 0..1000, seed)
+                .into_iter()
+                .map(|x| x as u64)
+                .collect::<Vec<_>>();
+            let hand_rolled = hand_rolled_traveling_salesman_u64(&destinations, &0, &500);
+            let generic = traveling_salesman(destinations.iter(), &0, &500, |pair| {
+                pair.0.abs_diff(**pair.1)
+            });
+            let total = |route: &[&u64]| -> u64 {
+                route.windows(2).map(|leg| leg[0].abs_diff(*leg[1])).sum()
+            };
+            assert_eq!(total(&hand_rolled), total(&generic));
+        }
+
+        // Legs across the whole range would overflow a u64 sum
+        let destinations = [u64::MAX, 0, u64::MAX];
+        let result = hand_rolled_traveling_salesman_u64(&destinations, &0, &u64::MAX);
+        assert_eq!(result, [&0, &0, &u64::MAX, &u64::MAX, &u64::MAX]);
+        assert_eq!(hand_rolled_traveling_salesman_u64(&[], &3, &4), [&3, &4]);
+    }
+
+    #[test]
+    fn test_hand_rolled_f64_matches_generic() {
+        for seed in 0..10 {
+            let destinations = generators::random_integers(6, -99..100, seed)
+                .into_iter()
+                .map(|x| f64::from(x) / 8.0)
+                .collect::<Vec<_>>();
+            let hand_rolled = hand_rolled_traveling_salesman_f64(&destinations, &-20.0, &20.0);
+            let generic = traveling_salesman(destinations.iter(), &-20.0, &20.0, |pair| {
+                (**pair.0 - **pair.1).abs()
+            });
+            let total = |route: &[&f64]| -> f64 {
+                route.windows(2).map(|leg| (leg[0] - leg[1]).abs()).sum()
+            };
+            assert!((total(&hand_rolled) - total(&generic)).abs() < 1e-9);
+        }
+
+        // A NaN destination is visited, but does not stop the rest being ordered
+        let destinations = [2.0, f64::NAN, 1.0];
+        let result = hand_rolled_traveling_salesman_f64(&destinations, &0.0, &3.0);
+        assert_eq!(result.len(), 5);
+        assert!(result.iter().any(|x| x.is_nan()));
+    }
+
     #[test]
     fn test_hand_rolled_traveling_salesman() {
         let destinations = vec![1, 2, 3, 4, 5];
@@ -0,0 +1,554 @@
+//! Local search: improving a route by small changes until none of them shortens it.
+//!
+//! Each kind of change is a [`Neighborhood`], and a [`LocalSearch`] composes several,
+//! so pipelines such as 2-opt followed by Or-opt can be built from the parts here or
+//! from custom neighborhoods. Every neighborhood leaves the first and last stops in
+//! place, so they work on open routes as well as round trips.
+
+use crate::{delta, kdtree::KdTree, DistanceMatrix, Point, Route};
+
+/// Changes must shorten a route by more than this, so rounding errors in the
+/// distances cannot make a search cycle between equally long routes.
+const EPSILON: f64 = 1e-10;
+
+/// A kind of change to a route.
+pub trait Neighborhood {
+    /// Makes changes from this neighborhood while they shorten `route`.
+    ///
+    /// # Arguments
+    ///
+    /// * `route` - The route to improve in place
+    /// * `matrix` - The distances between destinations, which should be symmetric
+    ///
+    /// # Returns
+    ///
+    /// Whether any change was made
+    fn improve(&self, route: &mut Route, matrix: &DistanceMatrix) -> bool;
+}
+
+/// Reverses a segment, replacing two legs with two others.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TwoOpt;
+
+impl Neighborhood for TwoOpt {
+    fn improve(&self, route: &mut Route, matrix: &DistanceMatrix) -> bool {
+        let stops = route.stops_mut();
+        let len = stops.len();
+        let mut changed = false;
+        let mut improved = true;
+        while improved {
+            improved = false;
+            for i in 0..len.saturating_sub(3) {
+                for j in i + 2..len - 1 {
+                    // Replace legs a-b and c-d with a-c and b-d by reversing b..=c
+                    if delta::two_opt(stops, i + 1, j, |a, b| matrix.get(a, b)) < -EPSILON {
+                        stops[i + 1..=j].reverse();
+                        improved = true;
+                    }
+                }
+            }
+            changed |= improved;
+        }
+        changed
+    }
+}
+
+/// The `k` nearest other destinations to each destination, nearest first.
+///
+/// Good routes rarely have a leg to a destination that is not among the few nearest, so
+/// restricting moves to legs between candidates, as [`CandidateTwoOpt`] does, loses
+/// little and makes each pass take O(nk) rather than O(n²) time.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CandidateLists {
+    neighbors: Vec<Vec<usize>>,
+}
+
+impl CandidateLists {
+    /// Finds the `k` nearest destinations to each destination in `matrix`, or all the
+    /// others if there are fewer than `k`.
+    pub fn new(matrix: &DistanceMatrix, k: usize) -> Self {
+        let neighbors = (0..matrix.len())
+            .map(|from| {
+                let mut others = (0..matrix.len())
+                    .filter(|&to| to != from)
+                    .collect::<Vec<_>>();
+                let row = matrix.row(from).collect::<Vec<_>>();
+                let k = k.min(others.len());
+                if k > 0 && k < others.len() {
+                    others.select_nth_unstable_by(k - 1, |&a, &b| row[a].total_cmp(&row[b]));
+                }
+                others.truncate(k);
+                others.sort_by(|&a, &b| row[a].total_cmp(&row[b]));
+                others
+            })
+            .collect();
+        CandidateLists { neighbors }
+    }
+
+    /// Finds the `k` nearest other points to each of `points` with a [`KdTree`], in
+    /// O(nk log n) time with no distance matrix, for problems too large to build one.
+    /// Distances are planar, so these are the lists of [`new`](Self::new) for
+    /// Euclidean problems.
+    pub fn from_points(points: &[Point], k: usize) -> Self {
+        let tree = KdTree::new(points.to_vec());
+        let neighbors = points
+            .iter()
+            .enumerate()
+            .map(|(from, point)| {
+                // The point itself is among its nearest, though not always first when
+                // others coincide with it
+                let mut nearest = tree.nearest(point, k + 1);
+                nearest.retain(|&to| to != from);
+                nearest.truncate(k);
+                nearest
+            })
+            .collect();
+        CandidateLists { neighbors }
+    }
+
+    /// The candidates of `destination`, nearest first.
+    pub fn neighbors(&self, destination: usize) -> &[usize] {
+        &self.neighbors[destination]
+    }
+}
+
+/// 2-opt restricted to moves that add a leg between a destination and one of its
+/// candidates, for routes of thousands of destinations.
+#[derive(Debug, Clone)]
+pub struct CandidateTwoOpt {
+    pub candidates: CandidateLists,
+}
+
+impl CandidateTwoOpt {
+    /// Builds candidate lists of the `k` nearest destinations from `matrix`.
+    pub fn new(matrix: &DistanceMatrix, k: usize) -> Self {
+        CandidateTwoOpt {
+            candidates: CandidateLists::new(matrix, k),
+        }
+    }
+
+    /// Improves `route` as [`improve`](Neighborhood::improve) does, computing each
+    /// distance as it is needed rather than looking it up, so no matrix need be built.
+    /// With candidates from [`CandidateLists::from_points`], this is 2-opt for planar
+    /// problems far too large for a matrix.
+    ///
+    /// # Arguments
+    ///
+    /// * `route` - The route to improve in place
+    /// * `size` - The number of destinations, one more than the largest index
+    /// * `distance` - Computes the distance between two destinations, which should be
+    ///   symmetric
+    ///
+    /// # Returns
+    ///
+    /// Whether any change was made
+    pub fn improve_with(
+        &self,
+        route: &mut Route,
+        size: usize,
+        distance: impl Fn(usize, usize) -> f64,
+    ) -> bool {
+        let stops = route.stops_mut();
+        let len = stops.len();
+        if len < 4 {
+            return false;
+        }
+        // The position of each destination. The start of a round trip is at both ends,
+        // which `position` maps to the first
+        let last = stops[len - 1];
+        let mut position = vec![0; size];
+        for (index, &stop) in stops.iter().enumerate().rev() {
+            position[stop] = index;
+        }
+
+        let mut changed = false;
+        let mut improved = true;
+        while improved {
+            improved = false;
+            for i in 0..len {
+                let a = stops[i];
+                // Adding leg a-c removes either the legs after a and after c, or the legs
+                // before a and before c, which are the moves on their positions
+                for forward in [true, false] {
+                    let neighbor = if forward { i + 1 } else { i.wrapping_sub(1) };
+                    if neighbor >= len {
+                        continue;
+                    }
+                    let current = distance(a, stops[neighbor]);
+                    for &c in self.candidates.neighbors(a) {
+                        if distance(a, c) >= current {
+                            // Candidates are nearest first, so none further on gains
+                            break;
+                        }
+                        let (p, q) = if forward {
+                            let j = position[c];
+                            (i.min(j), i.max(j))
+                        } else {
+                            let j = if c == last { len - 1 } else { position[c] };
+                            if i == 0 || j == 0 {
+                                continue;
+                            }
+                            ((i - 1).min(j - 1), (i - 1).max(j - 1))
+                        };
+                        if q < p + 2 || q + 1 >= len {
+                            continue;
+                        }
+                        // Replace legs w-x and y-z with w-y and x-z by reversing x..=y
+                        if delta::two_opt(stops, p + 1, q, &distance) < -EPSILON {
+                            stops[p + 1..=q].reverse();
+                            for (index, &stop) in stops.iter().enumerate().take(q + 1).skip(p + 1) {
+                                position[stop] = index;
+                            }
+                            improved = true;
+                            break;
+                        }
+                    }
+                }
+            }
+            changed |= improved;
+        }
+        changed
+    }
+}
+
+impl Neighborhood for CandidateTwoOpt {
+    fn improve(&self, route: &mut Route, matrix: &DistanceMatrix) -> bool {
+        self.improve_with(route, matrix.len(), |a, b| matrix.get(a, b))
+    }
+}
+
+/// Replaces three legs with three others, by exchanging and possibly reversing the two
+/// segments between them. Each pass takes O(n³) time.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ThreeOpt;
+
+/// The ways [`ThreeOpt`] reconnects two segments that change all three legs, as
+/// whether the first and second segments are reversed and whether they are exchanged.
+const RECONNECTIONS: [(bool, bool, bool); 4] = [
+    (false, false, true),
+    (true, false, true),
+    (false, true, true),
+    (true, true, false),
+];
+
+impl Neighborhood for ThreeOpt {
+    fn improve(&self, route: &mut Route, matrix: &DistanceMatrix) -> bool {
+        let stops = route.stops_mut();
+        let len = stops.len();
+        let mut changed = false;
+        let mut improved = true;
+        while improved {
+            improved = false;
+            for i in 0..len.saturating_sub(3) {
+                for j in i + 1..len - 2 {
+                    for k in j + 1..len - 1 {
+                        // The segments b..=c and d..=e between legs a-b, c-d and e-f
+                        let [a, b, c, d, e, f] =
+                            [i, i + 1, j, j + 1, k, k + 1].map(|index| stops[index]);
+                        let current = matrix.get(a, b) + matrix.get(c, d) + matrix.get(e, f);
+
+                        // The ends of each segment in the order they are visited, and
+                        // the length of the legs joining them with a and f
+                        let ends = |reversed: bool, (first, last): (usize, usize)| {
+                            if reversed {
+                                (last, first)
+                            } else {
+                                (first, last)
+                            }
+                        };
+                        let joined =
+                            |&(reverse_first, reverse_second, exchange): &(bool, bool, bool)| {
+                                let (x, y) =
+                                    (ends(reverse_first, (b, c)), ends(reverse_second, (d, e)));
+                                let (x, y) = if exchange { (y, x) } else { (x, y) };
+                                matrix.get(a, x.0) + matrix.get(x.1, y.0) + matrix.get(y.1, f)
+                            };
+                        let (distance, reconnection) = RECONNECTIONS
+                            .iter()
+                            .map(|reconnection| (joined(reconnection), reconnection))
+                            .min_by(|a, b| a.0.total_cmp(&b.0))
+                            .expect("there are reconnections");
+
+                        if distance - current < -EPSILON {
+                            let &(reverse_first, reverse_second, exchange) = reconnection;
+                            let mut first = stops[i + 1..=j].to_vec();
+                            let mut second = stops[j + 1..=k].to_vec();
+                            if reverse_first {
+                                first.reverse();
+                            }
+                            if reverse_second {
+                                second.reverse();
+                            }
+                            let reordered = if exchange {
+                                [second, first].concat()
+                            } else {
+                                [first, second].concat()
+                            };
+                            stops[i + 1..=k].copy_from_slice(&reordered);
+                            improved = true;
+                        }
+                    }
+                }
+            }
+            changed |= improved;
+        }
+        changed
+    }
+}
+
+/// Moves a segment of up to three destinations elsewhere in the route, in either
+/// orientation.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OrOpt;
+
+impl Neighborhood for OrOpt {
+    fn improve(&self, route: &mut Route, matrix: &DistanceMatrix) -> bool {
+        let stops = route.stops_mut();
+        let len = stops.len();
+        let mut changed = false;
+        let mut improved = true;
+        while improved {
+            improved = false;
+            for segment in 1..=3 {
+                for i in 1..len.saturating_sub(segment) {
+                    let last = i + segment - 1;
+                    let (before, first, end, after) =
+                        (stops[i - 1], stops[i], stops[last], stops[last + 1]);
+                    let removed = matrix.get(before, first) + matrix.get(end, after)
+                        - matrix.get(before, after);
+                    // Insert between stops j and j + 1, anywhere outside the segment
+                    for j in (0..i - 1).chain(last + 1..len - 1) {
+                        let (p, q) = (stops[j], stops[j + 1]);
+                        let forward = matrix.get(p, first) + matrix.get(end, q);
+                        let backward = matrix.get(p, end) + matrix.get(first, q);
+                        let added = forward.min(backward) - matrix.get(p, q);
+                        if added - removed < -EPSILON {
+                            let moved = if j < i {
+                                stops[j + 1..=last].rotate_right(segment);
+                                j + 1..j + 1 + segment
+                            } else {
+                                stops[i..=j].rotate_left(segment);
+                                j + 1 - segment..j + 1
+                            };
+                            if backward < forward {
+                                stops[moved].reverse();
+                            }
+                            improved = true;
+                            break;
+                        }
+                    }
+                }
+            }
+            changed |= improved;
+        }
+        changed
+    }
+}
+
+/// Exchanges two destinations.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Swap;
+
+impl Neighborhood for Swap {
+    fn improve(&self, route: &mut Route, matrix: &DistanceMatrix) -> bool {
+        let stops = route.stops_mut();
+        let len = stops.len();
+        let mut changed = false;
+        let mut improved = true;
+        while improved {
+            improved = false;
+            for i in 1..len.saturating_sub(1) {
+                for j in i + 1..len - 1 {
+                    if delta::swap(stops, i, j, |a, b| matrix.get(a, b)) < -EPSILON {
+                        stops.swap(i, j);
+                        improved = true;
+                    }
+                }
+            }
+            changed |= improved;
+        }
+        changed
+    }
+}
+
+/// A pipeline of neighborhoods, run in order and repeated until none of them improves
+/// the route, so the result is a local optimum of every one.
+#[derive(Default)]
+pub struct LocalSearch {
+    neighborhoods: Vec<Box<dyn Neighborhood>>,
+}
+
+impl LocalSearch {
+    /// An empty pipeline, which leaves routes unchanged.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a neighborhood to the end of the pipeline.
+    pub fn with_neighborhood(mut self, neighborhood: impl Neighborhood + 'static) -> Self {
+        self.neighborhoods.push(Box::new(neighborhood));
+        self
+    }
+}
+
+impl Neighborhood for LocalSearch {
+    fn improve(&self, route: &mut Route, matrix: &DistanceMatrix) -> bool {
+        let mut changed = false;
+        loop {
+            let mut improved = false;
+            for neighborhood in &self.neighborhoods {
+                improved |= neighborhood.improve(route, matrix);
+            }
+            if !improved {
+                return changed;
+            }
+            changed = true;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{rng::SplitMix64, Metric, Point, Problem};
+
+    use super::*;
+
+    fn random_problem(seed: u64) -> Problem {
+        let mut rng = SplitMix64::new(seed);
+        let mut problem = Problem::new(
+            (0..12)
+                .map(|_| Point::new(rng.below(100) as f64, rng.below(100) as f64))
+                .collect(),
+            Metric::Euclidean,
+        );
+        if seed % 2 == 1 {
+            problem.end = 7;
+        }
+        problem
+    }
+
+    fn index_order(problem: &Problem) -> Route {
+        Route::new(
+            [
+                vec![problem.start],
+                problem.inner_destinations(),
+                vec![problem.end],
+            ]
+            .concat(),
+        )
+    }
+
+    #[test]
+    fn test_neighborhoods_improve() {
+        let neighborhoods: [(&str, &dyn Neighborhood); 4] = [
+            ("2-opt", &TwoOpt),
+            ("3-opt", &ThreeOpt),
+            ("or-opt", &OrOpt),
+            ("swap", &Swap),
+        ];
+        for seed in 0..6 {
+            let problem = random_problem(seed);
+            let matrix = problem.distance_matrix();
+            let original = index_order(&problem);
+
+            for (name, neighborhood) in neighborhoods {
+                let mut route = original.clone();
+                assert!(neighborhood.improve(&mut route, &matrix), "{name}");
+                assert!(
+                    matrix.route_distance(&route) < matrix.route_distance(&original),
+                    "{name}"
+                );
+                assert_eq!(route[0], original[0], "{name}");
+                assert_eq!(
+                    route[route.len() - 1],
+                    original[original.len() - 1],
+                    "{name}"
+                );
+                let mut inner = route[1..route.len() - 1].to_vec();
+                inner.sort();
+                assert_eq!(inner, problem.inner_destinations(), "{name}");
+
+                // A local optimum stays put
+                assert!(!neighborhood.improve(&mut route, &matrix), "{name}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_candidate_lists() {
+        let problem = Problem::new(
+            [0.0, 5.0, 1.0, 3.5].map(|x| Point::new(x, 0.0)).to_vec(),
+            Metric::Euclidean,
+        );
+        let matrix = problem.distance_matrix();
+        let candidates = CandidateLists::new(&matrix, 2);
+        assert_eq!(candidates.neighbors(0), &[2, 3]);
+        assert_eq!(candidates.neighbors(1), &[3, 2]);
+        assert_eq!(CandidateLists::new(&matrix, 10).neighbors(3), &[1, 2, 0]);
+        assert!(CandidateLists::new(&matrix, 0).neighbors(0).is_empty());
+
+        assert_eq!(CandidateLists::from_points(&problem.points, 2), candidates);
+        let problem = random_problem(3);
+        assert_eq!(
+            CandidateLists::from_points(&problem.points, 8),
+            CandidateLists::new(&problem.distance_matrix(), 8)
+        );
+    }
+
+    #[test]
+    fn test_candidate_two_opt() {
+        for seed in 0..6 {
+            let problem = random_problem(seed);
+            let matrix = problem.distance_matrix();
+
+            // With every other destination a candidate, it finds a 2-opt local optimum
+            let mut route = index_order(&problem);
+            assert!(CandidateTwoOpt::new(&matrix, problem.len()).improve(&mut route, &matrix));
+            assert!(!TwoOpt.improve(&mut route, &matrix));
+            assert_eq!(route[0], problem.start);
+            assert_eq!(route[route.len() - 1], problem.end);
+        }
+
+        // Few candidates are enough for routes nearly as short on large problems
+        let mut rng = SplitMix64::new(10);
+        let problem = Problem::new(
+            (0..400)
+                .map(|_| Point::new(rng.below(1000) as f64, rng.below(1000) as f64))
+                .collect(),
+            Metric::Euclidean,
+        );
+        let matrix = problem.distance_matrix();
+        let mut full = index_order(&problem);
+        TwoOpt.improve(&mut full, &matrix);
+        let mut pruned = index_order(&problem);
+        CandidateTwoOpt::new(&matrix, 8).improve(&mut pruned, &matrix);
+        let mut inner = pruned[1..400].to_vec();
+        inner.sort();
+        assert_eq!(inner, problem.inner_destinations());
+        assert!(matrix.route_distance(&pruned) < matrix.route_distance(&full) * 1.1);
+    }
+
+    #[test]
+    fn test_local_search() {
+        let problem = random_problem(4);
+        let matrix = problem.distance_matrix();
+        let search = LocalSearch::new()
+            .with_neighborhood(TwoOpt)
+            .with_neighborhood(OrOpt);
+
+        let mut route = index_order(&problem);
+        assert!(search.improve(&mut route, &matrix));
+        assert!(!TwoOpt.improve(&mut route, &matrix));
+        assert!(!OrOpt.improve(&mut route, &matrix));
+
+        let mut unchanged = index_order(&problem);
+        assert!(!LocalSearch::new().improve(&mut unchanged, &matrix));
+        assert_eq!(unchanged, index_order(&problem));
+
+        // Nothing to improve with fewer than two destinations between start and end
+        let mut short = Route::new(vec![0, 1, 0]);
+        for neighborhood in [&TwoOpt as &dyn Neighborhood, &ThreeOpt, &OrOpt, &Swap] {
+            assert!(!neighborhood.improve(&mut short, &matrix));
+        }
+    }
+}
@@ -0,0 +1,681 @@
+//! Minimum-weight perfect matching by Edmonds' blossom algorithm, as Christofides
+//! needs to pair up the odd-degree destinations of a spanning tree.
+//!
+//! This follows the O(n³) primal-dual formulation in Galil's "Efficient algorithms for
+//! finding maximum matching in graphs" (1986), maximizing weight among matchings of
+//! maximum cardinality. On a complete graph with an even number of vertices that is
+//! a perfect matching, and maximizing `C - distance` minimizes the distance.
+
+use crate::DistanceMatrix;
+
+/// Marks the absence of an endpoint, edge or blossom in the index arrays.
+const NONE: usize = usize::MAX;
+
+/// Labels of the alternating forest.
+const FREE: u8 = 0;
+const OUTER: u8 = 1;
+const INNER: u8 = 2;
+/// Temporarily marks blossoms already on the path in [`Matcher::scan_blossom`].
+const BREADCRUMB: u8 = 4;
+
+/// Pairs up `vertices` to minimize the total distance between the pairs.
+///
+/// # Arguments
+///
+/// * `matrix` - The distances, which should be symmetric
+/// * `vertices` - The destinations to pair up, an even number of them
+///
+/// # Returns
+///
+/// The pairs of destinations
+///
+/// # Panics
+///
+/// If there is an odd number of vertices, which cannot all be paired.
+pub fn min_weight_perfect_matching<Distance>(
+    matrix: &DistanceMatrix<Distance>,
+    vertices: &[usize],
+) -> Vec<(usize, usize)>
+where
+    Distance: Copy + Into<f64>,
+{
+    assert!(
+        vertices.len().is_multiple_of(2),
+        "an odd number of vertices cannot be perfectly matched"
+    );
+    let n = vertices.len();
+    let distance = |a: usize, b: usize| matrix.get(vertices[a], vertices[b]).into();
+    let longest = (0..n)
+        .flat_map(|a| (a + 1..n).map(move |b| (a, b)))
+        .map(|(a, b)| distance(a, b))
+        .fold(0.0, f64::max);
+    let edges = (0..n)
+        .flat_map(|a| (a + 1..n).map(move |b| (a, b)))
+        .map(|(a, b)| (a, b, longest - distance(a, b)))
+        .collect();
+
+    let mates = Matcher::new(n, edges).solve();
+    (0..n)
+        .filter(|&v| v < mates[v])
+        .map(|v| (vertices[v], vertices[mates[v]]))
+        .collect()
+}
+
+/// The state of the blossom algorithm. Vertices are `0..n` and blossoms `n..2n`;
+/// edge `k` has endpoints `2k` and `2k + 1`, so `p ^ 1` is the other end of `p`.
+struct Matcher {
+    n: usize,
+    edges: Vec<(usize, usize, f64)>,
+    /// The vertex at each endpoint.
+    endpoint: Vec<usize>,
+    /// The remote endpoints of the edges at each vertex.
+    neighbours: Vec<Vec<usize>>,
+    /// The remote endpoint of each vertex's matched edge.
+    mate: Vec<usize>,
+    label: Vec<u8>,
+    /// The endpoint through which each labelled vertex or blossom got its label.
+    label_end: Vec<usize>,
+    /// The outermost blossom containing each vertex.
+    in_blossom: Vec<usize>,
+    parent: Vec<usize>,
+    /// The sub-blossoms of each blossom, starting from its base, around the cycle.
+    children: Vec<Vec<usize>>,
+    base: Vec<usize>,
+    /// The endpoints joining each consecutive pair of `children`.
+    child_ends: Vec<Vec<usize>>,
+    /// The least-slack edge from each vertex or blossom to an outer blossom.
+    best_edge: Vec<usize>,
+    /// For outer blossoms, the least-slack edges to each other outer blossom.
+    blossom_best_edges: Vec<Option<Vec<usize>>>,
+    unused_blossoms: Vec<usize>,
+    dual: Vec<f64>,
+    /// Whether each edge has zero slack, so it can be used in the forest.
+    allowed: Vec<bool>,
+    queue: Vec<usize>,
+}
+
+impl Matcher {
+    fn new(n: usize, edges: Vec<(usize, usize, f64)>) -> Self {
+        let greatest = edges.iter().map(|&(_, _, w)| w).fold(0.0, f64::max);
+        let mut neighbours = vec![Vec::new(); n];
+        let mut endpoint = Vec::with_capacity(2 * edges.len());
+        for (k, &(i, j, _)) in edges.iter().enumerate() {
+            endpoint.extend([i, j]);
+            neighbours[i].push(2 * k + 1);
+            neighbours[j].push(2 * k);
+        }
+        Matcher {
+            n,
+            endpoint,
+            neighbours,
+            mate: vec![NONE; n],
+            label: vec![FREE; 2 * n],
+            label_end: vec![NONE; 2 * n],
+            in_blossom: (0..n).collect(),
+            parent: vec![NONE; 2 * n],
+            children: vec![Vec::new(); 2 * n],
+            base: (0..n).chain(std::iter::repeat_n(NONE, n)).collect(),
+            child_ends: vec![Vec::new(); 2 * n],
+            best_edge: vec![NONE; 2 * n],
+            blossom_best_edges: vec![None; 2 * n],
+            unused_blossoms: (n..2 * n).collect(),
+            dual: std::iter::repeat_n(greatest, n)
+                .chain(std::iter::repeat_n(0.0, n))
+                .collect(),
+            allowed: vec![false; edges.len()],
+            queue: Vec::new(),
+            edges,
+        }
+    }
+
+    fn slack(&self, k: usize) -> f64 {
+        let (i, j, w) = self.edges[k];
+        self.dual[i] + self.dual[j] - 2.0 * w
+    }
+
+    /// The vertices inside blossom `b`.
+    fn leaves(&self, b: usize) -> Vec<usize> {
+        if b < self.n {
+            return vec![b];
+        }
+        self.children[b]
+            .iter()
+            .flat_map(|&child| self.leaves(child))
+            .collect()
+    }
+
+    /// Labels vertex `w` and its outermost blossom through endpoint `p`, and an inner
+    /// blossom's mate as outer in turn.
+    fn assign_label(&mut self, w: usize, label: u8, p: usize) {
+        let b = self.in_blossom[w];
+        self.label[w] = label;
+        self.label[b] = label;
+        self.label_end[w] = p;
+        self.label_end[b] = p;
+        self.best_edge[w] = NONE;
+        self.best_edge[b] = NONE;
+        if label == OUTER {
+            let leaves = self.leaves(b);
+            self.queue.extend(leaves);
+        } else {
+            let mate = self.mate[self.base[b]];
+            self.assign_label(self.endpoint[mate], OUTER, mate ^ 1);
+        }
+    }
+
+    /// Traces back from outer vertices `v` and `w` to find either the base of a new
+    /// blossom, or [`NONE`] if they are in different trees and so form an augmenting path.
+    fn scan_blossom(&mut self, mut v: usize, mut w: usize) -> usize {
+        let mut path = Vec::new();
+        let mut base = NONE;
+        while v != NONE || w != NONE {
+            let b = self.in_blossom[v];
+            if self.label[b] & BREADCRUMB != 0 {
+                base = self.base[b];
+                break;
+            }
+            path.push(b);
+            self.label[b] = OUTER | BREADCRUMB;
+            if self.label_end[b] == NONE {
+                // The root of a tree
+                v = NONE;
+            } else {
+                let t = self.in_blossom[self.endpoint[self.label_end[b]]];
+                v = self.endpoint[self.label_end[t]];
+            }
+            if w != NONE {
+                std::mem::swap(&mut v, &mut w);
+            }
+        }
+        for b in path {
+            self.label[b] = OUTER;
+        }
+        base
+    }
+
+    /// Contracts the cycle closed by edge `k` with base `base` into a new blossom.
+    fn add_blossom(&mut self, base: usize, k: usize) {
+        let (mut v, mut w, _) = self.edges[k];
+        let bb = self.in_blossom[base];
+        let mut bv = self.in_blossom[v];
+        let mut bw = self.in_blossom[w];
+        let b = self.unused_blossoms.pop().expect("at most n blossoms");
+        self.base[b] = base;
+        self.parent[b] = NONE;
+        self.parent[bb] = b;
+
+        let mut path = Vec::new();
+        let mut ends = Vec::new();
+        while bv != bb {
+            self.parent[bv] = b;
+            path.push(bv);
+            ends.push(self.label_end[bv]);
+            v = self.endpoint[self.label_end[bv]];
+            bv = self.in_blossom[v];
+        }
+        path.push(bb);
+        path.reverse();
+        ends.reverse();
+        ends.push(2 * k);
+        while bw != bb {
+            self.parent[bw] = b;
+            path.push(bw);
+            ends.push(self.label_end[bw] ^ 1);
+            w = self.endpoint[self.label_end[bw]];
+            bw = self.in_blossom[w];
+        }
+
+        self.label[b] = OUTER;
+        self.label_end[b] = self.label_end[bb];
+        self.dual[b] = 0.0;
+        for v in self.leaves_of(&path) {
+            if self.label[self.in_blossom[v]] == INNER {
+                // Inner vertices become outer, so need scanning
+                self.queue.push(v);
+            }
+            self.in_blossom[v] = b;
+        }
+
+        // The least-slack edge from the new blossom to each other outer blossom
+        let mut best_to = vec![NONE; 2 * self.n];
+        for &child in &path {
+            let lists = match self.blossom_best_edges[child].take() {
+                Some(list) => vec![list],
+                None => self
+                    .leaves(child)
+                    .into_iter()
+                    .map(|v| self.neighbours[v].iter().map(|p| p / 2).collect())
+                    .collect(),
+            };
+            for k in lists.into_iter().flatten() {
+                let (mut i, mut j, _) = self.edges[k];
+                if self.in_blossom[j] == b {
+                    std::mem::swap(&mut i, &mut j);
+                }
+                let bj = self.in_blossom[j];
+                if bj != b
+                    && self.label[bj] == OUTER
+                    && (best_to[bj] == NONE || self.slack(k) < self.slack(best_to[bj]))
+                {
+                    best_to[bj] = k;
+                }
+            }
+            self.best_edge[child] = NONE;
+        }
+        let best_edges = best_to
+            .into_iter()
+            .filter(|&k| k != NONE)
+            .collect::<Vec<_>>();
+        self.best_edge[b] = NONE;
+        for &k in &best_edges {
+            if self.best_edge[b] == NONE || self.slack(k) < self.slack(self.best_edge[b]) {
+                self.best_edge[b] = k;
+            }
+        }
+        self.blossom_best_edges[b] = Some(best_edges);
+        self.children[b] = path;
+        self.child_ends[b] = ends;
+    }
+
+    fn leaves_of(&self, blossoms: &[usize]) -> Vec<usize> {
+        blossoms.iter().flat_map(|&b| self.leaves(b)).collect()
+    }
+
+    /// Index `j` into the children of `b`, counting negative indices from the end.
+    fn child(&self, b: usize, j: isize) -> usize {
+        self.children[b][j.rem_euclid(self.children[b].len() as isize) as usize]
+    }
+
+    fn child_end(&self, b: usize, j: isize) -> usize {
+        self.child_ends[b][j.rem_euclid(self.child_ends[b].len() as isize) as usize]
+    }
+
+    /// Where to start walking around blossom `b` from child `t` to reach the base by
+    /// way of its matched edges: the index of `t`, the direction of the walk, and
+    /// whether endpoints are read from the edge before (1) or after (0).
+    fn walk_from(&self, b: usize, t: usize) -> (isize, isize, usize) {
+        let len = self.children[b].len() as isize;
+        let j = self.children[b]
+            .iter()
+            .position(|&child| child == t)
+            .expect("t is a child of b") as isize;
+        if j & 1 == 1 {
+            (j - len, 1, 0)
+        } else {
+            (j, -1, 1)
+        }
+    }
+
+    /// Undoes blossom `b`, relabelling its children if it was inner mid-stage, or
+    /// recursively expanding those with zero dual at the end of a stage.
+    fn expand_blossom(&mut self, b: usize, end_of_stage: bool) {
+        for child in self.children[b].clone() {
+            self.parent[child] = NONE;
+            if child < self.n {
+                self.in_blossom[child] = child;
+            } else if end_of_stage && self.dual[child] == 0.0 {
+                self.expand_blossom(child, end_of_stage);
+            } else {
+                for v in self.leaves(child) {
+                    self.in_blossom[v] = child;
+                }
+            }
+        }
+
+        if !end_of_stage && self.label[b] == INNER {
+            // Relabel the children on the even-length path from where the blossom
+            // was entered to its base
+            let entry = self.in_blossom[self.endpoint[self.label_end[b] ^ 1]];
+            let (mut j, step, trick) = self.walk_from(b, entry);
+            let mut p = self.label_end[b];
+            while j != 0 {
+                self.label[self.endpoint[p ^ 1]] = FREE;
+                let end = self.child_end(b, j - trick as isize) ^ trick ^ 1;
+                self.label[self.endpoint[end]] = FREE;
+                self.assign_label(self.endpoint[p ^ 1], INNER, p);
+                let k = self.child_end(b, j - trick as isize) / 2;
+                self.allowed[k] = true;
+                j += step;
+                p = self.child_end(b, j - trick as isize) ^ trick;
+                self.allowed[p / 2] = true;
+                j += step;
+            }
+            // The base child is inner, without relabelling its mate
+            let bv = self.child(b, j);
+            self.label[self.endpoint[p ^ 1]] = INNER;
+            self.label[bv] = INNER;
+            self.label_end[self.endpoint[p ^ 1]] = p;
+            self.label_end[bv] = p;
+            self.best_edge[bv] = NONE;
+
+            // Children on the odd-length path keep a label only if reached from outside
+            j += step;
+            while self.child(b, j) != entry {
+                let bv = self.child(b, j);
+                if self.label[bv] == OUTER {
+                    j += step;
+                    continue;
+                }
+                if let Some(v) = self.leaves(bv).into_iter().find(|&v| self.label[v] != FREE) {
+                    self.label[v] = FREE;
+                    let mate = self.endpoint[self.mate[self.base[bv]]];
+                    self.label[mate] = FREE;
+                    self.assign_label(v, INNER, self.label_end[v]);
+                }
+                j += step;
+            }
+        }
+
+        self.label[b] = FREE;
+        self.label_end[b] = NONE;
+        self.children[b].clear();
+        self.child_ends[b].clear();
+        self.base[b] = NONE;
+        self.blossom_best_edges[b] = None;
+        self.best_edge[b] = NONE;
+        self.unused_blossoms.push(b);
+    }
+
+    /// Swaps matched and unmatched edges on the path inside blossom `b` from vertex
+    /// `v` to the base, making `v` the new base.
+    fn augment_blossom(&mut self, b: usize, v: usize) {
+        let mut t = v;
+        while self.parent[t] != b {
+            t = self.parent[t];
+        }
+        if t >= self.n {
+            self.augment_blossom(t, v);
+        }
+        let (start, step, trick) = self.walk_from(b, t);
+        let mut j = start;
+        while j != 0 {
+            j += step;
+            let t = self.child(b, j);
+            let p = self.child_end(b, j - trick as isize) ^ trick;
+            if t >= self.n {
+                self.augment_blossom(t, self.endpoint[p]);
+            }
+            j += step;
+            let t = self.child(b, j);
+            if t >= self.n {
+                self.augment_blossom(t, self.endpoint[p ^ 1]);
+            }
+            self.mate[self.endpoint[p]] = p ^ 1;
+            self.mate[self.endpoint[p ^ 1]] = p;
+        }
+        // Rotate the children so the new base comes first
+        let i = start.rem_euclid(self.children[b].len() as isize) as usize;
+        self.children[b].rotate_left(i);
+        self.child_ends[b].rotate_left(i);
+        self.base[b] = self.base[self.children[b][0]];
+    }
+
+    /// Swaps matched and unmatched edges along the augmenting path through edge `k`.
+    fn augment_matching(&mut self, k: usize) {
+        let (v, w, _) = self.edges[k];
+        for (mut s, mut p) in [(v, 2 * k + 1), (w, 2 * k)] {
+            loop {
+                let bs = self.in_blossom[s];
+                if bs >= self.n {
+                    self.augment_blossom(bs, s);
+                }
+                self.mate[s] = p;
+                if self.label_end[bs] == NONE {
+                    // Reached the root of the tree
+                    break;
+                }
+                let t = self.endpoint[self.label_end[bs]];
+                let bt = self.in_blossom[t];
+                s = self.endpoint[self.label_end[bt]];
+                let j = self.endpoint[self.label_end[bt] ^ 1];
+                if bt >= self.n {
+                    self.augment_blossom(bt, j);
+                }
+                self.mate[j] = self.label_end[bt];
+                p = self.label_end[bt] ^ 1;
+            }
+        }
+    }
+
+    /// Runs stages until no augmenting path remains, returning each vertex's mate.
+    fn solve(mut self) -> Vec<usize> {
+        let n = self.n;
+        for _ in 0..n {
+            self.label.fill(FREE);
+            self.best_edge.fill(NONE);
+            for best in &mut self.blossom_best_edges[n..] {
+                *best = None;
+            }
+            self.allowed.fill(false);
+            self.queue.clear();
+            for v in 0..n {
+                if self.mate[v] == NONE && self.label[self.in_blossom[v]] == FREE {
+                    self.assign_label(v, OUTER, NONE);
+                }
+            }
+
+            if !self.augment_once() {
+                break;
+            }
+
+            // Expand outer blossoms whose dual has dropped to zero
+            for b in n..2 * n {
+                if self.parent[b] == NONE
+                    && self.base[b] != NONE
+                    && self.label[b] == OUTER
+                    && self.dual[b] == 0.0
+                {
+                    self.expand_blossom(b, true);
+                }
+            }
+        }
+
+        (0..n)
+            .map(|v| match self.mate[v] {
+                NONE => NONE,
+                p => self.endpoint[p],
+            })
+            .collect()
+    }
+
+    /// Grows the forest and adjusts the duals until an augmenting path is found and
+    /// applied, returning false if there is none.
+    fn augment_once(&mut self) -> bool {
+        let n = self.n;
+        loop {
+            while let Some(v) = self.queue.pop() {
+                for p in self.neighbours[v].clone() {
+                    let k = p / 2;
+                    let w = self.endpoint[p];
+                    if self.in_blossom[v] == self.in_blossom[w] {
+                        continue;
+                    }
+                    let mut slack = 0.0;
+                    if !self.allowed[k] {
+                        slack = self.slack(k);
+                        if slack <= 0.0 {
+                            self.allowed[k] = true;
+                        }
+                    }
+
+                    let bw = self.in_blossom[w];
+                    if self.allowed[k] {
+                        if self.label[bw] == FREE {
+                            self.assign_label(w, INNER, p ^ 1);
+                        } else if self.label[bw] == OUTER {
+                            let base = self.scan_blossom(v, w);
+                            if base != NONE {
+                                self.add_blossom(base, k);
+                            } else {
+                                self.augment_matching(k);
+                                return true;
+                            }
+                        } else if self.label[w] == FREE {
+                            // w is inside an inner blossom but not yet reached
+                            self.label[w] = INNER;
+                            self.label_end[w] = p ^ 1;
+                        }
+                    } else if self.label[bw] == OUTER {
+                        let b = self.in_blossom[v];
+                        if self.best_edge[b] == NONE || slack < self.slack(self.best_edge[b]) {
+                            self.best_edge[b] = k;
+                        }
+                    } else if self.label[w] == FREE
+                        && (self.best_edge[w] == NONE || slack < self.slack(self.best_edge[w]))
+                    {
+                        self.best_edge[w] = k;
+                    }
+                }
+            }
+
+            // No tight edge grows the forest, so change the duals by the least that
+            // makes one tight, expands a blossom, or ends the stage
+            enum Delta {
+                Grow(usize),
+                Join(usize),
+                Expand(usize),
+            }
+            let mut best: Option<(f64, Delta)> = None;
+            let mut consider = |delta: f64, kind: Delta| {
+                if best.as_ref().is_none_or(|(least, _)| delta < *least) {
+                    best = Some((delta, kind));
+                }
+            };
+            for v in 0..n {
+                if self.label[self.in_blossom[v]] == FREE && self.best_edge[v] != NONE {
+                    consider(
+                        self.slack(self.best_edge[v]),
+                        Delta::Grow(self.best_edge[v]),
+                    );
+                }
+            }
+            for b in 0..2 * n {
+                if self.parent[b] == NONE && self.label[b] == OUTER && self.best_edge[b] != NONE {
+                    consider(
+                        self.slack(self.best_edge[b]) / 2.0,
+                        Delta::Join(self.best_edge[b]),
+                    );
+                }
+            }
+            for b in n..2 * n {
+                if self.base[b] != NONE && self.parent[b] == NONE && self.label[b] == INNER {
+                    consider(self.dual[b], Delta::Expand(b));
+                }
+            }
+            let (delta, kind) = match best {
+                Some(best) => (best.0, Some(best.1)),
+                // Nothing can change, so the matching has maximum cardinality
+                None => (
+                    self.dual[..n]
+                        .iter()
+                        .copied()
+                        .fold(f64::INFINITY, f64::min)
+                        .max(0.0),
+                    None,
+                ),
+            };
+
+            for v in 0..n {
+                match self.label[self.in_blossom[v]] {
+                    OUTER => self.dual[v] -= delta,
+                    INNER => self.dual[v] += delta,
+                    _ => {}
+                }
+            }
+            for b in n..2 * n {
+                if self.base[b] != NONE && self.parent[b] == NONE {
+                    match self.label[b] {
+                        OUTER => self.dual[b] += delta,
+                        INNER => self.dual[b] -= delta,
+                        _ => {}
+                    }
+                }
+            }
+
+            match kind {
+                None => return false,
+                Some(Delta::Grow(k)) => {
+                    self.allowed[k] = true;
+                    let (i, j, _) = self.edges[k];
+                    let outer = if self.label[self.in_blossom[i]] == FREE {
+                        j
+                    } else {
+                        i
+                    };
+                    self.queue.push(outer);
+                }
+                Some(Delta::Join(k)) => {
+                    self.allowed[k] = true;
+                    self.queue.push(self.edges[k].0);
+                }
+                Some(Delta::Expand(b)) => self.expand_blossom(b, false),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rng::SplitMix64;
+
+    use super::*;
+
+    /// The least total distance of any perfect matching, by trying them all.
+    fn brute_force(matrix: &DistanceMatrix, vertices: &[usize]) -> f64 {
+        let Some((&first, rest)) = vertices.split_first() else {
+            return 0.0;
+        };
+        (0..rest.len())
+            .map(|i| {
+                let mut remaining = rest.to_vec();
+                let partner = remaining.remove(i);
+                matrix.get(first, partner) + brute_force(matrix, &remaining)
+            })
+            .fold(f64::INFINITY, f64::min)
+    }
+
+    #[test]
+    fn test_min_weight_perfect_matching() {
+        // Two tight pairs far apart, which a greedy pairing of 1 and 2 gets wrong
+        let positions = [0.0, 4.0, 5.0, 9.0];
+        let matrix = DistanceMatrix::from_fn(4, |a, b| f64::abs(positions[a] - positions[b]));
+        let mut matching = min_weight_perfect_matching(&matrix, &[0, 1, 2, 3]);
+        matching.sort();
+        assert_eq!(matching, vec![(0, 1), (2, 3)]);
+
+        assert!(min_weight_perfect_matching(&matrix, &[]).is_empty());
+        assert_eq!(min_weight_perfect_matching(&matrix, &[3, 1]), vec![(3, 1)]);
+    }
+
+    #[test]
+    #[should_panic(expected = "odd number")]
+    fn test_odd_vertices() {
+        min_weight_perfect_matching(&DistanceMatrix::from_fn(3, |_, _| 1.0), &[0, 1, 2]);
+    }
+
+    #[test]
+    fn test_matches_brute_force() {
+        let mut rng = SplitMix64::new(3);
+        for trial in 0..200 {
+            let n = 2 * (1 + trial % 5);
+            // Few distinct distances make ties, and so blossoms, common
+            let points = (0..n + 2)
+                .map(|_| (rng.below(6) as f64, rng.below(6) as f64))
+                .collect::<Vec<_>>();
+            let matrix = DistanceMatrix::from_fn(points.len(), |a, b| {
+                (points[a].0 - points[b].0).abs() + (points[a].1 - points[b].1).abs()
+            });
+            let vertices = (2..n + 2).collect::<Vec<_>>();
+
+            let matching = min_weight_perfect_matching(&matrix, &vertices);
+            let mut matched = matching
+                .iter()
+                .flat_map(|&(a, b)| [a, b])
+                .collect::<Vec<_>>();
+            matched.sort();
+            assert_eq!(matched, vertices);
+            let total = matching.iter().map(|&(a, b)| matrix.get(a, b)).sum::<f64>();
+            assert!((total - brute_force(&matrix, &vertices)).abs() < 1e-9);
+        }
+    }
+}
@@ -0,0 +1,382 @@
+//! Precomputed distances between every pair of destinations.
+
+use std::{fmt, ops::Add};
+
+use num_traits::{CheckedAdd, Zero};
+
+use crate::{
+    metric::{find_violations, MetricViolation},
+    traveling_salesman, Problem, Route,
+};
+#[cfg(feature = "rayon")]
+use crate::{Metric, Point};
+
+/// An error produced when building a [`DistanceMatrix`] from rows.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MatrixError {
+    /// Row `row` has `len` entries but the matrix has `size` rows.
+    NotSquare { row: usize, len: usize, size: usize },
+}
+
+impl fmt::Display for MatrixError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MatrixError::NotSquare { row, len, size } => {
+                write!(f, "row {row} has {len} entries but there are {size} rows")
+            }
+        }
+    }
+}
+
+impl std::error::Error for MatrixError {}
+
+/// A square matrix where entry `(from, to)` is the distance from destination
+/// `from` to destination `to`. It need not be symmetric.
+///
+/// A matrix built symmetric, with [`from_symmetric_fn`](Self::from_symmetric_fn),
+/// stores only its upper triangle, in about half the memory.
+#[derive(Debug, Clone)]
+pub struct DistanceMatrix<Distance = f64> {
+    size: usize,
+    /// Whether only the entries with `from <= to` are stored
+    symmetric: bool,
+    /// Row-major entries, of the upper triangle only if symmetric
+    distances: Vec<Distance>,
+}
+
+impl<Distance> DistanceMatrix<Distance> {
+    /// Builds a `size` × `size` matrix by calling `distance(from, to)` for every entry.
+    pub fn from_fn(size: usize, mut distance: impl FnMut(usize, usize) -> Distance) -> Self {
+        let distances = (0..size)
+            .flat_map(|from| (0..size).map(move |to| (from, to)))
+            .map(|(from, to)| distance(from, to))
+            .collect();
+        DistanceMatrix {
+            size,
+            symmetric: false,
+            distances,
+        }
+    }
+
+    /// Builds a symmetric `size` × `size` matrix, storing only its upper triangle, by
+    /// calling `distance(from, to)` for every entry with `from <= to`.
+    pub fn from_symmetric_fn(
+        size: usize,
+        mut distance: impl FnMut(usize, usize) -> Distance,
+    ) -> Self {
+        let distances = (0..size)
+            .flat_map(|from| (from..size).map(move |to| (from, to)))
+            .map(|(from, to)| distance(from, to))
+            .collect();
+        DistanceMatrix {
+            size,
+            symmetric: true,
+            distances,
+        }
+    }
+
+    /// Builds a matrix from its rows, which must all be as long as there are rows.
+    pub fn from_rows(rows: Vec<Vec<Distance>>) -> Result<Self, MatrixError> {
+        let size = rows.len();
+        if let Some((row, len)) = rows
+            .iter()
+            .map(Vec::len)
+            .enumerate()
+            .find(|&(_, len)| len != size)
+        {
+            return Err(MatrixError::NotSquare { row, len, size });
+        }
+
+        Ok(DistanceMatrix {
+            size,
+            symmetric: false,
+            distances: rows.into_iter().flatten().collect(),
+        })
+    }
+
+    /// The number of destinations.
+    pub fn len(&self) -> usize {
+        self.size
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+
+    /// Whether the matrix was built symmetric, so the distance from `a` to `b` is
+    /// always the distance back. A matrix built from every entry is not, even if they
+    /// happen to match; [`to_symmetric`](Self::to_symmetric) checks and packs it.
+    pub fn is_symmetric(&self) -> bool {
+        self.symmetric
+    }
+
+    /// Applies `f` to every distance, keeping the storage.
+    pub fn map<T>(&self, f: impl FnMut(&Distance) -> T) -> DistanceMatrix<T> {
+        DistanceMatrix {
+            size: self.size,
+            symmetric: self.symmetric,
+            distances: self.distances.iter().map(f).collect(),
+        }
+    }
+
+    /// Where entry `(from, to)` is stored.
+    fn index(&self, from: usize, to: usize) -> usize {
+        assert!(
+            from < self.size && to < self.size,
+            "({from}, {to}) is outside a matrix of {} destinations",
+            self.size
+        );
+        if self.symmetric {
+            // Row i of the upper triangle holds the entries i..size, after the
+            // size + (size - 1) + ... + (size - i + 1) entries of the rows before it
+            let (i, j) = (from.min(to), from.max(to));
+            i * self.size - i * (i + 1) / 2 + j
+        } else {
+            from * self.size + to
+        }
+    }
+}
+
+impl<Distance> DistanceMatrix<Distance>
+where
+    Distance: Copy,
+{
+    /// The distance from `from` to `to`.
+    pub fn get(&self, from: usize, to: usize) -> Distance {
+        self.distances[self.index(from, to)]
+    }
+
+    /// The distances from `from` to every destination.
+    pub fn row(&self, from: usize) -> impl Iterator<Item = Distance> + '_ {
+        (0..self.size).map(move |to| self.get(from, to))
+    }
+
+    /// Packs the matrix into symmetric storage, if every distance equals the distance
+    /// back.
+    pub fn to_symmetric(&self) -> Option<Self>
+    where
+        Distance: PartialEq,
+    {
+        let n = self.size;
+        (0..n)
+            .all(|a| (a + 1..n).all(|b| self.get(a, b) == self.get(b, a)))
+            .then(|| Self::from_symmetric_fn(n, |from, to| self.get(from, to)))
+    }
+
+    /// The total distance of a route given as destination indices.
+    pub fn route_distance(&self, route: &[usize]) -> Distance
+    where
+        Distance: Zero,
+    {
+        route
+            .windows(2)
+            .map(|leg| self.get(leg[0], leg[1]))
+            .fold(Distance::zero(), Add::add)
+    }
+
+    /// The total distance of a route, or `None` if adding up its legs overflows, as
+    /// large integer distances can.
+    pub fn checked_route_distance(&self, route: &[usize]) -> Option<Distance>
+    where
+        Distance: Zero + CheckedAdd,
+    {
+        route.windows(2).try_fold(Distance::zero(), |total, leg| {
+            total.checked_add(&self.get(leg[0], leg[1]))
+        })
+    }
+
+    /// Finds the shortest route from `start` through every destination to `end`
+    /// by exhaustive search.
+    pub fn solve(&self, start: usize, end: usize) -> Route
+    where
+        Distance: PartialOrd + Zero,
+    {
+        let inner = (0..self.size)
+            .filter(|&i| i != start && i != end)
+            .collect::<Vec<_>>();
+        Route::new(traveling_salesman(
+            inner.into_iter(),
+            start,
+            end,
+            |(&from, &to)| self.get(from, to),
+        ))
+    }
+}
+
+impl<Distance> DistanceMatrix<Distance>
+where
+    Distance: Copy + Into<f64>,
+{
+    /// Checks that the matrix is symmetric and obeys the triangle inequality, as
+    /// [`validate_metric`](crate::metric::validate_metric) does for points.
+    /// Matrices of road distances or travel times often do neither.
+    pub fn validate_metric(&self) -> Vec<MetricViolation> {
+        find_violations(self.size, |from, to| self.get(from, to).into())
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl DistanceMatrix {
+    /// Computes the distance between every pair of `points` under `metric` on all
+    /// cores, a row of the upper triangle at a time, for metrics slow enough that
+    /// building the matrix dominates. The result is symmetric, like
+    /// [`Problem::distance_matrix`].
+    pub fn build_parallel(points: &[Point], metric: Metric) -> Self {
+        use rayon::prelude::*;
+
+        let size = points.len();
+        let rows = (0..size)
+            .into_par_iter()
+            .map(|from| {
+                points[from..]
+                    .iter()
+                    .map(|to| metric.distance(&points[from], to))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        DistanceMatrix {
+            size,
+            symmetric: true,
+            distances: rows.concat(),
+        }
+    }
+}
+
+/// Matrices are equal when their entries are, however they are stored.
+impl<Distance> PartialEq for DistanceMatrix<Distance>
+where
+    Distance: Copy + PartialEq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.size == other.size && (0..self.size).all(|from| self.row(from).eq(other.row(from)))
+    }
+}
+
+impl Problem {
+    /// Computes the distance between every pair of destinations up front. Every
+    /// [`Metric`] is symmetric, so the matrix is too.
+    pub fn distance_matrix(&self) -> DistanceMatrix {
+        DistanceMatrix::from_symmetric_fn(self.len(), |from, to| self.distance(from, to))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Metric, Point};
+
+    use super::*;
+
+    #[test]
+    fn test_asymmetric_matrix() {
+        // Going "clockwise" 0 -> 1 -> 2 -> 0 is cheap, the other way is expensive
+        let matrix =
+            DistanceMatrix::from_rows(vec![vec![0, 1, 9], vec![9, 0, 1], vec![1, 9, 0]]).unwrap();
+
+        assert_eq!(matrix.len(), 3);
+        assert_eq!(matrix.get(0, 2), 9);
+        assert!(matrix.row(1).eq([9, 0, 1]));
+
+        let route = matrix.solve(0, 0);
+        assert_eq!(route.stops(), [0, 1, 2, 0]);
+        assert_eq!(matrix.route_distance(&route), 3);
+        assert_eq!(matrix.validate_metric().len(), 6);
+    }
+
+    #[test]
+    fn test_integer_distances() {
+        // Wide integers solve like any other distance
+        let matrix = DistanceMatrix::from_fn(4, |a, b| (a.abs_diff(b) as i128) << 80);
+        let route = matrix.solve(0, 0);
+        assert_eq!(matrix.route_distance(&route), 6 << 80);
+        assert_eq!(matrix.checked_route_distance(&route), Some(6 << 80));
+
+        // Summing legs that overflow is caught
+        let matrix = DistanceMatrix::from_fn(3, |a, b| if a == b { 0 } else { u64::MAX / 2 });
+        assert_eq!(matrix.checked_route_distance(&[0, 1]), Some(u64::MAX / 2));
+        assert_eq!(matrix.checked_route_distance(&[0, 1, 2, 0]), None);
+        assert_eq!(matrix.checked_route_distance(&[]), Some(0));
+    }
+
+    #[test]
+    fn test_symmetric_matrix() {
+        let mut calls = 0;
+        let matrix = DistanceMatrix::from_symmetric_fn(4, |from, to| {
+            assert!(from <= to);
+            calls += 1;
+            (to - from) * 10 + from
+        });
+        assert_eq!(calls, 10);
+        assert_eq!(matrix.distances.len(), 10);
+        assert!(matrix.is_symmetric());
+        assert_eq!(matrix.get(1, 3), 21);
+        assert_eq!(matrix.get(3, 1), 21);
+        assert_eq!(matrix.get(3, 3), 3);
+        assert!(matrix.row(2).eq([20, 11, 2, 12]));
+
+        // Equal to the same entries stored in full, which pack back to it
+        let full = DistanceMatrix::from_fn(4, |from, to| matrix.get(from, to));
+        assert!(!full.is_symmetric());
+        assert_eq!(full, matrix);
+        assert_eq!(full.to_symmetric(), Some(matrix.clone()));
+        assert!(full.to_symmetric().unwrap().is_symmetric());
+        assert_eq!(
+            DistanceMatrix::from_rows(vec![vec![0, 1], vec![2, 0]])
+                .unwrap()
+                .to_symmetric(),
+            None
+        );
+        assert_eq!(matrix.route_distance(&[0, 3, 1, 0]), 30 + 21 + 10);
+        assert_eq!(matrix.map(|&distance| distance * 2).get(3, 0), 60);
+    }
+
+    #[test]
+    #[should_panic(expected = "outside a matrix")]
+    fn test_get_outside() {
+        DistanceMatrix::from_symmetric_fn(3, |_, _| 0.0).get(1, 3);
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn test_build_parallel() {
+        let problem = Problem::new(
+            (0..50)
+                .map(|i| Point::new((i * 37 % 101) as f64, (i * 53 % 97) as f64))
+                .collect(),
+            Metric::Haversine,
+        );
+        let matrix = DistanceMatrix::build_parallel(&problem.points, problem.metric);
+        assert!(matrix.is_symmetric());
+        assert_eq!(matrix, problem.distance_matrix());
+        assert!(DistanceMatrix::build_parallel(&[], Metric::Euclidean).is_empty());
+    }
+
+    #[test]
+    fn test_from_rows_not_square() {
+        assert_eq!(
+            DistanceMatrix::from_rows(vec![vec![0.0, 1.0], vec![1.0]]),
+            Err(MatrixError::NotSquare {
+                row: 1,
+                len: 1,
+                size: 2
+            })
+        );
+    }
+
+    #[test]
+    fn test_problem_distance_matrix() {
+        let problem = Problem::new(
+            vec![
+                Point::new(0.0, 0.0),
+                Point::new(3.0, 4.0),
+                Point::new(0.0, 1.0),
+            ],
+            Metric::Euclidean,
+        );
+
+        let matrix = problem.distance_matrix();
+        assert!(matrix.is_symmetric());
+        assert_eq!(matrix.get(0, 1), 5.0);
+        assert_eq!(matrix.get(1, 0), 5.0);
+        assert_eq!(matrix.solve(0, 0), problem.solve());
+    }
+}
@@ -0,0 +1,262 @@
+//! Memoizing free functions with the [`memoize!`](crate::memoize!) macro, in place of
+//! wiring up [`cached_fn`](crate::cached_fn) and a place to keep it by hand.
+//!
+//! ```ignore
+//! memoize! {
+//!     #[memoize(shared, capacity = 10_000, ttl = Duration::from_secs(300))]
+//!     pub fn road_distance(from: u32, to: u32) -> f64 {
+//!         query_routing_service(from, to)
+//!     }
+//! }
+//! ```
+//!
+//! A memoized function keeps its own cache, for each thread by default or shared by
+//! every thread with `shared`, keyed by its arguments, which must all be `Hash + Eq +
+//! Clone`, as its result must be `Clone`. Recursive calls go through the cache too.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    hash::Hash,
+    time::{Duration, Instant},
+};
+
+/// Limits on what a [`Memo`] keeps, set by the options of [`memoize!`](crate::memoize!).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MemoOptions {
+    capacity: Option<usize>,
+    ttl: Option<Duration>,
+}
+
+impl MemoOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keeps at most `capacity` results, forgetting the earliest cached first.
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.capacity = Some(capacity);
+        self
+    }
+
+    /// Forgets each result `ttl` after it was computed.
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+}
+
+/// The cache of a function memoized by [`memoize!`](crate::memoize!).
+#[derive(Debug, Clone)]
+pub struct Memo<Input, Output> {
+    options: MemoOptions,
+    entries: HashMap<Input, (Output, Instant)>,
+    /// The inputs in the order they were first cached, for forgetting the earliest.
+    order: VecDeque<Input>,
+}
+
+impl<Input, Output> Memo<Input, Output>
+where
+    Input: Hash + Eq + Clone,
+    Output: Clone,
+{
+    pub fn new(options: MemoOptions) -> Self {
+        Memo {
+            options,
+            entries: HashMap::new(),
+            order: VecDeque::new(),
+        }
+    }
+
+    /// The cached result for `input`, if there is one that has not expired.
+    pub fn get(&self, input: &Input) -> Option<Output> {
+        let (output, computed) = self.entries.get(input)?;
+        match self.options.ttl {
+            Some(ttl) if computed.elapsed() >= ttl => None,
+            _ => Some(output.clone()),
+        }
+    }
+
+    /// Caches `output` as the result for `input`, forgetting the earliest results if
+    /// that makes too many.
+    pub fn insert(&mut self, input: Input, output: Output) {
+        let replaced = self
+            .entries
+            .insert(input.clone(), (output, Instant::now()))
+            .is_some();
+        if !replaced {
+            self.order.push_back(input);
+        }
+        let capacity = self.options.capacity.unwrap_or(usize::MAX);
+        while self.entries.len() > capacity {
+            let earliest = self.order.pop_front().expect("every entry is in the order");
+            self.entries.remove(&earliest);
+        }
+    }
+
+    /// The number of results cached, including expired ones not yet replaced.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// Memoizes a free function, which keeps its results in a cache of its own.
+///
+/// The function may be preceded by `#[memoize(...)]` with any of these options:
+///
+/// * `shared` - One cache for every thread, behind a lock, rather than one per thread
+/// * `capacity = n` - Keep at most `n` results, forgetting the earliest cached first
+/// * `ttl = duration` - Forget each result `duration` after it was computed
+///
+/// `shared` must come first. The function may not be generic, and its arguments must be
+/// plain names.
+#[macro_export]
+macro_rules! memoize {
+    (
+        #[memoize(shared $(, $option:ident = $value:expr)* $(,)?)]
+        $(#[$attr:meta])*
+        $vis:vis fn $name:ident($($arg:ident: $type:ty),* $(,)?) -> $output:ty $body:block
+    ) => {
+        $(#[$attr])*
+        $vis fn $name($($arg: $type),*) -> $output {
+            static CACHE: ::std::sync::OnceLock<
+                ::std::sync::Mutex<$crate::memoize::Memo<($($type,)*), $output>>,
+            > = ::std::sync::OnceLock::new();
+            let cache = CACHE.get_or_init(|| {
+                ::std::sync::Mutex::new($crate::memoize::Memo::new(
+                    $crate::memoize::MemoOptions::new()$(.$option($value))*,
+                ))
+            });
+            let lock = || cache.lock().unwrap_or_else(::std::sync::PoisonError::into_inner);
+            let key = ($(::std::clone::Clone::clone(&$arg),)*);
+            if let ::std::option::Option::Some(output) = lock().get(&key) {
+                return output;
+            }
+            fn compute($($arg: $type),*) -> $output $body
+            // Computed without the lock held, so recursive calls can take it
+            let output = compute($($arg),*);
+            lock().insert(key, ::std::clone::Clone::clone(&output));
+            output
+        }
+    };
+    (
+        #[memoize($($option:ident = $value:expr),* $(,)?)]
+        $(#[$attr:meta])*
+        $vis:vis fn $name:ident($($arg:ident: $type:ty),* $(,)?) -> $output:ty $body:block
+    ) => {
+        $(#[$attr])*
+        $vis fn $name($($arg: $type),*) -> $output {
+            ::std::thread_local! {
+                static CACHE: ::std::cell::RefCell<
+                    $crate::memoize::Memo<($($type,)*), $output>,
+                > = ::std::cell::RefCell::new($crate::memoize::Memo::new(
+                    $crate::memoize::MemoOptions::new()$(.$option($value))*,
+                ));
+            }
+            let key = ($(::std::clone::Clone::clone(&$arg),)*);
+            if let ::std::option::Option::Some(output) = CACHE.with_borrow(|cache| cache.get(&key)) {
+                return output;
+            }
+            fn compute($($arg: $type),*) -> $output $body
+            // Computed without the cache borrowed, so recursive calls can use it
+            let output = compute($($arg),*);
+            CACHE.with_borrow_mut(|cache| cache.insert(key, ::std::clone::Clone::clone(&output)));
+            output
+        }
+    };
+    (
+        $(#[$attr:meta])*
+        $vis:vis fn $name:ident($($arg:ident: $type:ty),* $(,)?) -> $output:ty $body:block
+    ) => {
+        $crate::memoize! {
+            #[memoize()]
+            $(#[$attr])*
+            $vis fn $name($($arg: $type),*) -> $output $body
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        cell::Cell,
+        sync::atomic::{AtomicU64, Ordering},
+        thread,
+    };
+
+    use super::*;
+
+    thread_local! {
+        static FIB_CALLS: Cell<u64> = const { Cell::new(0) };
+    }
+
+    memoize! {
+        /// The `n`th Fibonacci number, memoized for each thread.
+        fn fib(n: u64) -> u64 {
+            FIB_CALLS.set(FIB_CALLS.get() + 1);
+            if n < 2 {
+                n
+            } else {
+                fib(n - 1) + fib(n - 2)
+            }
+        }
+    }
+
+    static DISTANCE_CALLS: AtomicU64 = AtomicU64::new(0);
+
+    memoize! {
+        #[memoize(shared, capacity = 2)]
+        fn distance(from: i32, to: i32) -> u32 {
+            DISTANCE_CALLS.fetch_add(1, Ordering::Relaxed);
+            from.abs_diff(to)
+        }
+    }
+
+    memoize! {
+        #[memoize(ttl = Duration::ZERO)]
+        fn expired(name: String) -> usize {
+            name.len()
+        }
+    }
+
+    #[test]
+    fn test_memoize() {
+        assert_eq!(fib(50), 12_586_269_025);
+        assert_eq!(FIB_CALLS.get(), 51);
+        assert_eq!(fib(50), 12_586_269_025);
+        assert_eq!(FIB_CALLS.get(), 51);
+        assert_eq!(expired("depot".to_string()), 5);
+
+        // One cache for every thread, keeping only the two latest results
+        thread::scope(|scope| {
+            scope.spawn(|| assert_eq!(distance(1, 4), 3));
+        });
+        assert_eq!(distance(1, 4), 3);
+        assert_eq!(DISTANCE_CALLS.load(Ordering::Relaxed), 1);
+        distance(0, 1);
+        distance(0, 2);
+        distance(1, 4);
+        assert_eq!(DISTANCE_CALLS.load(Ordering::Relaxed), 4);
+    }
+
+    #[test]
+    fn test_memo() {
+        let mut memo = Memo::new(MemoOptions::new().capacity(2));
+        memo.insert(1, 'a');
+        memo.insert(2, 'b');
+        memo.insert(1, 'c');
+        assert_eq!(memo.get(&1), Some('c'));
+        memo.insert(3, 'd');
+        assert_eq!(memo.len(), 2);
+        assert_eq!(memo.get(&1), None);
+        assert_eq!(memo.get(&2), Some('b'));
+
+        let mut memo = Memo::new(MemoOptions::new().ttl(Duration::ZERO));
+        memo.insert("depot", 0);
+        assert_eq!(memo.get(&"depot"), None);
+        assert!(!memo.is_empty());
+    }
+}
@@ -0,0 +1,253 @@
+//! Points and the metrics used to measure the distance between them.
+
+use crate::rng::SplitMix64;
+
+/// A location in the plane, or a geographic coordinate.
+///
+/// For [`Metric::Haversine`] `x` is the latitude and `y` the longitude, both in degrees,
+/// matching the column order of `id,lat,lon` files.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Point {
+    pub x: f64,
+    pub y: f64,
+}
+
+impl Point {
+    pub fn new(x: f64, y: f64) -> Self {
+        Point { x, y }
+    }
+}
+
+/// Mean radius of the Earth in meters, as used by [`Metric::Haversine`].
+pub const EARTH_RADIUS_METERS: f64 = 6_371_000.0;
+
+/// How the distance between two [`Point`]s is measured.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "lowercase")
+)]
+pub enum Metric {
+    /// Straight-line distance in the plane.
+    #[default]
+    Euclidean,
+    /// Sum of the absolute coordinate differences.
+    Manhattan,
+    /// Great-circle distance in meters between latitude/longitude pairs.
+    Haversine,
+    /// Straight-line distance rounded to the nearest integer, as TSPLIB's `EUC_2D`
+    /// specifies, so route lengths match published optima exactly.
+    #[cfg_attr(feature = "serde", serde(rename = "rounded_euclidean"))]
+    RoundedEuclidean,
+}
+
+impl Metric {
+    /// Computes the distance between `a` and `b`.
+    pub fn distance(&self, a: &Point, b: &Point) -> f64 {
+        match self {
+            Metric::Euclidean => (a.x - b.x).hypot(a.y - b.y),
+            Metric::Manhattan => (a.x - b.x).abs() + (a.y - b.y).abs(),
+            Metric::Haversine => {
+                let (lat1, lat2) = (a.x.to_radians(), b.x.to_radians());
+                let d_lat = lat2 - lat1;
+                let d_lon = (b.y - a.y).to_radians();
+
+                let h = (d_lat / 2.0).sin().powi(2)
+                    + lat1.cos() * lat2.cos() * (d_lon / 2.0).sin().powi(2);
+                // Rounding (or latitudes outside ±90°) can push h just outside [0, 1],
+                // where asin(sqrt(h)) would be NaN
+                2.0 * EARTH_RADIUS_METERS * h.clamp(0.0, 1.0).sqrt().asin()
+            }
+            // TSPLIB's nint, which rounds halves up
+            Metric::RoundedEuclidean => ((a.x - b.x).hypot(a.y - b.y) + 0.5).floor(),
+        }
+    }
+}
+
+/// Up to this many destinations every triple is checked; beyond it triples are sampled.
+const EXHAUSTIVE_LIMIT: usize = 40;
+/// The number of triples sampled from larger sets.
+const SAMPLES: usize = 50_000;
+/// Distances within this much of each other, relative to their size, count as equal.
+const RELATIVE_TOLERANCE: f64 = 1e-9;
+
+/// A way in which distances fail to behave like a metric.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MetricViolation {
+    /// The distance from `from` to `to` differs from the distance back.
+    Asymmetric {
+        from: usize,
+        to: usize,
+        forward: f64,
+        backward: f64,
+    },
+    /// Going from `from` to `to` directly is longer than going by way of `via`.
+    Triangle {
+        from: usize,
+        via: usize,
+        to: usize,
+        direct: f64,
+        detour: f64,
+    },
+}
+
+/// Checks that `metric` behaves like a metric over `points`: that distances are
+/// symmetric and obey the triangle inequality.
+///
+/// Algorithms such as Christofides and bound-based pruning assume both, and can
+/// return poor or wrong routes without them. Sets of up to 40 points are checked
+/// exhaustively; for larger sets a deterministic sample of triples is checked.
+///
+/// # Returns
+///
+/// Every violation found, or an empty list if none were
+pub fn validate_metric(points: &[Point], metric: Metric) -> Vec<MetricViolation> {
+    find_violations(points.len(), |from, to| {
+        metric.distance(&points[from], &points[to])
+    })
+}
+
+/// Checks pairs and triples of the destinations `0..len` under `distance`, as
+/// described on [`validate_metric`].
+pub(crate) fn find_violations(
+    len: usize,
+    distance: impl Fn(usize, usize) -> f64,
+) -> Vec<MetricViolation> {
+    let exceeds = |a: f64, b: f64| a - b > RELATIVE_TOLERANCE * a.abs().max(b.abs()).max(1.0);
+    let mut violations = Vec::new();
+
+    for from in 0..len {
+        for to in from + 1..len {
+            let (forward, backward) = (distance(from, to), distance(to, from));
+            if exceeds(forward, backward) || exceeds(backward, forward) {
+                violations.push(MetricViolation::Asymmetric {
+                    from,
+                    to,
+                    forward,
+                    backward,
+                });
+            }
+        }
+    }
+
+    let mut check = |from: usize, via: usize, to: usize| {
+        let direct = distance(from, to);
+        let detour = distance(from, via) + distance(via, to);
+        if exceeds(direct, detour) {
+            violations.push(MetricViolation::Triangle {
+                from,
+                via,
+                to,
+                direct,
+                detour,
+            });
+        }
+    };
+
+    if len <= EXHAUSTIVE_LIMIT {
+        for from in 0..len {
+            for via in 0..len {
+                for to in 0..len {
+                    if from != via && via != to && from != to {
+                        check(from, via, to);
+                    }
+                }
+            }
+        }
+    } else {
+        // A fixed seed keeps the report the same from run to run
+        let mut rng = SplitMix64::new(0x9e37_79b9_7f4a_7c15);
+        let mut next = || rng.below(len);
+        for _ in 0..SAMPLES {
+            let (from, via, to) = (next(), next(), next());
+            if from != via && via != to && from != to {
+                check(from, via, to);
+            }
+        }
+    }
+
+    violations
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_planar_metrics() {
+        let a = Point::new(0.0, 0.0);
+        let b = Point::new(3.0, 4.0);
+
+        assert_eq!(Metric::Euclidean.distance(&a, &b), 5.0);
+        assert_eq!(Metric::Manhattan.distance(&a, &b), 7.0);
+        assert_eq!(Metric::RoundedEuclidean.distance(&a, &b), 5.0);
+        // Halves round up, as TSPLIB's nint does
+        let c = Point::new(2.5, 0.0);
+        assert_eq!(Metric::RoundedEuclidean.distance(&a, &c), 3.0);
+        assert_eq!(
+            Metric::RoundedEuclidean.distance(&a, &Point::new(1.0, 1.0)),
+            1.0
+        );
+    }
+
+    #[test]
+    fn test_haversine() {
+        // One degree of latitude is roughly 111.2km anywhere on the globe
+        let a = Point::new(10.0, 20.0);
+        let b = Point::new(11.0, 20.0);
+
+        let distance = Metric::Haversine.distance(&a, &b);
+        assert!((distance - 111_195.0).abs() < 1.0, "{distance}");
+        assert_eq!(Metric::Haversine.distance(&a, &a), 0.0);
+
+        // Out of range latitudes give a meaningless but finite distance
+        let distance = Metric::Haversine.distance(&Point::new(1e9, 0.0), &Point::new(-1e9, 3.0));
+        assert!(distance.is_finite());
+    }
+
+    #[test]
+    fn test_validate_metric() {
+        let points = (0..50)
+            .map(|i| Point::new(f64::from(i % 7), f64::from(i * i % 11)))
+            .collect::<Vec<_>>();
+
+        for metric in [Metric::Euclidean, Metric::Manhattan, Metric::Haversine] {
+            assert_eq!(validate_metric(&points[..5], metric), vec![]);
+            assert_eq!(validate_metric(&points, metric), vec![]);
+        }
+    }
+
+    #[test]
+    fn test_find_violations() {
+        // Going 0 -> 2 directly costs 5 but only 2 by way of 1, and 1 -> 2 is cheaper than 2 -> 1
+        let distances = [[0.0, 1.0, 5.0], [1.0, 0.0, 1.0], [5.0, 3.0, 0.0]];
+        let violations = find_violations(3, |from, to| distances[from][to]);
+
+        assert_eq!(
+            violations,
+            vec![
+                MetricViolation::Asymmetric {
+                    from: 1,
+                    to: 2,
+                    forward: 1.0,
+                    backward: 3.0
+                },
+                MetricViolation::Triangle {
+                    from: 0,
+                    via: 1,
+                    to: 2,
+                    direct: 5.0,
+                    detour: 2.0
+                },
+                MetricViolation::Triangle {
+                    from: 2,
+                    via: 1,
+                    to: 0,
+                    direct: 5.0,
+                    detour: 4.0
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,204 @@
+//! Minimum spanning trees over a [`DistanceMatrix`].
+//!
+//! No route is shorter than a minimum spanning tree of its destinations, since removing
+//! a leg from a round trip leaves a spanning tree, which makes the tree's weight a quick
+//! sanity check on a route's length.
+
+use std::ops::Add;
+
+use num_traits::Zero;
+
+use crate::DistanceMatrix;
+
+/// A spanning tree as the pairs of destinations it joins.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpanningTree<Distance = f64> {
+    pub edges: Vec<(usize, usize)>,
+    /// The total distance of the edges.
+    pub weight: Distance,
+}
+
+impl<Distance> SpanningTree<Distance> {
+    /// The number of edges at each destination.
+    pub fn degrees(&self, len: usize) -> Vec<usize> {
+        let mut degrees = vec![0; len];
+        for &(a, b) in &self.edges {
+            degrees[a] += 1;
+            degrees[b] += 1;
+        }
+        degrees
+    }
+}
+
+/// Finds a minimum spanning tree by Prim's algorithm, which takes O(n²) time and
+/// suits the complete graph of a distance matrix.
+///
+/// The matrix should be symmetric. Only the distance from the tree to each
+/// destination added is used.
+pub fn prim<Distance>(matrix: &DistanceMatrix<Distance>) -> SpanningTree<Distance>
+where
+    Distance: Copy + PartialOrd + Zero,
+{
+    tree(matrix, prim_by(matrix.len(), |a, b| matrix.get(a, b)))
+}
+
+/// Finds a minimum spanning tree by Kruskal's algorithm, which takes O(n² log n)
+/// time to sort every pair of destinations.
+///
+/// The matrix should be symmetric. Only the distances from lower to higher indices
+/// are used.
+pub fn kruskal<Distance>(matrix: &DistanceMatrix<Distance>) -> SpanningTree<Distance>
+where
+    Distance: Copy + PartialOrd + Zero,
+{
+    let n = matrix.len();
+    let mut pairs = (0..n)
+        .flat_map(|a| (a + 1..n).map(move |b| (a, b)))
+        .collect::<Vec<_>>();
+    pairs.sort_by(|&(a, b), &(c, d)| {
+        matrix
+            .get(a, b)
+            .partial_cmp(&matrix.get(c, d))
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+
+    // A disjoint-set forest of the components joined so far
+    let mut parent = (0..n).collect::<Vec<_>>();
+    fn root(parent: &mut [usize], mut i: usize) -> usize {
+        while parent[i] != i {
+            // Path halving
+            parent[i] = parent[parent[i]];
+            i = parent[i];
+        }
+        i
+    }
+
+    let mut edges = Vec::with_capacity(n.saturating_sub(1));
+    for (a, b) in pairs {
+        let (root_a, root_b) = (root(&mut parent, a), root(&mut parent, b));
+        if root_a != root_b {
+            parent[root_a] = root_b;
+            edges.push((a, b));
+            if edges.len() + 1 == n {
+                break;
+            }
+        }
+    }
+    tree(matrix, edges)
+}
+
+/// The edges of a minimum spanning tree of `n` destinations by Prim's algorithm,
+/// with distances given by `weight`, for callers that adjust them as they go.
+pub(crate) fn prim_by<Distance>(
+    n: usize,
+    weight: impl Fn(usize, usize) -> Distance,
+) -> Vec<(usize, usize)>
+where
+    Distance: PartialOrd,
+{
+    let mut in_tree = vec![false; n];
+    // The cheapest edge from each destination to the tree so far
+    let mut cheapest: Vec<Option<(Distance, usize)>> = (0..n).map(|_| None).collect();
+    let mut edges = Vec::with_capacity(n.saturating_sub(1));
+
+    let mut next = 0;
+    for _ in 0..n {
+        in_tree[next] = true;
+        if let Some((_, from)) = cheapest[next] {
+            edges.push((from, next));
+        }
+        let added = next;
+        let mut closest: Option<usize> = None;
+        for other in (0..n).filter(|&other| !in_tree[other]) {
+            let distance = weight(added, other);
+            if cheapest[other]
+                .as_ref()
+                .is_none_or(|(cheapest, _)| distance < *cheapest)
+            {
+                cheapest[other] = Some((distance, added));
+            }
+            // Every destination considered has a cheapest edge by now
+            let distance = |i: usize| cheapest[i].as_ref().map(|(distance, _)| distance);
+            if closest.is_none_or(|closest| distance(other) < distance(closest)) {
+                closest = Some(other);
+            }
+        }
+        next = closest.unwrap_or(added);
+    }
+    edges
+}
+
+fn tree<Distance>(
+    matrix: &DistanceMatrix<Distance>,
+    edges: Vec<(usize, usize)>,
+) -> SpanningTree<Distance>
+where
+    Distance: Copy + Zero,
+{
+    SpanningTree {
+        weight: edges
+            .iter()
+            .map(|&(a, b)| matrix.get(a, b))
+            .fold(Distance::zero(), Add::add),
+        edges,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{rng::SplitMix64, Metric, Point, Problem};
+
+    use super::*;
+
+    #[test]
+    fn test_spanning_trees() {
+        // A path along the x axis with a spur up from its middle
+        let mut problem = Problem::new(
+            vec![
+                Point::new(0.0, 0.0),
+                Point::new(4.0, 0.0),
+                Point::new(2.0, 0.0),
+                Point::new(2.0, 3.0),
+            ],
+            Metric::Manhattan,
+        );
+        let matrix = problem.distance_matrix();
+
+        for tree in [prim(&matrix), kruskal(&matrix)] {
+            assert_eq!(tree.weight, 7.0);
+            let mut edges = tree
+                .edges
+                .iter()
+                .map(|&(a, b)| (a.min(b), a.max(b)))
+                .collect::<Vec<_>>();
+            edges.sort();
+            assert_eq!(edges, vec![(0, 2), (1, 2), (2, 3)]);
+            assert_eq!(tree.degrees(4), vec![1, 1, 3, 1]);
+        }
+
+        problem.points.truncate(1);
+        assert!(prim(&problem.distance_matrix()).edges.is_empty());
+        assert!(kruskal(&DistanceMatrix::<u32>::from_fn(0, |_, _| 0))
+            .edges
+            .is_empty());
+    }
+
+    #[test]
+    fn test_prim_and_kruskal_agree() {
+        let mut rng = SplitMix64::new(42);
+        for _ in 0..20 {
+            let problem = Problem::new(
+                (0..7)
+                    .map(|_| Point::new(rng.below(50) as f64, rng.below(50) as f64))
+                    .collect(),
+                Metric::Euclidean,
+            );
+            let matrix = problem.distance_matrix();
+            let (prim, kruskal) = (prim(&matrix), kruskal(&matrix));
+            assert_eq!(prim.edges.len(), 6);
+            assert_eq!(kruskal.edges.len(), 6);
+            assert!((prim.weight - kruskal.weight).abs() < 1e-9);
+            assert!(prim.weight <= problem.route_distance(&problem.solve()));
+        }
+    }
+}
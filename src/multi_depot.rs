@@ -0,0 +1,371 @@
+//! Routing several vehicles, each making a round trip from its own depot, with the
+//! destinations shared between them to minimize the total distance.
+
+use crate::{DistanceMatrix, Problem, Route};
+
+/// The most destinations, other than depots, [`multi_depot_exhaustive`] accepts.
+pub const MAX_EXHAUSTIVE_DESTINATIONS: usize = 12;
+/// The most passes [`multi_depot`] makes moving destinations between routes.
+const PASSES: usize = 100;
+
+/// Finds the round trips from each of `depots` that together visit every other
+/// destination once with the least total distance, by solving every depot's route
+/// over every subset of the destinations exactly, then choosing the best way to
+/// divide the destinations between depots. `problem`'s start and end are ignored.
+///
+/// # Arguments
+///
+/// * `problem` - The destinations and the metric between them
+/// * `depots` - The indices of the depots, one per vehicle
+///
+/// # Returns
+///
+/// A round trip for each depot in the order given, which is just the depot twice for
+/// a depot that visits nothing
+///
+/// # Panics
+///
+/// Panics if there are no depots, or more than [`MAX_EXHAUSTIVE_DESTINATIONS`]
+/// destinations besides them.
+pub fn multi_depot_exhaustive(problem: &Problem, depots: &[usize]) -> Vec<Route> {
+    assert!(!depots.is_empty(), "there must be at least one depot");
+    let matrix = problem.distance_matrix();
+    let destinations = destinations(problem, depots);
+    let count = destinations.len();
+    assert!(
+        count <= MAX_EXHAUSTIVE_DESTINATIONS,
+        "{count} destinations is too many to divide between depots exhaustively"
+    );
+    let subsets = 1usize << count;
+    let trips = depots
+        .iter()
+        .map(|&depot| Trips::new(&matrix, depot, depot, &destinations))
+        .collect::<Vec<_>>();
+
+    // best[subset] is the least distance for the depots so far to visit the subset, and
+    // choices[k][subset] the part of it that depot k visits
+    let mut best = trips[0].distances.clone();
+    let mut choices = vec![(0..subsets).collect::<Vec<_>>()];
+    for trips in &trips[1..] {
+        let mut next = vec![f64::INFINITY; subsets];
+        let mut choice = vec![0; subsets];
+        for subset in 0..subsets {
+            // Every part of the subset, including none of it and all of it
+            let mut part = subset;
+            loop {
+                let distance = best[subset & !part] + trips.distances[part];
+                if distance < next[subset] {
+                    next[subset] = distance;
+                    choice[subset] = part;
+                }
+                if part == 0 {
+                    break;
+                }
+                part = (part - 1) & subset;
+            }
+        }
+        best = next;
+        choices.push(choice);
+    }
+
+    let mut remaining = subsets - 1;
+    let mut routes = vec![Route::default(); depots.len()];
+    for k in (0..depots.len()).rev() {
+        let part = choices[k][remaining];
+        routes[k] = trips[k].route(part, &destinations);
+        remaining &= !part;
+    }
+    routes
+}
+
+/// Finds short round trips from each of `depots` that together visit every other
+/// destination once, for problems too large for [`multi_depot_exhaustive`]. Each
+/// destination starts with its nearest depot, whose route `solve` finds; destinations
+/// are then moved to wherever inserting them saves the most while that shortens the
+/// total, and the changed routes solved again. `problem`'s start and end are ignored.
+///
+/// # Arguments
+///
+/// * `problem` - The destinations and the metric between them
+/// * `depots` - The indices of the depots, one per vehicle
+/// * `solve` - Solves the round trip from a single depot
+///
+/// # Returns
+///
+/// A round trip for each depot in the order given, which is just the depot twice for
+/// a depot that visits nothing
+///
+/// # Panics
+///
+/// Panics if there are no depots.
+pub fn multi_depot(
+    problem: &Problem,
+    depots: &[usize],
+    solve: impl Fn(&Problem) -> Route,
+) -> Vec<Route> {
+    assert!(!depots.is_empty(), "there must be at least one depot");
+    let matrix = problem.distance_matrix();
+    let mut groups = vec![Vec::new(); depots.len()];
+    for destination in destinations(problem, depots) {
+        let nearest = (0..depots.len())
+            .min_by(|&a, &b| {
+                let distance = |k: usize| matrix.get(depots[k], destination);
+                distance(a).total_cmp(&distance(b))
+            })
+            .expect("there are depots");
+        groups[nearest].push(destination);
+    }
+    let mut routes = depots
+        .iter()
+        .zip(&groups)
+        .map(|(&depot, group)| solve_depot(problem, depot, group, &solve))
+        .collect::<Vec<_>>();
+
+    let mut changed = vec![false; depots.len()];
+    for _ in 0..PASSES {
+        if !relocate_best(&mut routes, &matrix, &mut changed) {
+            break;
+        }
+    }
+    for (k, route) in routes.iter_mut().enumerate() {
+        if changed[k] {
+            *route = solve_depot(problem, depots[k], &route[1..route.len() - 1], &solve);
+        }
+    }
+    routes
+}
+
+/// The shortest route from a start to an end through each subset of the destinations,
+/// such as a round trip from a depot, found together by the Held-Karp recurrence in
+/// O(2^n n²) time.
+pub(crate) struct Trips {
+    start: usize,
+    end: usize,
+    /// The distance of the shortest route through each subset.
+    pub(crate) distances: Vec<f64>,
+    /// For each subset and last destination in it, the shortest distance from the start
+    /// through the subset to that destination, and the destination before it.
+    paths: Vec<Vec<(f64, usize)>>,
+    /// The distance from each destination to the end.
+    returns: Vec<f64>,
+}
+
+impl Trips {
+    /// Marks the start as the destination before the first.
+    const START: usize = usize::MAX;
+
+    pub(crate) fn new(
+        matrix: &DistanceMatrix,
+        start: usize,
+        end: usize,
+        destinations: &[usize],
+    ) -> Self {
+        let count = destinations.len();
+        let subsets = 1usize << count;
+        let mut paths = vec![vec![(f64::INFINITY, Self::START); count]; subsets];
+        for (i, &destination) in destinations.iter().enumerate() {
+            paths[1 << i][i] = (matrix.get(start, destination), Self::START);
+        }
+        let returns = destinations
+            .iter()
+            .map(|&destination| matrix.get(destination, end))
+            .collect::<Vec<_>>();
+        let mut distances = vec![matrix.get(start, end); subsets];
+        for subset in 1..subsets {
+            let mut shortest = f64::INFINITY;
+            for last in (0..count).filter(|&last| subset & (1 << last) != 0) {
+                let (distance, _) = paths[subset][last];
+                shortest = shortest.min(distance + returns[last]);
+                for next in (0..count).filter(|&next| subset & (1 << next) == 0) {
+                    let extended = distance + matrix.get(destinations[last], destinations[next]);
+                    let path = &mut paths[subset | (1 << next)][next];
+                    if extended < path.0 {
+                        *path = (extended, last);
+                    }
+                }
+            }
+            distances[subset] = shortest;
+        }
+        Trips {
+            start,
+            end,
+            distances,
+            paths,
+            returns,
+        }
+    }
+
+    /// The shortest route through `subset` of `destinations`.
+    pub(crate) fn route(&self, subset: usize, destinations: &[usize]) -> Route {
+        let total = |last: usize| self.paths[subset][last].0 + self.returns[last];
+        let mut last = (0..destinations.len())
+            .filter(|&last| subset & (1 << last) != 0)
+            .min_by(|&a, &b| total(a).total_cmp(&total(b)))
+            .unwrap_or(Self::START);
+        // Follow the path back from the last destination to the start
+        let mut reversed = Vec::new();
+        let mut remaining = subset;
+        while last != Self::START {
+            reversed.push(destinations[last]);
+            let previous = self.paths[remaining][last].1;
+            remaining &= !(1 << last);
+            last = previous;
+        }
+        let mut stops = vec![self.start];
+        stops.extend(reversed.into_iter().rev());
+        stops.push(self.end);
+        Route::new(stops)
+    }
+}
+
+/// Every destination that is not a depot.
+fn destinations(problem: &Problem, depots: &[usize]) -> Vec<usize> {
+    (0..problem.len())
+        .filter(|destination| !depots.contains(destination))
+        .collect()
+}
+
+/// Solves the round trip from `depot` through `group` with `solve`, as a subproblem
+/// with the depot first.
+fn solve_depot(
+    problem: &Problem,
+    depot: usize,
+    group: &[usize],
+    solve: &impl Fn(&Problem) -> Route,
+) -> Route {
+    if group.is_empty() {
+        return Route::new(vec![depot, depot]);
+    }
+    let indices = [&[depot], group].concat();
+    let subproblem = Problem::new(
+        indices.iter().map(|&i| problem.points[i]).collect(),
+        problem.metric,
+    );
+    Route::new(
+        solve(&subproblem)
+            .stops()
+            .iter()
+            .map(|&local| indices[local])
+            .collect(),
+    )
+}
+
+/// Makes the move of a destination from one route into another that shortens the total
+/// the most, marking the routes it changes. Returns whether any move shortens it.
+fn relocate_best(routes: &mut [Route], matrix: &DistanceMatrix, changed: &mut [bool]) -> bool {
+    let distance = |a, b| matrix.get(a, b);
+    let mut best: Option<(f64, usize, usize, Route, Route)> = None;
+    for from in 0..routes.len() {
+        for &destination in &routes[from][1..routes[from].len() - 1] {
+            let removed = routes[from].with_removed(destination);
+            let saved = matrix.route_distance(&routes[from]) - matrix.route_distance(&removed);
+            for to in (0..routes.len()).filter(|&to| to != from) {
+                let inserted = routes[to].with_inserted(destination, distance);
+                let added = matrix.route_distance(&inserted) - matrix.route_distance(&routes[to]);
+                let gain = saved - added;
+                if gain > 1e-10 && best.as_ref().is_none_or(|best| gain > best.0) {
+                    best = Some((gain, from, to, removed.clone(), inserted));
+                }
+            }
+        }
+    }
+    let Some((_, from, to, removed, inserted)) = best else {
+        return false;
+    };
+    routes[from] = removed;
+    routes[to] = inserted;
+    changed[from] = true;
+    changed[to] = true;
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{beam::beam_search, rng::SplitMix64, Metric, Point};
+
+    use super::*;
+
+    fn total(problem: &Problem, routes: &[Route]) -> f64 {
+        routes
+            .iter()
+            .map(|route| problem.route_distance(route))
+            .sum()
+    }
+
+    fn visits_each_once(problem: &Problem, depots: &[usize], routes: &[Route]) -> bool {
+        let mut visited = routes
+            .iter()
+            .flat_map(|route| route[1..route.len() - 1].to_vec())
+            .collect::<Vec<_>>();
+        visited.sort();
+        routes
+            .iter()
+            .zip(depots)
+            .all(|(route, &depot)| route[0] == depot && route[route.len() - 1] == depot)
+            && visited == destinations(problem, depots)
+    }
+
+    #[test]
+    fn test_multi_depot_exhaustive() {
+        // Two groups far apart, each with a depot
+        let points = [(0.0, 0.0), (100.0, 0.0)]
+            .iter()
+            .flat_map(|&(x, y)| (0..4).map(move |i| Point::new(x + f64::from(i), y)))
+            .collect::<Vec<_>>();
+        let problem = Problem::new(points, Metric::Euclidean);
+        let routes = multi_depot_exhaustive(&problem, &[4, 0]);
+        // In either direction
+        let inner = |route: &Route| {
+            let mut inner = route[1..route.len() - 1].to_vec();
+            inner.sort();
+            inner
+        };
+        assert_eq!(inner(&routes[0]), vec![5, 6, 7]);
+        assert_eq!(inner(&routes[1]), vec![1, 2, 3]);
+        assert_eq!(total(&problem, &routes), 12.0);
+
+        // A single depot is the travelling salesman problem
+        let routes = multi_depot_exhaustive(&problem, &[0]);
+        assert_eq!(
+            total(&problem, &routes),
+            problem.route_distance(&problem.solve())
+        );
+
+        // A depot too far away visits nothing
+        let mut problem = problem;
+        problem.points.push(Point::new(1000.0, 1000.0));
+        problem.service_times.push(0.0);
+        let routes = multi_depot_exhaustive(&problem, &[0, 8, 4]);
+        assert_eq!(routes[1].stops(), &[8, 8]);
+    }
+
+    #[test]
+    fn test_multi_depot() {
+        let mut rng = SplitMix64::new(4);
+        for trial in 0..5 {
+            let problem = Problem::new(
+                (0..11)
+                    .map(|_| Point::new(rng.below(100) as f64, rng.below(100) as f64))
+                    .collect(),
+                Metric::Euclidean,
+            );
+            let depots = [0, 1, 2][..2 + trial % 2].to_vec();
+            let exact = multi_depot_exhaustive(&problem, &depots);
+            let routes = multi_depot(&problem, &depots, Problem::solve);
+            assert!(visits_each_once(&problem, &depots, &exact));
+            assert!(visits_each_once(&problem, &depots, &routes));
+            assert!(total(&problem, &routes) >= total(&problem, &exact) - 1e-9);
+            assert!(total(&problem, &routes) <= total(&problem, &exact) * 1.2);
+        }
+
+        let problem = Problem::new(
+            (0..300)
+                .map(|_| Point::new(rng.below(1000) as f64, rng.below(1000) as f64))
+                .collect(),
+            Metric::Euclidean,
+        );
+        let depots = [0, 100, 200];
+        let routes = multi_depot(&problem, &depots, |problem| beam_search(problem, 4));
+        assert!(visits_each_once(&problem, &depots, &routes));
+    }
+}
@@ -0,0 +1,485 @@
+//! What makes one route better than another, for optimizing criteria other than the
+//! total distance, such as the longest single leg.
+
+use std::{collections::HashSet, ops::ControlFlow};
+
+use itertools::Itertools;
+
+use crate::{
+    search::{search_routes, Improvement},
+    DistanceMatrix, Problem, Route,
+};
+
+/// A cost to minimize over routes.
+///
+/// Closures taking the matrix and the stops are objectives, for custom criteria.
+pub trait Objective {
+    /// The cost of the route visiting `stops`, lower being better.
+    fn cost(&self, matrix: &DistanceMatrix, stops: &[usize]) -> f64;
+}
+
+impl<F> Objective for F
+where
+    F: Fn(&DistanceMatrix, &[usize]) -> f64,
+{
+    fn cost(&self, matrix: &DistanceMatrix, stops: &[usize]) -> f64 {
+        self(matrix, stops)
+    }
+}
+
+/// The sum of the legs, which every solver minimizes by default.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TotalDistance;
+
+impl Objective for TotalDistance {
+    fn cost(&self, matrix: &DistanceMatrix, stops: &[usize]) -> f64 {
+        matrix.route_distance(stops)
+    }
+}
+
+/// The longest leg, for when no single leg should be too long, such as the range of
+/// a vehicle between charges.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LongestLeg;
+
+impl Objective for LongestLeg {
+    fn cost(&self, matrix: &DistanceMatrix, stops: &[usize]) -> f64 {
+        stops
+            .windows(2)
+            .map(|leg| matrix.get(leg[0], leg[1]))
+            .fold(0.0, f64::max)
+    }
+}
+
+/// The sum of a penalty for each turn, computed from the previous, current and next
+/// stops, such as for U-turns or changing between transit lines. There is no turn at
+/// the first and last stops.
+///
+/// Turns cost nothing but their penalties, so combine them with [`TotalDistance`] in a
+/// [`Weighted`] sum to penalize turns on the way.
+#[derive(Debug, Clone, Copy)]
+pub struct Turns<F> {
+    penalty: F,
+}
+
+impl<F> Turns<F>
+where
+    F: Fn(usize, usize, usize) -> f64,
+{
+    /// Penalizes each turn from `previous` through `current` to `next` by
+    /// `penalty(previous, current, next)`.
+    pub fn new(penalty: F) -> Self {
+        Turns { penalty }
+    }
+}
+
+impl<F> Objective for Turns<F>
+where
+    F: Fn(usize, usize, usize) -> f64,
+{
+    fn cost(&self, _matrix: &DistanceMatrix, stops: &[usize]) -> f64 {
+        stops
+            .windows(3)
+            .map(|turn| (self.penalty)(turn[0], turn[1], turn[2]))
+            .sum()
+    }
+}
+
+/// The sum of a setup cost for each leg, from a second matrix indexed by the types of
+/// the destinations at either end, such as changing a machine over between kinds of
+/// job when scheduling them as a route. The setup between two destinations of the same
+/// type is the matrix's diagonal, usually nothing.
+///
+/// Like [`Turns`], combine setups with [`TotalDistance`] in a [`Weighted`] sum to count
+/// both the travel and the changeovers.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SetupCosts {
+    types: Vec<usize>,
+    setups: DistanceMatrix,
+}
+
+impl SetupCosts {
+    /// Costs each leg from a destination of type `types[from]` to one of type
+    /// `types[to]` by `setups.get(types[from], types[to])`.
+    ///
+    /// # Arguments
+    ///
+    /// * `types` - The type of each destination, by index
+    /// * `setups` - The cost of changing from each type to each other, which need not
+    ///   be symmetric
+    ///
+    /// # Panics
+    ///
+    /// Panics if a type is not a row of `setups`.
+    pub fn new(types: Vec<usize>, setups: DistanceMatrix) -> Self {
+        assert!(
+            types.iter().all(|&kind| kind < setups.len()),
+            "every type needs a row of setup costs"
+        );
+        SetupCosts { types, setups }
+    }
+}
+
+impl Objective for SetupCosts {
+    fn cost(&self, _matrix: &DistanceMatrix, stops: &[usize]) -> f64 {
+        stops
+            .windows(2)
+            .map(|leg| self.setups.get(self.types[leg[0]], self.types[leg[1]]))
+            .sum()
+    }
+}
+
+/// The number of legs not in a baseline route, in either direction, for keeping a
+/// re-optimized route recognizable to the drivers who know the old one.
+///
+/// Combine it with [`TotalDistance`] in a [`Weighted`] sum, where its weight is the
+/// distance a changed leg must save to be worth making.
+/// [`stability::reoptimize`](crate::stability::reoptimize) optimizes the same sum on
+/// problems too large for exhaustive search.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct EdgeChanges {
+    baseline: HashSet<(usize, usize)>,
+}
+
+impl EdgeChanges {
+    /// Counts changes from the legs of `baseline`, a route as destination indices.
+    pub fn new(baseline: &[usize]) -> Self {
+        EdgeChanges {
+            baseline: baseline
+                .windows(2)
+                .map(|leg| undirected(leg[0], leg[1]))
+                .collect(),
+        }
+    }
+
+    /// Whether the baseline travels between `from` and `to`, in either direction.
+    pub fn is_kept(&self, from: usize, to: usize) -> bool {
+        self.baseline.contains(&undirected(from, to))
+    }
+}
+
+impl Objective for EdgeChanges {
+    fn cost(&self, _matrix: &DistanceMatrix, stops: &[usize]) -> f64 {
+        stops
+            .windows(2)
+            .filter(|leg| !self.is_kept(leg[0], leg[1]))
+            .count() as f64
+    }
+}
+
+fn undirected(a: usize, b: usize) -> (usize, usize) {
+    (a.min(b), a.max(b))
+}
+
+/// A weighted sum of objectives, for trading them off against each other.
+#[derive(Default)]
+pub struct Weighted {
+    parts: Vec<(f64, Box<dyn Objective>)>,
+}
+
+impl Weighted {
+    /// An empty sum, under which every route costs nothing.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds `objective` to the sum, multiplied by `weight`.
+    pub fn with_objective(mut self, weight: f64, objective: impl Objective + 'static) -> Self {
+        self.parts.push((weight, Box::new(objective)));
+        self
+    }
+}
+
+impl Objective for Weighted {
+    fn cost(&self, matrix: &DistanceMatrix, stops: &[usize]) -> f64 {
+        self.parts
+            .iter()
+            .map(|(weight, objective)| weight * objective.cost(matrix, stops))
+            .sum()
+    }
+}
+
+/// Finds the route from `start` through every destination to `end` that minimizes
+/// `objective`, by exhaustive search as [`Problem::search_objective`] does, for a matrix
+/// without a problem.
+///
+/// # Arguments
+///
+/// * `matrix` - The distances between destinations
+/// * `start` - The index of the destination to start at
+/// * `end` - The index of the destination to end at, which may be `start`
+/// * `objective` - The cost to minimize
+///
+/// # Returns
+///
+/// The cheapest route, the first found if several tie
+pub fn solve_exhaustive(
+    matrix: &DistanceMatrix,
+    start: usize,
+    end: usize,
+    objective: &dyn Objective,
+) -> Route {
+    let inner = (0..matrix.len())
+        .filter(|&i| i != start && i != end)
+        .collect::<Vec<_>>();
+    search_routes(
+        inner,
+        start,
+        end,
+        |stops| objective.cost(matrix, stops),
+        &mut |_: &Improvement| ControlFlow::Continue(()),
+    )
+    .route
+}
+
+/// A route on the Pareto front of several objectives, with its cost under each.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParetoRoute {
+    pub route: Route,
+    pub costs: Vec<f64>,
+}
+
+/// Finds every route of `problem` that no other route beats under all of `objectives`,
+/// such as distance against tolls, by exhaustive search. This leaves the trade-off to
+/// the caller, rather than fixing it in advance with a [`Weighted`] sum.
+///
+/// # Arguments
+///
+/// * `problem` - The problem to solve
+/// * `objectives` - The costs to minimize
+///
+/// # Returns
+///
+/// One route for each distinct combination of costs on the front, ordered by their
+/// costs under the first objective, then the second and so on
+pub fn pareto_routes(problem: &Problem, objectives: &[&dyn Objective]) -> Vec<ParetoRoute> {
+    let matrix = problem.distance_matrix();
+    let inner = problem.inner_destinations();
+    let count = inner.len();
+    // Whether costs `a` are no worse than `b` under every objective
+    let no_worse = |a: &[f64], b: &[f64]| a.iter().zip(b).all(|(a, b)| a <= b);
+
+    let mut front: Vec<ParetoRoute> = Vec::new();
+    for permutation in inner.into_iter().permutations(count) {
+        let stops = [vec![problem.start], permutation, vec![problem.end]].concat();
+        let costs = objectives
+            .iter()
+            .map(|objective| objective.cost(&matrix, &stops))
+            .collect::<Vec<_>>();
+        if front.iter().any(|other| no_worse(&other.costs, &costs)) {
+            continue;
+        }
+        front.retain(|other| !no_worse(&costs, &other.costs));
+        front.push(ParetoRoute {
+            route: Route::new(stops),
+            costs,
+        });
+    }
+    front.sort_by(|a, b| {
+        a.costs
+            .iter()
+            .zip(&b.costs)
+            .map(|(a, b)| a.total_cmp(b))
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    front
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{rng::SplitMix64, Metric, Point};
+
+    use super::*;
+
+    #[test]
+    fn test_objectives() {
+        let matrix = DistanceMatrix::from_fn(3, |a, b| (a.abs_diff(b) * 10) as f64);
+        let stops = [0, 2, 1, 0];
+        assert_eq!(TotalDistance.cost(&matrix, &stops), 40.0);
+        assert_eq!(LongestLeg.cost(&matrix, &stops), 20.0);
+        assert_eq!(LongestLeg.cost(&matrix, &[1]), 0.0);
+        let weighted = Weighted::new()
+            .with_objective(0.5, TotalDistance)
+            .with_objective(2.0, LongestLeg);
+        assert_eq!(weighted.cost(&matrix, &stops), 60.0);
+        assert_eq!(Weighted::new().cost(&matrix, &stops), 0.0);
+    }
+
+    #[test]
+    fn test_turns() {
+        // Destinations on a line at their index, where doubling back costs 10
+        let matrix = DistanceMatrix::from_fn(5, |a, b| a.abs_diff(b) as f64);
+        let u_turn = |previous: usize, current: usize, next: usize| {
+            if (current > previous) != (next > current) {
+                10.0
+            } else {
+                0.0
+            }
+        };
+        let turns = Turns::new(u_turn);
+        assert_eq!(turns.cost(&matrix, &[0, 1, 2, 3, 4]), 0.0);
+        assert_eq!(turns.cost(&matrix, &[0, 2, 1, 3, 4]), 20.0);
+        assert_eq!(turns.cost(&matrix, &[0, 4]), 0.0);
+
+        // The best round trip goes out and back, doubling back only at the far end
+        let objective = Weighted::new()
+            .with_objective(1.0, TotalDistance)
+            .with_objective(1.0, turns);
+        let route = solve_exhaustive(&matrix, 0, 0, &objective);
+        assert_eq!(route.stops(), &[0, 1, 2, 3, 4, 0]);
+        assert_eq!(objective.cost(&matrix, &route), 18.0);
+
+        // A penalty heavy enough on one turn makes the route avoid it
+        let objective = Weighted::new()
+            .with_objective(1.0, TotalDistance)
+            .with_objective(
+                1.0,
+                Turns::new(|previous, current, next| {
+                    if [previous, current, next] == [1, 2, 3] {
+                        100.0
+                    } else {
+                        0.0
+                    }
+                }),
+            );
+        let route = solve_exhaustive(&matrix, 0, 4, &objective);
+        assert_eq!(route.stops(), &[0, 1, 3, 2, 4]);
+        assert_eq!(objective.cost(&matrix, &route), 6.0);
+    }
+
+    #[test]
+    fn test_setup_costs() {
+        // Jobs on a line at their index, alternating between two types, where changing
+        // from type 0 to type 1 costs 10 and back costs 1
+        let matrix = DistanceMatrix::from_fn(5, |a, b| a.abs_diff(b) as f64);
+        let setups = DistanceMatrix::from_rows(vec![vec![0.0, 10.0], vec![1.0, 0.0]]).unwrap();
+        let setup = SetupCosts::new(vec![0, 1, 0, 1, 0], setups);
+        assert_eq!(setup.cost(&matrix, &[0, 1, 2, 3, 4]), 22.0);
+        assert_eq!(setup.cost(&matrix, &[0, 2, 4, 1, 3]), 10.0);
+        assert_eq!(setup.cost(&matrix, &[0]), 0.0);
+
+        // The jobs of each type are done together, with only one changeover each way
+        let types = [0, 1, 0, 1, 0];
+        let objective = Weighted::new()
+            .with_objective(1.0, TotalDistance)
+            .with_objective(1.0, setup);
+        let route = solve_exhaustive(&matrix, 0, 0, &objective);
+        let changeovers = route
+            .windows(2)
+            .filter(|leg| types[leg[0]] != types[leg[1]])
+            .count();
+        assert_eq!(changeovers, 2);
+        assert_eq!(objective.cost(&matrix, &route), 19.0);
+    }
+
+    #[test]
+    fn test_edge_changes() {
+        let matrix = DistanceMatrix::from_fn(5, |a, b| a.abs_diff(b) as f64);
+        let changes = EdgeChanges::new(&[0, 2, 1, 3, 4, 0]);
+        assert!(changes.is_kept(1, 2) && changes.is_kept(2, 1));
+        assert!(!changes.is_kept(1, 4));
+        assert_eq!(changes.cost(&matrix, &[0, 4, 3, 1, 2, 0]), 0.0);
+        assert_eq!(changes.cost(&matrix, &[0, 1, 2, 3, 4, 0]), 2.0);
+
+        // Only a saving above the penalty is worth changing a leg for
+        let stable = |penalty| {
+            let objective = Weighted::new()
+                .with_objective(1.0, TotalDistance)
+                .with_objective(penalty, changes.clone());
+            solve_exhaustive(&matrix, 0, 0, &objective)
+        };
+        assert_eq!(stable(0.0).stops(), [0, 1, 2, 3, 4, 0]);
+        assert_eq!(stable(5.0).stops(), [0, 2, 1, 3, 4, 0]);
+    }
+
+    #[test]
+    #[should_panic(expected = "row of setup costs")]
+    fn test_setup_costs_missing_type() {
+        SetupCosts::new(vec![0, 2], DistanceMatrix::from_fn(2, |_, _| 0.0));
+    }
+
+    #[test]
+    fn test_solve_exhaustive() {
+        let mut rng = SplitMix64::new(3);
+        for trial in 0..5 {
+            let mut problem = Problem::new(
+                (0..7)
+                    .map(|_| Point::new(rng.below(100) as f64, rng.below(100) as f64))
+                    .collect(),
+                Metric::Euclidean,
+            );
+            if trial % 2 == 1 {
+                problem.end = 2;
+            }
+            let matrix = problem.distance_matrix();
+            let (start, end) = (problem.start, problem.end);
+            let shortest = problem.solve();
+
+            let route = solve_exhaustive(&matrix, start, end, &TotalDistance);
+            assert!(
+                (matrix.route_distance(&route) - matrix.route_distance(&shortest)).abs() < 1e-9
+            );
+
+            // Minimizing the longest leg may lengthen the route, but never its longest leg
+            let route = solve_exhaustive(&matrix, start, end, &LongestLeg);
+            assert!(problem.is_route(&route));
+            assert!(LongestLeg.cost(&matrix, &route) <= LongestLeg.cost(&matrix, &shortest));
+            assert!(matrix.route_distance(&route) >= matrix.route_distance(&shortest) - 1e-9);
+        }
+
+        // A custom objective: visit destination 3 first, then as short as possible
+        let matrix = DistanceMatrix::from_fn(5, |a, b| a.abs_diff(b) as f64);
+        let three_first = |matrix: &DistanceMatrix, stops: &[usize]| {
+            matrix.route_distance(stops) + if stops[1] == 3 { 0.0 } else { 100.0 }
+        };
+        let route = solve_exhaustive(&matrix, 0, 0, &three_first);
+        assert_eq!(route.stops(), &[0, 3, 4, 2, 1, 0]);
+        assert_eq!(
+            solve_exhaustive(&matrix, 0, 4, &TotalDistance).stops(),
+            &[0, 1, 2, 3, 4]
+        );
+    }
+
+    #[test]
+    fn test_pareto_routes() {
+        let mut rng = SplitMix64::new(8);
+        let mut problem = Problem::new(
+            (0..7)
+                .map(|_| Point::new(rng.below(100) as f64, rng.below(100) as f64))
+                .collect(),
+            Metric::Euclidean,
+        );
+        problem.end = 1;
+        let matrix = problem.distance_matrix();
+        // Tolls on some roads between destinations, unrelated to their length
+        let toll = DistanceMatrix::from_fn(7, |a, b| ((a + 1) * (b + 1) % 4) as f64);
+        let tolls = |_: &DistanceMatrix, stops: &[usize]| toll.route_distance(stops);
+        let objectives: [&dyn Objective; 3] = [&TotalDistance, &tolls, &LongestLeg];
+
+        for count in 1..=3 {
+            let front = pareto_routes(&problem, &objectives[..count]);
+            assert!(!front.is_empty());
+            // The front starts with the shortest route
+            let shortest = matrix.route_distance(&problem.solve());
+            assert!((front[0].costs[0] - shortest).abs() < 1e-9);
+
+            for (i, a) in front.iter().enumerate() {
+                assert!(problem.is_route(&a.route));
+                for b in &front[i + 1..] {
+                    let no_worse = |a: &[f64], b: &[f64]| a.iter().zip(b).all(|(a, b)| a <= b);
+                    assert!(!no_worse(&a.costs, &b.costs) && !no_worse(&b.costs, &a.costs));
+                }
+            }
+        }
+        // A single objective has a single best cost
+        assert_eq!(pareto_routes(&problem, &objectives[..1]).len(), 1);
+
+        // Trading distance for tolls, routes with fewer tolls are longer
+        let front = pareto_routes(&problem, &objectives[..2]);
+        assert!(front.len() > 1);
+        assert!(front
+            .windows(2)
+            .all(|pair| pair[0].costs[1] > pair[1].costs[1]));
+    }
+}
@@ -0,0 +1,252 @@
+//! Destinations that may be skipped at a cost, the profitable tour problem: a route
+//! need not visit everyone, but each destination left out adds its penalty, such as the
+//! value of an order that waits until tomorrow. The best route minimizes its distance
+//! plus the penalties of the destinations it skips.
+
+use crate::{multi_depot::Trips, Problem, Route};
+
+/// The most destinations, other than the start and end, [`solve_exhaustive`] accepts.
+pub const MAX_EXHAUSTIVE_DESTINATIONS: usize = 15;
+/// The most destinations [`solve_with_penalties`] drops or adds from each start.
+const PASSES: usize = 1000;
+
+/// The distance of `stops` plus the penalty of every destination it does not visit.
+///
+/// # Arguments
+///
+/// * `problem` - The problem `stops` is a route of
+/// * `stops` - The route, visiting some of the destinations
+/// * `penalties` - The cost of skipping each destination, by index
+///
+/// # Panics
+///
+/// Panics if there is not one penalty per destination.
+pub fn total_cost(problem: &Problem, stops: &[usize], penalties: &[f64]) -> f64 {
+    check_penalties(problem, penalties);
+    let skipped = (0..problem.len())
+        .filter(|destination| !stops.contains(destination))
+        .map(|destination| penalties[destination])
+        .sum::<f64>();
+    problem.route_distance(stops) + skipped
+}
+
+/// Finds the route from `start` to `end` with the least [`total_cost`], by finding the
+/// shortest route through every subset of the destinations together with the
+/// Held-Karp recurrence. The penalties of the start and end are never paid.
+///
+/// # Arguments
+///
+/// * `problem` - The destinations and where the route starts and ends
+/// * `penalties` - The cost of skipping each destination, by index
+///
+/// # Returns
+///
+/// The route with the least distance plus penalties, which is just the start and end
+/// if every destination is cheaper to skip
+///
+/// # Panics
+///
+/// Panics if there is not one penalty per destination, or there are more than
+/// [`MAX_EXHAUSTIVE_DESTINATIONS`] besides the start and end.
+pub fn solve_exhaustive(problem: &Problem, penalties: &[f64]) -> Route {
+    check_penalties(problem, penalties);
+    let inner = problem.inner_destinations();
+    let count = inner.len();
+    assert!(
+        count <= MAX_EXHAUSTIVE_DESTINATIONS,
+        "{count} destinations is too many to choose between exhaustively"
+    );
+    let trips = Trips::new(
+        &problem.distance_matrix(),
+        problem.start,
+        problem.end,
+        &inner,
+    );
+    let cost = |subset: usize| {
+        let skipped = (0..count)
+            .filter(|i| subset & (1 << i) == 0)
+            .map(|i| penalties[inner[i]])
+            .sum::<f64>();
+        trips.distances[subset] + skipped
+    };
+    let subset = (0..1usize << count)
+        .min_by(|&a, &b| cost(a).total_cmp(&cost(b)))
+        .expect("there is at least the empty subset");
+    trips.route(subset, &inner)
+}
+
+/// Finds a route with a low [`total_cost`] for problems too large for
+/// [`solve_exhaustive`]. Starting both from the route through everyone that `solve`
+/// finds and from the route through no one, the destination that saves the most more
+/// than its penalty is repeatedly dropped, or the one whose penalty is the most more
+/// than it costs to insert added, then the destinations kept are solved again. The
+/// cheaper of the two results is returned.
+///
+/// # Arguments
+///
+/// * `problem` - The destinations and where the route starts and ends
+/// * `penalties` - The cost of skipping each destination, by index
+/// * `solve` - Solves a route through every destination of a problem
+///
+/// # Panics
+///
+/// Panics if there is not one penalty per destination.
+pub fn solve_with_penalties(
+    problem: &Problem,
+    penalties: &[f64],
+    solve: impl Fn(&Problem) -> Route,
+) -> Route {
+    check_penalties(problem, penalties);
+    [solve(problem), Route::new(vec![problem.start, problem.end])]
+        .into_iter()
+        .map(|route| {
+            let route = drop_and_add(problem, penalties, route);
+            let resolved = solve_visited(problem, &route, &solve);
+            if problem.route_distance(&resolved) < problem.route_distance(&route) {
+                resolved
+            } else {
+                route
+            }
+        })
+        .min_by(|a, b| {
+            total_cost(problem, a, penalties).total_cmp(&total_cost(problem, b, penalties))
+        })
+        .expect("there are two starting routes")
+}
+
+/// Drops or adds the destination that lowers the [`total_cost`] of `route` the most,
+/// until none does.
+fn drop_and_add(problem: &Problem, penalties: &[f64], mut route: Route) -> Route {
+    let distance = |a, b| problem.distance(a, b);
+    for _ in 0..PASSES {
+        let current = problem.route_distance(&route);
+        let mut best: Option<(f64, Route)> = None;
+        for destination in problem.inner_destinations() {
+            let (changed, gain) = if route.contains(&destination) {
+                let removed = route.with_removed(destination);
+                let saved = current - problem.route_distance(&removed);
+                (removed, saved - penalties[destination])
+            } else {
+                let inserted = route.with_inserted(destination, distance);
+                let added = problem.route_distance(&inserted) - current;
+                (inserted, penalties[destination] - added)
+            };
+            if gain > 1e-10 && best.as_ref().is_none_or(|best| gain > best.0) {
+                best = Some((gain, changed));
+            }
+        }
+        let Some((_, changed)) = best else {
+            break;
+        };
+        route = changed;
+    }
+    route
+}
+
+fn check_penalties(problem: &Problem, penalties: &[f64]) {
+    assert_eq!(
+        penalties.len(),
+        problem.len(),
+        "there must be one penalty per destination"
+    );
+}
+
+/// Solves the route through just the destinations `route` visits with `solve`, as a
+/// subproblem with the same start and end.
+fn solve_visited(problem: &Problem, route: &Route, solve: &impl Fn(&Problem) -> Route) -> Route {
+    let mut indices = vec![problem.start];
+    indices.extend(
+        problem
+            .inner_destinations()
+            .into_iter()
+            .filter(|d| route.contains(d)),
+    );
+    let open = problem.end != problem.start;
+    if open {
+        indices.push(problem.end);
+    }
+    let mut subproblem = Problem::new(
+        indices.iter().map(|&i| problem.points[i]).collect(),
+        problem.metric,
+    );
+    if open {
+        subproblem.end = indices.len() - 1;
+    }
+    Route::new(
+        solve(&subproblem)
+            .stops()
+            .iter()
+            .map(|&local| indices[local])
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{christofides::christofides, rng::SplitMix64, Metric, Point};
+
+    use super::*;
+
+    #[test]
+    fn test_solve_exhaustive() {
+        // A depot at the origin, two destinations near it and one far away
+        let problem = Problem::new(
+            [(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (50.0, 0.0)]
+                .map(|(x, y)| Point::new(x, y))
+                .to_vec(),
+            Metric::Euclidean,
+        );
+        let route = solve_exhaustive(&problem, &[0.0, 10.0, 10.0, 10.0]);
+        assert!(route.len() == 4 && !route.contains(&3));
+        assert_eq!(
+            total_cost(&problem, &route, &[0.0, 10.0, 10.0, 10.0]),
+            problem.route_distance(&route) + 10.0
+        );
+
+        // Penalties larger than any detour visit everyone, and none visit no one
+        let route = solve_exhaustive(&problem, &[0.0, 1000.0, 1000.0, 1000.0]);
+        assert!(
+            (problem.route_distance(&route) - problem.route_distance(&problem.solve())).abs()
+                < 1e-9
+        );
+        assert_eq!(solve_exhaustive(&problem, &[0.0; 4]).stops(), &[0, 0]);
+
+        // An open route goes straight to its end when that is cheapest
+        let mut open = problem.clone();
+        open.end = 3;
+        assert_eq!(solve_exhaustive(&open, &[0.0; 4]).stops(), &[0, 3]);
+    }
+
+    #[test]
+    fn test_solve_with_penalties() {
+        let mut rng = SplitMix64::new(11);
+        for trial in 0..10 {
+            let mut problem = Problem::new(
+                (0..12)
+                    .map(|_| Point::new(rng.below(100) as f64, rng.below(100) as f64))
+                    .collect(),
+                Metric::Euclidean,
+            );
+            if trial % 2 == 1 {
+                problem.end = 11;
+            }
+            let penalties = (0..12).map(|_| rng.below(60) as f64).collect::<Vec<_>>();
+
+            let exact = solve_exhaustive(&problem, &penalties);
+            let route = solve_with_penalties(&problem, &penalties, christofides);
+            assert!(route.first() == Some(&problem.start) && route.last() == Some(&problem.end));
+            let cost = total_cost(&problem, &route, &penalties);
+            assert!(cost >= total_cost(&problem, &exact, &penalties) - 1e-9);
+            // Never worse than visiting everyone or no one
+            assert!(cost <= total_cost(&problem, &christofides(&problem), &penalties) + 1e-9);
+            assert!(cost <= total_cost(&problem, &[problem.start, problem.end], &penalties) + 1e-9);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "one penalty per destination")]
+    fn test_missing_penalties() {
+        let problem = Problem::new(vec![Point::new(0.0, 0.0); 3], Metric::Euclidean);
+        solve_exhaustive(&problem, &[1.0]);
+    }
+}
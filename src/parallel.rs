@@ -0,0 +1,185 @@
+//! Exhaustive search on all cores, one branch for each destination visited first, with
+//! the branches sharing the shortest distance found so far to prune each other.
+//!
+//! Which of several equally short routes a shared bound lets through depends on which
+//! thread finds one first, so by default the result can differ from run to run when
+//! routes tie. [`SolverConfig::deterministic`] trades a little pruning for the route
+//! [`Problem::solve`] returns.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use rayon::prelude::*;
+
+use crate::{background::SolverConfig, Problem, Route};
+
+/// Pruning against a bound tolerates differences in rounding this small, relatively, as
+/// partial routes are summed in a different order than complete ones.
+const TOLERANCE: f64 = 1e-9;
+
+/// Finds the shortest route from the start through every destination to the end, by
+/// exhaustive search split across rayon's thread pool.
+///
+/// # Arguments
+///
+/// * `problem` - The problem to solve, whose distances must not be negative
+/// * `config` - Whether the result must be deterministic; its budget and seed are
+///   ignored, as the search always completes
+///
+/// # Returns
+///
+/// A shortest route, and with [`SolverConfig::deterministic`] the one [`Problem::solve`]
+/// returns, the first in the order of its permutations
+pub fn solve_parallel(problem: &Problem, config: &SolverConfig) -> Route {
+    let inner = problem.inner_destinations();
+    if inner.is_empty() {
+        return problem.solve();
+    }
+    let distance = |a, b| problem.distance(a, b);
+    // The bound as the bits of a non-negative f64, whose order as integers is the same
+    let bound = AtomicU64::new(f64::INFINITY.to_bits());
+    let deterministic = config.deterministic;
+
+    let branches = (0..inner.len())
+        .into_par_iter()
+        .filter_map(|first| {
+            let mut rest = inner.clone();
+            let first = rest.remove(first);
+            let mut branch = Branch {
+                problem,
+                distance: &distance,
+                bound: &bound,
+                deterministic,
+                stops: vec![problem.start, first],
+                best: None,
+            };
+            branch.extend(&rest, problem.distance(problem.start, first));
+            branch.best
+        })
+        .collect::<Vec<_>>();
+
+    // Branches come back in the order of their first destinations, so the first of the
+    // shortest is the one serial search finds first, if the bound let it through
+    let (_, stops) = branches
+        .into_iter()
+        .reduce(|best, candidate| {
+            if candidate.0 < best.0 {
+                candidate
+            } else {
+                best
+            }
+        })
+        .expect("the branch that set the bound has a route");
+    Route::new(stops)
+}
+
+/// The depth-first search below one first destination.
+struct Branch<'a, F> {
+    problem: &'a Problem,
+    distance: &'a F,
+    bound: &'a AtomicU64,
+    deterministic: bool,
+    stops: Vec<usize>,
+    /// The shortest complete route of the branch, the first found of any tie.
+    best: Option<(f64, Vec<usize>)>,
+}
+
+impl<F: Fn(usize, usize) -> f64> Branch<'_, F> {
+    /// Tries every order of `rest` after the stops so far, `partial` long.
+    fn extend(&mut self, rest: &[usize], partial: f64) {
+        let bound = f64::from_bits(self.bound.load(Ordering::Relaxed));
+        // Deterministically, only routes longer than the bound are pruned, so every
+        // route as short as the best survives for the reduction to pick among
+        let pruned = if self.deterministic {
+            partial > bound * (1.0 + TOLERANCE)
+        } else {
+            partial >= bound
+        };
+        if pruned {
+            invariant!(
+                partial
+                    <= partial
+                        + crate::bound::shortest_path_through(
+                            self.stops[self.stops.len() - 1],
+                            rest,
+                            self.problem.end,
+                            self.distance,
+                        )
+                        + TOLERANCE * partial.abs().max(1.0),
+                "a pruned partial route has a completion shorter than it, is a distance negative?"
+            );
+            return;
+        }
+        if rest.is_empty() {
+            self.stops.push(self.problem.end);
+            // Summed as `traveling_salesman` sums them, so ties tie exactly
+            let stops = &self.stops;
+            let inner = &stops[1..stops.len() - 1];
+            let distance = inner
+                .windows(2)
+                .map(|leg| (self.distance)(leg[0], leg[1]))
+                .fold(0.0, |sum, leg| sum + leg)
+                + (self.distance)(stops[0], inner[0])
+                + (self.distance)(inner[inner.len() - 1], stops[stops.len() - 1]);
+            if self.best.as_ref().is_none_or(|best| distance < best.0) {
+                self.best = Some((distance, self.stops.clone()));
+                self.bound.fetch_min(distance.to_bits(), Ordering::Relaxed);
+            }
+            self.stops.pop();
+            return;
+        }
+        let last = self.stops[self.stops.len() - 1];
+        for index in 0..rest.len() {
+            let next = rest[index];
+            let remaining = [&rest[..index], &rest[index + 1..]].concat();
+            self.stops.push(next);
+            self.extend(&remaining, partial + (self.distance)(last, next));
+            self.stops.pop();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Metric, Point};
+
+    use super::*;
+
+    #[test]
+    fn test_solve_parallel() {
+        let problem = Problem::new(
+            (0..8)
+                .map(|i| Point::new(f64::from(i * 37 % 11), f64::from(i * 5 % 7)))
+                .collect(),
+            Metric::Euclidean,
+        );
+        let config = SolverConfig::default();
+        let route = solve_parallel(&problem, &config);
+        assert!(problem.is_route(&route));
+        assert!(
+            (problem.route_distance(&route) - problem.route_distance(&problem.solve())).abs()
+                < 1e-9
+        );
+    }
+
+    #[test]
+    fn test_deterministic() {
+        // A grid has many equally short routes, which a shared bound picks among racily
+        let problem = Problem::new(
+            (0..9)
+                .map(|i| Point::new(f64::from(i % 3), f64::from(i / 3)))
+                .collect(),
+            Metric::Manhattan,
+        );
+        let config = SolverConfig::default().with_deterministic(true);
+        let serial = problem.solve();
+        for _ in 0..5 {
+            assert_eq!(solve_parallel(&problem, &config), serial);
+        }
+
+        let mut open = problem.clone();
+        open.end = 8;
+        assert_eq!(solve_parallel(&open, &config), open.solve());
+        let single = Problem::new(vec![Point::new(0.0, 0.0)], Metric::Euclidean);
+        assert_eq!(solve_parallel(&single, &config).stops(), [0, 0]);
+    }
+}
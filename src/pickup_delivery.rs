@@ -0,0 +1,217 @@
+//! Pickup-and-delivery pairs, where a route must visit each pickup before its
+//! delivery, as a courier collects a parcel before dropping it off.
+
+use itertools::Itertools;
+
+use crate::{
+    violations::{self, Constraint, ViolationReport},
+    Problem, Route,
+};
+
+/// A destination that must be visited before another.
+///
+/// The start of a round trip counts as visited first when it is a pickup and last when
+/// it is a delivery, so goods can be loaded at the depot or brought back to it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Pair {
+    pub pickup: usize,
+    pub delivery: usize,
+}
+
+impl Pair {
+    pub fn new(pickup: usize, delivery: usize) -> Self {
+        Pair { pickup, delivery }
+    }
+
+    /// The positions of the pickup and the delivery on `stops`, if it visits both.
+    fn positions(&self, stops: &[usize]) -> Option<(usize, usize)> {
+        let pickup = stops.iter().position(|&stop| stop == self.pickup)?;
+        let delivery = stops.iter().rposition(|&stop| stop == self.delivery)?;
+        Some((pickup, delivery))
+    }
+}
+
+/// Whether `stops` visits every pickup before its delivery.
+pub fn is_feasible(stops: &[usize], pairs: &[Pair]) -> bool {
+    pairs.iter().all(|pair| {
+        pair.positions(stops)
+            .is_some_and(|(pickup, delivery)| pickup < delivery)
+    })
+}
+
+/// Which pairs `stops` visits in order, and how many positions too early the delivery
+/// of each it does not is, in the order of `pairs`.
+pub fn report(stops: &[usize], pairs: &[Pair]) -> ViolationReport {
+    let mut report = ViolationReport::new();
+    for &pair in pairs {
+        report.record(
+            Constraint::Precedence(pair),
+            violations::precedence_violation(stops, pair),
+        );
+    }
+    report
+}
+
+/// Finds the shortest route of `problem` that visits every pickup before its delivery,
+/// by exhaustive search over only the routes that do.
+///
+/// # Returns
+///
+/// The shortest feasible route, or `None` if no route is feasible, such as when the
+/// pairs require a cycle, or a delivery at an open route's start
+pub fn solve_exhaustive(problem: &Problem, pairs: &[Pair]) -> Option<Route> {
+    let inner = problem.inner_destinations();
+    let count = inner.len();
+    inner
+        .into_iter()
+        .permutations(count)
+        .map(|permutation| [vec![problem.start], permutation, vec![problem.end]].concat())
+        .filter(|stops| is_feasible(stops, pairs))
+        .map(|stops| (problem.route_distance(&stops), stops))
+        .min_by(|a, b| a.0.total_cmp(&b.0))
+        .map(|(_, stops)| Route::new(stops))
+}
+
+/// Makes `route` feasible by moving the delivery of each pair visited too early to
+/// where it adds the least distance after its pickup, repeating until every pair is
+/// satisfied. The first and last stops stay in place.
+///
+/// # Arguments
+///
+/// * `route` - The route to repair, usually from a solver that ignores the pairs
+/// * `pairs` - The pickups and deliveries
+/// * `distance` - Computes the distance between two destination indices
+///
+/// # Returns
+///
+/// The repaired route, or `None` if it cannot be made feasible
+pub fn repair(
+    route: &Route,
+    pairs: &[Pair],
+    distance: impl Fn(usize, usize) -> f64,
+) -> Option<Route> {
+    let mut stops = route.stops().to_vec();
+    let last = stops.len().checked_sub(1)?;
+    // Moving a delivery later can put a pair it is the pickup of out of order, but only
+    // endlessly if the pairs form a cycle
+    for _ in 0..=stops.len() * pairs.len() {
+        let Some((pickup, delivery)) = pairs
+            .iter()
+            .filter_map(|pair| pair.positions(&stops))
+            .find(|&(pickup, delivery)| pickup >= delivery)
+        else {
+            return is_feasible(&stops, pairs).then(|| Route::new(stops));
+        };
+        if delivery == 0 || delivery == last {
+            // Nothing can come before the start, and the end stays where it is
+            return None;
+        }
+        // Insert the delivery between positions `after - 1` and `after`, anywhere from
+        // just after the pickup, which moves back one, to just before the last stop
+        let moved = stops.remove(delivery);
+        let added = |after: usize| {
+            distance(stops[after - 1], moved) + distance(moved, stops[after])
+                - distance(stops[after - 1], stops[after])
+        };
+        let after = (pickup..last).min_by(|&a, &b| added(a).total_cmp(&added(b)))?;
+        stops.insert(after, moved);
+    }
+    None
+}
+
+/// Finds a short feasible route by solving `problem` with `solve`, which ignores the
+/// pairs, then [`repair`]ing the result.
+pub fn solve_with_repair(
+    problem: &Problem,
+    pairs: &[Pair],
+    solve: impl Fn(&Problem) -> Route,
+) -> Option<Route> {
+    repair(&solve(problem), pairs, |a, b| problem.distance(a, b))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{christofides::christofides, rng::SplitMix64, Metric, Point};
+
+    use super::*;
+
+    #[test]
+    fn test_is_feasible() {
+        let pairs = [Pair::new(3, 1), Pair::new(0, 2)];
+        assert!(is_feasible(&[0, 3, 1, 2, 0], &pairs));
+        assert!(!is_feasible(&[0, 1, 3, 2, 0], &pairs));
+        // Deliveries to the depot of a round trip come at the end
+        assert!(is_feasible(&[0, 2, 1, 0], &[Pair::new(1, 0)]));
+        assert!(!is_feasible(&[0, 2, 1], &[Pair::new(1, 0)]));
+        assert!(!is_feasible(&[0, 2, 1], &[Pair::new(1, 5)]));
+
+        let report = report(&[0, 1, 3, 2, 0], &pairs);
+        assert_eq!(report.violated().count(), 1);
+        assert_eq!(report.checks[0].violation, 2.0);
+    }
+
+    #[test]
+    fn test_solve_exhaustive() {
+        // On a line the shortest round trip goes out and back either way, and the pair
+        // chooses which
+        let problem = Problem::new(
+            [0.0, 1.0, 2.0, 3.0].map(|x| Point::new(x, 0.0)).to_vec(),
+            Metric::Euclidean,
+        );
+        let route = solve_exhaustive(&problem, &[Pair::new(3, 1)]).unwrap();
+        assert_eq!(route.stops(), &[0, 2, 3, 1, 0]);
+        let route = solve_exhaustive(&problem, &[Pair::new(3, 1), Pair::new(2, 3)]).unwrap();
+        assert_eq!(route.stops(), &[0, 2, 3, 1, 0]);
+        assert_eq!(
+            solve_exhaustive(&problem, &[Pair::new(1, 2), Pair::new(2, 1)]),
+            None
+        );
+    }
+
+    #[test]
+    fn test_repair() {
+        let mut rng = SplitMix64::new(5);
+        for trial in 0..20 {
+            let mut problem = Problem::new(
+                (0..8)
+                    .map(|_| Point::new(rng.below(100) as f64, rng.below(100) as f64))
+                    .collect(),
+                Metric::Euclidean,
+            );
+            if trial % 2 == 1 {
+                problem.end = 7;
+            }
+            let pairs = [
+                Pair::new(1, 2),
+                Pair::new(3, 4),
+                Pair::new(5, 6),
+                Pair::new(4, 6),
+            ];
+
+            let exact = solve_exhaustive(&problem, &pairs).unwrap();
+            let repaired = solve_with_repair(&problem, &pairs, christofides).unwrap();
+            assert!(problem.is_route(&repaired));
+            assert!(is_feasible(&repaired, &pairs));
+            assert!(problem.route_distance(&repaired) >= problem.route_distance(&exact) - 1e-9);
+            assert!(
+                problem.route_distance(&exact) >= problem.route_distance(&problem.solve()) - 1e-9
+            );
+        }
+
+        // A delivery at the start of an open route cannot be fixed
+        let distance = |a: usize, b: usize| a.abs_diff(b) as f64;
+        let route = Route::new(vec![0, 1, 2]);
+        assert_eq!(repair(&route, &[Pair::new(1, 0)], distance), None);
+        assert_eq!(
+            repair(&route, &[Pair::new(1, 2), Pair::new(2, 1)], distance),
+            None
+        );
+        // Nor a pickup at the end of one
+        let route = Route::new(vec![0, 3, 1, 2, 4]);
+        let repaired = repair(&route, &[Pair::new(4, 3), Pair::new(2, 1)], distance);
+        assert_eq!(repaired, None);
+        // A delivery visited too early is moved after its pickup
+        let repaired = repair(&route, &[Pair::new(2, 1)], distance).unwrap();
+        assert_eq!(repaired.stops(), &[0, 3, 2, 1, 4]);
+    }
+}
@@ -0,0 +1,394 @@
+//! Destinations pinned to fixed positions in the route, such as a lunch stop that must
+//! be the fourth, for exact search and for local search heuristics.
+//!
+//! Positions count the stops of the route from its start, at position 0, so position 4
+//! is the fourth stop after setting out.
+
+use std::time::Instant;
+
+use itertools::Itertools as _;
+
+use crate::{
+    background::SolverConfig,
+    christofides::christofides,
+    delta, ils,
+    local_search::Neighborhood,
+    rng::SplitMix64,
+    violations::{Constraint, ViolationReport},
+    DistanceMatrix, Problem, Route,
+};
+
+/// Changes must shorten a route by more than this, as in
+/// [`local_search`](crate::local_search).
+const EPSILON: f64 = 1e-10;
+
+/// Destinations that must be at given positions of the route.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Pins {
+    /// `(destination, position)` pairs, by position.
+    pins: Vec<(usize, usize)>,
+}
+
+impl Pins {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Pins `destination` to `position`.
+    pub fn with_pin(mut self, destination: usize, position: usize) -> Self {
+        let at = self.pins.partition_point(|&(_, other)| other < position);
+        self.pins.insert(at, (destination, position));
+        self
+    }
+
+    /// The position `destination` is pinned to, if any.
+    pub fn position_of(&self, destination: usize) -> Option<usize> {
+        self.pins
+            .iter()
+            .find(|&&(pinned, _)| pinned == destination)
+            .map(|&(_, position)| position)
+    }
+
+    /// Whether a destination is pinned to `position`.
+    pub fn is_pinned(&self, position: usize) -> bool {
+        self.pins
+            .binary_search_by_key(&position, |&(_, position)| position)
+            .is_ok()
+    }
+
+    /// Whether every pinned destination is at its position in `route`.
+    pub fn is_satisfied(&self, route: &[usize]) -> bool {
+        self.pins
+            .iter()
+            .all(|&(destination, position)| route.get(position) == Some(&destination))
+    }
+
+    /// Which pins `route` satisfies, and how many positions away from its pin each
+    /// destination it misplaces is, by position.
+    pub fn report(&self, route: &[usize]) -> ViolationReport {
+        let mut report = ViolationReport::new();
+        for &(destination, position) in &self.pins {
+            let violation = route
+                .iter()
+                .position(|&stop| stop == destination)
+                .map_or(f64::INFINITY, |at| at.abs_diff(position) as f64);
+            report.record(
+                Constraint::Pin {
+                    destination,
+                    position,
+                },
+                violation,
+            );
+        }
+        report
+    }
+
+    /// Checks that the pins can all hold in a route of `problem`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a pinned destination is the start or end or not in the problem, if
+    /// a position is not between the start and end, or if two pins share a destination
+    /// or a position.
+    fn check(&self, problem: &Problem) {
+        let inner = problem.inner_destinations();
+        for (index, &(destination, position)) in self.pins.iter().enumerate() {
+            assert!(
+                inner.contains(&destination),
+                "destination {destination} cannot be pinned, as it is not between the start and end"
+            );
+            assert!(
+                (1..=inner.len()).contains(&position),
+                "position {position} is not between the start and end"
+            );
+            assert!(
+                self.pins[index + 1..]
+                    .iter()
+                    .all(|&(other, at)| other != destination && at != position),
+                "destination {destination} or position {position} is pinned twice"
+            );
+        }
+    }
+}
+
+/// Finds the shortest route from the start through every destination to the end with
+/// each pinned destination at its position, by exhaustive search over the routes that
+/// respect the pins.
+///
+/// # Panics
+///
+/// Panics if the pins cannot all hold, as for [`iterated_local_search_pinned`].
+pub fn solve_exhaustive(problem: &Problem, pins: &Pins) -> Route {
+    pins.check(problem);
+    let matrix = problem.distance_matrix();
+    let free = problem
+        .inner_destinations()
+        .into_iter()
+        .filter(|&destination| pins.position_of(destination).is_none())
+        .collect::<Vec<_>>();
+    let count = free.len();
+    let stops = free
+        .into_iter()
+        .permutations(count)
+        .map(|permutation| fill(problem, pins, permutation))
+        .min_by(|a, b| {
+            matrix
+                .route_distance(a)
+                .total_cmp(&matrix.route_distance(b))
+        })
+        .expect("there is at least one permutation");
+    Route::new(stops)
+}
+
+/// Local search moves that leave pinned positions alone: reversing segments between
+/// pins and exchanging destinations at any two unpinned positions.
+#[derive(Debug, Clone)]
+pub struct PinnedMoves {
+    pins: Pins,
+}
+
+impl PinnedMoves {
+    pub fn new(pins: Pins) -> Self {
+        PinnedMoves { pins }
+    }
+}
+
+impl Neighborhood for PinnedMoves {
+    fn improve(&self, route: &mut Route, matrix: &DistanceMatrix) -> bool {
+        let stops = route.stops_mut();
+        let len = stops.len();
+        let distance = |a, b| matrix.get(a, b);
+        let mut changed = false;
+        let mut improved = true;
+        while improved {
+            improved = false;
+            for i in 1..len.saturating_sub(1) {
+                if self.pins.is_pinned(i) {
+                    continue;
+                }
+                for j in i + 1..len - 1 {
+                    if self.pins.is_pinned(j) {
+                        continue;
+                    }
+                    if delta::swap(stops, i, j, distance) < -EPSILON {
+                        stops.swap(i, j);
+                        improved = true;
+                    }
+                }
+                // Reverse i..=j for as long as the segment stays clear of pins
+                for j in (i + 1..len - 1).take_while(|&j| !self.pins.is_pinned(j)) {
+                    if delta::two_opt(stops, i, j, distance) < -EPSILON {
+                        stops[i..=j].reverse();
+                        improved = true;
+                    }
+                }
+            }
+            changed |= improved;
+        }
+        changed
+    }
+}
+
+/// Finds a short route with each pinned destination at its position by iterated local
+/// search: [`PinnedMoves`] until no move shortens the route, then a double-bridge kick
+/// of the unpinned destinations among their positions, keeping whichever route is
+/// shorter, for `config`'s budget as in
+/// [`iterated_local_search`](crate::ils::iterated_local_search).
+///
+/// # Arguments
+///
+/// * `problem` - The problem to solve
+/// * `config` - The budget, random seed and initial route, which must respect the pins
+/// * `pins` - The positions of the pinned destinations
+///
+/// # Panics
+///
+/// Panics if a pinned destination is the start or end or not in the problem, if a
+/// position is not between the start and end, if two pins share a destination or a
+/// position, or if the initial route is not a route of `problem` respecting the pins.
+pub fn iterated_local_search_pinned(
+    problem: &Problem,
+    config: &SolverConfig,
+    pins: &Pins,
+) -> Route {
+    pins.check(problem);
+    let matrix = problem.distance_matrix();
+    let moves = PinnedMoves::new(pins.clone());
+    // Visit the unpinned destinations in the order of a good unconstrained route
+    let mut best = config.starting_route(problem, |problem| {
+        let order = christofides(problem)
+            .into_stops()
+            .into_iter()
+            .filter(|&stop| {
+                stop != problem.start && stop != problem.end && pins.position_of(stop).is_none()
+            })
+            .collect();
+        Route::new(fill(problem, pins, order))
+    });
+    assert!(
+        pins.is_satisfied(&best),
+        "the initial route must respect the pins"
+    );
+    moves.improve(&mut best, &matrix);
+    let mut best_distance = matrix.route_distance(&best);
+
+    let free_positions = (1..best.len() - 1)
+        .filter(|&position| !pins.is_pinned(position))
+        .collect::<Vec<_>>();
+    let iterations = match (config.iterations, config.time_limit) {
+        (None, None) => ils::DEFAULT_ITERATIONS,
+        (iterations, _) => iterations.unwrap_or(u64::MAX),
+    };
+    let deadline = config.time_limit.map(|limit| Instant::now() + limit);
+    let mut rng = SplitMix64::new(config.seed);
+    for _ in 0..iterations {
+        if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            break;
+        }
+        // Kick the unpinned destinations as if they made a route of their own
+        let free = [0]
+            .into_iter()
+            .chain(free_positions.iter().map(|&position| best[position]))
+            .chain([0])
+            .collect::<Vec<_>>();
+        let Some(kicked) = ils::double_bridge(&free, &mut rng) else {
+            break;
+        };
+        let mut candidate = best.clone();
+        for (&position, &destination) in free_positions.iter().zip(&kicked[1..]) {
+            candidate.stops_mut()[position] = destination;
+        }
+        moves.improve(&mut candidate, &matrix);
+        let distance = matrix.route_distance(&candidate);
+        if distance < best_distance {
+            best = candidate;
+            best_distance = distance;
+        }
+    }
+    best
+}
+
+/// The route with the pinned destinations at their positions and the `free` ones in
+/// order in the positions left between the start and end.
+fn fill(problem: &Problem, pins: &Pins, free: Vec<usize>) -> Vec<usize> {
+    let inner = problem.inner_destinations().len();
+    let mut free = free.into_iter();
+    let mut stops = Vec::with_capacity(inner + 2);
+    stops.push(problem.start);
+    for position in 1..=inner {
+        let pinned = pins
+            .pins
+            .iter()
+            .find(|&&(_, at)| at == position)
+            .map(|&(destination, _)| destination);
+        stops.extend(pinned.or_else(|| free.next()));
+    }
+    stops.push(problem.end);
+    stops
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        generators::{self, Bounds},
+        Metric,
+    };
+
+    use super::*;
+
+    fn problem(len: usize, seed: u64) -> Problem {
+        Problem::new(
+            generators::uniform_points(len, Bounds::square(100.0), seed),
+            Metric::Euclidean,
+        )
+    }
+
+    #[test]
+    fn test_pins() {
+        let pins = Pins::new().with_pin(3, 4).with_pin(5, 1);
+        assert_eq!(pins.position_of(3), Some(4));
+        assert_eq!(pins.position_of(4), None);
+        assert!(pins.is_pinned(1) && !pins.is_pinned(3));
+        assert!(pins.is_satisfied(&[0, 5, 1, 2, 3, 4, 0]));
+        assert!(!pins.is_satisfied(&[0, 3, 1, 2, 5, 4, 0]));
+
+        let report = pins.report(&[0, 3, 1, 2, 5, 4, 0]);
+        assert_eq!(
+            report
+                .checks
+                .iter()
+                .map(|check| check.violation)
+                .collect::<Vec<_>>(),
+            [3.0, 3.0]
+        );
+        assert!(pins.report(&[0, 5, 1, 2, 3, 4, 0]).is_feasible());
+    }
+
+    #[test]
+    fn test_solve_exhaustive() {
+        let problem = problem(8, 1);
+        let optimal = problem.solve();
+        assert_eq!(
+            problem.route_distance(&solve_exhaustive(&problem, &Pins::new())),
+            problem.route_distance(&optimal)
+        );
+
+        // Pin a destination somewhere other than where the optimal route has it
+        let destination = optimal[2];
+        let pins = Pins::new().with_pin(destination, 5);
+        let route = solve_exhaustive(&problem, &pins);
+        assert!(problem.is_route(&route) && pins.is_satisfied(&route));
+        assert!(problem.route_distance(&route) > problem.route_distance(&optimal));
+
+        // Which is the best of the routes that respect the pin
+        let best = problem
+            .inner_destinations()
+            .into_iter()
+            .permutations(7)
+            .map(|permutation| [vec![0], permutation, vec![0]].concat())
+            .filter(|stops| pins.is_satisfied(stops))
+            .map(|stops| problem.route_distance(&stops))
+            .fold(f64::INFINITY, f64::min);
+        assert!((problem.route_distance(&route) - best).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_iterated_local_search_pinned() {
+        let problem = problem(9, 2);
+        let pins = Pins::new().with_pin(4, 1).with_pin(7, 4).with_pin(2, 8);
+        let exact = solve_exhaustive(&problem, &pins);
+        let config = SolverConfig::default().with_iterations(200);
+        let route = iterated_local_search_pinned(&problem, &config, &pins);
+        assert!(problem.is_route(&route) && pins.is_satisfied(&route));
+        assert!((problem.route_distance(&route) - problem.route_distance(&exact)).abs() < 1e-9);
+
+        // Larger problems keep their pins too
+        let problem = self::problem(60, 3);
+        let pins = Pins::new()
+            .with_pin(10, 4)
+            .with_pin(20, 30)
+            .with_pin(30, 59);
+        let route = iterated_local_search_pinned(&problem, &config, &pins);
+        assert!(problem.is_route(&route) && pins.is_satisfied(&route));
+        assert!(
+            problem.route_distance(&route)
+                < problem.route_distance(&fill(
+                    &problem,
+                    &pins,
+                    (1..60).filter(|&d| d % 10 != 0).collect()
+                ))
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "pinned twice")]
+    fn test_pinned_twice() {
+        solve_exhaustive(&problem(5, 0), &Pins::new().with_pin(1, 2).with_pin(3, 2));
+    }
+
+    #[test]
+    #[should_panic(expected = "not between the start and end")]
+    fn test_pinned_outside() {
+        solve_exhaustive(&problem(5, 0), &Pins::new().with_pin(1, 5));
+    }
+}
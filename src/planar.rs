@@ -0,0 +1,82 @@
+//! Solving planar problems straight from their coordinates, computing each distance as
+//! it is needed, so instances of hundreds of thousands of destinations can be solved
+//! without the n² distance matrix the other improvement heuristics build.
+
+use crate::{
+    background::SolverConfig,
+    hilbert::hilbert_order,
+    local_search::{CandidateLists, CandidateTwoOpt},
+    Problem, Route,
+};
+
+/// The number of nearest destinations each destination may gain a leg to.
+pub const CANDIDATES: usize = 8;
+
+/// Finds a route by ordering the destinations along a Hilbert curve and improving the
+/// order with 2-opt restricted to legs between each destination and its [`CANDIDATES`]
+/// nearest, computing distances from the coordinates with the problem's metric. Takes
+/// O(n) memory and about O(n log n) time beyond the 2-opt reversals.
+///
+/// Nearest destinations are found in the plane, which suits [`Metric::Euclidean`] and
+/// [`Metric::RoundedEuclidean`] exactly and the other metrics approximately.
+///
+/// [`Metric::Euclidean`]: crate::Metric::Euclidean
+/// [`Metric::RoundedEuclidean`]: crate::Metric::RoundedEuclidean
+///
+/// # Arguments
+///
+/// * `problem` - The problem to solve
+///
+/// # Returns
+///
+/// A route from `start` through every destination to `end`
+pub fn planar_two_opt(problem: &Problem) -> Route {
+    planar_two_opt_with(problem, &SolverConfig::default())
+}
+
+/// Improves `config`'s initial route, or the Hilbert order without one, by the 2-opt of
+/// [`planar_two_opt`].
+///
+/// # Panics
+///
+/// Panics if `config`'s initial route is not a route of `problem`.
+pub fn planar_two_opt_with(problem: &Problem, config: &SolverConfig) -> Route {
+    let mut route = config.starting_route(problem, hilbert_order);
+    let two_opt = CandidateTwoOpt {
+        candidates: CandidateLists::from_points(&problem.points, CANDIDATES),
+    };
+    two_opt.improve_with(&mut route, problem.points.len(), |a, b| {
+        problem.distance(a, b)
+    });
+    route
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        generators::{self, Bounds},
+        Metric,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_planar_two_opt() {
+        let mut problem = Problem::new(
+            generators::uniform_points(2_000, Bounds::square(10_000.0), 5),
+            Metric::RoundedEuclidean,
+        );
+        problem.end = 7;
+        let route = planar_two_opt(&problem);
+        assert!(problem.is_route(&route));
+        assert_eq!((route[0], route[route.len() - 1]), (0, 7));
+
+        let distance = problem.route_distance(&route);
+        assert_eq!(distance.fract(), 0.0);
+        assert!(distance < 0.9 * problem.route_distance(&hilbert_order(&problem)));
+
+        // Warm started from its own route, 2-opt finds nothing left to improve
+        let config = SolverConfig::default().with_initial_route(route.clone());
+        assert_eq!(planar_two_opt_with(&problem, &config), route);
+    }
+}
@@ -0,0 +1,155 @@
+//! An elite archive of the best distinct routes found across solver runs, for
+//! reporting the k best routes and seeding searches from a diverse set of good ones.
+
+use std::collections::HashSet;
+
+use crate::Route;
+
+/// The `capacity` shortest distinct routes offered to it, shortest first.
+///
+/// Routes are told apart by their [canonical](Route::canonical) form, so a round trip
+/// and its reverse count as one route. Of routes with equal distances, the one offered
+/// first ranks first.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SolutionPool {
+    capacity: usize,
+    /// Canonical routes and their distances, shortest first.
+    entries: Vec<(Route, f64)>,
+    /// The canonical routes in `entries`, for rejecting duplicates in O(1).
+    seen: HashSet<Route>,
+}
+
+impl SolutionPool {
+    /// An empty pool keeping at most `capacity` routes.
+    pub fn new(capacity: usize) -> Self {
+        SolutionPool {
+            capacity,
+            entries: Vec::with_capacity(capacity),
+            seen: HashSet::with_capacity(capacity),
+        }
+    }
+
+    /// Adds `route` if it is not in the pool already and is shorter than the longest
+    /// route kept, which is then dropped if the pool is full. Takes O(k + n) time for a
+    /// pool of k routes of n stops.
+    ///
+    /// # Arguments
+    ///
+    /// * `route` - A route found by a solver
+    /// * `distance` - The route's total distance
+    ///
+    /// # Returns
+    ///
+    /// Whether the route was added
+    pub fn offer(&mut self, route: &Route, distance: f64) -> bool {
+        if self.entries.len() == self.capacity
+            && self
+                .entries
+                .last()
+                .is_none_or(|&(_, longest)| distance >= longest)
+        {
+            return false;
+        }
+        let canonical = route.canonical();
+        if self.seen.contains(&canonical) {
+            return false;
+        }
+        let position = self
+            .entries
+            .partition_point(|&(_, other)| other.total_cmp(&distance).is_le());
+        self.seen.insert(canonical.clone());
+        self.entries.insert(position, (canonical, distance));
+        if self.entries.len() > self.capacity {
+            let (dropped, _) = self.entries.pop().expect("the pool is over capacity");
+            self.seen.remove(&dropped);
+        }
+        true
+    }
+
+    /// Offers every route of another pool, such as one kept by another run.
+    pub fn merge(&mut self, other: &SolutionPool) {
+        for (route, distance) in other.iter() {
+            self.offer(route, distance);
+        }
+    }
+
+    /// The shortest route in the pool and its distance.
+    pub fn best(&self) -> Option<(&Route, f64)> {
+        self.iter().next()
+    }
+
+    /// The routes in canonical form with their distances, shortest first.
+    pub fn iter(&self) -> impl Iterator<Item = (&Route, f64)> + '_ {
+        self.entries
+            .iter()
+            .map(|(route, distance)| (route, *distance))
+    }
+
+    /// The routes in canonical form, shortest first.
+    pub fn routes(&self) -> impl Iterator<Item = &Route> + '_ {
+        self.entries.iter().map(|(route, _)| route)
+    }
+
+    /// Whether the pool holds `route` or its reverse.
+    pub fn contains(&self, route: &Route) -> bool {
+        self.seen.contains(&route.canonical())
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_solution_pool() {
+        let mut pool = SolutionPool::new(2);
+        assert_eq!(pool.best(), None);
+        assert!(pool.offer(&Route::new(vec![0, 1, 2, 3, 0]), 12.0));
+        assert!(pool.offer(&Route::new(vec![0, 2, 1, 3, 0]), 10.0));
+        // The reverse of a route already kept
+        assert!(!pool.offer(&Route::new(vec![0, 3, 2, 1, 0]), 12.0));
+        assert!(pool.contains(&Route::new(vec![0, 3, 1, 2, 0])));
+        // No shorter than the longest kept
+        assert!(!pool.offer(&Route::new(vec![0, 1, 3, 2, 0]), 12.0));
+        assert!(pool.offer(&Route::new(vec![0, 1, 3, 2, 0]), 11.0));
+
+        assert_eq!(pool.len(), 2);
+        assert_eq!(pool.best(), Some((&Route::new(vec![0, 2, 1, 3, 0]), 10.0)));
+        assert_eq!(
+            pool.iter()
+                .map(|(_, distance)| distance)
+                .collect::<Vec<_>>(),
+            [10.0, 11.0]
+        );
+        // The route dropped to make room can be offered again
+        assert!(!pool.contains(&Route::new(vec![0, 1, 2, 3, 0])));
+
+        let mut other = SolutionPool::new(3);
+        other.offer(&Route::new(vec![0, 1, 2, 3, 0]), 9.0);
+        other.offer(&Route::new(vec![0, 3, 1, 2, 0]), 10.0);
+        pool.merge(&other);
+        assert_eq!(
+            pool.routes().cloned().collect::<Vec<_>>(),
+            [
+                Route::new(vec![0, 1, 2, 3, 0]),
+                Route::new(vec![0, 2, 1, 3, 0])
+            ]
+        );
+
+        let mut empty = SolutionPool::new(0);
+        assert!(!empty.offer(&Route::new(vec![0, 0]), 0.0));
+        assert!(empty.is_empty());
+    }
+}
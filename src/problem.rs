@@ -0,0 +1,338 @@
+//! A self-contained description of a routing problem: where the destinations are,
+//! how to measure between them, and where the route starts and ends.
+
+use std::{
+    fmt,
+    io::{self, BufRead, Write},
+};
+
+use crate::{
+    metric::{Metric, Point},
+    traveling_salesman, Route,
+};
+
+/// An error produced while loading a [`Problem`] from CSV.
+#[derive(Debug)]
+pub enum CsvError {
+    /// The underlying reader failed.
+    Io(io::Error),
+    /// A row did not have exactly three columns.
+    InvalidRow { line: usize, content: String },
+    /// A coordinate column was not a number.
+    InvalidNumber { line: usize, value: String },
+    /// The input contained no destinations.
+    Empty,
+}
+
+impl fmt::Display for CsvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CsvError::Io(err) => write!(f, "i/o error: {err}"),
+            CsvError::InvalidRow { line, content } => {
+                write!(f, "line {line}: expected `id,x,y` but found `{content}`")
+            }
+            CsvError::InvalidNumber { line, value } => {
+                write!(f, "line {line}: `{value}` is not a number")
+            }
+            CsvError::Empty => write!(f, "no destinations found"),
+        }
+    }
+}
+
+impl std::error::Error for CsvError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            CsvError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for CsvError {
+    fn from(err: io::Error) -> Self {
+        CsvError::Io(err)
+    }
+}
+
+/// A set of destinations with coordinates, the metric between them, and the
+/// indices of the start and end of the route.
+///
+/// `start` and `end` may be the same index, in which case the route is a round trip.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Problem {
+    /// A label for each destination, parallel to `points`.
+    pub ids: Vec<String>,
+    pub points: Vec<Point>,
+    /// The time spent at each destination, parallel to `points`, such as for a
+    /// delivery. It adds to the time a route takes but not to its distance.
+    pub service_times: Vec<f64>,
+    pub metric: Metric,
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Problem {
+    /// Creates a round trip starting and ending at the first point, labelling the
+    /// destinations by their index, with no service time at any.
+    pub fn new(points: Vec<Point>, metric: Metric) -> Self {
+        Problem {
+            ids: (0..points.len()).map(|i| i.to_string()).collect(),
+            service_times: vec![0.0; points.len()],
+            points,
+            metric,
+            start: 0,
+            end: 0,
+        }
+    }
+
+    /// Loads destinations from simple `id,x,y` (or `id,lat,lon`) CSV rows.
+    ///
+    /// A first row whose coordinates are not numbers is treated as a header and
+    /// skipped, as are blank lines. Fields may be wrapped in double quotes. The
+    /// resulting problem is a round trip starting at the first destination.
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` - The CSV input
+    /// * `metric` - How distances between the rows are measured
+    pub fn from_csv(reader: impl BufRead, metric: Metric) -> Result<Self, CsvError> {
+        let mut ids = Vec::new();
+        let mut points = Vec::new();
+
+        for (index, line) in reader.lines().enumerate() {
+            if let Some((id, point)) = parse_csv_row(index + 1, &line?, ids.is_empty())? {
+                ids.push(id.to_string());
+                points.push(point);
+            }
+        }
+
+        if points.is_empty() {
+            return Err(CsvError::Empty);
+        }
+
+        Ok(Problem {
+            ids,
+            service_times: vec![0.0; points.len()],
+            points,
+            metric,
+            start: 0,
+            end: 0,
+        })
+    }
+
+    /// Writes the destinations as `id,x,y` rows (`id,lat,lon` for haversine problems)
+    /// that [`Problem::from_csv`] reads back.
+    pub fn write_csv(&self, mut writer: impl Write) -> io::Result<()> {
+        match self.metric {
+            Metric::Haversine => writeln!(writer, "id,lat,lon")?,
+            _ => writeln!(writer, "id,x,y")?,
+        }
+        for (id, point) in self.ids.iter().zip(&self.points) {
+            writeln!(writer, "{id},{},{}", point.x, point.y)?;
+        }
+        Ok(())
+    }
+
+    /// The number of destinations, including the start and end.
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    /// The distance between the destinations at indices `a` and `b`.
+    pub fn distance(&self, a: usize, b: usize) -> f64 {
+        self.metric.distance(&self.points[a], &self.points[b])
+    }
+
+    /// The total distance of a route given as destination indices.
+    pub fn route_distance(&self, route: &[usize]) -> f64 {
+        route
+            .windows(2)
+            .map(|leg| self.distance(leg[0], leg[1]))
+            .sum()
+    }
+
+    /// The total service time at the destinations of a route given as destination
+    /// indices, counting the start of a round trip once.
+    pub fn service_time(&self, route: &[usize]) -> f64 {
+        let stops = match route {
+            [first, rest @ .., last] if first == last => &route[..rest.len() + 1],
+            _ => route,
+        };
+        stops.iter().map(|&i| self.service_times[i]).sum()
+    }
+
+    /// The time a route given as destination indices takes, traveling at `speed`
+    /// distance units per unit of time and stopping for the service time at each
+    /// destination.
+    pub fn route_duration(&self, route: &[usize], speed: f64) -> f64 {
+        self.route_distance(route) / speed + self.service_time(route)
+    }
+
+    /// Whether `route` goes from `start` through every other destination exactly once
+    /// to `end`.
+    pub fn is_route(&self, route: &[usize]) -> bool {
+        let [first, inner @ .., last] = route else {
+            return false;
+        };
+        let mut inner = inner.to_vec();
+        inner.sort_unstable();
+        *first == self.start && *last == self.end && inner == self.inner_destinations()
+    }
+
+    /// The indices of every destination other than the start and end.
+    pub fn inner_destinations(&self) -> Vec<usize> {
+        (0..self.len())
+            .filter(|&i| i != self.start && i != self.end)
+            .collect()
+    }
+
+    /// Finds the shortest route from `start` through every destination to `end`
+    /// by exhaustive search.
+    pub fn solve(&self) -> Route {
+        let route = Route::new(traveling_salesman(
+            self.inner_destinations().into_iter(),
+            self.start,
+            self.end,
+            |(&a, &b)| self.distance(a, b),
+        ));
+        // Visiting in index order is a valid route, so the optimum is no longer
+        invariant!(
+            self.route_distance(&route)
+                <= self.route_distance(
+                    &[vec![self.start], self.inner_destinations(), vec![self.end]].concat()
+                ) * (1.0 + 1e-9),
+            "the optimal route is longer than visiting in index order"
+        );
+        route
+    }
+}
+
+/// The id and point of CSV row number `line`, or `None` if it is blank, or if it is a
+/// header, whose coordinates are not numbers, and `header` allows one.
+pub(crate) fn parse_csv_row(
+    line: usize,
+    content: &str,
+    header: bool,
+) -> Result<Option<(&str, Point)>, CsvError> {
+    if content.trim().is_empty() {
+        return Ok(None);
+    }
+
+    let fields = content
+        .split(',')
+        .map(|field| field.trim().trim_matches('"'))
+        .collect::<Vec<_>>();
+    let [id, x, y] = fields[..] else {
+        return Err(CsvError::InvalidRow {
+            line,
+            content: content.to_string(),
+        });
+    };
+
+    let parse = |value: &str| {
+        value.parse::<f64>().map_err(|_| CsvError::InvalidNumber {
+            line,
+            value: value.to_string(),
+        })
+    };
+    match (parse(x), parse(y)) {
+        (Ok(x), Ok(y)) => Ok(Some((id, Point::new(x, y)))),
+        (Err(_), Err(_)) if header => Ok(None),
+        (Err(err), _) | (_, Err(err)) => Err(err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_route_duration() {
+        let mut problem = Problem::new(
+            [0.0, 3.0, 6.0].map(|x| Point::new(x, 0.0)).to_vec(),
+            Metric::Euclidean,
+        );
+        assert_eq!(problem.route_duration(&[0, 1, 2, 0], 2.0), 6.0);
+        problem.service_times = vec![5.0, 1.0, 2.0];
+        // The start of a round trip is served once
+        assert_eq!(problem.service_time(&[0, 1, 2, 0]), 8.0);
+        assert_eq!(problem.route_duration(&[0, 1, 2, 0], 2.0), 14.0);
+        problem.end = 2;
+        assert_eq!(problem.route_duration(&[0, 1, 2], 1.0), 14.0);
+        assert_eq!(problem.service_time(&[0]), 5.0);
+        assert_eq!(problem.service_time(&[]), 0.0);
+        // Service time does not change the shortest route
+        assert_eq!(problem.route_distance(&problem.solve()), 6.0);
+    }
+
+    #[test]
+    fn test_is_route() {
+        let mut problem = Problem::new(vec![Point::new(0.0, 0.0); 4], Metric::Euclidean);
+        assert!(problem.is_route(&[0, 2, 1, 3, 0]));
+        assert!(!problem.is_route(&[0, 2, 1, 0]));
+        assert!(!problem.is_route(&[0, 2, 2, 3, 0]));
+        assert!(!problem.is_route(&[1, 2, 0, 3, 1]));
+        assert!(!problem.is_route(&[]));
+        problem.end = 3;
+        assert!(problem.is_route(&[0, 2, 1, 3]));
+        assert!(!problem.is_route(&[0, 2, 1, 3, 0]));
+    }
+
+    #[test]
+    fn test_from_csv() {
+        let csv = "id,x,y\ndepot,0,0\n\"b\", 2, 0\nc,1,0\n\nd,3,0\n";
+
+        let mut problem = Problem::from_csv(csv.as_bytes(), Metric::Euclidean).unwrap();
+        assert_eq!(problem.ids, vec!["depot", "b", "c", "d"]);
+        assert_eq!(problem.points[1], Point::new(2.0, 0.0));
+
+        problem.end = 3;
+        let route = problem.solve();
+        assert_eq!(route.stops(), [0, 2, 1, 3]);
+        assert_eq!(problem.route_distance(&route), 3.0);
+    }
+
+    #[test]
+    fn test_csv_round_trip() {
+        let problem = Problem::new(
+            vec![Point::new(0.5, -1.0), Point::new(2.0, 3.25)],
+            Metric::Manhattan,
+        );
+
+        let mut buffer = Vec::new();
+        problem.write_csv(&mut buffer).unwrap();
+        assert_eq!(buffer, b"id,x,y\n0,0.5,-1\n1,2,3.25\n");
+
+        let read = Problem::from_csv(buffer.as_slice(), Metric::Manhattan).unwrap();
+        assert_eq!(read, problem);
+    }
+
+    #[test]
+    fn test_from_csv_lat_lon() {
+        let csv = "id,lat,lon\na,51.5007,-0.1246\nb,48.8584,2.2945\n";
+
+        let problem = Problem::from_csv(csv.as_bytes(), Metric::Haversine).unwrap();
+        let km = problem.distance(0, 1) / 1000.0;
+        assert!((km - 340.0).abs() < 5.0, "{km}");
+    }
+
+    #[test]
+    fn test_from_csv_errors() {
+        assert!(matches!(
+            Problem::from_csv("a,1\n".as_bytes(), Metric::Euclidean),
+            Err(CsvError::InvalidRow { line: 1, .. })
+        ));
+        assert!(matches!(
+            Problem::from_csv("a,1,2\nb,x,2\n".as_bytes(), Metric::Euclidean),
+            Err(CsvError::InvalidNumber { line: 2, .. })
+        ));
+        assert!(matches!(
+            Problem::from_csv("id,x,y\n".as_bytes(), Metric::Euclidean),
+            Err(CsvError::Empty)
+        ));
+    }
+}
@@ -0,0 +1,326 @@
+//! Every solver in the crate that works on a [`Problem`], with what it can handle,
+//! so the benches, the differential tests and the command line can find and compare
+//! them all without keeping their own lists.
+//!
+//! To add a solver, add an entry to the `register!` invocation below.
+
+use std::{ops::BitOr, ops::ControlFlow, ptr};
+
+use crate::{
+    background::SolverConfig, beam, branch_bound, christofides, cluster, difficulty,
+    hand_rolled_traveling_salesman, hand_rolled_traveling_salesman_unchecked, hilbert, ils, planar,
+    search::Improvement, tempering, variants, Metric, Problem, Route,
+};
+
+/// Whether a solver always finds the optimal route.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Category {
+    Exact,
+    Heuristic,
+}
+
+/// The kinds of problem a solver can handle, as a set of flags.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Capabilities(u32);
+
+impl Capabilities {
+    pub const NONE: Capabilities = Capabilities(0);
+    /// Routes may end somewhere other than where they start.
+    pub const OPEN_PATHS: Capabilities = Capabilities(1 << 0);
+    /// Destinations may be anywhere. Without this, they must be at integer
+    /// coordinates along the x axis, with a planar metric.
+    pub const ANY_POINTS: Capabilities = Capabilities(1 << 1);
+
+    pub const fn contains(self, other: Capabilities) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl BitOr for Capabilities {
+    type Output = Capabilities;
+
+    fn bitor(self, other: Capabilities) -> Capabilities {
+        Capabilities(self.0 | other.0)
+    }
+}
+
+/// A solver in the registry.
+#[derive(Debug, Clone, Copy)]
+pub struct Registered {
+    /// A unique name in kebab-case, as used on the command line.
+    pub name: &'static str,
+    pub description: &'static str,
+    pub category: Category,
+    pub capabilities: Capabilities,
+    pub solve: fn(&Problem) -> Route,
+    /// For solvers with a budget or random choices, solves within a
+    /// [`SolverConfig`]'s limits and from its seed.
+    pub solve_configured: Option<fn(&Problem, &SolverConfig) -> Route>,
+}
+
+impl Registered {
+    /// Whether the solver can handle `problem`.
+    pub fn supports(&self, problem: &Problem) -> bool {
+        (problem.start == problem.end || self.capabilities.contains(Capabilities::OPEN_PATHS))
+            && (self.capabilities.contains(Capabilities::ANY_POINTS) || on_integer_line(problem))
+    }
+
+    /// Solves `problem` with `config`'s budget, seed and initial route if the solver
+    /// takes them, or ignoring `config` if it does not.
+    pub fn solve_with(&self, problem: &Problem, config: &SolverConfig) -> Route {
+        match self.solve_configured {
+            Some(solve) => solve(problem, config),
+            None => (self.solve)(problem),
+        }
+    }
+}
+
+/// Looks up a solver by name.
+pub fn find(name: &str) -> Option<&'static Registered> {
+    IMPLEMENTATIONS
+        .iter()
+        .find(|registered| registered.name == name)
+}
+
+/// `Some` of its argument, or `None` without one.
+macro_rules! optional {
+    () => {
+        None
+    };
+    ($value:expr) => {
+        Some($value)
+    };
+}
+
+/// Builds [`IMPLEMENTATIONS`] from entries of the form
+/// `name: Category [CAPABILITY | ...] => solve, "description";`, with
+/// `=> solve; with solve_configured,` for solvers that take a [`SolverConfig`].
+macro_rules! register {
+    ($($name:literal: $category:ident [$($capability:ident)|*] => $solve:expr $(; with $configured:expr)?, $description:literal;)*) => {
+        /// Every registered solver.
+        pub static IMPLEMENTATIONS: &[Registered] = &[$(
+            Registered {
+                name: $name,
+                description: $description,
+                category: Category::$category,
+                capabilities: Capabilities(0 $(| Capabilities::$capability.0)*),
+                solve: $solve,
+                solve_configured: optional!($($configured)?),
+            },
+        )*];
+    };
+}
+
+register! {
+    "exhaustive": Exact [OPEN_PATHS | ANY_POINTS] => Problem::solve,
+        "Try every permutation; exact but only practical for about a dozen stops";
+    "matrix": Exact [OPEN_PATHS | ANY_POINTS] => solve_matrix,
+        "Try every permutation over distances computed up front";
+    "search": Exact [OPEN_PATHS | ANY_POINTS] => solve_search,
+        "Try every permutation, keeping track of the best route as it goes";
+    "hand-rolled": Exact [OPEN_PATHS] => solve_hand_rolled::<false>,
+        "The hand-optimized exhaustive search over integers on a line";
+    "hand-rolled-unchecked": Exact [OPEN_PATHS] => solve_hand_rolled::<true>,
+        "The hand-optimized search without bounds checks";
+    "recursive": Exact [OPEN_PATHS | ANY_POINTS] => solve_recursive,
+        "Exhaustive search written as recursive backtracking";
+    "imperative": Exact [OPEN_PATHS | ANY_POINTS] => solve_imperative,
+        "Exhaustive search written with loops over permuted indices";
+    "fold": Exact [OPEN_PATHS | ANY_POINTS] => solve_fold,
+        "Exhaustive search written as one iterator chain ending in a fold";
+    "held-karp": Exact [OPEN_PATHS | ANY_POINTS] => solve_held_karp,
+        "Dynamic programming over subsets of destinations, memoized with cached_fix";
+    "branch-and-bound": Exact [OPEN_PATHS | ANY_POINTS] => solve_branch_and_bound,
+        "Depth-first search from a Christofides route, pruning by the cheapest legs left";
+    "christofides": Heuristic [OPEN_PATHS | ANY_POINTS] => christofides::christofides,
+        "Spanning tree plus a matching of its odd-degree destinations, within 3/2 of optimal";
+    "ils": Heuristic [OPEN_PATHS | ANY_POINTS] => solve_ils; with ils::iterated_local_search,
+        "Iterated local search: 2-opt, kicked out of local optima by double-bridge moves";
+    "beam": Heuristic [OPEN_PATHS | ANY_POINTS] => solve_beam,
+        "Extend partial routes one destination at a time, keeping the 16 shortest";
+    "hilbert": Heuristic [OPEN_PATHS | ANY_POINTS] => hilbert::hilbert_order,
+        "Visit destinations in the order a Hilbert curve passes them, for huge instances";
+    "planar": Heuristic [OPEN_PATHS | ANY_POINTS] => planar::planar_two_opt; with planar::planar_two_opt_with,
+        "Hilbert order improved by 2-opt between near neighbors, with no distance matrix";
+    "cluster": Heuristic [OPEN_PATHS | ANY_POINTS] => solve_cluster,
+        "Iterated local search within clusters of about 50 destinations, stitched together";
+    "tempering": Heuristic [OPEN_PATHS | ANY_POINTS] => solve_tempering; with tempering_configured,
+        "Simulated annealing on 4 chains at different temperatures, exchanging routes";
+    "auto": Heuristic [OPEN_PATHS | ANY_POINTS] => difficulty::solve_auto,
+        "Whichever solver is estimated to suit the problem: exact when practical, else a heuristic";
+}
+
+fn solve_tempering(problem: &Problem) -> Route {
+    tempering_configured(problem, &SolverConfig::default())
+}
+
+fn tempering_configured(problem: &Problem, config: &SolverConfig) -> Route {
+    tempering::parallel_tempering(problem, config, tempering::DEFAULT_CHAINS)
+}
+
+fn solve_branch_and_bound(problem: &Problem) -> Route {
+    branch_bound::branch_and_bound(problem, 0.0).route
+}
+
+/// Not warm started: each cluster is solved separately, and a whole-problem route or
+/// iteration budget has no counterpart in its subproblems.
+fn solve_cluster(problem: &Problem) -> Route {
+    cluster::cluster_first(problem, problem.len().div_ceil(50), solve_ils)
+}
+
+fn solve_beam(problem: &Problem) -> Route {
+    beam::beam_search(problem, beam::DEFAULT_WIDTH)
+}
+
+fn solve_ils(problem: &Problem) -> Route {
+    ils::iterated_local_search(problem, &SolverConfig::default())
+}
+
+fn solve_recursive(problem: &Problem) -> Route {
+    Route::new(variants::traveling_salesman_recursive(
+        problem.inner_destinations().into_iter(),
+        problem.start,
+        problem.end,
+        |(&a, &b)| problem.distance(a, b),
+    ))
+}
+
+fn solve_imperative(problem: &Problem) -> Route {
+    Route::new(variants::traveling_salesman_imperative(
+        problem.inner_destinations().into_iter(),
+        problem.start,
+        problem.end,
+        |(&a, &b)| problem.distance(a, b),
+    ))
+}
+
+fn solve_fold(problem: &Problem) -> Route {
+    Route::new(variants::traveling_salesman_fold(
+        problem.inner_destinations().into_iter(),
+        problem.start,
+        problem.end,
+        |(&a, &b)| problem.distance(a, b),
+    ))
+}
+
+fn solve_held_karp(problem: &Problem) -> Route {
+    Route::new(variants::traveling_salesman_held_karp(
+        problem.inner_destinations().into_iter(),
+        problem.start,
+        problem.end,
+        |(&a, &b)| problem.distance(a, b),
+    ))
+}
+
+fn solve_matrix(problem: &Problem) -> Route {
+    problem.distance_matrix().solve(problem.start, problem.end)
+}
+
+fn solve_search(problem: &Problem) -> Route {
+    problem
+        .search(&mut |_: &Improvement| ControlFlow::Continue(()))
+        .route
+}
+
+/// Solves a problem on the integer line with [`hand_rolled_traveling_salesman`], or
+/// [`hand_rolled_traveling_salesman_unchecked`] when `UNCHECKED`.
+fn solve_hand_rolled<const UNCHECKED: bool>(problem: &Problem) -> Route {
+    let coordinate = |index: usize| problem.points[index].x as i32;
+    let inner = problem.inner_destinations();
+    let destinations = inner
+        .iter()
+        .map(|&index| coordinate(index))
+        .collect::<Vec<_>>();
+    let (start, end) = (coordinate(problem.start), coordinate(problem.end));
+
+    let route = if UNCHECKED {
+        hand_rolled_traveling_salesman_unchecked(&destinations, &start, &end)
+    } else {
+        hand_rolled_traveling_salesman(&destinations, &start, &end)
+    };
+    // The route borrows from `destinations`, which tells apart destinations at the same coordinate
+    let inner_stops = route[1..route.len() - 1].iter().map(|&stop| {
+        let position = destinations
+            .iter()
+            .position(|destination| ptr::eq(destination, stop))
+            .expect("the route only borrows from the destinations");
+        inner[position]
+    });
+
+    let mut stops = vec![problem.start];
+    stops.extend(inner_stops);
+    stops.push(problem.end);
+    Route::new(stops)
+}
+
+/// Whether every destination is at an integer coordinate on the x axis, where
+/// planar distances are the integer differences.
+fn on_integer_line(problem: &Problem) -> bool {
+    problem.metric != Metric::Haversine
+        && problem.points.iter().all(|point| {
+            point.y == 0.0
+                && point.x.fract() == 0.0
+                && (f64::from(i32::MIN)..=f64::from(i32::MAX)).contains(&point.x)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        compare::{compare_implementations, implementations, Instance},
+        Point,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_names_are_unique() {
+        for (index, registered) in IMPLEMENTATIONS.iter().enumerate() {
+            assert!(find(registered.name).is_some_and(|found| ptr::eq(found, registered)));
+            assert!(IMPLEMENTATIONS[index + 1..]
+                .iter()
+                .all(|other| other.name != registered.name));
+        }
+        assert!(find("nonexistent").is_none());
+    }
+
+    #[test]
+    fn test_supports() {
+        let hand_rolled = find("hand-rolled").unwrap();
+        let mut line = Problem::new(
+            vec![
+                Point::new(3.0, 0.0),
+                Point::new(-2.0, 0.0),
+                Point::new(7.0, 0.0),
+            ],
+            Metric::Manhattan,
+        );
+        assert!(hand_rolled.supports(&line));
+        line.points[1].y = 1.0;
+        assert!(!hand_rolled.supports(&line));
+        assert!(find("exhaustive").unwrap().supports(&line));
+    }
+
+    #[test]
+    fn test_registered_solvers_agree() {
+        // Destinations on the integer line that every solver supports, with a repeat
+        let points = [0.0, 5.0, -3.0, 5.0, 2.0, 9.0]
+            .map(|x| Point::new(x, 0.0))
+            .to_vec();
+        let round_trip = Problem::new(points.clone(), Metric::Euclidean);
+        let mut open = Problem::new(points, Metric::Manhattan);
+        open.start = 2;
+        open.end = 5;
+
+        let instances = [
+            Instance::new("round trip", round_trip),
+            Instance::new("open", open),
+        ];
+        let comparison = compare_implementations(&instances, &implementations());
+        assert!(comparison.is_consistent(), "{:?}", comparison.mismatches);
+        assert_eq!(
+            comparison.results.len(),
+            instances.len() * IMPLEMENTATIONS.len()
+        );
+    }
+}
@@ -0,0 +1,206 @@
+//! Path relinking: walking from one good route toward another, one swap at a time, in
+//! search of a shorter route between them. Elite routes tend to share most of their
+//! legs, so the routes on the path mix the strengths of both.
+
+use crate::{delta, local_search::Neighborhood, pool::SolutionPool, DistanceMatrix, Route};
+
+/// Walks from `from` to `guide` by swaps, each putting one more stop where `guide` has
+/// it, choosing at each step the swap that leaves the route shortest. Takes O(n²) time
+/// for routes of n stops.
+///
+/// `guide` is followed in whichever direction shares more positions with `from`, as a
+/// round trip and its reverse are the same route under a symmetric metric.
+///
+/// # Arguments
+///
+/// * `from` - The route to start from
+/// * `guide` - The route to walk toward
+/// * `matrix` - The distances between destinations, which should be symmetric
+///
+/// # Returns
+///
+/// The shortest route strictly between the two and its distance, or `None` if they are
+/// fewer than two swaps apart so there is no route between them
+///
+/// # Panics
+///
+/// Panics if the routes do not visit the same destinations between the same start and
+/// end.
+pub fn path_relink(from: &Route, guide: &Route, matrix: &DistanceMatrix) -> Option<(Route, f64)> {
+    let len = from.len();
+    assert!(
+        len == guide.len() && from.first() == guide.first() && from.last() == guide.last(),
+        "routes must visit the same destinations between the same start and end"
+    );
+    if len < 2 {
+        return None;
+    }
+    let inner = 1..len - 1;
+    let mut target = guide.stops().to_vec();
+    let mut reversed = target.clone();
+    if from.first() == from.last() {
+        reversed[inner.clone()].reverse();
+    }
+    let shared = |target: &[usize]| inner.clone().filter(|&i| from[i] == target[i]).count();
+    if shared(&reversed) > shared(&target) {
+        target = reversed;
+    }
+
+    let mut stops = from.stops().to_vec();
+    let mut position = vec![usize::MAX; matrix.len()];
+    for i in inner.clone() {
+        position[stops[i]] = i;
+    }
+    // Each stop of the guide must be matched by a distinct stop of `from`
+    let mut unmatched = position.clone();
+    assert!(
+        inner
+            .clone()
+            .all(|i| std::mem::replace(&mut unmatched[target[i]], usize::MAX) != usize::MAX),
+        "routes must visit the same destinations between the same start and end"
+    );
+    let mut differing = inner.filter(|&i| stops[i] != target[i]).collect::<Vec<_>>();
+    let distance = |a, b| matrix.get(a, b);
+    let mut current = matrix.route_distance(&stops);
+    let mut best: Option<(Vec<usize>, f64)> = None;
+    while !differing.is_empty() {
+        let (index, change) = differing
+            .iter()
+            .enumerate()
+            .map(|(index, &i)| (index, delta::swap(&stops, i, position[target[i]], distance)))
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .expect("some position still differs");
+        let i = differing[index];
+        let j = position[target[i]];
+        stops.swap(i, j);
+        position[stops[i]] = i;
+        position[stops[j]] = j;
+        current += change;
+        differing.retain(|&k| stops[k] != target[k]);
+
+        let between = !differing.is_empty();
+        if between
+            && best
+                .as_ref()
+                .is_none_or(|&(_, shortest)| current < shortest)
+        {
+            best = Some((stops.clone(), current));
+        }
+    }
+    best.map(|(stops, distance)| (Route::new(stops), distance))
+}
+
+/// Relinks every pair of routes in `pool` in both directions, improves the shortest
+/// route found between each pair with `neighborhood`, and offers it to the pool.
+///
+/// # Arguments
+///
+/// * `pool` - Elite routes of one problem, such as from several solver runs
+/// * `matrix` - The distances between destinations, which should be symmetric
+/// * `neighborhood` - The local search applied to each relinked route
+///
+/// # Returns
+///
+/// The number of routes added to the pool
+pub fn relink_pool(
+    pool: &mut SolutionPool,
+    matrix: &DistanceMatrix,
+    neighborhood: &dyn Neighborhood,
+) -> usize {
+    let elite = pool.routes().cloned().collect::<Vec<_>>();
+    let mut added = 0;
+    for from in &elite {
+        for guide in &elite {
+            if from == guide {
+                continue;
+            }
+            if let Some((mut route, _)) = path_relink(from, guide, matrix) {
+                neighborhood.improve(&mut route, matrix);
+                let distance = matrix.route_distance(&route);
+                added += usize::from(pool.offer(&route, distance));
+            }
+        }
+    }
+    added
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{local_search::TwoOpt, Metric, Point, Problem};
+
+    use super::*;
+
+    /// Eight destinations on a circle, visited in index order by the shortest route.
+    fn circle() -> DistanceMatrix {
+        let points = (0..8)
+            .map(|i| {
+                let angle = i as f64 * std::f64::consts::TAU / 8.0;
+                Point::new(angle.cos(), angle.sin())
+            })
+            .collect();
+        Problem::new(points, Metric::Euclidean).distance_matrix()
+    }
+
+    #[test]
+    fn test_path_relink() {
+        let matrix = circle();
+        let optimal = Route::new(vec![0, 1, 2, 3, 4, 5, 6, 7, 0]);
+        let from = Route::new(vec![0, 2, 1, 3, 4, 5, 6, 7, 0]);
+        let guide = Route::new(vec![0, 1, 2, 3, 4, 6, 5, 7, 0]);
+
+        let (route, distance) = path_relink(&from, &guide, &matrix).unwrap();
+        assert_eq!(route, optimal);
+        assert!((distance - matrix.route_distance(&optimal)).abs() < 1e-9);
+        assert!(distance < matrix.route_distance(&from));
+        assert!(distance < matrix.route_distance(&guide));
+
+        // Followed in the direction sharing more positions
+        let mut backward = guide.stops().to_vec();
+        backward[1..8].reverse();
+        let (route, _) = path_relink(&from, &Route::new(backward), &matrix).unwrap();
+        assert_eq!(route, optimal);
+
+        // One swap apart, so nothing lies between
+        assert_eq!(path_relink(&from, &optimal, &matrix), None);
+        assert_eq!(path_relink(&from, &from, &matrix), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "same destinations")]
+    fn test_path_relink_different_destinations() {
+        path_relink(
+            &Route::new(vec![0, 1, 2, 0]),
+            &Route::new(vec![0, 1, 3, 0]),
+            &circle(),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "same destinations")]
+    fn test_path_relink_repeated_destination() {
+        path_relink(
+            &Route::new(vec![0, 1, 2, 0]),
+            &Route::new(vec![0, 1, 1, 0]),
+            &circle(),
+        );
+    }
+
+    #[test]
+    fn test_relink_pool() {
+        let matrix = circle();
+        let mut pool = SolutionPool::new(4);
+        for stops in [
+            vec![0, 2, 1, 3, 4, 5, 6, 7, 0],
+            vec![0, 1, 2, 3, 4, 6, 5, 7, 0],
+        ] {
+            let distance = matrix.route_distance(&stops);
+            pool.offer(&Route::new(stops), distance);
+        }
+        assert_eq!(relink_pool(&mut pool, &matrix, &TwoOpt), 1);
+        assert_eq!(
+            pool.best().unwrap().0,
+            &Route::new(vec![0, 1, 2, 3, 4, 5, 6, 7, 0])
+        );
+        assert_eq!(pool.len(), 3);
+    }
+}
@@ -0,0 +1,374 @@
+//! Recording solver results for comparison experiments and writing them out for
+//! analysis in tools like pandas or Polars.
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    fmt::Write as _,
+    io::{self, Write},
+    sync::atomic::{AtomicBool, AtomicU64, Ordering},
+    time::Duration,
+};
+
+use crate::geo::escape_xml;
+
+/// The outcome of running one solver on one instance.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SolverResult {
+    pub solver: String,
+    pub instance: String,
+    /// Total distance of the route the solver returned.
+    pub distance: f64,
+    /// Wall-clock time the solver took.
+    pub time: Duration,
+    /// Relative excess over the best known distance for the instance, e.g. `0.05`
+    /// for 5% longer. `None` when no reference distance is known.
+    pub gap: Option<f64>,
+    /// Heap allocations the solver made, when counted with [`CountingAllocator`].
+    pub allocations: Option<u64>,
+}
+
+impl SolverResult {
+    pub fn new(
+        solver: impl Into<String>,
+        instance: impl Into<String>,
+        distance: f64,
+        time: Duration,
+    ) -> Self {
+        SolverResult {
+            solver: solver.into(),
+            instance: instance.into(),
+            distance,
+            time,
+            gap: None,
+            allocations: None,
+        }
+    }
+
+    /// Sets the gap relative to a known optimal (or best known) distance.
+    pub fn with_optimal(mut self, optimal: f64) -> Self {
+        self.gap = Some(gap(self.distance, optimal));
+        self
+    }
+
+    pub fn with_allocations(mut self, allocations: u64) -> Self {
+        self.allocations = Some(allocations);
+        self
+    }
+}
+
+fn gap(distance: f64, optimal: f64) -> f64 {
+    if optimal == 0.0 {
+        if distance == 0.0 {
+            0.0
+        } else {
+            f64::INFINITY
+        }
+    } else {
+        (distance - optimal) / optimal
+    }
+}
+
+/// Fills in the gap of every result that has none, relative to the shortest
+/// distance any solver found for the same instance.
+pub fn fill_gaps(results: &mut [SolverResult]) {
+    let best = |instance: &str, results: &[SolverResult]| {
+        results
+            .iter()
+            .filter(|result| result.instance == instance)
+            .map(|result| result.distance)
+            .fold(f64::INFINITY, f64::min)
+    };
+
+    for index in 0..results.len() {
+        if results[index].gap.is_none() {
+            let best = best(&results[index].instance, results);
+            results[index].gap = Some(gap(results[index].distance, best));
+        }
+    }
+}
+
+/// Writes results as CSV with the columns `solver,instance,distance,time_seconds,gap`.
+/// A missing gap is left empty.
+pub fn write_csv(mut writer: impl Write, results: &[SolverResult]) -> io::Result<()> {
+    writeln!(writer, "solver,instance,distance,time_seconds,gap")?;
+    for result in results {
+        writeln!(
+            writer,
+            "{},{},{},{},{}",
+            csv_field(&result.solver),
+            csv_field(&result.instance),
+            result.distance,
+            result.time.as_secs_f64(),
+            result.gap.map(|gap| gap.to_string()).unwrap_or_default()
+        )?;
+    }
+    Ok(())
+}
+
+/// Quotes a field if it contains a character that would otherwise break the row.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Formats results as a Markdown table, one row per result, for pasting into pull
+/// requests and write-ups.
+///
+/// Missing gaps and allocation counts are shown as `-`.
+pub fn generate(results: &[SolverResult]) -> String {
+    let mut markdown = format!("| {} |\n", HEADINGS.join(" | "));
+    markdown.push_str("| --- | --- | ---: | ---: | ---: | ---: |\n");
+    for result in results {
+        let cells = cells(result).map(|cell| cell.replace('|', "\\|"));
+        // Writing to a String cannot fail
+        let _ = writeln!(markdown, "| {} |", cells.join(" | "));
+    }
+    markdown
+}
+
+/// Formats results as an HTML table with the same columns as [`generate`].
+pub fn generate_html(results: &[SolverResult]) -> String {
+    let mut html = String::from("<table>\n  <thead>\n    <tr>");
+    for heading in HEADINGS {
+        let _ = write!(html, "<th>{heading}</th>");
+    }
+    html.push_str("</tr>\n  </thead>\n  <tbody>\n");
+    for result in results {
+        html.push_str("    <tr>");
+        for cell in cells(result) {
+            let _ = write!(html, "<td>{}</td>", escape_xml(&cell));
+        }
+        html.push_str("</tr>\n");
+    }
+    html.push_str("  </tbody>\n</table>\n");
+    html
+}
+
+const HEADINGS: [&str; 6] = [
+    "Solver",
+    "Instance",
+    "Distance",
+    "Time",
+    "Gap",
+    "Allocations",
+];
+
+fn cells(result: &SolverResult) -> [String; 6] {
+    [
+        result.solver.clone(),
+        result.instance.clone(),
+        format!("{:.2}", result.distance),
+        format!("{:.2?}", result.time),
+        result
+            .gap
+            .map(|gap| format!("{:.2}%", gap * 100.0))
+            .unwrap_or_else(|| "-".to_string()),
+        result
+            .allocations
+            .map(|allocations| allocations.to_string())
+            .unwrap_or_else(|| "-".to_string()),
+    ]
+}
+
+static ALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+static COUNTING: AtomicBool = AtomicBool::new(false);
+
+/// A global allocator that counts allocations, so the comparison harness can report
+/// how many each solver makes. Install it in the benchmark or test binary:
+///
+/// ```ignore
+/// #[global_allocator]
+/// static ALLOCATOR: CountingAllocator = CountingAllocator;
+/// ```
+///
+/// The count covers every thread, so run solvers one at a time while counting.
+pub struct CountingAllocator;
+
+impl CountingAllocator {
+    /// The number of allocations made so far, or `None` if this is not the global
+    /// allocator.
+    pub fn allocations() -> Option<u64> {
+        COUNTING
+            .load(Ordering::Relaxed)
+            .then(|| ALLOCATIONS.load(Ordering::Relaxed))
+    }
+
+    fn count() {
+        COUNTING.store(true, Ordering::Relaxed);
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+// SAFETY: Every method forwards to the system allocator with the caller's arguments,
+// so the system allocator's guarantees carry over.
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        CountingAllocator::count();
+        // SAFETY: The caller upholds `alloc`'s contract, which is the same for System
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        CountingAllocator::count();
+        // SAFETY: As for `alloc`
+        unsafe { System.alloc_zeroed(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        // SAFETY: `ptr` was allocated by System through this allocator with `layout`
+        unsafe { System.dealloc(ptr, layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        CountingAllocator::count();
+        // SAFETY: As for `dealloc`, and the caller upholds `realloc`'s contract on `new_size`
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+}
+
+/// Writes results as a Parquet file with the same columns as [`write_csv`]; `gap`
+/// is an optional column.
+#[cfg(feature = "parquet")]
+pub fn write_parquet<W>(writer: W, results: &[SolverResult]) -> parquet::errors::Result<()>
+where
+    W: Write + Send,
+{
+    use std::sync::Arc;
+
+    use parquet::{
+        data_type::{ByteArray, ByteArrayType, DoubleType},
+        file::writer::SerializedFileWriter,
+        schema::parser::parse_message_type,
+    };
+
+    let schema = parse_message_type(
+        "message solver_results {
+            REQUIRED BYTE_ARRAY solver (UTF8);
+            REQUIRED BYTE_ARRAY instance (UTF8);
+            REQUIRED DOUBLE distance;
+            REQUIRED DOUBLE time_seconds;
+            OPTIONAL DOUBLE gap;
+        }",
+    )?;
+    let mut writer = SerializedFileWriter::new(writer, Arc::new(schema), Default::default())?;
+    let mut row_group = writer.next_row_group()?;
+
+    let strings = |field: fn(&SolverResult) -> &str| {
+        results
+            .iter()
+            .map(|result| ByteArray::from(field(result)))
+            .collect::<Vec<_>>()
+    };
+    for column in [strings(|r| &r.solver), strings(|r| &r.instance)] {
+        let mut column_writer = row_group.next_column()?.expect("schema has five columns");
+        column_writer
+            .typed::<ByteArrayType>()
+            .write_batch(&column, None, None)?;
+        column_writer.close()?;
+    }
+
+    let distances = results.iter().map(|r| r.distance).collect::<Vec<_>>();
+    let times = results
+        .iter()
+        .map(|r| r.time.as_secs_f64())
+        .collect::<Vec<_>>();
+    for column in [distances, times] {
+        let mut column_writer = row_group.next_column()?.expect("schema has five columns");
+        column_writer
+            .typed::<DoubleType>()
+            .write_batch(&column, None, None)?;
+        column_writer.close()?;
+    }
+
+    // Optional values are written densely with a definition level per row
+    let gaps = results.iter().filter_map(|r| r.gap).collect::<Vec<_>>();
+    let defined = results
+        .iter()
+        .map(|r| i16::from(r.gap.is_some()))
+        .collect::<Vec<_>>();
+    let mut column_writer = row_group.next_column()?.expect("schema has five columns");
+    column_writer
+        .typed::<DoubleType>()
+        .write_batch(&gaps, Some(&defined), None)?;
+    column_writer.close()?;
+
+    row_group.close()?;
+    writer.close()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn results() -> Vec<SolverResult> {
+        vec![
+            SolverResult::new("exhaustive", "square", 40.0, Duration::from_millis(5)),
+            SolverResult::new("greedy, fast", "square", 50.0, Duration::from_micros(10)),
+            SolverResult::new("greedy, fast", "line", 3.0, Duration::from_micros(1))
+                .with_optimal(2.0),
+        ]
+    }
+
+    #[test]
+    fn test_fill_gaps() {
+        let mut results = results();
+        fill_gaps(&mut results);
+
+        let gaps = results.iter().map(|r| r.gap).collect::<Vec<_>>();
+        assert_eq!(gaps, vec![Some(0.0), Some(0.25), Some(0.5)]);
+    }
+
+    #[test]
+    fn test_write_csv() {
+        let mut buffer = Vec::new();
+        write_csv(&mut buffer, &results()).unwrap();
+
+        let text = String::from_utf8(buffer).unwrap();
+        let lines = text.lines().collect::<Vec<_>>();
+        assert_eq!(lines[0], "solver,instance,distance,time_seconds,gap");
+        assert_eq!(lines[1], "exhaustive,square,40,0.005,");
+        assert_eq!(lines[3], "\"greedy, fast\",line,3,0.000001,0.5");
+    }
+
+    #[cfg(feature = "parquet")]
+    #[test]
+    fn test_write_parquet() {
+        use parquet::file::reader::{FileReader, SerializedFileReader};
+
+        let path = std::env::temp_dir().join("coding_compairson_report_test.parquet");
+        write_parquet(std::fs::File::create(&path).unwrap(), &results()).unwrap();
+
+        let reader = SerializedFileReader::new(std::fs::File::open(&path).unwrap()).unwrap();
+        let metadata = reader.metadata();
+        assert_eq!(metadata.file_metadata().num_rows(), 3);
+        assert_eq!(metadata.file_metadata().schema_descr().num_columns(), 5);
+    }
+
+    #[test]
+    fn test_generate() {
+        let results = vec![
+            SolverResult::new("exhaustive", "a|b", 40.0, Duration::from_millis(5))
+                .with_optimal(40.0)
+                .with_allocations(12),
+            SolverResult::new("greedy", "a|b", 50.125, Duration::from_micros(10)),
+        ];
+
+        assert_eq!(
+            generate(&results),
+            "| Solver | Instance | Distance | Time | Gap | Allocations |\n\
+             | --- | --- | ---: | ---: | ---: | ---: |\n\
+             | exhaustive | a\\|b | 40.00 | 5.00ms | 0.00% | 12 |\n\
+             | greedy | a\\|b | 50.12 | 10.00µs | - | - |\n"
+        );
+
+        let html = generate_html(&results);
+        assert!(html.contains("<th>Allocations</th>"));
+        assert!(html.contains(
+            "<tr><td>greedy</td><td>a|b</td><td>50.12</td><td>10.00µs</td><td>-</td><td>-</td></tr>"
+        ));
+    }
+}
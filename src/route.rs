@@ -0,0 +1,441 @@
+//! A solved route, as the sequence of destination indices it visits.
+
+use std::{
+    fmt::Display,
+    fmt::Write as _,
+    ops::{Add, Deref, Sub},
+};
+
+use num_traits::Zero;
+
+use crate::delta;
+
+/// The destinations of a route in visiting order, as indices into the problem's
+/// destinations. A round trip lists its start again at the end.
+///
+/// Routes dereference to a slice of the indices.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent)
+)]
+pub struct Route {
+    stops: Vec<usize>,
+}
+
+impl Route {
+    pub fn new(stops: Vec<usize>) -> Self {
+        Route { stops }
+    }
+
+    pub fn stops(&self) -> &[usize] {
+        &self.stops
+    }
+
+    pub fn into_stops(self) -> Vec<usize> {
+        self.stops
+    }
+
+    /// The stops for reordering in place, as local search does.
+    pub fn stops_mut(&mut self) -> &mut [usize] {
+        &mut self.stops
+    }
+
+    /// The consecutive pairs of stops the route travels between.
+    pub fn legs(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.stops.windows(2).map(|leg| (leg[0], leg[1]))
+    }
+
+    /// The route with `destination` inserted where it adds the least distance, for
+    /// adding a late destination without solving the problem again. Takes O(n) time.
+    ///
+    /// # Arguments
+    ///
+    /// * `destination` - The destination index to insert
+    /// * `distance` - Computes the distance between two destination indices
+    pub fn with_inserted<Distance>(
+        &self,
+        destination: usize,
+        distance: impl Fn(usize, usize) -> Distance,
+    ) -> Route
+    where
+        Distance: Copy + PartialOrd + Add<Output = Distance> + Sub<Output = Distance>,
+    {
+        let added = |(from, to): (usize, usize)| {
+            distance(from, destination) + distance(destination, to) - distance(from, to)
+        };
+        let mut best: Option<(usize, Distance)> = None;
+        for (index, leg) in self.legs().enumerate() {
+            let cost = added(leg);
+            if best.is_none_or(|(_, best)| cost < best) {
+                best = Some((index, cost));
+            }
+        }
+        let mut stops = self.stops.clone();
+        match best {
+            Some((index, _)) => stops.insert(index + 1, destination),
+            // With no legs there is nowhere to insert but the end
+            None => stops.push(destination),
+        }
+        Route::new(stops)
+    }
+
+    /// The route without `destination`, joining the stops either side of it. The first
+    /// and last stops are kept, so the route still starts and ends in the same place; if
+    /// `destination` is not visited between them the route is unchanged.
+    pub fn with_removed(&self, destination: usize) -> Route {
+        let mut stops = self.stops.clone();
+        if let [_, inner @ .., _] = &self.stops[..] {
+            if let Some(index) = inner.iter().position(|&stop| stop == destination) {
+                stops.remove(index + 1);
+            }
+        }
+        Route::new(stops)
+    }
+
+    /// Reverses the stops at positions `i..=j`.
+    ///
+    /// # Arguments
+    ///
+    /// * `i`, `j` - The first and last positions of the segment, with `i <= j`
+    /// * `distance` - Computes the distance between two destination indices, which
+    ///   should be symmetric, as only the legs at the ends of the segment are compared
+    ///
+    /// # Returns
+    ///
+    /// The change in the route's distance, as [`delta::two_opt`] computes it
+    pub fn reverse_segment<Distance>(
+        &mut self,
+        i: usize,
+        j: usize,
+        distance: impl Fn(usize, usize) -> Distance,
+    ) -> Distance
+    where
+        Distance: Zero + Sub<Output = Distance>,
+    {
+        let delta = delta::two_opt(&self.stops, i, j, distance);
+        self.stops[i..=j].reverse();
+        delta
+    }
+
+    /// Exchanges the stops at positions `i` and `j`.
+    ///
+    /// # Returns
+    ///
+    /// The change in the route's distance
+    pub fn swap<Distance>(
+        &mut self,
+        i: usize,
+        j: usize,
+        distance: impl Fn(usize, usize) -> Distance,
+    ) -> Distance
+    where
+        Distance: Zero + Sub<Output = Distance>,
+    {
+        let delta = delta::swap(&self.stops, i, j, distance);
+        self.stops.swap(i, j);
+        delta
+    }
+
+    /// Moves the stop at position `i` to position `j`, shifting the stops between.
+    ///
+    /// # Returns
+    ///
+    /// The change in the route's distance
+    pub fn relocate<Distance>(
+        &mut self,
+        i: usize,
+        j: usize,
+        distance: impl Fn(usize, usize) -> Distance,
+    ) -> Distance
+    where
+        Distance: Zero + Sub<Output = Distance>,
+    {
+        let delta = delta::relocate(&self.stops, i, j, distance);
+        let stop = self.stops.remove(i);
+        self.stops.insert(j, stop);
+        delta
+    }
+
+    /// Makes a round trip start and end at the stop at `position`, which leaves its
+    /// distance unchanged.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the route is not a round trip, or `position` is not on it.
+    pub fn rotate(&mut self, position: usize) {
+        let len = self.stops.len();
+        assert!(
+            len > 0 && self.stops[0] == self.stops[len - 1],
+            "only round trips can be rotated"
+        );
+        assert!(position < len, "position {position} is not on the route");
+        self.stops.pop();
+        self.stops.rotate_left(position % (len - 1).max(1));
+        self.stops.push(self.stops[0]);
+    }
+
+    /// The route in the direction whose stops come first in lexicographic order, so
+    /// that a round trip and its reverse, which have the same distance under a
+    /// symmetric metric, compare and hash equal. The start and end stay where they
+    /// are, so open paths, whose direction is fixed by them, are returned unchanged.
+    ///
+    /// Only meaningful for symmetric metrics: with an asymmetric matrix the two
+    /// directions have different distances and are not interchangeable.
+    pub fn canonical(&self) -> Route {
+        let mut canonical = self.clone();
+        if let [first, .., last] = self.stops[..] {
+            if first == last {
+                let inner = &mut canonical.stops[1..self.stops.len() - 1];
+                if inner.iter().rev().lt(inner.iter()) {
+                    inner.reverse();
+                }
+            }
+        }
+        canonical
+    }
+
+    /// A hash of the [canonical](Route::canonical) route that is the same on every
+    /// platform, run and version of this crate, unlike [`Hash`], so it can identify
+    /// routes in files and across solver runs.
+    pub fn canonical_hash(&self) -> u64 {
+        // 64-bit FNV-1a over each stop as eight little-endian bytes
+        const OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+        const PRIME: u64 = 0x0000_0100_0000_01b3;
+        self.canonical()
+            .stops
+            .iter()
+            .flat_map(|&stop| (stop as u64).to_le_bytes())
+            .fold(OFFSET, |hash, byte| {
+                (hash ^ u64::from(byte)).wrapping_mul(PRIME)
+            })
+    }
+
+    /// Emits the route as a Graphviz DOT digraph, with each edge labelled by the
+    /// distance of its leg.
+    ///
+    /// # Arguments
+    ///
+    /// * `distance` - Computes the distance between two destination indices
+    pub fn to_dot<Distance>(&self, distance: impl Fn(usize, usize) -> Distance) -> String
+    where
+        Distance: Display,
+    {
+        let mut dot = String::from("digraph route {\n");
+        // Writing to a String cannot fail
+        for (from, to) in self.legs() {
+            let _ = writeln!(
+                dot,
+                "    {from} -> {to} [label=\"{}\"];",
+                distance(from, to)
+            );
+        }
+        // A route of a single stop has no legs but should still show the node
+        if let [only] = self.stops[..] {
+            let _ = writeln!(dot, "    {only};");
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+impl Deref for Route {
+    type Target = [usize];
+
+    fn deref(&self) -> &Self::Target {
+        &self.stops
+    }
+}
+
+impl From<Vec<usize>> for Route {
+    fn from(stops: Vec<usize>) -> Self {
+        Route::new(stops)
+    }
+}
+
+impl From<Route> for Vec<usize> {
+    fn from(route: Route) -> Self {
+        route.stops
+    }
+}
+
+impl IntoIterator for Route {
+    type Item = usize;
+    type IntoIter = std::vec::IntoIter<usize>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.stops.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a Route {
+    type Item = &'a usize;
+    type IntoIter = std::slice::Iter<'a, usize>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.stops.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{rng::SplitMix64, Metric, Point, Problem};
+
+    use super::*;
+
+    #[test]
+    fn test_to_dot() {
+        let route = Route::new(vec![0, 2, 1, 0]);
+        let dot = route.to_dot(|a, b| a.abs_diff(b) * 10);
+
+        assert_eq!(
+            dot,
+            "digraph route {\n    0 -> 2 [label=\"20\"];\n    2 -> 1 [label=\"10\"];\n    1 -> 0 [label=\"10\"];\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_to_dot_single_stop() {
+        let dot = Route::new(vec![3]).to_dot(|_, _| 0);
+        assert_eq!(dot, "digraph route {\n    3;\n}\n");
+    }
+
+    #[test]
+    fn test_with_inserted() {
+        // Destinations on a line at their index
+        let distance = |a: usize, b: usize| a.abs_diff(b) as f64;
+        let route = Route::new(vec![0, 2, 6, 4, 0]);
+        assert_eq!(
+            route.with_inserted(3, distance).stops(),
+            &[0, 2, 3, 6, 4, 0]
+        );
+        assert_eq!(
+            route.with_inserted(8, distance).stops(),
+            &[0, 2, 8, 6, 4, 0]
+        );
+        // Ties go to the earliest leg
+        assert_eq!(
+            route.with_inserted(1, distance).stops(),
+            &[0, 1, 2, 6, 4, 0]
+        );
+        assert_eq!(
+            Route::new(vec![0]).with_inserted(5, distance).stops(),
+            &[0, 5]
+        );
+
+        // A late destination added to a problem is inserted into its solution
+        let mut problem = Problem::new(
+            [0.0, 4.0, 8.0, 4.0].map(|y| Point::new(0.0, y)).to_vec(),
+            Metric::Euclidean,
+        );
+        problem.points[3].x = 4.0;
+        let route = problem.solve();
+        problem.points.push(Point::new(4.0, 8.0));
+        let route = route.with_inserted(4, |a, b| problem.distance(a, b));
+        assert!(problem.is_route(&route));
+        assert_eq!(
+            problem.route_distance(&route),
+            problem.route_distance(&problem.solve())
+        );
+    }
+
+    #[test]
+    fn test_with_removed() {
+        let route = Route::new(vec![0, 2, 1, 3, 0]);
+        assert_eq!(route.with_removed(1).stops(), &[0, 2, 3, 0]);
+        // The ends stay, and destinations not on the route change nothing
+        assert_eq!(route.with_removed(0), route);
+        assert_eq!(route.with_removed(7), route);
+        let open = Route::new(vec![1, 3]);
+        assert_eq!(open.with_removed(3), open);
+        assert_eq!(Route::new(vec![]).with_removed(0), Route::new(vec![]));
+    }
+
+    #[test]
+    fn test_segment_moves() {
+        let mut rng = SplitMix64::new(6);
+        let problem = Problem::new(
+            (0..9)
+                .map(|_| Point::new(rng.below(100) as f64, rng.below(100) as f64))
+                .collect(),
+            Metric::Euclidean,
+        );
+        let distance = |a, b| problem.distance(a, b);
+
+        // Every move's delta matches recomputing the distance, including at the ends
+        // and between neighbors
+        let mut route = Route::new((0..9).chain([0]).collect());
+        for i in 0..10 {
+            for j in 0..10 {
+                for kind in 0..3 {
+                    let before = problem.route_distance(&route);
+                    let delta = match kind {
+                        0 if i <= j => route.reverse_segment(i, j, distance),
+                        0 => continue,
+                        1 => route.swap(i, j, distance),
+                        _ => route.relocate(i, j, distance),
+                    };
+                    let after = problem.route_distance(&route);
+                    assert!((after - before - delta).abs() < 1e-9, "{kind} {i} {j}");
+                }
+            }
+        }
+
+        let mut route = Route::new(vec![0, 1, 2, 3, 4]);
+        route.reverse_segment(1, 3, |_, _| 0);
+        assert_eq!(route.stops(), &[0, 3, 2, 1, 4]);
+        route.swap(0, 4, |_, _| 0);
+        assert_eq!(route.stops(), &[4, 3, 2, 1, 0]);
+        route.relocate(1, 3, |_, _| 0);
+        assert_eq!(route.stops(), &[4, 2, 1, 3, 0]);
+        route.relocate(3, 0, |_, _| 0);
+        assert_eq!(route.stops(), &[3, 4, 2, 1, 0]);
+    }
+
+    #[test]
+    fn test_rotate() {
+        let mut route = Route::new(vec![0, 2, 1, 3, 0]);
+        route.rotate(2);
+        assert_eq!(route.stops(), &[1, 3, 0, 2, 1]);
+        route.rotate(4);
+        assert_eq!(route.stops(), &[1, 3, 0, 2, 1]);
+        let mut single = Route::new(vec![5, 5]);
+        single.rotate(1);
+        assert_eq!(single.stops(), &[5, 5]);
+    }
+
+    #[test]
+    #[should_panic(expected = "round trips")]
+    fn test_rotate_open_route() {
+        Route::new(vec![0, 1, 2]).rotate(1);
+    }
+
+    #[test]
+    fn test_canonical() {
+        let route = Route::new(vec![0, 3, 1, 2, 0]);
+        let reversed = Route::new(vec![0, 2, 1, 3, 0]);
+        assert_eq!(route.canonical(), reversed);
+        assert_eq!(reversed.canonical(), reversed);
+        assert_eq!(route.canonical_hash(), reversed.canonical_hash());
+        assert_ne!(
+            route.canonical_hash(),
+            Route::new(vec![0, 1, 3, 2, 0]).canonical_hash()
+        );
+        // Fixed, so hashes written by one build can be read by another
+        assert_eq!(Route::new(vec![]).canonical_hash(), 0xcbf2_9ce4_8422_2325);
+
+        let open = Route::new(vec![0, 3, 1, 2]);
+        assert_eq!(open.canonical(), open);
+        assert_eq!(Route::new(vec![4]).canonical(), Route::new(vec![4]));
+    }
+
+    #[test]
+    fn test_route_as_slice() {
+        let route = Route::from(vec![4, 5, 6]);
+        assert_eq!(route.len(), 3);
+        assert_eq!(route[1], 5);
+        assert_eq!(route.legs().collect::<Vec<_>>(), vec![(4, 5), (5, 6)]);
+        assert_eq!(Vec::from(route), vec![4, 5, 6]);
+    }
+}
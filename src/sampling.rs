@@ -0,0 +1,179 @@
+//! The distances of uniformly random routes, as a baseline for how much better a solver
+//! does than chance.
+
+use std::fmt;
+
+use crate::{rng::SplitMix64, Problem};
+
+/// The distribution of distances over a sample of random routes, from
+/// [`Problem::sample_routes`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct RouteSample {
+    /// The distance of each sampled route, shortest first.
+    distances: Vec<f64>,
+}
+
+impl RouteSample {
+    /// The number of routes sampled.
+    pub fn len(&self) -> usize {
+        self.distances.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.distances.is_empty()
+    }
+
+    /// The distance of each sampled route, shortest first.
+    pub fn distances(&self) -> &[f64] {
+        &self.distances
+    }
+
+    pub fn min(&self) -> f64 {
+        self.percentile(0.0)
+    }
+
+    pub fn max(&self) -> f64 {
+        self.percentile(100.0)
+    }
+
+    pub fn mean(&self) -> f64 {
+        self.distances.iter().sum::<f64>() / self.distances.len() as f64
+    }
+
+    pub fn median(&self) -> f64 {
+        self.percentile(50.0)
+    }
+
+    /// The distance below which `percent` of the sampled routes fall, interpolating
+    /// linearly between the two nearest samples.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `percent` is not between 0 and 100, or if nothing was sampled.
+    pub fn percentile(&self, percent: f64) -> f64 {
+        assert!(
+            (0.0..=100.0).contains(&percent),
+            "{percent} is not a percentage"
+        );
+        assert!(!self.is_empty(), "no routes were sampled");
+        let rank = percent / 100.0 * (self.distances.len() - 1) as f64;
+        let (below, above) = (rank.floor() as usize, rank.ceil() as usize);
+        let fraction = rank - below as f64;
+        self.distances[below] + (self.distances[above] - self.distances[below]) * fraction
+    }
+
+    /// The fraction of sampled routes shorter than `distance`, which for a solver's
+    /// route is the chance that a random route would have beaten it.
+    pub fn fraction_shorter(&self, distance: f64) -> f64 {
+        self.distances
+            .partition_point(|&sampled| sampled < distance) as f64
+            / self.distances.len() as f64
+    }
+}
+
+/// Summarizes the sample in a line such as
+/// "1000 random routes: min 412.5, p5 455.1, median 502.9, p95 551.0, max 590.2".
+impl fmt::Display for RouteSample {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return write!(f, "no random routes");
+        }
+        write!(
+            f,
+            "{} random routes: min {:.1}, p5 {:.1}, median {:.1}, p95 {:.1}, max {:.1}",
+            self.len(),
+            self.min(),
+            self.percentile(5.0),
+            self.median(),
+            self.percentile(95.0),
+            self.max()
+        )
+    }
+}
+
+impl Problem {
+    /// Scores `samples` routes, each visiting the destinations between the start and
+    /// end in a uniformly random order.
+    ///
+    /// # Arguments
+    ///
+    /// * `samples` - The number of routes to score
+    /// * `seed` - Seeds the random orders, so the sample is reproducible
+    ///
+    /// # Returns
+    ///
+    /// The distribution of the routes' distances
+    pub fn sample_routes(&self, samples: usize, seed: u64) -> RouteSample {
+        let matrix = self.distance_matrix();
+        let mut rng = SplitMix64::new(seed);
+        let mut stops = [vec![self.start], self.inner_destinations(), vec![self.end]].concat();
+        let last = stops.len() - 1;
+        let mut distances = (0..samples)
+            .map(|_| {
+                rng.shuffle(&mut stops[1..last]);
+                matrix.route_distance(&stops)
+            })
+            .collect::<Vec<_>>();
+        distances.sort_by(f64::total_cmp);
+        RouteSample { distances }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        generators::{self, Bounds},
+        Metric, Point,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_sample_routes() {
+        let problem = Problem::new(
+            generators::uniform_points(30, Bounds::square(100.0), 6),
+            Metric::Euclidean,
+        );
+        let sample = problem.sample_routes(500, 1);
+        assert_eq!(sample.len(), 500);
+        assert_eq!(sample, problem.sample_routes(500, 1));
+        assert!(sample.min() <= sample.percentile(5.0));
+        assert!(sample.percentile(5.0) <= sample.median());
+        assert!(sample.median() <= sample.max());
+        assert!(sample.min() <= sample.mean() && sample.mean() <= sample.max());
+
+        // A good heuristic beats every random route
+        let route = crate::ils::iterated_local_search(&problem, &Default::default());
+        assert_eq!(sample.fraction_shorter(problem.route_distance(&route)), 0.0);
+        assert_eq!(sample.fraction_shorter(f64::INFINITY), 1.0);
+        assert!(sample.to_string().starts_with("500 random routes: min "));
+    }
+
+    #[test]
+    fn test_percentile() {
+        let sample = RouteSample {
+            distances: vec![1.0, 2.0, 4.0, 8.0, 16.0],
+        };
+        assert_eq!(sample.median(), 4.0);
+        assert_eq!(sample.percentile(62.5), 6.0);
+        assert_eq!((sample.min(), sample.max()), (1.0, 16.0));
+        assert_eq!(sample.fraction_shorter(4.0), 0.4);
+
+        // Every order of a round trip over two destinations has the same distance
+        let problem = Problem::new(
+            [(0.0, 0.0), (3.0, 0.0), (3.0, 4.0)]
+                .map(|(x, y)| Point::new(x, y))
+                .to_vec(),
+            Metric::Euclidean,
+        );
+        let sample = problem.sample_routes(10, 0);
+        assert_eq!((sample.min(), sample.max()), (12.0, 12.0));
+        assert_eq!(problem.sample_routes(0, 0).to_string(), "no random routes");
+    }
+
+    #[test]
+    #[should_panic(expected = "no routes were sampled")]
+    fn test_empty_percentile() {
+        RouteSample { distances: vec![] }.median();
+    }
+}
@@ -0,0 +1,214 @@
+//! Exhaustive search that reports on itself as it goes, for callers that want to
+//! watch the best route improve or stop the search early.
+
+use std::ops::ControlFlow;
+
+use itertools::Itertools as _;
+
+use crate::{objective::Objective, Problem, Route};
+
+/// A route shorter than every one examined before it, or cheaper under the objective
+/// searched with.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Improvement {
+    pub route: Route,
+    /// The route's total distance, or its cost under the objective searched with.
+    pub distance: f64,
+    /// How many routes had been examined when this one was found, including itself.
+    pub examined: u64,
+}
+
+/// Receives reports from [`Problem::search`]. Returning [`ControlFlow::Break`]
+/// from either method stops the search.
+pub trait Observer {
+    /// Called with each route shorter than all those before it. The first route
+    /// examined is always an improvement.
+    fn improved(&mut self, _improvement: &Improvement) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+
+    /// Called after each route is examined. `total` is the number of routes the
+    /// search will examine unless stopped, or `None` if it does not fit in a `u64`.
+    fn examined(&mut self, _examined: u64, _total: Option<u64>) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+}
+
+/// Closures observe only improvements.
+impl<F> Observer for F
+where
+    F: FnMut(&Improvement) -> ControlFlow<()>,
+{
+    fn improved(&mut self, improvement: &Improvement) -> ControlFlow<()> {
+        self(improvement)
+    }
+}
+
+impl Problem {
+    /// Finds the shortest route from `start` through every destination to `end`
+    /// by exhaustive search, reporting each improvement and every route examined
+    /// to `observer`.
+    ///
+    /// # Returns
+    ///
+    /// The shortest route found, which is optimal unless the observer stopped the search
+    pub fn search(&self, observer: &mut impl Observer) -> Improvement {
+        search_routes(
+            self.inner_destinations(),
+            self.start,
+            self.end,
+            |stops| self.route_distance(stops),
+            observer,
+        )
+    }
+
+    /// Finds the route from `start` through every destination to `end` that minimizes
+    /// `objective`, by exhaustive search as [`search`](Problem::search) does, which
+    /// minimizes [`TotalDistance`](crate::objective::TotalDistance).
+    ///
+    /// # Returns
+    ///
+    /// The cheapest route found and its cost, which is optimal unless the observer
+    /// stopped the search
+    pub fn search_objective(
+        &self,
+        objective: &dyn Objective,
+        observer: &mut impl Observer,
+    ) -> Improvement {
+        let matrix = self.distance_matrix();
+        search_routes(
+            self.inner_destinations(),
+            self.start,
+            self.end,
+            |stops| objective.cost(&matrix, stops),
+            observer,
+        )
+    }
+}
+
+/// Examines every order of `inner` as a route from `start` to `end`, reporting to
+/// `observer`, and returns the first of those with the least `cost`.
+pub(crate) fn search_routes(
+    inner: Vec<usize>,
+    start: usize,
+    end: usize,
+    cost: impl Fn(&[usize]) -> f64,
+    observer: &mut impl Observer,
+) -> Improvement {
+    let total = (1..=inner.len() as u64).try_fold(1u64, u64::checked_mul);
+
+    let mut best: Option<Improvement> = None;
+    let mut examined = 0;
+    // With no inner destinations there is still the one route from start to end
+    for permutation in inner.iter().copied().permutations(inner.len()) {
+        let mut stops = Vec::with_capacity(permutation.len() + 2);
+        stops.push(start);
+        stops.extend(permutation);
+        stops.push(end);
+
+        examined += 1;
+        let distance = cost(&stops);
+        if best.as_ref().is_none_or(|best| distance < best.distance) {
+            let improvement = Improvement {
+                route: Route::new(stops),
+                distance,
+                examined,
+            };
+            let flow = observer.improved(&improvement);
+            best = Some(improvement);
+            if flow.is_break() {
+                break;
+            }
+        }
+
+        if observer.examined(examined, total).is_break() {
+            break;
+        }
+    }
+
+    best.expect("there is always at least one route")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        objective::{LongestLeg, TotalDistance},
+        Metric, Point,
+    };
+
+    use super::*;
+
+    fn problem() -> Problem {
+        Problem::new(
+            (0..6)
+                .map(|i| Point::new(f64::from(i * 7 % 6), f64::from(i * i % 5)))
+                .collect(),
+            Metric::Euclidean,
+        )
+    }
+
+    #[test]
+    fn test_search_improves_to_optimum() {
+        let problem = problem();
+        let mut improvements = Vec::new();
+        let best = problem.search(&mut |improvement: &Improvement| {
+            improvements.push(improvement.clone());
+            ControlFlow::Continue(())
+        });
+
+        assert!(improvements
+            .windows(2)
+            .all(|pair| pair[1].distance < pair[0].distance));
+        assert_eq!(improvements.last(), Some(&best));
+        assert_eq!(best.distance, problem.route_distance(&problem.solve()));
+    }
+
+    #[test]
+    fn test_search_stops_early() {
+        struct StopAfter(u64, Option<u64>);
+
+        impl Observer for StopAfter {
+            fn examined(&mut self, examined: u64, total: Option<u64>) -> ControlFlow<()> {
+                self.1 = total;
+                if examined == self.0 {
+                    ControlFlow::Break(())
+                } else {
+                    ControlFlow::Continue(())
+                }
+            }
+        }
+
+        let mut observer = StopAfter(3, None);
+        let best = problem().search(&mut observer);
+        assert_eq!(observer.1, Some(120));
+        assert!(best.examined <= 3);
+    }
+
+    #[test]
+    fn test_search_objective() {
+        let problem = problem();
+        let shortest = problem.search(&mut |_: &Improvement| ControlFlow::Continue(()));
+        let by_distance = problem.search_objective(&TotalDistance, &mut |_: &Improvement| {
+            ControlFlow::Continue(())
+        });
+        assert_eq!(by_distance, shortest);
+
+        let mut improvements = 0;
+        let best = problem.search_objective(&LongestLeg, &mut |_: &Improvement| {
+            improvements += 1;
+            ControlFlow::Continue(())
+        });
+        let matrix = problem.distance_matrix();
+        assert_eq!(best.distance, LongestLeg.cost(&matrix, &best.route));
+        assert!(best.distance <= LongestLeg.cost(&matrix, &shortest.route));
+        assert!(improvements >= 1);
+    }
+
+    #[test]
+    fn test_search_single_destination() {
+        let problem = Problem::new(vec![Point::new(1.0, 1.0)], Metric::Euclidean);
+        let best = problem.search(&mut |_: &Improvement| ControlFlow::Continue(()));
+        assert_eq!(best.route.stops(), [0, 0]);
+        assert_eq!(best.examined, 1);
+    }
+}
@@ -0,0 +1,149 @@
+//! How sensitive a solved route is to changes in its distances, for answering "what if
+//! this road closes" before it does: how much each leg could lengthen before another
+//! route would be shorter, and which destinations add the most to the total.
+
+use crate::{variants::traveling_salesman_held_karp, DistanceMatrix, Route};
+
+/// How far one leg of a route could lengthen before the route stops being optimal.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LegSensitivity {
+    pub from: usize,
+    pub to: usize,
+    /// The distance the leg could grow by, [`f64::INFINITY`] if every route travels it.
+    pub slack: f64,
+}
+
+/// How much one destination adds to a route's distance.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DestinationInfluence {
+    pub destination: usize,
+    /// The distance saved by skipping the destination and joining its neighbors.
+    pub saving: f64,
+}
+
+/// The result of [`sensitivity`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sensitivity {
+    /// Each leg of the route in order.
+    pub legs: Vec<LegSensitivity>,
+    /// Each destination between the start and end, most influential first.
+    pub destinations: Vec<DestinationInfluence>,
+}
+
+impl Sensitivity {
+    /// The leg with the least slack, the one whose lengthening would change the optimal
+    /// route soonest.
+    pub fn most_sensitive_leg(&self) -> Option<&LegSensitivity> {
+        self.legs.iter().min_by(|a, b| a.slack.total_cmp(&b.slack))
+    }
+}
+
+/// Analyzes how sensitive `route` is to the distances of `matrix`.
+///
+/// The slack of each leg is the distance of the shortest route that does not travel it,
+/// found exactly by Held–Karp, less the distance of `route`. It says how much the leg
+/// can lengthen before `route` stops being optimal, so it is meaningful only for an
+/// optimal route, such as one from an exact solver; for any other it may be negative.
+/// On a symmetric matrix a leg is closed in both directions, as a road would be.
+///
+/// # Arguments
+///
+/// * `route` - The route to analyze, visiting the destinations it is solved over
+/// * `matrix` - The distances between destinations
+///
+/// # Returns
+///
+/// The slack of each leg and the influence of each destination. Takes O(2ⁿn³) time for
+/// a route of n destinations, so is practical only for up to about 15
+pub fn sensitivity(route: &Route, matrix: &DistanceMatrix) -> Sensitivity {
+    let distance = matrix.route_distance(route);
+    let symmetric = matrix.is_symmetric();
+    let (start, end, inner) = match route.stops() {
+        [start, inner @ .., end] => (*start, *end, inner),
+        _ => (0, 0, &[][..]),
+    };
+
+    let legs = route
+        .legs()
+        .map(|(from, to)| {
+            let closed = |a, b| (a, b) == (from, to) || (symmetric && (b, a) == (from, to));
+            let without = DistanceMatrix::from_fn(matrix.len(), |a, b| {
+                if closed(a, b) {
+                    f64::INFINITY
+                } else {
+                    matrix.get(a, b)
+                }
+            });
+            let alternative =
+                traveling_salesman_held_karp(inner.iter().copied(), start, end, |(&a, &b)| {
+                    without.get(a, b)
+                });
+            LegSensitivity {
+                from,
+                to,
+                slack: without.route_distance(&alternative) - distance,
+            }
+        })
+        .collect();
+
+    let mut destinations = route
+        .windows(3)
+        .map(|stops| {
+            let [previous, destination, next] = [stops[0], stops[1], stops[2]];
+            DestinationInfluence {
+                destination,
+                saving: matrix.get(previous, destination) + matrix.get(destination, next)
+                    - matrix.get(previous, next),
+            }
+        })
+        .collect::<Vec<_>>();
+    destinations.sort_by(|a, b| b.saving.total_cmp(&a.saving));
+
+    Sensitivity { legs, destinations }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Metric, Point, Problem};
+
+    use super::*;
+
+    #[test]
+    fn test_sensitivity() {
+        // A 4 by 3 rectangle with a fifth destination just outside its right side
+        let problem = Problem::new(
+            [(0.0, 0.0), (4.0, 0.0), (4.5, 1.5), (4.0, 3.0), (0.0, 3.0)]
+                .map(|(x, y)| Point::new(x, y))
+                .to_vec(),
+            Metric::Euclidean,
+        );
+        let matrix = problem.distance_matrix();
+        let route = matrix.solve(0, 0).canonical();
+        assert_eq!(route.stops(), &[0, 1, 2, 3, 4, 0]);
+
+        let analysis = sensitivity(&route, &matrix);
+        assert_eq!(analysis.legs.len(), 5);
+        assert!(analysis.legs.iter().all(|leg| leg.slack >= 0.0));
+        // Closing the left side forces the route across the rectangle's diagonals
+        let left = analysis.legs.last().unwrap();
+        assert_eq!((left.from, left.to), (4, 0));
+        let detour = matrix.route_distance(&[0, 1, 2, 4, 3, 0]) - matrix.route_distance(&route);
+        let cheapest = analysis.most_sensitive_leg().unwrap();
+        assert!(cheapest.slack <= left.slack && left.slack <= detour + 1e-9);
+
+        let outside = analysis
+            .destinations
+            .iter()
+            .find(|influence| influence.destination == 2)
+            .unwrap();
+        assert!((outside.saving - (2.0 * 1.5f64.hypot(0.5) - 3.0)).abs() < 1e-9);
+        // Skipping the corner at 4 cuts across the rectangle's diagonal
+        assert_eq!(analysis.destinations[0].destination, 4);
+        assert_eq!(analysis.destinations[0].saving, 2.0);
+
+        // With two destinations every round trip travels the only leg
+        let pair = sensitivity(&Route::new(vec![0, 1, 0]), &matrix);
+        assert!(pair.legs.iter().all(|leg| leg.slack == f64::INFINITY));
+        assert!(sensitivity(&Route::new(vec![]), &matrix).legs.is_empty());
+    }
+}
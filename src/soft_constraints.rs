@@ -0,0 +1,437 @@
+//! Time windows and precedences that a route should respect but may break at a price,
+//! so a solver always has a usable route to return, even when no route satisfies them
+//! all, along with what it broke.
+//!
+//! Time is measured in the problem's distances, as if travelling at unit speed, and a
+//! route is at its first stop at time 0. Arriving before a window opens means waiting
+//! for it, which costs nothing but time; arriving after it closes costs the lateness
+//! times its penalty. Each stop's service time is spent there, after any wait, before
+//! leaving for the next. Visiting a delivery before its pickup costs a fixed penalty.
+
+use std::collections::HashMap;
+
+use crate::{
+    background::SolverConfig,
+    ils,
+    objective::{self, Objective},
+    pickup_delivery::{self, Pair},
+    violations::{self, Constraint, ViolationReport},
+    DistanceMatrix, Problem, Route,
+};
+
+/// When a destination should be arrived at.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TimeWindow {
+    pub earliest: f64,
+    pub latest: f64,
+}
+
+impl TimeWindow {
+    pub fn new(earliest: f64, latest: f64) -> Self {
+        TimeWindow { earliest, latest }
+    }
+}
+
+/// Time windows and precedences, each broken at a configurable price.
+///
+/// The constraints are an [`Objective`] costing only their penalties, so combine them
+/// with the distance, as [`solve`] and [`solve_exhaustive`] do, to trade the two off.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SoftConstraints {
+    windows: HashMap<usize, TimeWindow>,
+    precedences: Vec<Pair>,
+    /// The time spent at each destination, none for those past its end.
+    service_times: Vec<f64>,
+    lateness_penalty: f64,
+    precedence_penalty: f64,
+}
+
+impl Default for SoftConstraints {
+    fn default() -> Self {
+        SoftConstraints {
+            windows: HashMap::new(),
+            precedences: Vec::new(),
+            service_times: Vec::new(),
+            lateness_penalty: 1.0,
+            precedence_penalty: 1.0,
+        }
+    }
+}
+
+impl SoftConstraints {
+    /// No constraints or service times, with penalties of 1 per unit of lateness and
+    /// per pair out of order.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Asks for `destination` to be arrived at within `window`, replacing any window
+    /// it had.
+    pub fn with_time_window(mut self, destination: usize, window: TimeWindow) -> Self {
+        self.windows.insert(destination, window);
+        self
+    }
+
+    /// Asks for `pair`'s pickup to be visited before its delivery, as in
+    /// [`pickup_delivery`].
+    pub fn with_precedence(mut self, pair: Pair) -> Self {
+        self.precedences.push(pair);
+        self
+    }
+
+    /// Spends `service_times[i]` at each destination `i` before leaving it, as in
+    /// [`Problem::service_times`]. [`solve`] and [`solve_exhaustive`] use their problem's.
+    pub fn with_service_times(mut self, service_times: Vec<f64>) -> Self {
+        self.service_times = service_times;
+        self
+    }
+
+    /// Costs each unit of time a destination is arrived at after its window closes
+    /// `penalty`, the distance worth travelling to save it.
+    pub fn with_lateness_penalty(mut self, penalty: f64) -> Self {
+        self.lateness_penalty = penalty;
+        self
+    }
+
+    /// Costs each pair visited out of order `penalty`.
+    pub fn with_precedence_penalty(mut self, penalty: f64) -> Self {
+        self.precedence_penalty = penalty;
+        self
+    }
+
+    /// Which constraints the route visiting `stops` satisfies and which it breaks: its
+    /// time windows by destination, then its precedences in the order given.
+    pub fn report(&self, matrix: &DistanceMatrix, stops: &[usize]) -> ViolationReport {
+        let mut lateness = HashMap::new();
+        self.arrivals(matrix, stops, |destination, arrival, window| {
+            lateness.insert(destination, f64::max(arrival - window.latest, 0.0));
+        });
+        let mut report = ViolationReport::new();
+        let mut windows = self.windows.iter().collect::<Vec<_>>();
+        windows.sort_by_key(|&(&destination, _)| destination);
+        for (&destination, &window) in windows {
+            report.record(
+                Constraint::TimeWindow {
+                    destination,
+                    window,
+                },
+                lateness.get(&destination).copied().unwrap_or(f64::INFINITY),
+            );
+        }
+        for &pair in &self.precedences {
+            report.record(
+                Constraint::Precedence(pair),
+                violations::precedence_violation(stops, pair),
+            );
+        }
+        report
+    }
+
+    /// Calls `visit` with each stop after the first that has a window, the time the
+    /// route arrives at it and the window.
+    fn arrivals(
+        &self,
+        matrix: &DistanceMatrix,
+        stops: &[usize],
+        mut visit: impl FnMut(usize, f64, &TimeWindow),
+    ) {
+        let service = |stop: usize| self.service_times.get(stop).copied().unwrap_or(0.0);
+        let mut time = stops.first().map_or(0.0, |&first| service(first));
+        for leg in stops.windows(2) {
+            time += matrix.get(leg[0], leg[1]);
+            if let Some(window) = self.windows.get(&leg[1]) {
+                visit(leg[1], time, window);
+                time = f64::max(time, window.earliest);
+            }
+            time += service(leg[1]);
+        }
+    }
+
+    /// These constraints spending `problem`'s service times.
+    fn for_problem(&self, problem: &Problem) -> Self {
+        self.clone()
+            .with_service_times(problem.service_times.clone())
+    }
+}
+
+impl Objective for SoftConstraints {
+    fn cost(&self, matrix: &DistanceMatrix, stops: &[usize]) -> f64 {
+        let mut lateness = 0.0;
+        self.arrivals(matrix, stops, |_, arrival, window| {
+            lateness += f64::max(arrival - window.latest, 0.0);
+        });
+        let out_of_order = self
+            .precedences
+            .iter()
+            .filter(|&&pair| !pickup_delivery::is_feasible(stops, &[pair]))
+            .count();
+        self.lateness_penalty * lateness + self.precedence_penalty * out_of_order as f64
+    }
+}
+
+/// A route found under soft constraints, with what it costs and what it breaks.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SoftSolution {
+    pub route: Route,
+    pub distance: f64,
+    /// The penalties of the constraints it breaks.
+    pub penalty: f64,
+    pub report: ViolationReport,
+}
+
+impl SoftSolution {
+    fn new(problem: &Problem, constraints: &SoftConstraints, route: Route) -> Self {
+        reevaluate(&route, &problem.distance_matrix(), constraints)
+    }
+
+    /// Whether the route breaks no constraint.
+    pub fn is_feasible(&self) -> bool {
+        self.report.is_feasible()
+    }
+}
+
+/// Costs an existing route under changed distances or travel times, such as after a
+/// road closure or in rush hour, without solving again, for checking whether a plan
+/// still holds.
+///
+/// # Arguments
+///
+/// * `route` - The route to check, such as one already handed out
+/// * `matrix` - The distances or travel times now in effect
+/// * `constraints` - The constraints the route should still respect
+///
+/// # Returns
+///
+/// The route with its distance, penalties and report under `matrix`
+pub fn reevaluate(
+    route: &Route,
+    matrix: &DistanceMatrix,
+    constraints: &SoftConstraints,
+) -> SoftSolution {
+    SoftSolution {
+        route: route.clone(),
+        distance: matrix.route_distance(route),
+        penalty: constraints.cost(matrix, route),
+        report: constraints.report(matrix, route),
+    }
+}
+
+/// Finds the route of `problem` with the least distance plus penalties, by exhaustive
+/// search, spending `problem`'s service times in place of any `constraints` has.
+pub fn solve_exhaustive(problem: &Problem, constraints: &SoftConstraints) -> SoftSolution {
+    let constraints = &constraints.for_problem(problem);
+    let route = objective::solve_exhaustive(
+        &problem.distance_matrix(),
+        problem.start,
+        problem.end,
+        &|matrix: &DistanceMatrix, stops: &[usize]| total(constraints, matrix, stops),
+    );
+    SoftSolution::new(problem, constraints, route)
+}
+
+/// Finds a route of `problem` with a low distance plus penalties, by
+/// [`iterated_local_search_objective`](ils::iterated_local_search_objective).
+///
+/// Lateness depends on every leg before it, so each move is costed over the whole
+/// route, which suits problems of tens of destinations rather than thousands.
+///
+/// # Arguments
+///
+/// * `problem` - The problem to solve
+/// * `constraints` - The constraints and their penalties, which spend `problem`'s
+///   service times in place of any they have
+/// * `config` - The budget, random seed and initial route
+///
+/// # Panics
+///
+/// Panics if `config`'s initial route is not a route of `problem`.
+pub fn solve(
+    problem: &Problem,
+    constraints: &SoftConstraints,
+    config: &SolverConfig,
+) -> SoftSolution {
+    let constraints = &constraints.for_problem(problem);
+    let route = ils::iterated_local_search_objective(
+        problem,
+        config,
+        &|matrix: &DistanceMatrix, stops: &[usize]| total(constraints, matrix, stops),
+    );
+    SoftSolution::new(problem, constraints, route)
+}
+
+/// The distance plus penalties of the route visiting `stops`.
+fn total(constraints: &SoftConstraints, matrix: &DistanceMatrix, stops: &[usize]) -> f64 {
+    matrix.route_distance(stops) + constraints.cost(matrix, stops)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        generators::{self, Bounds},
+        Metric, Point,
+    };
+
+    use super::*;
+
+    fn line() -> Problem {
+        Problem::new(
+            [0.0, 1.0, 2.0, 3.0].map(|x| Point::new(x, 0.0)).to_vec(),
+            Metric::Euclidean,
+        )
+    }
+
+    #[test]
+    fn test_report() {
+        let problem = line();
+        let matrix = problem.distance_matrix();
+        let constraints = SoftConstraints::new()
+            .with_time_window(1, TimeWindow::new(4.0, 10.0))
+            .with_time_window(3, TimeWindow::new(0.0, 5.0))
+            .with_precedence(Pair::new(2, 1))
+            .with_lateness_penalty(2.0)
+            .with_precedence_penalty(100.0);
+        // Arrives at 1 at time 1, waits until 4, then reaches 3 at 6
+        let stops = [0, 1, 2, 3, 0];
+        let report = constraints.report(&matrix, &stops);
+        assert_eq!(
+            report
+                .checks
+                .iter()
+                .map(|check| check.violation)
+                .collect::<Vec<_>>(),
+            [0.0, 1.0, 2.0]
+        );
+        assert_eq!(
+            report
+                .violated()
+                .map(|check| check.constraint)
+                .collect::<Vec<_>>(),
+            [
+                Constraint::TimeWindow {
+                    destination: 3,
+                    window: TimeWindow::new(0.0, 5.0)
+                },
+                Constraint::Precedence(Pair::new(2, 1))
+            ]
+        );
+        assert_eq!(constraints.cost(&matrix, &stops), 102.0);
+        assert_eq!(constraints.cost(&matrix, &[0, 3, 2, 1, 0]), 0.0);
+    }
+
+    #[test]
+    fn test_service_times() {
+        // Arriving at 2 at time 2 meets its window, unless 1 takes a while to serve
+        let mut problem = line();
+        let constraints = SoftConstraints::new()
+            .with_time_window(2, TimeWindow::new(0.0, 3.0))
+            .with_time_window(3, TimeWindow::new(0.0, 10.0));
+        let stops = [0, 1, 2, 3, 0];
+        assert_eq!(constraints.cost(&problem.distance_matrix(), &stops), 0.0);
+
+        problem.service_times = vec![0.5, 1.5, 0.0, 0.0];
+        let served = constraints
+            .clone()
+            .with_service_times(problem.service_times.clone());
+        let matrix = problem.distance_matrix();
+        assert_eq!(served.cost(&matrix, &stops), 1.0);
+        assert_eq!(
+            served
+                .report(&matrix, &stops)
+                .violated()
+                .map(|check| check.constraint)
+                .collect::<Vec<_>>(),
+            [Constraint::TimeWindow {
+                destination: 2,
+                window: TimeWindow::new(0.0, 3.0)
+            }]
+        );
+        assert_eq!(
+            reevaluate(&Route::new(stops.to_vec()), &matrix, &served).penalty,
+            1.0
+        );
+
+        // The solvers spend the problem's service times, so reach 2 before 1
+        let solution = solve_exhaustive(&problem, &constraints);
+        assert!(solution.is_feasible());
+        assert_eq!(solution.route.stops(), &[0, 2, 3, 1, 0]);
+        let config = SolverConfig::default().with_iterations(20);
+        assert!(solve(&problem, &constraints, &config).is_feasible());
+    }
+
+    #[test]
+    fn test_infeasible() {
+        // The pairs form a cycle, so no route satisfies both, but one still comes back
+        let problem = line();
+        let constraints = SoftConstraints::new()
+            .with_precedence(Pair::new(1, 2))
+            .with_precedence(Pair::new(2, 1))
+            .with_precedence_penalty(10.0);
+        let solution = solve_exhaustive(&problem, &constraints);
+        assert!(problem.is_route(&solution.route));
+        assert!(!solution.is_feasible());
+        assert_eq!(solution.report.violated().count(), 1);
+        assert_eq!((solution.distance, solution.penalty), (6.0, 10.0));
+
+        // Cheap enough lateness is worth the shorter route
+        let constraints = SoftConstraints::new()
+            .with_time_window(3, TimeWindow::new(0.0, 1.0))
+            .with_lateness_penalty(0.1);
+        let solution = solve_exhaustive(&problem, &constraints);
+        assert_eq!(solution.distance, 6.0);
+        assert!((solution.penalty - 0.2).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_reevaluate() {
+        let problem = line();
+        let constraints = SoftConstraints::new().with_time_window(2, TimeWindow::new(0.0, 3.0));
+        let solution = solve_exhaustive(&problem, &constraints);
+        assert!(solution.is_feasible());
+
+        // Congestion doubles every travel time, making the window unreachable
+        let slow = problem.distance_matrix().map(|&time| time * 2.0);
+        let now = reevaluate(&solution.route, &slow, &constraints);
+        assert_eq!(now.route, solution.route);
+        assert_eq!(now.distance, 2.0 * solution.distance);
+        assert!(!now.is_feasible());
+        assert_eq!(now.report.violated().count(), 1);
+
+        let same = reevaluate(&solution.route, &problem.distance_matrix(), &constraints);
+        assert_eq!(same, solution);
+    }
+
+    #[test]
+    fn test_solve() {
+        let problem = Problem::new(
+            generators::uniform_points(9, Bounds::square(100.0), 4),
+            Metric::Euclidean,
+        );
+        let matrix = problem.distance_matrix();
+        // Windows around where the shortest route gets to a few destinations, but in
+        // the other direction
+        let optimal = problem.solve();
+        let reversed = optimal.iter().rev().copied().collect::<Vec<_>>();
+        let mut constraints = SoftConstraints::new()
+            .with_precedence(Pair::new(reversed[5], reversed[2]))
+            .with_lateness_penalty(5.0);
+        let mut time = 0.0;
+        for (position, leg) in reversed.windows(2).enumerate() {
+            time += matrix.get(leg[0], leg[1]);
+            if position % 3 == 0 {
+                constraints = constraints.with_time_window(leg[1], TimeWindow::new(0.0, time));
+            }
+        }
+
+        let exact = solve_exhaustive(&problem, &constraints);
+        let config = SolverConfig::default().with_iterations(50);
+        let solution = solve(&problem, &constraints, &config);
+        assert!(problem.is_route(&solution.route));
+        let cost = |solution: &SoftSolution| solution.distance + solution.penalty;
+        assert!(cost(&solution) >= cost(&exact) - 1e-9);
+        assert!((cost(&solution) - cost(&exact)).abs() < 1e-6);
+        assert_eq!(
+            total(&constraints, &matrix, &solution.route),
+            cost(&solution)
+        );
+    }
+}
@@ -0,0 +1,137 @@
+//! Re-optimizing a route that is already in use, such as after destinations are added
+//! or travel times change, without reshuffling it into one its drivers no longer
+//! recognize.
+
+use crate::{
+    background::SolverConfig, ils, local_search::TwoOpt, objective::EdgeChanges, DistanceMatrix,
+    Problem, Route,
+};
+
+/// Finds a short route for `problem` that keeps close to `baseline`, minimizing its
+/// distance plus `penalty` for every leg the baseline does not travel in either
+/// direction: the [`EdgeChanges`] objective, by iterated local search.
+///
+/// The search starts from the baseline, adapted to the problem: destinations it visits
+/// that the problem does not have, and repeats, are dropped, and destinations it does
+/// not visit are inserted where they add the least distance. So yesterday's route can
+/// be the baseline for today's problem, as long as destinations keep their indices.
+///
+/// # Arguments
+///
+/// * `problem` - The problem to solve
+/// * `baseline` - The route in use, as destination indices
+/// * `penalty` - The distance a changed leg must save to be worth making; zero
+///   optimizes distance alone
+/// * `config` - The budget and seed of the search; its initial route is ignored in
+///   favor of the baseline
+///
+/// # Returns
+///
+/// The route found with the least penalized distance
+pub fn reoptimize(
+    problem: &Problem,
+    baseline: &[usize],
+    penalty: f64,
+    config: &SolverConfig,
+) -> Route {
+    let changes = EdgeChanges::new(baseline);
+    let matrix = problem.distance_matrix();
+    let penalized = DistanceMatrix::from_symmetric_fn(problem.len(), |from, to| {
+        let penalty = if changes.is_kept(from, to) {
+            0.0
+        } else {
+            penalty
+        };
+        matrix.get(from, to) + penalty
+    });
+    let config = config
+        .clone()
+        .with_initial_route(adapt(problem, baseline, &matrix));
+    ils::search(problem, &penalized, &config, &TwoOpt, None)
+}
+
+/// The baseline's stops that are inner destinations of `problem`, each once in the
+/// order first visited, between its start and end, with the destinations it misses
+/// inserted where they add the least distance.
+fn adapt(problem: &Problem, baseline: &[usize], matrix: &DistanceMatrix) -> Route {
+    let mut kept = vec![false; problem.len()];
+    kept[problem.start] = true;
+    kept[problem.end] = true;
+    let mut stops = vec![problem.start];
+    for &stop in baseline {
+        if stop < problem.len() && !kept[stop] {
+            kept[stop] = true;
+            stops.push(stop);
+        }
+    }
+    stops.push(problem.end);
+
+    let mut route = Route::new(stops);
+    for destination in problem.inner_destinations() {
+        if !kept[destination] {
+            route = route.with_inserted(destination, |from, to| matrix.get(from, to));
+        }
+    }
+    route
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        generators::{self, Bounds},
+        objective::Objective,
+        Metric, Point,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_reoptimize() {
+        let mut points = generators::uniform_points(40, Bounds::square(100.0), 12);
+        let config = SolverConfig::default().with_iterations(200);
+        let baseline =
+            ils::iterated_local_search(&Problem::new(points.clone(), Metric::Euclidean), &config);
+
+        // Three destinations join the route and the rest shift a little
+        points.extend(generators::uniform_points(3, Bounds::square(100.0), 13));
+        let nudged = generators::uniform_points(points.len(), Bounds::square(4.0), 14);
+        for (point, nudge) in points.iter_mut().zip(&nudged) {
+            *point = Point::new(point.x + nudge.x, point.y + nudge.y);
+        }
+        let problem = Problem::new(points, Metric::Euclidean);
+        let matrix = problem.distance_matrix();
+        let changes = EdgeChanges::new(&baseline);
+
+        let results = [0.0, 5.0, 1000.0].map(|penalty| {
+            let route = reoptimize(&problem, &baseline, penalty, &config);
+            assert!(problem.is_route(&route), "{penalty}");
+            (
+                problem.route_distance(&route),
+                changes.cost(&matrix, &route),
+            )
+        });
+        // A heavier penalty keeps more of the baseline at the price of distance
+        assert!(results.windows(2).all(|pair| pair[0].1 >= pair[1].1));
+        assert!(results.windows(2).all(|pair| pair[0].0 <= pair[1].0 + 1e-9));
+        // Prohibitively, only the legs to the new destinations change
+        assert!(results[2].1 <= 6.0, "{results:?}");
+        assert!(results[0].1 > results[2].1);
+    }
+
+    #[test]
+    fn test_adapt() {
+        let problem = Problem::new(
+            (0..5).map(|x| Point::new(f64::from(x), 0.0)).collect(),
+            Metric::Euclidean,
+        );
+        let matrix = problem.distance_matrix();
+        // 7 is gone, 3 is repeated and 4 is new
+        let route = adapt(&problem, &[0, 2, 7, 1, 3, 3, 0], &matrix);
+        assert_eq!(route.stops(), [0, 2, 1, 4, 3, 0]);
+
+        // With no penalty, the search is free to straighten it out
+        let config = SolverConfig::default().with_iterations(10);
+        let route = reoptimize(&problem, &[0, 2, 1, 3, 0], 0.0, &config);
+        assert_eq!(problem.route_distance(&route), 8.0);
+    }
+}
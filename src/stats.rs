@@ -0,0 +1,301 @@
+//! Statistics over repeated solver timings, so comparisons can say whether one solver
+//! is faster than another with some confidence rather than from a single run.
+
+use std::{fmt, time::Duration};
+
+use crate::{report::SolverResult, rng::SplitMix64};
+
+/// The number of resamples drawn for bootstrap confidence intervals.
+const RESAMPLES: usize = 2_000;
+/// The seed for bootstrap resampling, fixed so reports are reproducible.
+const SEED: u64 = 0x5eed;
+
+/// How one solver's timings compare to another's.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    Faster,
+    Slower,
+    /// The difference is not significant at the requested confidence.
+    Inconclusive,
+}
+
+/// The result of [`compare_timings`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimingComparison {
+    /// The mean time of the first solver minus that of the second, in seconds.
+    pub mean_difference: f64,
+    /// A bootstrap confidence interval for `mean_difference`, in seconds.
+    pub interval: (f64, f64),
+    /// The two-sided p-value of Welch's t-test that the means are equal.
+    pub p_value: f64,
+    pub confidence: f64,
+}
+
+impl TimingComparison {
+    /// Whether the first solver is faster than the second, at the confidence compared with.
+    pub fn verdict(&self) -> Verdict {
+        // A NaN p-value, from too few timings, is never significant
+        let significant = self.p_value < 1.0 - self.confidence;
+        if !significant {
+            Verdict::Inconclusive
+        } else if self.mean_difference < 0.0 {
+            Verdict::Faster
+        } else {
+            Verdict::Slower
+        }
+    }
+
+    /// Describes the comparison in a sentence such as
+    /// "exhaustive is faster than matrix with 95% confidence".
+    pub fn describe(&self, first: &str, second: &str) -> String {
+        let confidence = self.confidence * 100.0;
+        match self.verdict() {
+            Verdict::Faster => {
+                format!("{first} is faster than {second} with {confidence}% confidence")
+            }
+            Verdict::Slower => {
+                format!("{first} is slower than {second} with {confidence}% confidence")
+            }
+            Verdict::Inconclusive => format!(
+                "{first} and {second} are not distinguishable with {confidence}% confidence"
+            ),
+        }
+    }
+}
+
+impl fmt::Display for TimingComparison {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "difference {:+.3e}s ({}% interval {:+.3e}s to {:+.3e}s, p = {:.3})",
+            self.mean_difference,
+            self.confidence * 100.0,
+            self.interval.0,
+            self.interval.1,
+            self.p_value
+        )
+    }
+}
+
+/// Compares two sets of repeated timings with Welch's t-test, which does not assume
+/// equal variances, and a bootstrap confidence interval for the difference of means.
+///
+/// # Arguments
+///
+/// * `first` - Timings of the first solver
+/// * `second` - Timings of the second solver
+/// * `confidence` - The confidence level, such as `0.95`
+///
+/// # Returns
+///
+/// The comparison, which is [`Verdict::Inconclusive`] unless both sets have at least
+/// two timings
+pub fn compare_timings(
+    first: &[Duration],
+    second: &[Duration],
+    confidence: f64,
+) -> TimingComparison {
+    let first = first.iter().map(Duration::as_secs_f64).collect::<Vec<_>>();
+    let second = second.iter().map(Duration::as_secs_f64).collect::<Vec<_>>();
+
+    TimingComparison {
+        mean_difference: mean(&first) - mean(&second),
+        interval: bootstrap_interval(&first, &second, confidence),
+        p_value: welch_p_value(&first, &second),
+        confidence,
+    }
+}
+
+/// The times of every result for `solver`, such as from repeated harness runs.
+pub fn timings(results: &[SolverResult], solver: &str) -> Vec<Duration> {
+    results
+        .iter()
+        .filter(|result| result.solver == solver)
+        .map(|result| result.time)
+        .collect()
+}
+
+fn mean(samples: &[f64]) -> f64 {
+    samples.iter().sum::<f64>() / samples.len() as f64
+}
+
+/// The unbiased sample variance.
+fn variance(samples: &[f64]) -> f64 {
+    let mean = mean(samples);
+    samples.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / (samples.len() - 1) as f64
+}
+
+/// The two-sided p-value of Welch's t-test, or NaN without two samples of each.
+fn welch_p_value(a: &[f64], b: &[f64]) -> f64 {
+    if a.len() < 2 || b.len() < 2 {
+        return f64::NAN;
+    }
+    let (va, vb) = (variance(a) / a.len() as f64, variance(b) / b.len() as f64);
+    let difference = mean(a) - mean(b);
+    if va + vb == 0.0 {
+        // Both sets are constant, so they either certainly differ or certainly don't
+        return if difference == 0.0 { 1.0 } else { 0.0 };
+    }
+
+    let t = difference / (va + vb).sqrt();
+    // The Welch–Satterthwaite approximation of the degrees of freedom
+    let df =
+        (va + vb).powi(2) / (va.powi(2) / (a.len() - 1) as f64 + vb.powi(2) / (b.len() - 1) as f64);
+    student_t_two_sided(t, df)
+}
+
+/// The probability that a Student's t variable with `df` degrees of freedom is
+/// further from zero than `t`.
+fn student_t_two_sided(t: f64, df: f64) -> f64 {
+    regularized_incomplete_beta(df / 2.0, 0.5, df / (df + t * t))
+}
+
+/// A percentile bootstrap interval for the difference of the means of `a` and `b`.
+fn bootstrap_interval(a: &[f64], b: &[f64], confidence: f64) -> (f64, f64) {
+    if a.is_empty() || b.is_empty() {
+        return (f64::NAN, f64::NAN);
+    }
+    let mut rng = SplitMix64::new(SEED);
+    let mut resampled_mean = |samples: &[f64]| {
+        (0..samples.len())
+            .map(|_| samples[rng.below(samples.len())])
+            .sum::<f64>()
+            / samples.len() as f64
+    };
+
+    let mut differences = (0..RESAMPLES)
+        .map(|_| resampled_mean(a) - resampled_mean(b))
+        .collect::<Vec<_>>();
+    differences.sort_by(f64::total_cmp);
+
+    let tail = (1.0 - confidence) / 2.0;
+    let at =
+        |quantile: f64| differences[((quantile * RESAMPLES as f64) as usize).min(RESAMPLES - 1)];
+    (at(tail), at(1.0 - tail))
+}
+
+/// The natural log of the gamma function, by the Lanczos approximation.
+fn ln_gamma(x: f64) -> f64 {
+    const COEFFICIENTS: [f64; 9] = [
+        0.999_999_999_999_809_9,
+        676.520_368_121_885_1,
+        -1_259.139_216_722_402_8,
+        771.323_428_777_653_1,
+        -176.615_029_162_140_6,
+        12.507_343_278_686_905,
+        -0.138_571_095_265_720_12,
+        9.984_369_578_019_572e-6,
+        1.505_632_735_149_311_6e-7,
+    ];
+    if x < 0.5 {
+        // The reflection formula
+        let pi = std::f64::consts::PI;
+        return (pi / (pi * x).sin()).ln() - ln_gamma(1.0 - x);
+    }
+    let x = x - 1.0;
+    let t = x + 7.5;
+    let sum = COEFFICIENTS[1..]
+        .iter()
+        .enumerate()
+        .fold(COEFFICIENTS[0], |sum, (i, c)| {
+            sum + c / (x + i as f64 + 1.0)
+        });
+    0.5 * (2.0 * std::f64::consts::PI).ln() + (x + 0.5) * t.ln() - t + sum.ln()
+}
+
+/// The regularized incomplete beta function I_x(a, b).
+fn regularized_incomplete_beta(a: f64, b: f64, x: f64) -> f64 {
+    if x <= 0.0 {
+        return 0.0;
+    }
+    if x >= 1.0 {
+        return 1.0;
+    }
+    let front =
+        (ln_gamma(a + b) - ln_gamma(a) - ln_gamma(b) + a * x.ln() + b * (1.0 - x).ln()).exp();
+    // The continued fraction converges quickly only on one side of the mean
+    if x < (a + 1.0) / (a + b + 2.0) {
+        front * beta_continued_fraction(a, b, x) / a
+    } else {
+        1.0 - front * beta_continued_fraction(b, a, 1.0 - x) / b
+    }
+}
+
+/// Evaluates the continued fraction for the incomplete beta function by Lentz's method.
+fn beta_continued_fraction(a: f64, b: f64, x: f64) -> f64 {
+    const TINY: f64 = 1e-300;
+    const EPSILON: f64 = 1e-14;
+
+    let nonzero = |value: f64| if value.abs() < TINY { TINY } else { value };
+    let mut c = 1.0;
+    let mut d = 1.0 / nonzero(1.0 - (a + b) * x / (a + 1.0));
+    let mut fraction = d;
+    for m in 1..=300 {
+        let m = f64::from(m);
+        // Even then odd terms of the fraction
+        for numerator in [
+            m * (b - m) * x / ((a + 2.0 * m - 1.0) * (a + 2.0 * m)),
+            -(a + m) * (a + b + m) * x / ((a + 2.0 * m) * (a + 2.0 * m + 1.0)),
+        ] {
+            d = 1.0 / nonzero(1.0 + numerator * d);
+            c = nonzero(1.0 + numerator / c);
+            fraction *= c * d;
+        }
+        if (c * d - 1.0).abs() < EPSILON {
+            break;
+        }
+    }
+    fraction
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_special_functions() {
+        // Γ(5) = 4! and Γ(1/2) = √π
+        assert!((ln_gamma(5.0) - 24f64.ln()).abs() < 1e-12);
+        assert!((ln_gamma(0.5) - std::f64::consts::PI.sqrt().ln()).abs() < 1e-12);
+        // For integers I_x(a, b) is a binomial tail: I_0.5(2, 3) = 11/16
+        assert!((regularized_incomplete_beta(2.0, 3.0, 0.5) - 0.6875).abs() < 1e-12);
+        // The 97.5th percentile of Student's t with 10 degrees of freedom is 2.228
+        assert!((student_t_two_sided(2.228, 10.0) - 0.05).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_compare_timings() {
+        let millis = |values: &[u64]| {
+            values
+                .iter()
+                .map(|&ms| Duration::from_millis(ms))
+                .collect::<Vec<_>>()
+        };
+        let fast = millis(&[10, 11, 9, 10, 12, 10, 11, 9]);
+        let slow = millis(&[20, 22, 19, 21, 20, 23, 18, 21]);
+
+        let comparison = compare_timings(&fast, &slow, 0.95);
+        assert_eq!(comparison.verdict(), Verdict::Faster);
+        assert!(comparison.interval.0 < comparison.mean_difference);
+        assert!(comparison.mean_difference < comparison.interval.1);
+        assert!(comparison.interval.1 < 0.0);
+        assert_eq!(
+            comparison.describe("a", "b"),
+            "a is faster than b with 95% confidence"
+        );
+        assert_eq!(
+            compare_timings(&slow, &fast, 0.95).verdict(),
+            Verdict::Slower
+        );
+
+        let similar = millis(&[10, 12, 9, 11, 10, 11, 10, 12]);
+        assert_eq!(
+            compare_timings(&fast, &similar, 0.95).verdict(),
+            Verdict::Inconclusive
+        );
+        assert_eq!(
+            compare_timings(&fast[..1], &slow, 0.95).verdict(),
+            Verdict::Inconclusive
+        );
+    }
+}
@@ -0,0 +1,123 @@
+//! Routing destinations read from an iterator or a CSV reader without ever holding a
+//! distance matrix, or even a [`Problem`](crate::Problem), for instances too large for
+//! n² distances to fit in memory.
+//!
+//! Memory stays proportional to the number of destinations: each is kept only as its
+//! point in a [`KdTree`], about forty bytes, while its distances are measured as the
+//! route needs them.
+
+use std::io::BufRead;
+
+use crate::{
+    beam,
+    kdtree::KdTree,
+    problem::{self, CsvError},
+    Metric, Point, Route,
+};
+
+/// A route over streamed destinations, which are numbered in the order they arrived.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StreamedRoute {
+    pub route: Route,
+    /// The route's length under the metric it was solved with.
+    pub distance: f64,
+}
+
+/// Builds a round trip from the first of `points` by the nearest-neighbor heuristic,
+/// as [`beam::nearest_neighbor`] does, storing only the points and a spatial index over
+/// them.
+///
+/// Neighbors are found by planar distance, as with [`beam::nearest_neighbor`], and the
+/// route's length is measured under `metric`.
+///
+/// # Arguments
+///
+/// * `points` - The destinations, the first being the start and end
+/// * `metric` - How the route's length is measured
+///
+/// # Panics
+///
+/// Panics if there are no points.
+pub fn nearest_neighbor_streaming(
+    points: impl IntoIterator<Item = Point>,
+    metric: Metric,
+) -> StreamedRoute {
+    let mut tree = KdTree::new(points.into_iter().collect());
+    assert!(!tree.is_empty(), "there must be at least one destination");
+    let stops = beam::nearest_neighbor_tour(&mut tree, 0, 0);
+    let points = tree.points();
+    let distance = stops
+        .windows(2)
+        .map(|leg| metric.distance(&points[leg[0]], &points[leg[1]]))
+        .sum();
+    StreamedRoute {
+        route: Route::new(stops),
+        distance,
+    }
+}
+
+/// Builds a round trip from the first row of `id,x,y` CSV, as
+/// [`Problem::from_csv`](crate::Problem::from_csv) reads it, by
+/// [`nearest_neighbor_streaming`]. Rows are parsed one at a time and their ids
+/// discarded, so the route's stops are row numbers among the destinations, counting
+/// from 0.
+///
+/// # Errors
+///
+/// As [`Problem::from_csv`](crate::Problem::from_csv), including when there are no
+/// destinations
+pub fn nearest_neighbor_csv(
+    reader: impl BufRead,
+    metric: Metric,
+) -> Result<StreamedRoute, CsvError> {
+    let mut points = Vec::new();
+    for (index, line) in reader.lines().enumerate() {
+        if let Some((_, point)) = problem::parse_csv_row(index + 1, &line?, points.is_empty())? {
+            points.push(point);
+        }
+    }
+    if points.is_empty() {
+        return Err(CsvError::Empty);
+    }
+    Ok(nearest_neighbor_streaming(points, metric))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        generators::{self, Bounds},
+        Problem,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_nearest_neighbor_streaming() {
+        let points = generators::uniform_points(500, Bounds::square(1000.0), 8);
+        let streamed = nearest_neighbor_streaming(points.iter().copied(), Metric::Euclidean);
+        let problem = Problem::new(points, Metric::Euclidean);
+        assert_eq!(streamed.route, beam::nearest_neighbor(&problem));
+        assert!((streamed.distance - problem.route_distance(&streamed.route)).abs() < 1e-6);
+
+        let single = nearest_neighbor_streaming([Point::new(1.0, 2.0)], Metric::Manhattan);
+        assert_eq!(single.route.stops(), [0, 0]);
+        assert_eq!(single.distance, 0.0);
+    }
+
+    #[test]
+    fn test_nearest_neighbor_csv() {
+        let csv = "id,x,y\ndepot,0,0\n\na,3,0\nb,1,0\nc,-4,0\n";
+        let streamed = nearest_neighbor_csv(csv.as_bytes(), Metric::Euclidean).unwrap();
+        assert_eq!(streamed.route.stops(), [0, 2, 1, 3, 0]);
+        assert_eq!(streamed.distance, 14.0);
+
+        assert!(matches!(
+            nearest_neighbor_csv("id,x,y\n".as_bytes(), Metric::Euclidean),
+            Err(CsvError::Empty)
+        ));
+        assert!(matches!(
+            nearest_neighbor_csv("a,1,2\nb,x,3\n".as_bytes(), Metric::Euclidean),
+            Err(CsvError::InvalidNumber { line: 2, .. })
+        ));
+    }
+}
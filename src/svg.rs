@@ -0,0 +1,132 @@
+//! Plotting routes as SVG images, for debugging heuristics and for documentation.
+
+use std::fmt::Write as _;
+
+use crate::Point;
+
+/// Width and height of the drawing area in SVG user units.
+const SIZE: f64 = 500.0;
+/// Blank space around the drawing area.
+const MARGIN: f64 = 20.0;
+
+/// Renders the points and the legs of `route` (indices into `points`) as an SVG image.
+///
+/// The first destination of the route is drawn larger than the others so the
+/// direction of travel can be followed from it.
+pub fn render_svg(route: &[usize], points: &[Point]) -> String {
+    render(&[(route, "#1f77b4", None)], route.first().copied(), points)
+}
+
+/// Renders `before` as a dashed grey route underneath `after`, to show what an
+/// improvement step changed.
+pub fn render_svg_comparison(before: &[usize], after: &[usize], points: &[Point]) -> String {
+    render(
+        &[(before, "#999999", Some("6 4")), (after, "#d62728", None)],
+        after.first().copied(),
+        points,
+    )
+}
+
+/// Draws each route as a polyline (with optional dash pattern) and then the points on top.
+fn render(
+    routes: &[(&[usize], &str, Option<&str>)],
+    first: Option<usize>,
+    points: &[Point],
+) -> String {
+    let project = projection(points);
+    let extent = SIZE + 2.0 * MARGIN;
+
+    let mut svg = String::new();
+    // Writing to a String cannot fail
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{extent}" height="{extent}" viewBox="0 0 {extent} {extent}">"#
+    );
+    let _ = writeln!(svg, r#"<rect width="100%" height="100%" fill="white"/>"#);
+
+    for (route, color, dash) in routes {
+        let coordinates = route
+            .iter()
+            .map(|&i| {
+                let (x, y) = project(&points[i]);
+                format!("{x:.2},{y:.2}")
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+        let dash = dash
+            .map(|dash| format!(r#" stroke-dasharray="{dash}""#))
+            .unwrap_or_default();
+        let _ = writeln!(
+            svg,
+            r#"<polyline points="{coordinates}" fill="none" stroke="{color}" stroke-width="2"{dash}/>"#
+        );
+    }
+
+    for (index, point) in points.iter().enumerate() {
+        let (x, y) = project(point);
+        let radius = if Some(index) == first { 6 } else { 4 };
+        let _ = writeln!(
+            svg,
+            r#"<circle cx="{x:.2}" cy="{y:.2}" r="{radius}" fill="black"><title>{index}</title></circle>"#
+        );
+    }
+
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// Returns a function mapping points into the drawing area, preserving the aspect
+/// ratio and flipping the y axis so that up is up.
+fn projection(points: &[Point]) -> impl Fn(&Point) -> (f64, f64) {
+    let min_x = points.iter().map(|p| p.x).fold(f64::INFINITY, f64::min);
+    let max_x = points.iter().map(|p| p.x).fold(f64::NEG_INFINITY, f64::max);
+    let min_y = points.iter().map(|p| p.y).fold(f64::INFINITY, f64::min);
+    let max_y = points.iter().map(|p| p.y).fold(f64::NEG_INFINITY, f64::max);
+
+    let span = (max_x - min_x).max(max_y - min_y);
+    let scale = if span > 0.0 { SIZE / span } else { 1.0 };
+
+    move |point| {
+        (
+            MARGIN + (point.x - min_x) * scale,
+            MARGIN + SIZE - (point.y - min_y) * scale,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_svg() {
+        let points = vec![
+            Point::new(0.0, 0.0),
+            Point::new(10.0, 0.0),
+            Point::new(10.0, 10.0),
+        ];
+
+        let svg = render_svg(&[0, 1, 2, 0], &points);
+        assert!(svg.starts_with("<svg "));
+        assert!(svg.ends_with("</svg>\n"));
+        // y is flipped so the origin is at the bottom left
+        assert!(svg.contains(r#"points="20.00,520.00 520.00,520.00 520.00,20.00 20.00,520.00""#));
+        assert_eq!(svg.matches("<circle").count(), 3);
+        assert!(svg.contains(r#"r="6""#));
+    }
+
+    #[test]
+    fn test_render_svg_comparison() {
+        let points = vec![Point::new(1.0, 1.0), Point::new(2.0, 3.0)];
+
+        let svg = render_svg_comparison(&[0, 1], &[1, 0], &points);
+        assert_eq!(svg.matches("<polyline").count(), 2);
+        assert_eq!(svg.matches("stroke-dasharray").count(), 1);
+    }
+
+    #[test]
+    fn test_render_single_point() {
+        let svg = render_svg(&[0], &[Point::new(5.0, 5.0)]);
+        assert!(svg.contains(r#"cx="20.00" cy="520.00""#));
+    }
+}
@@ -0,0 +1,275 @@
+//! Parallel tempering: simulated annealing on several chains at once, each at its own
+//! temperature and on its own thread, with the chains exchanging routes.
+
+use std::{thread, time::Instant};
+
+use crate::{
+    background::SolverConfig,
+    christofides::christofides,
+    delta,
+    events::{Traced, Trajectory},
+    local_search::{Neighborhood, TwoOpt},
+    rng::SplitMix64,
+    DistanceMatrix, Problem, Route,
+};
+
+/// The rounds run when the config sets neither an iteration nor a time limit.
+pub const DEFAULT_ROUNDS: u64 = 100;
+/// The number of chains used by the registered `tempering` solver.
+pub const DEFAULT_CHAINS: usize = 4;
+/// The moves each chain tries per round, between exchanges.
+const MOVES_PER_ROUND: u64 = 10_000;
+/// The hottest and coldest temperatures, as fractions of the mean leg of the initial route.
+const HOTTEST: f64 = 0.3;
+const COLDEST: f64 = 0.002;
+
+/// One chain of the annealing, at a fixed temperature.
+struct Chain {
+    temperature: f64,
+    rng: SplitMix64,
+    stops: Vec<usize>,
+    distance: f64,
+    best: Vec<usize>,
+    best_distance: f64,
+}
+
+impl Chain {
+    /// Tries `moves` random segment reversals, accepting each that lengthens the route
+    /// by `delta` with probability e^(-delta / temperature).
+    fn anneal(&mut self, matrix: &DistanceMatrix, moves: u64) {
+        let len = self.stops.len();
+        for _ in 0..moves {
+            // Reverse stops i..=j, strictly between the first and last stops
+            let mut i = 1 + self.rng.below(len - 2);
+            let mut j = 1 + self.rng.below(len - 2);
+            if i == j {
+                continue;
+            }
+            if i > j {
+                std::mem::swap(&mut i, &mut j);
+            }
+            let delta = delta::two_opt(&self.stops, i, j, |a, b| matrix.get(a, b));
+            if delta <= 0.0 || self.rng.unit() < (-delta / self.temperature).exp() {
+                self.stops[i..=j].reverse();
+                self.distance += delta;
+                if self.distance < self.best_distance {
+                    self.best_distance = self.distance;
+                    self.best.clone_from(&self.stops);
+                }
+            }
+        }
+    }
+}
+
+/// Finds a short route by parallel tempering: `chains` simulated annealing chains at
+/// temperatures spaced geometrically from hot to cold each run on their own thread, and
+/// between rounds neighboring chains exchange routes with the probability that keeps
+/// each chain sampling at its temperature. Hot chains explore widely and pass promising
+/// routes down to cold chains, which refine them, so it escapes local optima that
+/// trap a single chain.
+///
+/// Every chain starts from `config`'s initial route, or [`christofides`] if it has
+/// none, and the best route any chain visits is finished with 2-opt. The search runs for
+/// `config`'s iterations, counted in rounds, or its time limit, or [`DEFAULT_ROUNDS`]
+/// rounds if it has neither. Each chain has its own random numbers, so the result
+/// depends only on the seed and not on how the threads are scheduled.
+///
+/// # Arguments
+///
+/// * `problem` - The problem to solve
+/// * `config` - The budget, random seed and initial route
+/// * `chains` - The number of chains, and so of threads, at least one
+///
+/// # Returns
+///
+/// The shortest route found
+///
+/// # Panics
+///
+/// Panics if the initial route is not a route of `problem`.
+pub fn parallel_tempering(problem: &Problem, config: &SolverConfig, chains: usize) -> Route {
+    temper(problem, config, chains, None)
+}
+
+/// Parallel tempering as [`parallel_tempering`], returning with the route the best
+/// distance of any chain after each round, counting the starting route as iteration 0.
+/// The finishing 2-opt, if it shortens the route, is a last point at the final round.
+pub fn parallel_tempering_traced(
+    problem: &Problem,
+    config: &SolverConfig,
+    chains: usize,
+) -> Traced {
+    let mut trajectory = Trajectory::new();
+    let route = temper(problem, config, chains, Some(&mut trajectory));
+    Traced { route, trajectory }
+}
+
+fn temper(
+    problem: &Problem,
+    config: &SolverConfig,
+    chains: usize,
+    mut trajectory: Option<&mut Trajectory>,
+) -> Route {
+    let matrix = problem.distance_matrix();
+    let initial = config.starting_route(problem, christofides);
+    let stops = initial.stops().to_vec();
+    if stops.len() < 4 {
+        // Fewer than two destinations between the ends, so there is nothing to reverse
+        if let Some(trajectory) = trajectory {
+            trajectory.record(0, matrix.route_distance(&initial));
+        }
+        return initial;
+    }
+
+    let distance = matrix.route_distance(&initial);
+    if let Some(trajectory) = trajectory.as_deref_mut() {
+        trajectory.record(0, distance);
+    }
+    let mean_leg = distance / (stops.len() - 1) as f64;
+    let chains = chains.max(1);
+    let mut rng = SplitMix64::new(config.seed);
+    let mut chains = (0..chains)
+        .map(|k| {
+            let fraction = if chains > 1 {
+                k as f64 / (chains - 1) as f64
+            } else {
+                1.0
+            };
+            Chain {
+                temperature: mean_leg * HOTTEST * (COLDEST / HOTTEST).powf(fraction),
+                rng: SplitMix64::new(rng.next_u64()),
+                stops: stops.clone(),
+                distance,
+                best: stops.clone(),
+                best_distance: distance,
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let rounds = match (config.iterations, config.time_limit) {
+        (None, None) => DEFAULT_ROUNDS,
+        (iterations, _) => iterations.unwrap_or(u64::MAX),
+    };
+    let deadline = config.time_limit.map(|limit| Instant::now() + limit);
+    let mut rounds_run = 0;
+    for round in 1..=rounds {
+        if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            break;
+        }
+        thread::scope(|scope| {
+            for chain in &mut chains {
+                let matrix = &matrix;
+                scope.spawn(move || chain.anneal(matrix, MOVES_PER_ROUND));
+            }
+        });
+        // Exchange the routes of chains at neighboring temperatures, always if the
+        // hotter one's is shorter. Chain 0 is the hottest, so chain k + 1 is colder
+        for k in 0..chains.len() - 1 {
+            let (hotter, colder) = chains.split_at_mut(k + 1);
+            let (hotter, colder) = (&mut hotter[k], &mut colder[0]);
+            let exponent = (1.0 / colder.temperature - 1.0 / hotter.temperature)
+                * (colder.distance - hotter.distance);
+            if exponent >= 0.0 || rng.unit() < exponent.exp() {
+                std::mem::swap(&mut colder.stops, &mut hotter.stops);
+                std::mem::swap(&mut colder.distance, &mut hotter.distance);
+            }
+        }
+        rounds_run = round;
+        if let Some(trajectory) = trajectory.as_deref_mut() {
+            let best = chains
+                .iter()
+                .map(|chain| chain.best_distance)
+                .fold(f64::INFINITY, f64::min);
+            trajectory.record(round, best);
+        }
+    }
+
+    let best = chains
+        .into_iter()
+        .map(|chain| chain.best)
+        .min_by(|a, b| {
+            matrix
+                .route_distance(a)
+                .total_cmp(&matrix.route_distance(b))
+        })
+        .expect("there is at least one chain");
+    let mut best = Route::new(best);
+    TwoOpt.improve(&mut best, &matrix);
+    if let Some(trajectory) = trajectory {
+        trajectory.record(rounds_run, matrix.route_distance(&best));
+    }
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::{generators::uniform_problem, Metric, Point};
+
+    use super::*;
+
+    #[test]
+    fn test_finds_optimum_of_small_problems() {
+        for seed in 0..5 {
+            let mut problem = uniform_problem(9, seed);
+            if seed % 2 == 1 {
+                problem.end = 4;
+            }
+            let config = SolverConfig::default().with_seed(seed).with_iterations(10);
+            let route = parallel_tempering(&problem, &config, 3);
+            let optimal = problem.route_distance(&problem.solve());
+            assert!((problem.route_distance(&route) - optimal).abs() < 1e-9);
+        }
+
+        let pair = Problem::new(vec![Point::new(0.0, 0.0); 2], Metric::Euclidean);
+        let config = SolverConfig::default();
+        assert_eq!(parallel_tempering(&pair, &config, 2).stops(), &[0, 1, 0]);
+    }
+
+    #[test]
+    fn test_traced() {
+        let problem = uniform_problem(60, 2);
+        let config = SolverConfig::default().with_iterations(8).with_seed(1);
+        let traced = parallel_tempering_traced(&problem, &config, 3);
+        assert_eq!(traced.route, parallel_tempering(&problem, &config, 3));
+        let trajectory = traced.trajectory;
+        assert_eq!(trajectory.iterations, 8);
+        let start = problem.route_distance(&christofides(&problem));
+        assert_eq!(trajectory.best_at(0), Some(start));
+        let distance = trajectory.final_distance().unwrap();
+        assert!((distance - problem.route_distance(&traced.route)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_budget() {
+        let mut problem = uniform_problem(200, 9);
+        problem.end = 100;
+        let start = christofides(&problem);
+
+        let config = SolverConfig::default().with_time_limit(Duration::from_millis(50));
+        let started = Instant::now();
+        let route = parallel_tempering(&problem, &config, 4);
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert_eq!((route[0], route[199]), (0, 100));
+        let mut inner = route[1..199].to_vec();
+        inner.sort();
+        assert_eq!(inner, problem.inner_destinations());
+        assert!(problem.route_distance(&route) <= problem.route_distance(&start));
+
+        // The same seed finds the same route, whatever the threads do
+        let config = SolverConfig::default().with_iterations(5).with_seed(3);
+        assert_eq!(
+            parallel_tempering(&problem, &config, 4),
+            parallel_tempering(&problem, &config, 4)
+        );
+        // A warm start is refined rather than replaced
+        let warm = config.clone().with_initial_route(route.clone());
+        let refined = parallel_tempering(&problem, &warm, 4);
+        assert!(problem.route_distance(&refined) <= problem.route_distance(&route));
+
+        // A single chain is plain simulated annealing
+        let single = parallel_tempering(&problem, &config, 1);
+        assert_eq!(single.len(), 200);
+    }
+}
@@ -0,0 +1,242 @@
+//! Proptest strategies for random instances and reusable properties that any
+//! solver should satisfy, so new solvers get rigorous checks cheaply.
+//!
+//! The `check_*` functions return a [`TestCaseResult`] so they can be used with `?`
+//! inside `proptest!` blocks:
+//!
+//! ```ignore
+//! proptest! {
+//!     #[test]
+//!     fn my_solver_visits_everything(problem in problems(0..7, Metric::Euclidean)) {
+//!         let route = my_solver(&problem);
+//!         check_route_is_permutation(&route, &problem)?;
+//!     }
+//! }
+//! ```
+
+use std::ops::Range;
+
+use proptest::{collection::vec, prelude::*, test_runner::TestCaseResult};
+
+use crate::{
+    compare::verify_optimal,
+    hand_rolled_traveling_salesman_owned, hand_rolled_traveling_salesman_unchecked,
+    local_search::{Neighborhood, TwoOpt},
+    registry, traveling_salesman, DistanceMatrix, Metric, Point, Problem, Route,
+};
+
+/// Coordinates are kept within this range so distances stay well inside f64 precision.
+const COORDINATE_RANGE: Range<f64> = -1000.0..1000.0;
+
+/// Points with coordinates in a bounded range.
+pub fn points(len: Range<usize>) -> impl Strategy<Value = Vec<Point>> {
+    vec(
+        (COORDINATE_RANGE, COORDINATE_RANGE).prop_map(|(x, y)| Point::new(x, y)),
+        len,
+    )
+}
+
+/// Problems with between `len.start` and `len.end - 1` destinations (at least one),
+/// with a random start and end that are sometimes the same.
+pub fn problems(len: Range<usize>, metric: Metric) -> impl Strategy<Value = Problem> {
+    points(len.start.max(1)..len.end.max(2)).prop_flat_map(move |points| {
+        let n = points.len();
+        (Just(points), 0..n, 0..n).prop_map(move |(points, start, end)| {
+            let mut problem = Problem::new(points, metric);
+            problem.start = start;
+            problem.end = end;
+            problem
+        })
+    })
+}
+
+/// Square matrices of non-negative, not necessarily symmetric, integer distances.
+pub fn matrices(len: Range<usize>) -> impl Strategy<Value = DistanceMatrix<u32>> {
+    len.prop_flat_map(|n| vec(vec(0..1000u32, n), n))
+        .prop_map(|rows| DistanceMatrix::from_rows(rows).expect("rows are generated square"))
+}
+
+/// Destinations for the `i32` solvers, small enough that distances cannot overflow.
+pub fn i32_destinations(len: Range<usize>) -> impl Strategy<Value = Vec<i32>> {
+    vec(-10_000..10_000i32, len)
+}
+
+/// Checks that `route` starts at the problem's start, ends at its end, and visits
+/// every other destination exactly once.
+pub fn check_route_is_permutation(route: &[usize], problem: &Problem) -> TestCaseResult {
+    prop_assert_eq!(
+        route.first(),
+        Some(&problem.start),
+        "route does not begin at the start"
+    );
+    prop_assert_eq!(
+        route.last(),
+        Some(&problem.end),
+        "route does not finish at the end"
+    );
+
+    let expected_len = problem.len() + usize::from(problem.start == problem.end);
+    prop_assert_eq!(
+        route.len(),
+        expected_len,
+        "route has the wrong number of stops"
+    );
+
+    let mut visits = vec![0; problem.len()];
+    for &stop in &route[..route.len() - 1] {
+        prop_assert!(stop < problem.len(), "stop {} is not a destination", stop);
+        visits[stop] += 1;
+    }
+    if problem.start != problem.end {
+        visits[problem.end] += 1;
+    }
+    prop_assert!(
+        visits.iter().all(|&count| count == 1),
+        "destinations are not each visited once: {:?}",
+        visits
+    );
+    Ok(())
+}
+
+/// Checks that the exhaustive search and the independently written `held-karp` and
+/// `branch-and-bound` solvers of the [registry], those that support `problem`, find
+/// routes of the same distance. Routes themselves may differ when there are ties.
+pub fn check_exact_solvers_agree(problem: &Problem) -> TestCaseResult {
+    let exhaustive = problem.route_distance(&problem.solve());
+    let tolerance = 1e-9 * exhaustive.abs().max(1.0);
+
+    for name in ["held-karp", "branch-and-bound"] {
+        let solver = registry::find(name).expect("exact solvers are registered");
+        if !solver.supports(problem) {
+            continue;
+        }
+        let distance = problem.route_distance(&(solver.solve)(problem));
+        prop_assert!(
+            (exhaustive - distance).abs() <= tolerance,
+            "the exhaustive search found {} but {} found {}",
+            exhaustive,
+            name,
+            distance
+        );
+    }
+    Ok(())
+}
+
+/// Checks that 2-opt leaves `route` no longer than it was and still visiting the same
+/// stops between the same start and end.
+pub fn check_two_opt_never_worsens(route: &Route, matrix: &DistanceMatrix) -> TestCaseResult {
+    let before = matrix.route_distance(route);
+    let mut improved = route.clone();
+    let changed = TwoOpt.improve(&mut improved, matrix);
+    let after = matrix.route_distance(&improved);
+
+    prop_assert!(
+        after <= before + 1e-9 * before.abs().max(1.0),
+        "2-opt lengthened the route from {} to {}",
+        before,
+        after
+    );
+    prop_assert!(
+        changed || improved == *route,
+        "2-opt changed the route without saying so"
+    );
+    prop_assert_eq!(improved.first(), route.first());
+    prop_assert_eq!(improved.last(), route.last());
+    let mut stops = route.to_vec();
+    let mut improved = improved.to_vec();
+    stops.sort_unstable();
+    improved.sort_unstable();
+    prop_assert_eq!(stops, improved, "2-opt changed which stops are visited");
+    Ok(())
+}
+
+/// Checks that `route` is a shortest route of `problem`, a small one, by
+/// [`verify_optimal`].
+pub fn check_optimal(route: &[usize], problem: &Problem) -> TestCaseResult {
+    verify_optimal(problem, route)
+        .map(|_| ())
+        .map_err(|err| TestCaseError::fail(err.to_string()))
+}
+
+/// Checks that the generic, hand-rolled and unchecked solvers find routes of the same distance
+/// through `destinations` on the number line. Any `i32` values may be used.
+pub fn check_i32_solvers_agree(destinations: &[i32], start: i32, end: i32) -> TestCaseResult {
+    let distance = |route: &[i32]| -> u64 {
+        route
+            .windows(2)
+            .map(|leg| u64::from(leg[0].abs_diff(leg[1])))
+            .sum()
+    };
+
+    let generic = traveling_salesman(destinations.iter().copied(), start, end, |(a, b)| {
+        u64::from(a.abs_diff(*b))
+    });
+    let hand_rolled = hand_rolled_traveling_salesman_owned(destinations, start, end);
+    let unchecked = hand_rolled_traveling_salesman_unchecked(destinations, &start, &end)
+        .into_iter()
+        .copied()
+        .collect::<Vec<_>>();
+
+    prop_assert_eq!(distance(&generic), distance(&hand_rolled));
+    prop_assert_eq!(distance(&generic), distance(&unchecked));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    proptest! {
+        #[test]
+        fn test_solve_is_permutation(problem in problems(0..7, Metric::Euclidean)) {
+            check_route_is_permutation(&problem.solve(), &problem)?;
+        }
+
+        #[test]
+        fn test_held_karp_is_optimal(problem in problems(0..7, Metric::Euclidean)) {
+            let held_karp = crate::registry::find("held-karp").unwrap();
+            check_optimal(&(held_karp.solve)(&problem), &problem)?;
+        }
+
+        #[test]
+        fn test_exact_solvers_agree(problem in problems(0..7, Metric::Manhattan)) {
+            check_exact_solvers_agree(&problem)?;
+        }
+
+        #[test]
+        fn test_two_opt_never_worsens(
+            (problem, stops) in problems(2..9, Metric::Euclidean).prop_flat_map(|problem| {
+                let stops = problem.inner_destinations();
+                (Just(problem), Just(stops).prop_shuffle())
+            }),
+        ) {
+            let route = Route::new([&[problem.start][..], &stops, &[problem.end]].concat());
+            check_two_opt_never_worsens(&route, &problem.distance_matrix())?;
+        }
+
+        #[test]
+        fn test_i32_solvers_agree(
+            destinations in i32_destinations(0..6),
+            start in -10_000..10_000i32,
+            end in -10_000..10_000i32,
+        ) {
+            check_i32_solvers_agree(&destinations, start, end)?;
+        }
+
+        #[test]
+        fn test_matrices_are_square(matrix in matrices(0..6)) {
+            let stops = matrix.solve(0, 0);
+            prop_assert!(matrix.is_empty() || stops.len() == matrix.len() + 1);
+        }
+    }
+
+    #[test]
+    fn test_check_route_is_permutation_rejects() {
+        let problem = Problem::new(vec![Point::default(); 3], Metric::Euclidean);
+
+        assert!(check_route_is_permutation(&[0, 1, 2, 0], &problem).is_ok());
+        assert!(check_route_is_permutation(&[0, 1, 1, 0], &problem).is_err());
+        assert!(check_route_is_permutation(&[0, 1, 0], &problem).is_err());
+        assert!(check_route_is_permutation(&[1, 0, 2, 1], &problem).is_err());
+    }
+}
@@ -0,0 +1,175 @@
+//! A cap on how far a single route may go, such as a vehicle's range, with the
+//! destinations split into several consecutive trips from the depot when one route
+//! would be too long.
+
+use crate::{Problem, Route};
+
+/// Splits the order `route` visits destinations in into round trips from
+/// `problem.start`, each no longer than `max_distance`, choosing where to return to
+/// the start so the trips are as short as possible in total for that order.
+///
+/// This is the optimal split of Beasley's route-first, cluster-second method, and takes
+/// O(n²) time at worst. An open route's end is visited like any other destination.
+///
+/// # Arguments
+///
+/// * `problem` - The problem `route` solves
+/// * `route` - The order to visit the destinations in, such as the shortest route
+/// * `max_distance` - The longest any single trip may be
+///
+/// # Returns
+///
+/// The trips in order, or `None` if some destination is too far from the start to
+/// reach and return from within `max_distance`
+pub fn split_into_trips(problem: &Problem, route: &Route, max_distance: f64) -> Option<Vec<Route>> {
+    let depot = problem.start;
+    let order = route
+        .iter()
+        .copied()
+        .filter(|&stop| stop != depot)
+        .collect::<Vec<_>>();
+    let count = order.len();
+
+    // best[j] is the least total distance of trips visiting the first j destinations,
+    // and previous[j] where the last of those trips starts
+    let mut best = vec![f64::INFINITY; count + 1];
+    let mut previous = vec![0; count + 1];
+    best[0] = 0.0;
+    for i in 0..count {
+        if best[i].is_infinite() {
+            continue;
+        }
+        // The trip from the depot through order[i..=j] and back
+        let mut outward = problem.distance(depot, order[i]);
+        for j in i..count {
+            if j > i {
+                outward += problem.distance(order[j - 1], order[j]);
+            }
+            if outward > max_distance {
+                // Visiting more only goes further
+                break;
+            }
+            let trip = outward + problem.distance(order[j], depot);
+            if trip <= max_distance && best[i] + trip < best[j + 1] {
+                best[j + 1] = best[i] + trip;
+                previous[j + 1] = i;
+            }
+        }
+    }
+    if best[count].is_infinite() {
+        return None;
+    }
+
+    let mut trips = Vec::new();
+    let mut end = count;
+    while end > 0 {
+        let start = previous[end];
+        trips.push(Route::new(
+            [&[depot], &order[start..end], &[depot]].concat(),
+        ));
+        end = start;
+    }
+    trips.reverse();
+    Some(trips)
+}
+
+/// Solves `problem` with `solve`, splitting the route into consecutive trips from the
+/// start with [`split_into_trips`] if it is longer than `max_distance`.
+///
+/// # Returns
+///
+/// The single route if it is short enough, or else the trips, or `None` if some
+/// destination is out of range
+pub fn solve_with_max_length(
+    problem: &Problem,
+    max_distance: f64,
+    solve: impl Fn(&Problem) -> Route,
+) -> Option<Vec<Route>> {
+    let route = solve(problem);
+    if problem.route_distance(&route) <= max_distance {
+        return Some(vec![route]);
+    }
+    split_into_trips(problem, &route, max_distance)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{christofides::christofides, rng::SplitMix64, Metric, Point};
+
+    use super::*;
+
+    #[test]
+    fn test_split_into_trips() {
+        // Destinations either side of the depot at 0, on a line
+        let problem = Problem::new(
+            [0.0, 1.0, 2.0, -1.0, -2.0]
+                .map(|x| Point::new(x, 0.0))
+                .to_vec(),
+            Metric::Euclidean,
+        );
+        let route = problem.solve();
+        assert_eq!(problem.route_distance(&route), 8.0);
+
+        let trips = split_into_trips(&problem, &route, 4.0).unwrap();
+        assert_eq!(trips.len(), 2);
+        for trip in &trips {
+            assert_eq!(problem.route_distance(trip), 4.0);
+        }
+        let mut visited = trips
+            .iter()
+            .flat_map(|trip| trip[1..trip.len() - 1].to_vec())
+            .collect::<Vec<_>>();
+        visited.sort();
+        assert_eq!(visited, vec![1, 2, 3, 4]);
+
+        // A generous cap needs only one trip, and one too tight none
+        assert_eq!(
+            split_into_trips(&problem, &route, 8.0).unwrap(),
+            vec![route.clone()]
+        );
+        assert_eq!(split_into_trips(&problem, &route, 3.9), None);
+        assert_eq!(
+            split_into_trips(
+                &Problem::new(vec![Point::new(0.0, 0.0)], Metric::Euclidean),
+                &Route::new(vec![0, 0]),
+                1.0
+            ),
+            Some(vec![])
+        );
+    }
+
+    #[test]
+    fn test_solve_with_max_length() {
+        // A depot in the middle keeps every destination in range
+        let mut rng = SplitMix64::new(7);
+        let problem = Problem::new(
+            [Point::new(50.0, 50.0)]
+                .into_iter()
+                .chain((0..60).map(|_| Point::new(rng.below(100) as f64, rng.below(100) as f64)))
+                .collect(),
+            Metric::Euclidean,
+        );
+        let route = christofides(&problem);
+        let distance = problem.route_distance(&route);
+        assert_eq!(
+            solve_with_max_length(&problem, distance, christofides),
+            Some(vec![route])
+        );
+
+        let max_distance = distance / 3.0;
+        let trips = solve_with_max_length(&problem, max_distance, christofides).unwrap();
+        assert!(trips.len() >= 3);
+        assert!(trips
+            .iter()
+            .all(|trip| problem.route_distance(trip) <= max_distance));
+        let total = trips
+            .iter()
+            .map(|trip| problem.route_distance(trip))
+            .sum::<f64>();
+        assert!(total >= distance);
+        assert_eq!(
+            trips.iter().map(|trip| trip.len() - 2).sum::<usize>(),
+            problem.len() - 1
+        );
+    }
+}
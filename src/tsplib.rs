@@ -0,0 +1,449 @@
+//! Reading and writing files in the TSPLIB format.
+//!
+//! TSPLIB numbers nodes from 1; everything in this module converts to and from
+//! the zero-based indices used by the rest of the crate.
+
+use std::{
+    collections::HashSet,
+    fmt,
+    io::{self, BufRead, Write},
+};
+
+use crate::{Metric, Point, Problem};
+
+/// An error produced while reading a TSPLIB file.
+#[derive(Debug)]
+pub enum TsplibError {
+    /// The underlying reader failed.
+    Io(io::Error),
+    /// A `KEY : VALUE` header line could not be understood.
+    InvalidHeader { line: usize, content: String },
+    /// The `TYPE` header named something other than what was expected.
+    UnsupportedType(String),
+    /// A required section (e.g. `TOUR_SECTION`) was never found.
+    MissingSection(&'static str),
+    /// A node id was not a positive integer.
+    InvalidNode { line: usize, value: String },
+    /// A node id appeared more than once in a `TOUR_SECTION`.
+    DuplicateNode { line: usize, node: usize },
+    /// The number of nodes read did not match the `DIMENSION` header.
+    DimensionMismatch { expected: usize, found: usize },
+    /// The `EDGE_WEIGHT_TYPE` has no corresponding [`Metric`].
+    UnsupportedEdgeWeightType(String),
+    /// A `NODE_COORD_SECTION` line was not `id x y`.
+    InvalidCoordinate { line: usize, content: String },
+}
+
+impl fmt::Display for TsplibError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TsplibError::Io(err) => write!(f, "i/o error: {err}"),
+            TsplibError::InvalidHeader { line, content } => {
+                write!(f, "line {line}: invalid header `{content}`")
+            }
+            TsplibError::UnsupportedType(ty) => write!(f, "unsupported TYPE `{ty}`"),
+            TsplibError::MissingSection(section) => write!(f, "missing {section}"),
+            TsplibError::InvalidNode { line, value } => {
+                write!(f, "line {line}: invalid node id `{value}`")
+            }
+            TsplibError::DuplicateNode { line, node } => {
+                write!(f, "line {line}: node {node} is already in the tour")
+            }
+            TsplibError::DimensionMismatch { expected, found } => {
+                write!(f, "DIMENSION is {expected} but {found} nodes were listed")
+            }
+            TsplibError::UnsupportedEdgeWeightType(ty) => {
+                write!(f, "unsupported EDGE_WEIGHT_TYPE `{ty}`")
+            }
+            TsplibError::InvalidCoordinate { line, content } => {
+                write!(f, "line {line}: expected `id x y` but found `{content}`")
+            }
+        }
+    }
+}
+
+impl std::error::Error for TsplibError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            TsplibError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for TsplibError {
+    fn from(err: io::Error) -> Self {
+        TsplibError::Io(err)
+    }
+}
+
+/// A tour as stored in a TSPLIB `.tour` file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tour {
+    pub name: String,
+    pub comment: Option<String>,
+    /// Zero-based node indices in visiting order.
+    pub nodes: Vec<usize>,
+}
+
+impl Tour {
+    /// Creates a tour with the given name and no comment.
+    pub fn new(name: impl Into<String>, nodes: Vec<usize>) -> Self {
+        Tour {
+            name: name.into(),
+            comment: None,
+            nodes,
+        }
+    }
+}
+
+/// Writes `tour` in the TSPLIB `.tour` format.
+///
+/// Node indices are written one-based, and the tour section is terminated with `-1`
+/// followed by `EOF` as the format requires.
+pub fn write_tour(mut writer: impl Write, tour: &Tour) -> io::Result<()> {
+    writeln!(writer, "NAME : {}", tour.name)?;
+    if let Some(comment) = &tour.comment {
+        writeln!(writer, "COMMENT : {comment}")?;
+    }
+    writeln!(writer, "TYPE : TOUR")?;
+    writeln!(writer, "DIMENSION : {}", tour.nodes.len())?;
+    writeln!(writer, "TOUR_SECTION")?;
+    for node in &tour.nodes {
+        writeln!(writer, "{}", node + 1)?;
+    }
+    writeln!(writer, "-1")?;
+    writeln!(writer, "EOF")
+}
+
+/// Reads a tour in the TSPLIB `.tour` format.
+///
+/// Unknown header keys are ignored. Node ids may be spread over several lines or
+/// several per line; reading stops at `-1` or `EOF`.
+pub fn read_tour(reader: impl BufRead) -> Result<Tour, TsplibError> {
+    let mut name = String::new();
+    let mut comment = None;
+    let mut dimension = None;
+    let mut nodes = None;
+
+    let mut lines = reader.lines().enumerate();
+    while let Some((index, line)) = lines.next() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        if line == "EOF" {
+            break;
+        }
+        if line == "TOUR_SECTION" {
+            nodes = Some(read_tour_section(&mut lines)?);
+            break;
+        }
+
+        let (key, value) = split_header(line, index)?;
+        match key {
+            "NAME" => name = value.to_string(),
+            "COMMENT" => comment = Some(value.to_string()),
+            "TYPE" if value != "TOUR" => {
+                return Err(TsplibError::UnsupportedType(value.to_string()))
+            }
+            "DIMENSION" => dimension = Some(parse_dimension(line, value, index)?),
+            _ => {}
+        }
+    }
+
+    let nodes = nodes.ok_or(TsplibError::MissingSection("TOUR_SECTION"))?;
+    check_dimension(dimension, nodes.len())?;
+
+    Ok(Tour {
+        name,
+        comment,
+        nodes,
+    })
+}
+
+/// Reads one-based node ids up to the `-1` terminator (or `EOF`) and returns them zero-based,
+/// rejecting any listed twice.
+fn read_tour_section(
+    lines: &mut impl Iterator<Item = (usize, io::Result<String>)>,
+) -> Result<Vec<usize>, TsplibError> {
+    let mut nodes = Vec::new();
+    let mut seen = HashSet::new();
+    for (index, line) in lines {
+        let line = line?;
+        for token in line.split_whitespace() {
+            if token == "-1" || token == "EOF" {
+                return Ok(nodes);
+            }
+            match token.parse::<usize>() {
+                Ok(id) if id >= 1 => {
+                    if !seen.insert(id) {
+                        return Err(TsplibError::DuplicateNode {
+                            line: index + 1,
+                            node: id,
+                        });
+                    }
+                    nodes.push(id - 1)
+                }
+                _ => {
+                    return Err(TsplibError::InvalidNode {
+                        line: index + 1,
+                        value: token.to_string(),
+                    })
+                }
+            }
+        }
+    }
+    Ok(nodes)
+}
+
+/// Writes `problem` as a TSPLIB `.tsp` file with a `NODE_COORD_SECTION`.
+///
+/// TSPLIB has no notion of separate start and end nodes, so only the points and
+/// metric are written. Destination ids are replaced by their one-based index. Both
+/// Euclidean metrics are written as `EUC_2D`, which reads back rounded.
+///
+/// # Errors
+///
+/// Fails with [`io::ErrorKind::InvalidInput`] if the metric has no TSPLIB
+/// `EDGE_WEIGHT_TYPE`, as well as on write errors.
+pub fn write_problem(mut writer: impl Write, name: &str, problem: &Problem) -> io::Result<()> {
+    let edge_weight_type = match problem.metric {
+        Metric::Euclidean | Metric::RoundedEuclidean => "EUC_2D",
+        Metric::Manhattan => "MAN_2D",
+        Metric::Haversine => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "TSPLIB has no EDGE_WEIGHT_TYPE for haversine distances",
+            ))
+        }
+    };
+
+    writeln!(writer, "NAME : {name}")?;
+    writeln!(writer, "TYPE : TSP")?;
+    writeln!(writer, "DIMENSION : {}", problem.len())?;
+    writeln!(writer, "EDGE_WEIGHT_TYPE : {edge_weight_type}")?;
+    writeln!(writer, "NODE_COORD_SECTION")?;
+    for (index, point) in problem.points.iter().enumerate() {
+        writeln!(writer, "{} {} {}", index + 1, point.x, point.y)?;
+    }
+    writeln!(writer, "EOF")
+}
+
+/// Reads a symmetric TSPLIB `.tsp` file with a `NODE_COORD_SECTION`.
+///
+/// `EUC_2D` and `MAN_2D` edge weights are supported. `EUC_2D` distances are rounded
+/// to the nearest integer as TSPLIB specifies, with [`Metric::RoundedEuclidean`], so
+/// route lengths match published values exactly. The returned problem is a round trip
+/// from the first node.
+pub fn read_problem(reader: impl BufRead) -> Result<Problem, TsplibError> {
+    let mut dimension = None;
+    let mut metric = None;
+    let mut nodes = None;
+
+    let mut lines = reader.lines().enumerate();
+    while let Some((index, line)) = lines.next() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        if line == "EOF" {
+            break;
+        }
+        if line == "NODE_COORD_SECTION" {
+            nodes = Some(read_node_coord_section(&mut lines)?);
+            break;
+        }
+
+        let (key, value) = split_header(line, index)?;
+        match key {
+            "TYPE" if value != "TSP" => {
+                return Err(TsplibError::UnsupportedType(value.to_string()))
+            }
+            "DIMENSION" => dimension = Some(parse_dimension(line, value, index)?),
+            "EDGE_WEIGHT_TYPE" => {
+                metric = Some(match value {
+                    "EUC_2D" => Metric::RoundedEuclidean,
+                    "MAN_2D" => Metric::Manhattan,
+                    _ => return Err(TsplibError::UnsupportedEdgeWeightType(value.to_string())),
+                })
+            }
+            _ => {}
+        }
+    }
+
+    let (ids, points): (Vec<_>, Vec<_>) = nodes
+        .ok_or(TsplibError::MissingSection("NODE_COORD_SECTION"))?
+        .into_iter()
+        .unzip();
+    check_dimension(dimension, points.len())?;
+
+    Ok(Problem {
+        ids,
+        service_times: vec![0.0; points.len()],
+        points,
+        metric: metric.ok_or(TsplibError::MissingSection("EDGE_WEIGHT_TYPE"))?,
+        start: 0,
+        end: 0,
+    })
+}
+
+/// Reads `id x y` lines up to `EOF` (or the next section).
+fn read_node_coord_section(
+    lines: &mut impl Iterator<Item = (usize, io::Result<String>)>,
+) -> Result<Vec<(String, Point)>, TsplibError> {
+    let mut nodes = Vec::new();
+    for (index, line) in lines {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        if line == "EOF" || line.ends_with("_SECTION") {
+            break;
+        }
+
+        let invalid = || TsplibError::InvalidCoordinate {
+            line: index + 1,
+            content: line.to_string(),
+        };
+        let fields = line.split_whitespace().collect::<Vec<_>>();
+        let [id, x, y] = fields[..] else {
+            return Err(invalid());
+        };
+        let (Ok(x), Ok(y)) = (x.parse(), y.parse()) else {
+            return Err(invalid());
+        };
+        nodes.push((id.to_string(), Point::new(x, y)));
+    }
+    Ok(nodes)
+}
+
+/// Splits a `KEY : VALUE` header line into its trimmed parts.
+fn split_header(line: &str, index: usize) -> Result<(&str, &str), TsplibError> {
+    match line.split_once(':') {
+        Some((key, value)) => Ok((key.trim(), value.trim())),
+        None => Err(TsplibError::InvalidHeader {
+            line: index + 1,
+            content: line.to_string(),
+        }),
+    }
+}
+
+fn parse_dimension(line: &str, value: &str, index: usize) -> Result<usize, TsplibError> {
+    value.parse().map_err(|_| TsplibError::InvalidHeader {
+        line: index + 1,
+        content: line.to_string(),
+    })
+}
+
+fn check_dimension(dimension: Option<usize>, found: usize) -> Result<(), TsplibError> {
+    match dimension {
+        Some(expected) if expected != found => {
+            Err(TsplibError::DimensionMismatch { expected, found })
+        }
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tour_round_trip() {
+        let mut tour = Tour::new("example", vec![0, 3, 1, 2]);
+        tour.comment = Some("written by a test".to_string());
+
+        let mut buffer = Vec::new();
+        write_tour(&mut buffer, &tour).unwrap();
+        let text = String::from_utf8(buffer.clone()).unwrap();
+        assert!(text.contains("TOUR_SECTION\n1\n4\n2\n3\n-1\nEOF"));
+
+        let read = read_tour(buffer.as_slice()).unwrap();
+        assert_eq!(read, tour);
+    }
+
+    #[test]
+    fn test_read_published_tour() {
+        // Layout as found in the published *.opt.tour files
+        let text = "NAME : ulysses5.opt.tour\nCOMMENT : Optimal tour\nTYPE : TOUR\nDIMENSION : 5\nTOUR_SECTION\n1 2\n5\n4 3\n-1\nEOF\n";
+
+        let tour = read_tour(text.as_bytes()).unwrap();
+        assert_eq!(tour.name, "ulysses5.opt.tour");
+        assert_eq!(tour.nodes, vec![0, 1, 4, 3, 2]);
+    }
+
+    #[test]
+    fn test_read_tour_errors() {
+        let wrong_type = "TYPE : TSP\nTOUR_SECTION\n1\n-1\n";
+        assert!(matches!(
+            read_tour(wrong_type.as_bytes()),
+            Err(TsplibError::UnsupportedType(_))
+        ));
+
+        let mismatch = "DIMENSION : 3\nTOUR_SECTION\n1\n2\n-1\n";
+        assert!(matches!(
+            read_tour(mismatch.as_bytes()),
+            Err(TsplibError::DimensionMismatch {
+                expected: 3,
+                found: 2
+            })
+        ));
+
+        let zero = "TOUR_SECTION\n0\n-1\n";
+        assert!(matches!(
+            read_tour(zero.as_bytes()),
+            Err(TsplibError::InvalidNode { line: 2, .. })
+        ));
+
+        let repeated = "TOUR_SECTION
+1
+2
+1
+-1
+";
+        assert!(matches!(
+            read_tour(repeated.as_bytes()),
+            Err(TsplibError::DuplicateNode { line: 4, node: 1 })
+        ));
+
+        assert!(matches!(
+            read_tour("NAME : x\n".as_bytes()),
+            Err(TsplibError::MissingSection("TOUR_SECTION"))
+        ));
+    }
+
+    #[test]
+    fn test_problem_round_trip() {
+        let text = "NAME : square\nTYPE : TSP\nDIMENSION : 4\nEDGE_WEIGHT_TYPE : EUC_2D\nNODE_COORD_SECTION\n1 0 0\n2 0 10\n3 10.5 10\n4 10 0\nEOF\n";
+
+        let problem = read_problem(text.as_bytes()).unwrap();
+        assert_eq!(problem.ids, vec!["1", "2", "3", "4"]);
+        assert_eq!(problem.points[2], Point::new(10.5, 10.0));
+        assert_eq!(problem.metric, Metric::RoundedEuclidean);
+        assert_eq!(problem.distance(1, 2), 11.0);
+
+        let mut buffer = Vec::new();
+        write_problem(&mut buffer, "square", &problem).unwrap();
+        assert_eq!(String::from_utf8(buffer).unwrap(), text);
+        assert_eq!(read_problem(text.as_bytes()).unwrap(), problem);
+    }
+
+    #[test]
+    fn test_read_problem_errors() {
+        let explicit = "TYPE : TSP\nEDGE_WEIGHT_TYPE : EXPLICIT\nNODE_COORD_SECTION\n";
+        assert!(matches!(
+            read_problem(explicit.as_bytes()),
+            Err(TsplibError::UnsupportedEdgeWeightType(_))
+        ));
+
+        let short = "EDGE_WEIGHT_TYPE : EUC_2D\nNODE_COORD_SECTION\n1 0\n";
+        assert!(matches!(
+            read_problem(short.as_bytes()),
+            Err(TsplibError::InvalidCoordinate { line: 3, .. })
+        ));
+    }
+}
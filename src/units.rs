@@ -0,0 +1,202 @@
+//! Distances and durations with their units in the type, so a route cannot be solved
+//! with meters on some legs and kilometers or seconds on others.
+//!
+//! Each is a newtype over `f64` with the arithmetic the generic solvers need, and only
+//! explicit conversions between them. Adding different units does not compile:
+//!
+//! ```compile_fail
+//! use coding_compairson::units::{Kilometers, Meters};
+//!
+//! let total = Meters(500.0) + Kilometers(1.0);
+//! ```
+
+use std::{
+    fmt,
+    iter::Sum,
+    ops::{Add, AddAssign, Div, Mul, Sub},
+};
+
+use num_traits::Zero;
+
+/// Declares a unit newtype over `f64` with the arithmetic the solvers require, and
+/// `symbol` as its suffix when displayed.
+macro_rules! unit {
+    ($(#[$attr:meta])* $name:ident, $symbol:literal) => {
+        $(#[$attr])*
+        #[derive(Debug, Clone, Copy, Default, PartialEq, PartialOrd)]
+        pub struct $name(pub f64);
+
+        impl Add for $name {
+            type Output = $name;
+
+            fn add(self, other: $name) -> $name {
+                $name(self.0 + other.0)
+            }
+        }
+
+        impl AddAssign for $name {
+            fn add_assign(&mut self, other: $name) {
+                self.0 += other.0;
+            }
+        }
+
+        impl Sub for $name {
+            type Output = $name;
+
+            fn sub(self, other: $name) -> $name {
+                $name(self.0 - other.0)
+            }
+        }
+
+        /// Scaling by a plain number keeps the unit.
+        impl Mul<f64> for $name {
+            type Output = $name;
+
+            fn mul(self, factor: f64) -> $name {
+                $name(self.0 * factor)
+            }
+        }
+
+        impl Div<f64> for $name {
+            type Output = $name;
+
+            fn div(self, divisor: f64) -> $name {
+                $name(self.0 / divisor)
+            }
+        }
+
+        /// The ratio of two amounts of the same unit is a plain number.
+        impl Div for $name {
+            type Output = f64;
+
+            fn div(self, other: $name) -> f64 {
+                self.0 / other.0
+            }
+        }
+
+        impl Zero for $name {
+            fn zero() -> $name {
+                $name(0.0)
+            }
+
+            fn is_zero(&self) -> bool {
+                self.0 == 0.0
+            }
+        }
+
+        impl Sum for $name {
+            fn sum<I: Iterator<Item = $name>>(iter: I) -> $name {
+                $name(iter.map(|amount| amount.0).sum())
+            }
+        }
+
+        impl<'a> Sum<&'a $name> for $name {
+            fn sum<I: Iterator<Item = &'a $name>>(iter: I) -> $name {
+                iter.copied().sum()
+            }
+        }
+
+        /// For the solvers that work in `f64`, such as [`christofides`](crate::christofides).
+        impl From<$name> for f64 {
+            fn from(amount: $name) -> f64 {
+                amount.0
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                fmt::Display::fmt(&self.0, f)?;
+                f.write_str($symbol)
+            }
+        }
+    };
+}
+
+unit!(
+    /// A distance in meters, such as from [`Metric::Haversine`](crate::Metric::Haversine).
+    Meters,
+    " m"
+);
+unit!(
+    /// A distance in kilometers.
+    Kilometers,
+    " km"
+);
+unit!(
+    /// A duration in seconds, such as the time to drive a leg.
+    Seconds,
+    " s"
+);
+
+impl Meters {
+    /// The time it takes to travel this far at `meters_per_second`.
+    pub fn travel_time(self, meters_per_second: f64) -> Seconds {
+        Seconds(self.0 / meters_per_second)
+    }
+}
+
+impl From<Kilometers> for Meters {
+    fn from(distance: Kilometers) -> Meters {
+        Meters(distance.0 * 1000.0)
+    }
+}
+
+impl From<Meters> for Kilometers {
+    fn from(distance: Meters) -> Kilometers {
+        Kilometers(distance.0 / 1000.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{traveling_salesman, DistanceMatrix, Metric, Point, Problem};
+
+    use super::*;
+
+    #[test]
+    fn test_arithmetic() {
+        assert_eq!(Meters(1.5) + Meters(2.0), Meters(3.5));
+        assert_eq!(Meters(5.0) - Meters(2.0), Meters(3.0));
+        assert_eq!(Kilometers(2.0) * 3.0, Kilometers(6.0));
+        assert_eq!(Seconds(6.0) / 4.0, Seconds(1.5));
+        assert_eq!(Meters(6.0) / Meters(4.0), 1.5);
+        assert_eq!(
+            [Seconds(1.0), Seconds(2.0)].iter().sum::<Seconds>(),
+            Seconds(3.0)
+        );
+        let mut total = Meters::default();
+        total += Meters(4.0);
+        assert_eq!(total, Meters(4.0));
+        assert!(Meters(1.0) < Meters(2.0));
+        assert_eq!(Meters(12.5).to_string(), "12.5 m");
+        assert_eq!(format!("{:.1}", Kilometers(3.0)), "3.0 km");
+    }
+
+    #[test]
+    fn test_conversions() {
+        assert_eq!(Meters::from(Kilometers(1.5)), Meters(1500.0));
+        assert_eq!(Kilometers::from(Meters(250.0)), Kilometers(0.25));
+        assert_eq!(Meters(100.0).travel_time(20.0), Seconds(5.0));
+        assert_eq!(f64::from(Seconds(2.5)), 2.5);
+    }
+
+    #[test]
+    fn test_solvers_with_units() {
+        let problem = Problem::new(
+            [(0.0, 0.0), (3.0, 4.0), (0.0, 4.0), (3.0, 0.0)]
+                .map(|(x, y)| Point::new(x, y))
+                .to_vec(),
+            Metric::Euclidean,
+        );
+        let meters = |a: usize, b: usize| Meters(problem.distance(a, b));
+
+        let route = traveling_salesman(1..4, 0, 0, |(&a, &b)| meters(a, b));
+        let matrix = DistanceMatrix::from_fn(problem.len(), meters);
+        assert_eq!(matrix.route_distance(&route), Meters(14.0));
+        assert_eq!(matrix.solve(0, 0).stops(), problem.solve().stops());
+
+        // Durations are a cost like any other
+        let seconds = DistanceMatrix::from_fn(problem.len(), |a, b| meters(a, b).travel_time(2.0));
+        assert_eq!(seconds.route_distance(&route), Seconds(7.0));
+    }
+}
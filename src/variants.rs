@@ -0,0 +1,366 @@
+//! Alternate implementations of the exhaustive solver in different styles, to compare
+//! how they read and how they perform against [`traveling_salesman`](crate::traveling_salesman).
+//!
+//! Each has the same signature and finds a route of the same distance, though ties
+//! may be broken differently since each visits the permutations in its own order.
+//! [`traveling_salesman_held_karp`] is the exception that avoids visiting them all.
+
+use std::{cmp::Ordering, ops::Add};
+
+use itertools::Itertools as _;
+use num_traits::Zero;
+
+use crate::cached_fix;
+
+/// The recursive backtracking style: extend a partial route one unused destination at a
+/// time, and undo the choice on the way back out.
+///
+/// # Arguments
+///
+/// * `inner_destinations` - The destinations to visit
+/// * `start` - The starting destination
+/// * `end` - The ending destination
+/// * `compute_distance` - A function that computes the distance between two destinations
+///
+/// # Returns
+///
+/// The shortest path that visits all of the inner destinations starting at `start` and ending at `end`
+pub fn traveling_salesman_recursive<Destinations, Destination, Distance>(
+    inner_destinations: Destinations,
+    start: Destination,
+    end: Destination,
+    compute_distance: impl Fn((&Destination, &Destination)) -> Distance,
+) -> Vec<Destination>
+where
+    Destinations: Iterator<Item = Destination>,
+    Destination: Clone,
+    Distance: PartialOrd + Zero,
+{
+    struct Search<'a, Destination, Distance, F> {
+        destinations: &'a [Destination],
+        start: &'a Destination,
+        end: &'a Destination,
+        compute_distance: F,
+        used: Vec<bool>,
+        partial: Vec<usize>,
+        best: Option<(Distance, Vec<usize>)>,
+    }
+
+    impl<Destination, Distance, F> Search<'_, Destination, Distance, F>
+    where
+        Distance: PartialOrd + Zero,
+        F: Fn((&Destination, &Destination)) -> Distance,
+    {
+        fn extend(&mut self) {
+            if self.partial.len() == self.destinations.len() {
+                let stops = std::iter::once(self.start)
+                    .chain(self.partial.iter().map(|&i| &self.destinations[i]))
+                    .chain(std::iter::once(self.end));
+                let distance = stops
+                    .tuple_windows()
+                    .map(|(a, b)| (self.compute_distance)((a, b)))
+                    .fold(Distance::zero(), Add::add);
+                if self.best.as_ref().is_none_or(|(best, _)| distance < *best) {
+                    self.best = Some((distance, self.partial.clone()));
+                }
+                return;
+            }
+
+            for next in 0..self.destinations.len() {
+                if !self.used[next] {
+                    self.used[next] = true;
+                    self.partial.push(next);
+                    self.extend();
+                    self.partial.pop();
+                    self.used[next] = false;
+                }
+            }
+        }
+    }
+
+    let destinations = inner_destinations.collect::<Vec<_>>();
+    let mut search = Search {
+        destinations: &destinations,
+        start: &start,
+        end: &end,
+        compute_distance,
+        used: vec![false; destinations.len()],
+        partial: Vec::with_capacity(destinations.len()),
+        best: None,
+    };
+    search.extend();
+    let order = search.best.map(|(_, order)| order).unwrap_or_default();
+
+    let mut route = vec![start.clone()];
+    route.extend(order.into_iter().map(|i| destinations[i].clone()));
+    route.push(end);
+    route
+}
+
+/// The imperative style: step an array of indices through every permutation in
+/// lexicographic order by swapping and reversing in place, with plain loops throughout.
+///
+/// # Arguments
+///
+/// * `inner_destinations` - The destinations to visit
+/// * `start` - The starting destination
+/// * `end` - The ending destination
+/// * `compute_distance` - A function that computes the distance between two destinations
+///
+/// # Returns
+///
+/// The shortest path that visits all of the inner destinations starting at `start` and ending at `end`
+pub fn traveling_salesman_imperative<Destinations, Destination, Distance>(
+    inner_destinations: Destinations,
+    start: Destination,
+    end: Destination,
+    compute_distance: impl Fn((&Destination, &Destination)) -> Distance,
+) -> Vec<Destination>
+where
+    Destinations: Iterator<Item = Destination>,
+    Destination: Clone,
+    Distance: PartialOrd + Zero,
+{
+    let destinations = inner_destinations.collect::<Vec<_>>();
+    let n = destinations.len();
+
+    let mut order = (0..n).collect::<Vec<_>>();
+    let mut best_order = order.clone();
+    let mut best_distance = None;
+    loop {
+        let mut distance =
+            compute_distance((&start, order.first().map_or(&end, |&i| &destinations[i])));
+        for i in 1..n {
+            distance =
+                distance + compute_distance((&destinations[order[i - 1]], &destinations[order[i]]));
+        }
+        if n > 0 {
+            distance = distance + compute_distance((&destinations[order[n - 1]], &end));
+        }
+        if best_distance.as_ref().is_none_or(|best| distance < *best) {
+            best_distance = Some(distance);
+            best_order.copy_from_slice(&order);
+        }
+
+        // Find the rightmost index that is smaller than its successor
+        let mut pivot = n.saturating_sub(1);
+        while pivot > 0 && order[pivot - 1] >= order[pivot] {
+            pivot -= 1;
+        }
+        if pivot == 0 {
+            break; // the indices are in descending order, the last permutation
+        }
+        // Swap it with the rightmost index larger than it, then reverse the tail
+        let mut successor = n - 1;
+        while order[successor] <= order[pivot - 1] {
+            successor -= 1;
+        }
+        order.swap(pivot - 1, successor);
+        order[pivot..].reverse();
+    }
+
+    let mut route = Vec::with_capacity(n + 2);
+    route.push(start);
+    for i in best_order {
+        route.push(destinations[i].clone());
+    }
+    route.push(end);
+    route
+}
+
+/// The iterator style: a single chain from the permutations to the route, with the
+/// shortest found by folding rather than `min_by`.
+///
+/// # Arguments
+///
+/// * `inner_destinations` - The destinations to visit
+/// * `start` - The starting destination
+/// * `end` - The ending destination
+/// * `compute_distance` - A function that computes the distance between two destinations
+///
+/// # Returns
+///
+/// The shortest path that visits all of the inner destinations starting at `start` and ending at `end`
+pub fn traveling_salesman_fold<Destinations, Destination, Distance>(
+    inner_destinations: Destinations,
+    start: Destination,
+    end: Destination,
+    compute_distance: impl Fn((&Destination, &Destination)) -> Distance,
+) -> Vec<Destination>
+where
+    Destinations: Iterator<Item = Destination> + ExactSizeIterator,
+    Destination: Clone,
+    Distance: PartialOrd + Zero,
+{
+    let count = inner_destinations.len();
+    inner_destinations
+        .permutations(count)
+        .map(|inner| {
+            let route = std::iter::once(start.clone())
+                .chain(inner)
+                .chain(std::iter::once(end.clone()))
+                .collect::<Vec<_>>();
+            let distance = route
+                .iter()
+                .tuple_windows()
+                .map(&compute_distance)
+                .fold(Distance::zero(), Add::add);
+            (distance, route)
+        })
+        // Only a strictly shorter candidate replaces the best, so NaN never does
+        .fold(
+            None,
+            |best: Option<(Distance, Vec<Destination>)>, candidate| match best {
+                Some(best) if candidate.0.partial_cmp(&best.0) != Some(Ordering::Less) => {
+                    Some(best)
+                }
+                _ => Some(candidate),
+            },
+        )
+        .map(|(_, route)| route)
+        .unwrap_or_else(|| vec![start, end])
+}
+
+/// The dynamic programming style: Held–Karp, written as the recursive definition of
+/// the shortest partial route and memoized with [`cached_fix`], so each subset of the
+/// destinations is solved once. It takes O(2ⁿn²) time but also O(2ⁿn) memory.
+///
+/// # Arguments
+///
+/// * `inner_destinations` - The destinations to visit, fewer than 64
+/// * `start` - The starting destination
+/// * `end` - The ending destination
+/// * `compute_distance` - A function that computes the distance between two destinations
+///
+/// # Returns
+///
+/// The shortest path that visits all of the inner destinations starting at `start` and ending at `end`
+pub fn traveling_salesman_held_karp<Destinations, Destination, Distance>(
+    inner_destinations: Destinations,
+    start: Destination,
+    end: Destination,
+    compute_distance: impl Fn((&Destination, &Destination)) -> Distance,
+) -> Vec<Destination>
+where
+    Destinations: Iterator<Item = Destination>,
+    Destination: Clone,
+    Distance: PartialOrd + Zero + Clone,
+{
+    let destinations = inner_destinations.collect::<Vec<_>>();
+    let n = destinations.len();
+    if n == 0 {
+        return vec![start, end];
+    }
+    assert!(n < 64, "Held-Karp needs fewer than 64 destinations");
+
+    // The shortest route from `start` through the destinations in `visited`,
+    // ending at `last` which is one of them, with the destination before `last`
+    let shortest = cached_fix(
+        |shortest: &dyn Fn((u64, usize)) -> (Distance, Option<usize>),
+         (visited, last): (u64, usize)| {
+            let before = visited & !(1 << last);
+            if before == 0 {
+                return (compute_distance((&start, &destinations[last])), None);
+            }
+            let mut best: Option<(Distance, Option<usize>)> = None;
+            for previous in (0..n).filter(|&i| before & (1 << i) != 0) {
+                let distance = shortest((before, previous)).0
+                    + compute_distance((&destinations[previous], &destinations[last]));
+                if best.as_ref().is_none_or(|(best, _)| distance < *best) {
+                    best = Some((distance, Some(previous)));
+                }
+            }
+            best.expect("`before` is not empty")
+        },
+    );
+
+    let all = u64::MAX >> (64 - n);
+    let mut best: Option<(Distance, usize)> = None;
+    for (last, destination) in destinations.iter().enumerate() {
+        let distance = shortest((all, last)).0 + compute_distance((destination, &end));
+        if best.as_ref().is_none_or(|(best, _)| distance < *best) {
+            best = Some((distance, last));
+        }
+    }
+
+    // Walk back through the cached predecessors, which are not recomputed
+    let mut order = Vec::with_capacity(n);
+    let (mut visited, mut last) = (all, best.map(|(_, last)| last));
+    while let Some(current) = last {
+        order.push(current);
+        last = shortest((visited, current)).1;
+        visited &= !(1 << current);
+    }
+    drop(shortest);
+
+    let mut route = Vec::with_capacity(n + 2);
+    route.push(start);
+    route.extend(order.into_iter().rev().map(|i| destinations[i].clone()));
+    route.push(end);
+    route
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{generators, traveling_salesman};
+
+    use super::*;
+
+    type Solver = fn(std::vec::IntoIter<i32>, i32, i32, fn((&i32, &i32)) -> u32) -> Vec<i32>;
+
+    const VARIANTS: [(&str, Solver); 4] = [
+        ("recursive", traveling_salesman_recursive),
+        ("imperative", traveling_salesman_imperative),
+        ("fold", traveling_salesman_fold),
+        ("held-karp", traveling_salesman_held_karp),
+    ];
+
+    fn compute_distance(pair: (&i32, &i32)) -> u32 {
+        pair.0.abs_diff(*pair.1)
+    }
+
+    fn total(route: &[i32]) -> u32 {
+        route.windows(2).map(|leg| leg[0].abs_diff(leg[1])).sum()
+    }
+
+    #[test]
+    fn test_variants() {
+        for (name, solve) in VARIANTS {
+            assert_eq!(
+                solve(vec![4, 1, 5, 3, 2].into_iter(), 0, 6, compute_distance),
+                vec![0, 1, 2, 3, 4, 5, 6],
+                "{name}"
+            );
+            assert_eq!(
+                solve(vec![7].into_iter(), 0, 6, compute_distance),
+                vec![0, 7, 6],
+                "{name}"
+            );
+            assert_eq!(
+                solve(vec![].into_iter(), 0, 6, compute_distance),
+                vec![0, 6],
+                "{name}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_variants_match_generic() {
+        for seed in 0..10 {
+            let destinations = generators::random_integers(6, -99..100, seed);
+            let generic =
+                traveling_salesman(destinations.clone().into_iter(), 0, 0, compute_distance);
+
+            for (name, solve) in VARIANTS {
+                let route = solve(destinations.clone().into_iter(), 0, 0, compute_distance);
+                assert_eq!(total(&route), total(&generic), "{name}");
+
+                let mut visited = route[1..route.len() - 1].to_vec();
+                let mut expected = destinations.clone();
+                visited.sort();
+                expected.sort();
+                assert_eq!(visited, expected, "{name}");
+            }
+        }
+    }
+}
@@ -0,0 +1,176 @@
+//! Which constraints a route satisfies and which it breaks, and by how much, so a
+//! caller can act on a constrained solution without re-auditing the route.
+
+use std::fmt;
+
+use crate::{pickup_delivery::Pair, soft_constraints::TimeWindow};
+
+/// A constraint on a route.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Constraint {
+    /// Arrive at `destination` within `window`.
+    TimeWindow {
+        destination: usize,
+        window: TimeWindow,
+    },
+    /// Visit the pair's pickup before its delivery.
+    Precedence(Pair),
+    /// Visit `destination` at `position`, counting the start as position 0.
+    Pin { destination: usize, position: usize },
+}
+
+impl fmt::Display for Constraint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Constraint::TimeWindow {
+                destination,
+                window,
+            } => write!(
+                f,
+                "arrive at {destination} between {} and {}",
+                window.earliest, window.latest
+            ),
+            Constraint::Precedence(pair) => {
+                write!(f, "visit {} before {}", pair.pickup, pair.delivery)
+            }
+            Constraint::Pin {
+                destination,
+                position,
+            } => write!(f, "visit {destination} at position {position}"),
+        }
+    }
+}
+
+/// A constraint and how far a route is from satisfying it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ConstraintCheck {
+    pub constraint: Constraint,
+    /// Zero if the route satisfies the constraint, and otherwise how badly it breaks
+    /// it: the lateness for a time window, how many positions too early the delivery is
+    /// for a precedence, and how many positions away the destination is for a pin.
+    /// Infinite if the route does not visit a destination the constraint names.
+    pub violation: f64,
+}
+
+impl ConstraintCheck {
+    pub fn is_satisfied(&self) -> bool {
+        self.violation <= 0.0
+    }
+}
+
+/// Every constraint on a route, each with how far the route is from satisfying it, in
+/// the order the constraints were given.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ViolationReport {
+    pub checks: Vec<ConstraintCheck>,
+}
+
+impl ViolationReport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&mut self, constraint: Constraint, violation: f64) {
+        self.checks.push(ConstraintCheck {
+            constraint,
+            violation,
+        });
+    }
+
+    /// The constraints the route satisfies.
+    pub fn satisfied(&self) -> impl Iterator<Item = &ConstraintCheck> + '_ {
+        self.checks.iter().filter(|check| check.is_satisfied())
+    }
+
+    /// The constraints the route breaks.
+    pub fn violated(&self) -> impl Iterator<Item = &ConstraintCheck> + '_ {
+        self.checks.iter().filter(|check| !check.is_satisfied())
+    }
+
+    /// Whether the route satisfies every constraint.
+    pub fn is_feasible(&self) -> bool {
+        self.checks.iter().all(ConstraintCheck::is_satisfied)
+    }
+
+    /// The reports of `self` and `other` together, such as for a route under several
+    /// kinds of constraint.
+    pub fn merged(mut self, other: ViolationReport) -> Self {
+        self.checks.extend(other.checks);
+        self
+    }
+}
+
+/// One line per constraint, saying whether it is satisfied or by how much it is
+/// violated.
+impl fmt::Display for ViolationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for check in &self.checks {
+            if check.is_satisfied() {
+                writeln!(f, "{}: satisfied", check.constraint)?;
+            } else {
+                writeln!(f, "{}: violated by {}", check.constraint, check.violation)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// How many positions too early the delivery of `pair` is on `stops`, as
+/// [`pickup_delivery::is_feasible`](crate::pickup_delivery::is_feasible) counts them.
+pub(crate) fn precedence_violation(stops: &[usize], pair: Pair) -> f64 {
+    let pickup = stops.iter().position(|&stop| stop == pair.pickup);
+    let delivery = stops.iter().rposition(|&stop| stop == pair.delivery);
+    match (pickup, delivery) {
+        (Some(pickup), Some(delivery)) if pickup < delivery => 0.0,
+        (Some(pickup), Some(delivery)) => (pickup - delivery + 1) as f64,
+        _ => f64::INFINITY,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report() {
+        let mut report = ViolationReport::new();
+        assert!(report.is_feasible());
+        report.record(Constraint::Precedence(Pair::new(2, 1)), 0.0);
+        report.record(
+            Constraint::TimeWindow {
+                destination: 3,
+                window: TimeWindow::new(0.0, 5.0),
+            },
+            1.5,
+        );
+        assert!(!report.is_feasible());
+        assert_eq!(report.satisfied().count(), 1);
+        assert_eq!(report.violated().next().unwrap().violation, 1.5);
+        let report = report.merged(ViolationReport {
+            checks: vec![ConstraintCheck {
+                constraint: Constraint::Pin {
+                    destination: 4,
+                    position: 2,
+                },
+                violation: f64::INFINITY,
+            }],
+        });
+        assert_eq!(
+            report.to_string(),
+            "visit 2 before 1: satisfied\n\
+             arrive at 3 between 0 and 5: violated by 1.5\n\
+             visit 4 at position 2: violated by inf\n"
+        );
+    }
+
+    #[test]
+    fn test_precedence_violation() {
+        let stops = [0, 3, 1, 2, 0];
+        assert_eq!(precedence_violation(&stops, Pair::new(3, 2)), 0.0);
+        assert_eq!(precedence_violation(&stops, Pair::new(2, 1)), 2.0);
+        assert_eq!(precedence_violation(&stops, Pair::new(1, 3)), 2.0);
+        // The depot of a round trip delivers last
+        assert_eq!(precedence_violation(&stops, Pair::new(2, 0)), 0.0);
+        assert_eq!(precedence_violation(&stops, Pair::new(5, 1)), f64::INFINITY);
+    }
+}
@@ -0,0 +1,33 @@
+//! Counts allocations with [`CountingAllocator`] installed as the global allocator,
+//! which has to be done in a binary of its own so it does not count for every test.
+
+use coding_compairson::{
+    compare::{compare_implementations, implementations, Instance},
+    report::CountingAllocator,
+    Metric, Point, Problem,
+};
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+#[test]
+fn test_counting_allocator() {
+    let before = CountingAllocator::allocations().unwrap();
+    let boxed = std::hint::black_box(Box::new(1));
+    drop(boxed);
+    assert!(CountingAllocator::allocations().unwrap() > before);
+}
+
+#[test]
+fn test_compare_counts_allocations() {
+    let problem = Problem::new(
+        (0..5).map(|i| Point::new(f64::from(i), 0.0)).collect(),
+        Metric::Euclidean,
+    );
+    let comparison = compare_implementations(&[Instance::new("line", problem)], &implementations());
+    assert!(!comparison.results.is_empty());
+    assert!(comparison
+        .results
+        .iter()
+        .all(|result| result.allocations.is_some()));
+}
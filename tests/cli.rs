@@ -0,0 +1,62 @@
+//! Runs the `tsp` binary on problem files, checking what it prints.
+
+#![cfg(feature = "cli")]
+
+use std::{fs, process::Command};
+
+/// Writes `contents` to a file named `name` in a fresh temporary directory and solves
+/// it with `tsp solve`, returning what the binary printed.
+fn solve(name: &str, contents: &str, args: &[&str]) -> String {
+    let dir = std::env::temp_dir().join(format!("tsp-cli-{}-{name}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(name);
+    fs::write(&path, contents).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_tsp"))
+        .arg("solve")
+        .arg(&path)
+        .args(args)
+        .output()
+        .unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+    assert!(
+        output.status.success(),
+        "tsp failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn test_solve_open_route_document() {
+    let document = r#"{
+        "destinations": [
+            {"id": "a", "x": 0.0, "y": 0.0},
+            {"id": "b", "x": 2.0, "y": 0.0},
+            {"id": "c", "x": 1.0, "y": 0.0},
+            {"id": "d", "x": 3.0, "y": 0.0}
+        ],
+        "start": "a",
+        "end": "d"
+    }"#;
+
+    // The document's end is kept without an --end flag
+    let printed = solve("open.json", document, &[]);
+    assert!(printed.contains("route: a -> c -> b -> d\n"), "{printed}");
+    assert!(printed.contains("distance: 3\n"), "{printed}");
+
+    // A new start leaves an open route's end alone
+    let printed = solve("restart.json", document, &["--start", "c"]);
+    assert!(printed.contains("route: c -> a -> b -> d\n"), "{printed}");
+}
+
+#[test]
+fn test_solve_round_trip_from_new_start() {
+    let csv = "id,x,y\na,0,0\nb,0,1\nc,1,1\n";
+    let printed = solve("stops.csv", csv, &["--start", "b"]);
+    let route = printed.lines().next().unwrap();
+    assert!(
+        route.starts_with("route: b -> ") && route.ends_with(" -> b"),
+        "{route}"
+    );
+}
@@ -0,0 +1,112 @@
+//! Small instances with known optimal distances. Every exact solver must find them,
+//! so a change to an algorithm cannot silently lose optimality.
+
+use coding_compairson::{
+    compare::{compare_implementations, implementations, Instance},
+    registry::{Category, IMPLEMENTATIONS},
+    tsplib, Metric, Problem,
+};
+
+/// Distances may differ from the optimum by floating-point error only.
+const TOLERANCE: f64 = 1e-6;
+
+/// Metres along the equator per degree of longitude.
+const METERS_PER_DEGREE: f64 = 6_371_000.0 * std::f64::consts::PI / 180.0;
+
+/// An embedded instance and its optimal distance.
+struct Golden {
+    name: &'static str,
+    problem: Problem,
+    optimal: f64,
+}
+
+fn tsplib(name: &'static str, source: &str, optimal: f64) -> Golden {
+    Golden {
+        name,
+        problem: tsplib::read_problem(source.as_bytes()).expect("golden instance should parse"),
+        optimal,
+    }
+}
+
+fn csv(name: &'static str, source: &str, metric: Metric, end: usize, optimal: f64) -> Golden {
+    let mut problem =
+        Problem::from_csv(source.as_bytes(), metric).expect("golden instance should parse");
+    problem.end = end;
+    Golden {
+        name,
+        problem,
+        optimal,
+    }
+}
+
+fn corpus() -> Vec<Golden> {
+    vec![
+        tsplib("square4", include_str!("data/square4.tsp"), 4.0),
+        tsplib("hexagon6", include_str!("data/hexagon6.tsp"), 6.0),
+        tsplib("ladder8", include_str!("data/ladder8.tsp"), 8.0),
+        tsplib("grid9", include_str!("data/grid9.tsp"), 9.0),
+        tsplib(
+            "grid9_manhattan",
+            include_str!("data/grid9_manhattan.tsp"),
+            10.0,
+        ),
+        // An open path from the leftmost to the rightmost point of a line
+        csv(
+            "line5",
+            include_str!("data/line5.csv"),
+            Metric::Euclidean,
+            3,
+            4.0,
+        ),
+        csv(
+            "equator",
+            include_str!("data/equator.csv"),
+            Metric::Haversine,
+            0,
+            4.0 * METERS_PER_DEGREE,
+        ),
+    ]
+}
+
+#[test]
+fn test_exact_solvers_find_known_optima() {
+    let corpus = corpus();
+    let instances = corpus
+        .iter()
+        .map(|golden| Instance::new(golden.name, golden.problem.clone()))
+        .collect::<Vec<_>>();
+    let exact = implementations()
+        .into_iter()
+        .filter(|implementation| implementation.exact)
+        .collect::<Vec<_>>();
+
+    let comparison = compare_implementations(&instances, &exact);
+    for mismatch in &comparison.mismatches {
+        eprintln!("{mismatch}");
+    }
+    assert!(comparison.is_consistent());
+    let supported = corpus
+        .iter()
+        .flat_map(|golden| {
+            IMPLEMENTATIONS.iter().filter(|registered| {
+                registered.category == Category::Exact && registered.supports(&golden.problem)
+            })
+        })
+        .count();
+    assert_eq!(comparison.results.len(), supported);
+
+    for result in &comparison.results {
+        let golden = corpus
+            .iter()
+            .find(|golden| golden.name == result.instance)
+            .unwrap();
+        assert!(
+            (result.distance - golden.optimal).abs() <= TOLERANCE * golden.optimal.max(1.0),
+            "{} found {} on {} but the optimum is {}",
+            result.solver,
+            result.distance,
+            result.instance,
+            golden.optimal
+        );
+    }
+}